@@ -0,0 +1,294 @@
+use bevy::{
+    audio::{AddAudioSource, Decodable, Source},
+    prelude::*,
+};
+use bevy_egui::{egui, EguiContexts};
+use std::{
+    f32::consts::TAU,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{settings::Settings, stats::SimulationStats, ui_enabled, Particle, Velocity};
+
+const SAMPLE_RATE: u32 = 44_100;
+const VOICES: usize = 6;
+const BASE_FREQUENCY: f32 = 110.0;
+/// Pentatonic ratios so any mix of voices stays consonant.
+const VOICE_RATIOS: [f32; VOICES] = [1.0, 9.0 / 8.0, 5.0 / 4.0, 3.0 / 2.0, 5.0 / 3.0, 2.0];
+const PING_FREQUENCY: f32 = 880.0;
+const PING_DECAY: f32 = 6.0;
+/// Mean kinetic energy (world units²/s²) that maps to roughly 63% intensity.
+const REFERENCE_ENERGY: f32 = 20_000.0;
+/// Per-sample smoothing of parameter changes, so updates at frame rate do not click.
+const PARAMETER_SMOOTHING: f32 = 0.0005;
+
+pub struct AudioSynthPlugin;
+
+impl Plugin for AudioSynthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<SimSynth>()
+            .insert_resource(AudioSynth::default())
+            .add_systems(
+                Update,
+                (
+                    toggle_audio,
+                    drive_synth,
+                    audio_ui_system.run_if(ui_enabled),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// `f32` shared lock-free between the simulation and the audio thread.
+#[derive(Default)]
+struct AtomicF32(AtomicU32);
+
+impl AtomicF32 {
+    fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    fn set(&self, value: f32) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Targets written by [`drive_synth`] each frame and read by the decoder.
+#[derive(Default)]
+struct SynthParams {
+    volume: AtomicF32,
+    /// Global kinetic energy in 0..1, controls brightness and vibrato.
+    energy: AtomicF32,
+    /// Population share of the first species, one per voice.
+    voices: [AtomicF32; VOICES],
+    /// Incremented for every cluster formation event.
+    pings: AtomicU32,
+}
+
+/// Endless procedural audio source whose parameters follow the simulation.
+#[derive(Asset, TypePath)]
+pub struct SimSynth {
+    params: Arc<SynthParams>,
+}
+
+impl Decodable for SimSynth {
+    type DecoderItem = f32;
+    type Decoder = SynthDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        SynthDecoder {
+            params: self.params.clone(),
+            phases: [0.0; VOICES],
+            amplitudes: [0.0; VOICES],
+            volume: 0.0,
+            energy: 0.0,
+            vibrato_phase: 0.0,
+            ping_phase: 0.0,
+            ping_envelope: 0.0,
+            pings_seen: 0,
+        }
+    }
+}
+
+pub struct SynthDecoder {
+    params: Arc<SynthParams>,
+    phases: [f32; VOICES],
+    amplitudes: [f32; VOICES],
+    volume: f32,
+    energy: f32,
+    vibrato_phase: f32,
+    ping_phase: f32,
+    ping_envelope: f32,
+    pings_seen: u32,
+}
+
+impl Iterator for SynthDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let dt = 1.0 / SAMPLE_RATE as f32;
+        let smooth =
+            |current: &mut f32, target: f32| *current += (target - *current) * PARAMETER_SMOOTHING;
+        smooth(&mut self.volume, self.params.volume.get());
+        smooth(&mut self.energy, self.params.energy.get());
+
+        let pings = self.params.pings.load(Ordering::Relaxed);
+        if pings != self.pings_seen {
+            self.pings_seen = pings;
+            self.ping_envelope = 1.0;
+        }
+
+        // Faster, deeper vibrato the more energetic the system is
+        self.vibrato_phase = (self.vibrato_phase + (2.0 + self.energy * 6.0) * dt).fract();
+        let vibrato = 1.0 + (self.vibrato_phase * TAU).sin() * 0.01 * self.energy;
+
+        let mut sample = 0.0;
+        let voices = self.params.voices.iter().zip(VOICE_RATIOS);
+        for ((phase, amplitude), (target, ratio)) in
+            self.phases.iter_mut().zip(&mut self.amplitudes).zip(voices)
+        {
+            smooth(amplitude, target.get());
+            *phase = (*phase + BASE_FREQUENCY * ratio * vibrato * dt).fract();
+            let phase = *phase * TAU;
+            // Energy adds upper harmonics, brightening the tone
+            let tone = phase.sin()
+                + (phase * 2.0).sin() * 0.5 * self.energy
+                + (phase * 3.0).sin() * 0.25 * self.energy;
+            sample += tone * *amplitude;
+        }
+
+        self.ping_phase = (self.ping_phase + PING_FREQUENCY * dt).fract();
+        sample += (self.ping_phase * TAU).sin() * self.ping_envelope * 0.3;
+        self.ping_envelope *= (-PING_DECAY * dt).exp();
+
+        Some((sample * self.volume * (0.3 + 0.7 * self.energy)).clamp(-1.0, 1.0))
+    }
+}
+
+impl Source for SynthDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Sonification of the simulation: species populations set the mix of a
+/// pentatonic chord, kinetic energy its loudness and brightness, and each
+/// newly formed cluster plays a short ping.
+#[derive(Resource)]
+pub struct AudioSynth {
+    pub enabled: bool,
+    pub volume: f32,
+    params: Arc<SynthParams>,
+    player: Option<Entity>,
+    last_clusters: Option<usize>,
+    energy: f32,
+}
+
+impl Default for AudioSynth {
+    fn default() -> Self {
+        AudioSynth {
+            enabled: false,
+            volume: 0.5,
+            params: Arc::default(),
+            player: None,
+            last_clusters: None,
+            energy: 0.0,
+        }
+    }
+}
+
+fn toggle_audio(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    mut synth: ResMut<AudioSynth>,
+) {
+    if keyboard.just_pressed(settings.keys.toggle_audio) {
+        synth.enabled = !synth.enabled;
+    }
+}
+
+fn drive_synth(
+    mut commands: Commands,
+    mut synth: ResMut<AudioSynth>,
+    mut sources: ResMut<Assets<SimSynth>>,
+    stats: Res<SimulationStats>,
+    particles: Query<(&Particle, &Velocity)>,
+) {
+    match (synth.enabled, synth.player) {
+        (true, None) => {
+            let handle = sources.add(SimSynth {
+                params: synth.params.clone(),
+            });
+            synth.player = Some(
+                commands
+                    .spawn((AudioPlayer(handle), PlaybackSettings::LOOP))
+                    .id(),
+            );
+        }
+        (false, Some(player)) => {
+            commands.entity(player).despawn();
+            synth.player = None;
+            synth.last_clusters = None;
+            return;
+        }
+        (false, None) => return,
+        (true, Some(_)) => {}
+    }
+
+    let mut populations = [0usize; VOICES];
+    let mut total_energy = 0.0;
+    let mut count = 0;
+    for (particle, velocity) in &particles {
+        if let Some(population) = populations.get_mut(particle.color_id) {
+            *population += 1;
+        }
+        total_energy += 0.5 * velocity.0.length_squared();
+        count += 1;
+    }
+    let mean_energy = if count > 0 {
+        total_energy / count as f32
+    } else {
+        0.0
+    };
+    synth.energy = 1.0 - (-mean_energy / REFERENCE_ENERGY).exp();
+
+    let voiced: usize = populations.iter().sum();
+    for (voice, population) in synth.params.voices.iter().zip(populations) {
+        let share = if voiced > 0 {
+            population as f32 / voiced as f32
+        } else {
+            0.0
+        };
+        voice.set(share);
+    }
+    synth.params.volume.set(synth.volume);
+    synth.params.energy.set(synth.energy);
+
+    if let Some(sample) = stats.latest() {
+        if let Some(last) = synth.last_clusters {
+            if sample.cluster_count > last {
+                synth.params.pings.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        synth.last_clusters = Some(sample.cluster_count);
+    }
+}
+
+fn audio_ui_system(
+    mut contexts: EguiContexts,
+    settings: Res<Settings>,
+    mut synth: ResMut<AudioSynth>,
+) {
+    egui::Window::new("Audio")
+        .default_pos([620.0, 290.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(
+                &mut synth.enabled,
+                format!("Sonify the simulation ({:?})", settings.keys.toggle_audio),
+            );
+            ui.add(egui::Slider::new(&mut synth.volume, 0.0..=1.0).text("volume"));
+            ui.label(format!("Kinetic energy: {:.0}%", synth.energy * 100.0));
+            ui.label(format!(
+                "Voices follow the populations of the first {} species",
+                VOICES
+            ));
+        });
+}
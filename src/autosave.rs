@@ -0,0 +1,396 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    network::RemoteWorld, pool::ParticleSpawner, settings::Settings, snapshot::ParticleSnapshot,
+    ui_enabled, Headless, Particle, ParticleCount, ParticleSystem, Velocity,
+};
+
+/// Autosaves kept; each new one replaces the oldest, so a save cut short by
+/// a crash still leaves the ones before it.
+const ROTATION: u64 = 3;
+
+pub struct AutosavePlugin;
+
+impl Plugin for AutosavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Autosave>()
+            .init_non_send_resource::<store::AutosaveStore>()
+            .add_systems(
+                Update,
+                (
+                    receive_autosaves,
+                    write_autosave,
+                    restore_ui_system.run_if(ui_enabled),
+                    apply_restore,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Rules and every particle, written periodically so a long-running world
+/// survives a crash.
+#[derive(Serialize, Deserialize)]
+struct AutosaveSnapshot {
+    /// Increases with every autosave, to find the newest one.
+    sequence: u64,
+    /// Seconds the saving session had been running.
+    session_time: f32,
+    particle_system: ParticleSystem,
+    /// Every particle as a base64 [`ParticleSnapshot`] with velocities.
+    particles: String,
+}
+
+impl AutosaveSnapshot {
+    /// Reads a snapshot and its particles, fitting the rules to their palette
+    /// so a damaged file cannot break the world it is restored into.
+    fn parse(text: &str) -> Result<(Self, ParticleSnapshot), String> {
+        let mut snapshot: AutosaveSnapshot =
+            ron::from_str(text).map_err(|error| error.to_string())?;
+        if snapshot.particle_system.colors.is_empty() {
+            return Err("no colors".to_string());
+        }
+        snapshot.particle_system.fit_to_palette();
+        let bytes = STANDARD
+            .decode(&snapshot.particles)
+            .map_err(|error| error.to_string())?;
+        let particles = ParticleSnapshot::decode(&bytes)?;
+        Ok((snapshot, particles))
+    }
+}
+
+#[derive(Resource, Default)]
+struct Autosave {
+    /// Sequence number of the next autosave.
+    sequence: u64,
+    timer: Option<Timer>,
+    /// The previous session's newest autosave and its particles, until
+    /// restored or dismissed.
+    offer: Option<(AutosaveSnapshot, ParticleSnapshot)>,
+    restore: bool,
+    /// Older autosaves have been read, so new ones continue their sequence.
+    ready: bool,
+}
+
+/// Picks the newest of the stored autosaves, skipping any that a crash left
+/// unreadable. Without a UI nobody could answer the offer, so none is made.
+fn receive_autosaves(
+    headless: Option<Res<Headless>>,
+    mut autosave: ResMut<Autosave>,
+    mut store: NonSendMut<store::AutosaveStore>,
+) {
+    let Some(texts) = store.take_loaded() else {
+        return;
+    };
+    let newest = texts
+        .iter()
+        .filter_map(|text| match AutosaveSnapshot::parse(text) {
+            Ok(loaded) => Some(loaded),
+            Err(error) => {
+                warn!("Ignoring unreadable autosave: {}", error);
+                None
+            }
+        })
+        .max_by_key(|(snapshot, _)| snapshot.sequence);
+    if let Some((snapshot, particles)) = newest {
+        autosave.sequence = snapshot.sequence + 1;
+        if headless.is_none() && !particles.is_empty() {
+            autosave.offer = Some((snapshot, particles));
+        }
+    }
+    autosave.ready = true;
+}
+
+fn write_autosave(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    particle_system: Res<ParticleSystem>,
+    remote: Option<Res<RemoteWorld>>,
+    mut autosave: ResMut<Autosave>,
+    mut store: NonSendMut<store::AutosaveStore>,
+    particles: Query<(&Transform, &Particle, &Velocity)>,
+) {
+    // Saving before the offer is settled could replace the offered world as
+    // the newest, and a shared world client mirrors one saved elsewhere
+    if !autosave.ready
+        || autosave.offer.is_some()
+        || settings.autosave_minutes <= 0.0
+        || remote.is_some()
+    {
+        return;
+    }
+    let interval = settings.autosave_minutes * 60.0;
+    let timer = autosave
+        .timer
+        .get_or_insert_with(|| Timer::from_seconds(interval, TimerMode::Repeating));
+    if timer.duration().as_secs_f32() != interval {
+        timer.set_duration(std::time::Duration::from_secs_f32(interval));
+    }
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let mut saved = ParticleSnapshot::default();
+    for (transform, particle, velocity) in &particles {
+        saved.push(
+            transform.translation.truncate(),
+            particle.color_id,
+            Some(velocity.0),
+        );
+    }
+    let snapshot = AutosaveSnapshot {
+        sequence: autosave.sequence,
+        session_time: time.elapsed_secs(),
+        particle_system: particle_system.clone(),
+        particles: STANDARD.encode(saved.encode()),
+    };
+    match ron::to_string(&snapshot) {
+        Ok(text) => {
+            store.save((snapshot.sequence % ROTATION) as usize, text);
+            autosave.sequence += 1;
+        }
+        Err(error) => warn!("Failed to serialize autosave: {}", error),
+    }
+}
+
+fn restore_ui_system(mut contexts: EguiContexts, mut autosave: ResMut<Autosave>) {
+    let Some((snapshot, particles)) = &autosave.offer else {
+        return;
+    };
+    let mut restore = false;
+    let mut dismiss = false;
+    egui::Window::new("Restore Autosave")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("The last session left an autosave behind:");
+            ui.label(format!(
+                "{} particles, {} species, after {:.0} minutes",
+                particles.len(),
+                snapshot.particle_system.colors.len(),
+                snapshot.session_time / 60.0
+            ));
+            ui.horizontal(|ui| {
+                restore = ui.button("Restore").clicked();
+                dismiss = ui.button("Start Fresh").clicked();
+            });
+        });
+    if restore {
+        autosave.restore = true;
+    } else if dismiss {
+        autosave.offer = None;
+    }
+}
+
+fn apply_restore(
+    mut spawner: ParticleSpawner,
+    mut autosave: ResMut<Autosave>,
+    mut particle_system: ResMut<ParticleSystem>,
+    mut particle_count: ResMut<ParticleCount>,
+    particles: Query<Entity, With<Particle>>,
+) {
+    if !autosave.restore {
+        return;
+    }
+    autosave.restore = false;
+    let Some((snapshot, saved)) = autosave.offer.take() else {
+        return;
+    };
+    for entity in &particles {
+        spawner.despawn(entity);
+    }
+    particle_count.count = saved.len();
+    *particle_system = snapshot.particle_system;
+    for (pos, color_id, velocity) in saved.iter() {
+        let Some(&color) = particle_system.colors.get(color_id) else {
+            continue;
+        };
+        spawner
+            .spawn(pos, color_id, color)
+            .insert(Velocity(velocity));
+    }
+    info!("Restored autosave {}", snapshot.sequence);
+}
+
+/// Rotating autosave files in `autosave/`, each written to a temporary file
+/// first and renamed into place so a crash mid-write cannot corrupt it.
+#[cfg(not(target_arch = "wasm32"))]
+mod store {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        sync::mpsc,
+        thread,
+    };
+
+    const AUTOSAVE_DIR: &str = "autosave";
+
+    pub struct AutosaveStore {
+        loaded: Option<Vec<String>>,
+        /// Writes happen on a background thread so large worlds do not stall
+        /// a frame.
+        writes: mpsc::Sender<(usize, String)>,
+    }
+
+    impl Default for AutosaveStore {
+        fn default() -> Self {
+            let loaded = fs::read_dir(AUTOSAVE_DIR)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+                .filter_map(|path| fs::read_to_string(path).ok())
+                .collect();
+            let (writes, queued) = mpsc::channel::<(usize, String)>();
+            thread::spawn(move || {
+                for (slot, text) in queued {
+                    if let Err(error) = write(slot, &text) {
+                        bevy::log::warn!("Failed to write autosave: {}", error);
+                    }
+                }
+            });
+            AutosaveStore {
+                loaded: Some(loaded),
+                writes,
+            }
+        }
+    }
+
+    fn path(slot: usize) -> PathBuf {
+        Path::new(AUTOSAVE_DIR).join(format!("autosave_{}.ron", slot + 1))
+    }
+
+    fn write(slot: usize, text: &str) -> std::io::Result<()> {
+        fs::create_dir_all(AUTOSAVE_DIR)?;
+        let temporary = path(slot).with_extension("ron.tmp");
+        fs::write(&temporary, text)?;
+        fs::rename(temporary, path(slot))
+    }
+
+    impl AutosaveStore {
+        /// Texts of the stored autosaves, once they have been read.
+        pub fn take_loaded(&mut self) -> Option<Vec<String>> {
+            self.loaded.take()
+        }
+
+        pub fn save(&mut self, slot: usize, text: String) {
+            let _ = self.writes.send((slot, text));
+        }
+    }
+}
+
+/// Autosaves in an IndexedDB object store, whose transactions either
+/// complete or leave the previous value. Snapshots outgrow localStorage.
+#[cfg(target_arch = "wasm32")]
+mod store {
+    use std::{cell::RefCell, rc::Rc};
+
+    use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+    use wasm_bindgen_futures::{spawn_local, JsFuture};
+    use web_sys::{IdbDatabase, IdbRequest, IdbTransactionMode};
+
+    use super::ROTATION;
+
+    const DATABASE: &str = "particle_life";
+    const STORE: &str = "autosave";
+
+    pub struct AutosaveStore {
+        loaded: Rc<RefCell<Option<Vec<String>>>>,
+    }
+
+    impl Default for AutosaveStore {
+        fn default() -> Self {
+            let loaded = Rc::new(RefCell::new(None));
+            let result = loaded.clone();
+            spawn_local(async move {
+                let texts = match load_all().await {
+                    Ok(texts) => texts,
+                    Err(error) => {
+                        bevy::log::warn!("Could not read autosaves: {:?}", error);
+                        Vec::new()
+                    }
+                };
+                *result.borrow_mut() = Some(texts);
+            });
+            AutosaveStore { loaded }
+        }
+    }
+
+    impl AutosaveStore {
+        /// Texts of the stored autosaves, once they have been read.
+        pub fn take_loaded(&mut self) -> Option<Vec<String>> {
+            self.loaded.borrow_mut().take()
+        }
+
+        pub fn save(&mut self, slot: usize, text: String) {
+            spawn_local(async move {
+                if let Err(error) = store(slot, &text).await {
+                    bevy::log::warn!("Failed to write autosave: {:?}", error);
+                }
+            });
+        }
+    }
+
+    /// Resolves with the request's result once it succeeds.
+    async fn finish(request: &IdbRequest) -> Result<JsValue, JsValue> {
+        let promise = js_sys::Promise::new(&mut |resolve, reject| {
+            let target = request.clone();
+            let on_success = Closure::once_into_js(move || {
+                let result = target.result().unwrap_or(JsValue::UNDEFINED);
+                let _ = resolve.call1(&JsValue::NULL, &result);
+            });
+            let on_error = Closure::once_into_js(move || {
+                let _ = reject.call0(&JsValue::NULL);
+            });
+            request.set_onsuccess(Some(on_success.unchecked_ref()));
+            request.set_onerror(Some(on_error.unchecked_ref()));
+        });
+        JsFuture::from(promise).await
+    }
+
+    async fn open() -> Result<IdbDatabase, JsValue> {
+        let factory = web_sys::window()
+            .ok_or(JsValue::NULL)?
+            .indexed_db()?
+            .ok_or(JsValue::NULL)?;
+        let request = factory.open_with_u32(DATABASE, 1)?;
+        let upgrading = request.clone();
+        let on_upgrade = Closure::once_into_js(move || {
+            if let Ok(database) = upgrading.result() {
+                let database: IdbDatabase = database.unchecked_into();
+                let _ = database.create_object_store(STORE);
+            }
+        });
+        request.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+        Ok(finish(&request).await?.unchecked_into())
+    }
+
+    async fn load_all() -> Result<Vec<String>, JsValue> {
+        let database = open().await?;
+        let mut texts = Vec::new();
+        for slot in 0..ROTATION {
+            let request = database
+                .transaction_with_str(STORE)?
+                .object_store(STORE)?
+                .get(&JsValue::from(slot as u32))?;
+            if let Some(text) = finish(&request).await?.as_string() {
+                texts.push(text);
+            }
+        }
+        Ok(texts)
+    }
+
+    async fn store(slot: usize, text: &str) -> Result<(), JsValue> {
+        let database = open().await?;
+        let request = database
+            .transaction_with_str_and_mode(STORE, IdbTransactionMode::Readwrite)?
+            .object_store(STORE)?
+            .put_with_key(&JsValue::from_str(text), &JsValue::from(slot as u32))?;
+        finish(&request).await.map(|_| ())
+    }
+}
@@ -0,0 +1,571 @@
+use bevy::{
+    prelude::*,
+    render::renderer::{RenderAdapter, RenderDevice, RenderQueue},
+    utils::Instant,
+};
+use bevy_egui::{egui, EguiContexts};
+use rayon::prelude::*;
+use std::{collections::HashMap, time::Duration};
+
+use crate::{
+    gpu::{GpuNeighborSearch, MAX_NEIGHBORS},
+    quadtree::QuadTree,
+    settings::Settings,
+    ui_enabled,
+};
+
+const TIMING_LOG_INTERVAL: f32 = 5.0;
+const TIMING_SMOOTHING: f32 = 0.1;
+/// How far past the radius the GPU looks, as a fraction of it.
+const GPU_SEARCH_MARGIN: f32 = 0.25;
+
+pub struct BackendPlugin;
+
+impl Plugin for BackendPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SimulationBackends::default())
+            .add_systems(
+                Update,
+                (
+                    add_gpu_backend,
+                    cycle_backend,
+                    log_backend_timings,
+                    backend_ui_system.run_if(ui_enabled),
+                ),
+            );
+    }
+}
+
+/// Neighbor search and force accumulation strategy used by `update_particles`.
+pub trait SimulationBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Called once per tick, before any `accumulate`, with the current positions.
+    fn prepare(&mut self, positions: &[Vec2], species: &[usize], radius: f32);
+
+    /// Sums `force(distance / radius, other_species) * direction` over the
+    /// neighbors of `pos`, returning the total and the number of contributors.
+    fn accumulate(
+        &mut self,
+        pos: Vec2,
+        radius: f32,
+        positions: &[Vec2],
+        species: &[usize],
+        force: &dyn Fn(f32, usize) -> f32,
+    ) -> (Vec2, f32);
+
+    /// [`accumulate`](Self::accumulate) for every position in `queries`,
+    /// with `force` also given the index of the query it is for. Results
+    /// replace the contents of `results`, in query order; backends may spread
+    /// the work over threads.
+    fn accumulate_batch(
+        &mut self,
+        queries: &[Vec2],
+        radius: f32,
+        positions: &[Vec2],
+        species: &[usize],
+        force: &(dyn Fn(usize, f32, usize) -> f32 + Sync),
+        results: &mut Vec<(Vec2, f32)>,
+    ) {
+        results.clear();
+        for (query, &pos) in queries.iter().enumerate() {
+            results.push(
+                self.accumulate(pos, radius, positions, species, &|distance, other| {
+                    force(query, distance, other)
+                }),
+            );
+        }
+    }
+
+    /// Backend specific settings shown in the backend window.
+    fn settings_ui(&mut self, _ui: &mut egui::Ui) {}
+}
+
+/// Whether the parallel backend can use more than one thread. Browsers only
+/// share memory with web workers on cross-origin isolated pages, and only
+/// builds with the `wasm-threads` feature start the worker pool.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn threads_available() -> bool {
+    true
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm-threads"))]
+pub fn threads_available() -> bool {
+    let Some(window) = web_sys::window() else {
+        return false;
+    };
+    js_sys::Reflect::get(&window, &"crossOriginIsolated".into())
+        .map(|isolated| isolated.is_truthy())
+        .unwrap_or(false)
+}
+
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm-threads")))]
+pub fn threads_available() -> bool {
+    false
+}
+
+/// Exact O(n²) reference implementation.
+pub struct NaiveBackend;
+
+impl SimulationBackend for NaiveBackend {
+    fn name(&self) -> &'static str {
+        "Naive"
+    }
+
+    fn prepare(&mut self, _positions: &[Vec2], _species: &[usize], _radius: f32) {}
+
+    fn accumulate(
+        &mut self,
+        pos: Vec2,
+        radius: f32,
+        positions: &[Vec2],
+        species: &[usize],
+        force: &dyn Fn(f32, usize) -> f32,
+    ) -> (Vec2, f32) {
+        let mut total = Vec2::ZERO;
+        let mut count = 0.0;
+        for (&other_pos, &other_species) in positions.iter().zip(species) {
+            if other_pos == pos {
+                continue;
+            }
+            let to_other = other_pos - pos;
+            let distance = to_other.length() / radius;
+            if distance < 1.0 {
+                total += to_other.normalize() * force(distance, other_species);
+                count += 1.0;
+            }
+        }
+        (total, count)
+    }
+}
+
+/// Uniform grid with cells the size of the attraction radius.
+#[derive(Default)]
+pub struct GridBackend {
+    cell_size: f32,
+    /// Spatial hash of buffer indices; cell vectors are cleared, not dropped.
+    grid: HashMap<(i32, i32), Vec<u32>>,
+}
+
+impl GridBackend {
+    fn cell(&self, pos: Vec2) -> (i32, i32) {
+        (
+            (pos.x / self.cell_size).floor() as i32,
+            (pos.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Read-only neighbor sum, so several threads can share the grid.
+    fn sum(
+        &self,
+        pos: Vec2,
+        radius: f32,
+        positions: &[Vec2],
+        species: &[usize],
+        force: &dyn Fn(f32, usize) -> f32,
+    ) -> (Vec2, f32) {
+        let (cell_x, cell_y) = self.cell(pos);
+        let mut total = Vec2::ZERO;
+        let mut count = 0.0;
+
+        // Check neighboring cells
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let Some(cell_particles) = self.grid.get(&(cell_x + dx, cell_y + dy)) else {
+                    continue;
+                };
+                for &other in cell_particles {
+                    let other_pos = positions[other as usize];
+                    if pos == other_pos {
+                        continue;
+                    }
+                    let to_other = other_pos - pos;
+                    let distance = to_other.length() / radius;
+                    if distance < 1.0 {
+                        total += to_other.normalize() * force(distance, species[other as usize]);
+                        count += 1.0;
+                    }
+                }
+            }
+        }
+        (total, count)
+    }
+}
+
+/// The grid backend with the force pass spread over every core, through web
+/// workers in browsers that allow it. Falls back to one thread otherwise.
+#[derive(Default)]
+pub struct ParallelGridBackend {
+    grid: GridBackend,
+}
+
+impl SimulationBackend for ParallelGridBackend {
+    fn name(&self) -> &'static str {
+        "Parallel Grid"
+    }
+
+    fn prepare(&mut self, positions: &[Vec2], species: &[usize], radius: f32) {
+        self.grid.prepare(positions, species, radius);
+    }
+
+    fn accumulate(
+        &mut self,
+        pos: Vec2,
+        radius: f32,
+        positions: &[Vec2],
+        species: &[usize],
+        force: &dyn Fn(f32, usize) -> f32,
+    ) -> (Vec2, f32) {
+        self.grid.sum(pos, radius, positions, species, force)
+    }
+
+    fn accumulate_batch(
+        &mut self,
+        queries: &[Vec2],
+        radius: f32,
+        positions: &[Vec2],
+        species: &[usize],
+        force: &(dyn Fn(usize, f32, usize) -> f32 + Sync),
+        results: &mut Vec<(Vec2, f32)>,
+    ) {
+        let grid = &self.grid;
+        let sum = |(query, &pos): (usize, &Vec2)| {
+            grid.sum(pos, radius, positions, species, &|distance, other| {
+                force(query, distance, other)
+            })
+        };
+        if threads_available() {
+            queries
+                .par_iter()
+                .enumerate()
+                .map(sum)
+                .collect_into_vec(results);
+        } else {
+            results.clear();
+            results.extend(queries.iter().enumerate().map(sum));
+        }
+    }
+
+    fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        if !threads_available() {
+            ui.label(
+                "Running on one thread: browsers need a wasm-threads build on a \
+                 cross-origin isolated page.",
+            );
+        }
+    }
+}
+
+impl SimulationBackend for GridBackend {
+    fn name(&self) -> &'static str {
+        "Grid"
+    }
+
+    fn prepare(&mut self, positions: &[Vec2], _species: &[usize], radius: f32) {
+        self.cell_size = radius;
+        for cell in self.grid.values_mut() {
+            cell.clear();
+        }
+        // Drop stale cells once the map is mostly empty, e.g. after the radius changed
+        if self.grid.len() > positions.len().max(64) * 4 {
+            self.grid.retain(|_, cell| !cell.is_empty());
+        }
+        for (index, &pos) in positions.iter().enumerate() {
+            let cell = self.cell(pos);
+            self.grid.entry(cell).or_default().push(index as u32);
+        }
+    }
+
+    fn accumulate(
+        &mut self,
+        pos: Vec2,
+        radius: f32,
+        positions: &[Vec2],
+        species: &[usize],
+        force: &dyn Fn(f32, usize) -> f32,
+    ) -> (Vec2, f32) {
+        self.sum(pos, radius, positions, species, force)
+    }
+}
+
+/// Barnes–Hut approximation, worth it once the attraction radius covers a
+/// large part of the world.
+pub struct QuadTreeBackend {
+    tree: QuadTree,
+    /// Opening angle: larger values approximate more aggressively.
+    pub theta: f32,
+}
+
+impl Default for QuadTreeBackend {
+    fn default() -> Self {
+        QuadTreeBackend {
+            tree: QuadTree::default(),
+            theta: 0.5,
+        }
+    }
+}
+
+impl SimulationBackend for QuadTreeBackend {
+    fn name(&self) -> &'static str {
+        "Barnes–Hut"
+    }
+
+    fn prepare(&mut self, positions: &[Vec2], species: &[usize], _radius: f32) {
+        self.tree.build(positions, species);
+    }
+
+    fn accumulate(
+        &mut self,
+        pos: Vec2,
+        radius: f32,
+        positions: &[Vec2],
+        species: &[usize],
+        force: &dyn Fn(f32, usize) -> f32,
+    ) -> (Vec2, f32) {
+        self.tree
+            .accumulate(pos, radius, self.theta, positions, species, force)
+    }
+
+    fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.add(egui::Slider::new(&mut self.theta, 0.0..=1.5).text("theta"));
+    }
+}
+
+/// Lists neighbors in a compute shader and sums the forces from those lists
+/// on the CPU, so every force rule works unchanged. The shader searches a
+/// margin past the radius: on the web the lists arrive a tick or more late,
+/// and the margin still catches particles that moved into range since.
+/// Particles with more neighbors than a list holds, or that were not in the
+/// last search, are summed over every particle instead.
+pub struct GpuBackend {
+    search: GpuNeighborSearch,
+    /// Index of each position passed to `prepare`, by its bits.
+    index_of: HashMap<(u32, u32), usize>,
+    counts: Vec<u32>,
+    neighbors: Vec<u32>,
+    /// Number of particles the current lists were made for.
+    listed: usize,
+}
+
+impl GpuBackend {
+    pub fn new(search: GpuNeighborSearch) -> Self {
+        GpuBackend {
+            search,
+            index_of: HashMap::new(),
+            counts: Vec::new(),
+            neighbors: Vec::new(),
+            listed: 0,
+        }
+    }
+
+    /// The neighbor list of the particle at `pos`, if one is complete.
+    fn list(&self, pos: Vec2, particles: usize) -> Option<&[u32]> {
+        if self.listed != particles {
+            return None;
+        }
+        let index = *self.index_of.get(&(pos.x.to_bits(), pos.y.to_bits()))?;
+        let found = self.counts[index] as usize;
+        let start = index * MAX_NEIGHBORS;
+        (found <= MAX_NEIGHBORS).then(|| &self.neighbors[start..start + found])
+    }
+}
+
+impl SimulationBackend for GpuBackend {
+    fn name(&self) -> &'static str {
+        "GPU"
+    }
+
+    fn prepare(&mut self, positions: &[Vec2], _species: &[usize], radius: f32) {
+        self.index_of.clear();
+        self.index_of.extend(
+            positions
+                .iter()
+                .enumerate()
+                .map(|(index, pos)| ((pos.x.to_bits(), pos.y.to_bits()), index)),
+        );
+        // Pick up a search that finished since the last tick, then start the
+        // next one, which natively is ready right away
+        if let Some(listed) = self.search.read(&mut self.counts, &mut self.neighbors) {
+            self.listed = listed;
+        }
+        self.search
+            .dispatch(positions, radius * (1.0 + GPU_SEARCH_MARGIN));
+        if let Some(listed) = self.search.read(&mut self.counts, &mut self.neighbors) {
+            self.listed = listed;
+        }
+    }
+
+    fn accumulate(
+        &mut self,
+        pos: Vec2,
+        radius: f32,
+        positions: &[Vec2],
+        species: &[usize],
+        force: &dyn Fn(f32, usize) -> f32,
+    ) -> (Vec2, f32) {
+        let Some(list) = self.list(pos, positions.len()) else {
+            return NaiveBackend.accumulate(pos, radius, positions, species, force);
+        };
+        let mut total = Vec2::ZERO;
+        let mut count = 0.0;
+        for &other in list {
+            let other_pos = positions[other as usize];
+            if pos == other_pos {
+                continue;
+            }
+            let to_other = other_pos - pos;
+            let distance = to_other.length() / radius;
+            if distance < 1.0 {
+                total += to_other.normalize() * force(distance, species[other as usize]);
+                count += 1.0;
+            }
+        }
+        (total, count)
+    }
+}
+
+/// Index of the grid backend in [`SimulationBackends`].
+pub const GRID: usize = 0;
+/// Index of the parallel grid backend in [`SimulationBackends`].
+pub const PARALLEL_GRID: usize = 3;
+
+/// All available backends, the active one, and smoothed tick timings per backend.
+#[derive(Resource)]
+pub struct SimulationBackends {
+    backends: Vec<Box<dyn SimulationBackend>>,
+    timings: Vec<Option<Duration>>,
+    pub active: usize,
+    log_timer: Timer,
+}
+
+impl Default for SimulationBackends {
+    fn default() -> Self {
+        let backends: Vec<Box<dyn SimulationBackend>> = vec![
+            Box::new(GridBackend::default()),
+            Box::new(NaiveBackend),
+            Box::new(QuadTreeBackend::default()),
+            Box::new(ParallelGridBackend::default()),
+        ];
+        SimulationBackends {
+            timings: vec![None; backends.len()],
+            backends,
+            active: 0,
+            log_timer: Timer::from_seconds(TIMING_LOG_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+impl SimulationBackends {
+    pub fn active_mut(&mut self) -> &mut dyn SimulationBackend {
+        self.backends[self.active].as_mut()
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.backends.iter().map(|backend| backend.name()).collect()
+    }
+
+    pub fn active_name(&self) -> &'static str {
+        self.backends[self.active].name()
+    }
+
+    /// Starts timing a tick of the active backend.
+    pub fn start_timing(&self) -> Instant {
+        Instant::now()
+    }
+
+    pub fn finish_timing(&mut self, start: Instant) {
+        let elapsed = start.elapsed();
+        let timing = &mut self.timings[self.active];
+        *timing = Some(match *timing {
+            Some(previous) => {
+                previous.mul_f32(1.0 - TIMING_SMOOTHING) + elapsed.mul_f32(TIMING_SMOOTHING)
+            }
+            None => elapsed,
+        });
+    }
+
+    pub fn add(&mut self, backend: Box<dyn SimulationBackend>) {
+        self.backends.push(backend);
+        self.timings.push(None);
+    }
+
+    pub fn select(&mut self, index: usize) {
+        if index != self.active && index < self.backends.len() {
+            self.active = index;
+            info!("Switched simulation backend to {}", self.active_name());
+        }
+    }
+}
+
+/// Adds the GPU backend once the renderer is up, which on the web is a few
+/// frames after startup.
+fn add_gpu_backend(
+    mut backends: ResMut<SimulationBackends>,
+    device: Option<Res<RenderDevice>>,
+    queue: Option<Res<RenderQueue>>,
+    adapter: Option<Res<RenderAdapter>>,
+    mut added: Local<bool>,
+) {
+    if *added {
+        return;
+    }
+    let (Some(device), Some(queue), Some(adapter)) = (device, queue, adapter) else {
+        return;
+    };
+    *added = true;
+    match GpuNeighborSearch::new(device.clone(), queue.clone(), &adapter) {
+        Some(search) => backends.add(Box::new(GpuBackend::new(search))),
+        None => info!("No compute shader support, the GPU backend is unavailable"),
+    }
+}
+
+fn cycle_backend(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    mut backends: ResMut<SimulationBackends>,
+) {
+    if keyboard.just_pressed(settings.keys.cycle_backend) {
+        let next = (backends.active + 1) % backends.backends.len();
+        backends.select(next);
+    }
+}
+
+fn log_backend_timings(time: Res<Time>, mut backends: ResMut<SimulationBackends>) {
+    if !backends.log_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    for (backend, timing) in backends.backends.iter().zip(&backends.timings) {
+        if let Some(timing) = timing {
+            info!(
+                "Backend {}: {:.2} ms per tick",
+                backend.name(),
+                timing.as_secs_f64() * 1000.0
+            );
+        }
+    }
+}
+
+fn backend_ui_system(
+    mut contexts: EguiContexts,
+    settings: Res<Settings>,
+    mut backends: ResMut<SimulationBackends>,
+) {
+    egui::Window::new("Simulation Backend")
+        .default_pos([400.0, 380.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let mut selected = backends.active;
+            for index in 0..backends.backends.len() {
+                let timing = backends.timings[index].map_or_else(
+                    || "not run yet".to_string(),
+                    |timing| format!("{:.2} ms", timing.as_secs_f64() * 1000.0),
+                );
+                let label = format!("{} ({})", backends.backends[index].name(), timing);
+                ui.radio_value(&mut selected, index, label);
+            }
+            backends.select(selected);
+            ui.label(format!("{:?} cycles backends", settings.keys.cycle_backend));
+            ui.separator();
+            backends.active_mut().settings_ui(ui);
+        });
+}
@@ -0,0 +1,225 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+use bevy_egui::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    lod,
+    particle_assets::{ParticleAssets, ParticleMaterial},
+    settings::Settings,
+    MainCamera, ParticleSystem,
+};
+
+/// Behind every particle.
+const BACKGROUND_Z: f32 = -10.0;
+/// Smallest Oklab lightness difference kept between a species and the
+/// background when contrast adjustment is on.
+const MIN_LIGHTNESS_GAP: f32 = 0.3;
+
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_gradient_sprite).add_systems(
+            Update,
+            (apply_background, follow_camera, apply_contrast).chain(),
+        );
+    }
+}
+
+/// Window background, persisted in [`Settings`]. Colors are linear RGB as
+/// edited by egui's color pickers.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub enum BackgroundTheme {
+    #[default]
+    Black,
+    White,
+    Gradient {
+        top: [f32; 3],
+        bottom: [f32; 3],
+    },
+    Custom([f32; 3]),
+}
+
+impl BackgroundTheme {
+    fn label(&self) -> &'static str {
+        match self {
+            BackgroundTheme::Black => "Black",
+            BackgroundTheme::White => "White",
+            BackgroundTheme::Gradient { .. } => "Gradient",
+            BackgroundTheme::Custom(_) => "Custom",
+        }
+    }
+
+    /// Colors at the top and bottom of the screen.
+    fn colors(&self) -> (Color, Color) {
+        let linear = |[r, g, b]: [f32; 3]| Color::linear_rgb(r, g, b);
+        match *self {
+            BackgroundTheme::Black => (Color::BLACK, Color::BLACK),
+            BackgroundTheme::White => (Color::WHITE, Color::WHITE),
+            BackgroundTheme::Gradient { top, bottom } => (linear(top), linear(bottom)),
+            BackgroundTheme::Custom(color) => (linear(color), linear(color)),
+        }
+    }
+
+    /// Average Oklab lightness of the background.
+    pub fn lightness(&self) -> f32 {
+        let (top, bottom) = self.colors();
+        (Oklaba::from(top).lightness + Oklaba::from(bottom).lightness) / 2.0
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        let mut selected = self.label();
+        egui::ComboBox::from_label("Background")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for label in ["Black", "White", "Gradient", "Custom"] {
+                    ui.selectable_value(&mut selected, label, label);
+                }
+            });
+        if selected != self.label() {
+            *self = match selected {
+                "White" => BackgroundTheme::White,
+                "Gradient" => BackgroundTheme::Gradient {
+                    top: [0.02, 0.03, 0.08],
+                    bottom: [0.0, 0.0, 0.0],
+                },
+                "Custom" => BackgroundTheme::Custom([0.02, 0.02, 0.02]),
+                _ => BackgroundTheme::Black,
+            };
+        }
+        match self {
+            BackgroundTheme::Gradient { top, bottom } => {
+                ui.horizontal(|ui| {
+                    ui.label("Top");
+                    ui.color_edit_button_rgb(top);
+                    ui.label("Bottom");
+                    ui.color_edit_button_rgb(bottom);
+                });
+            }
+            BackgroundTheme::Custom(color) => {
+                ui.color_edit_button_rgb(color);
+            }
+            BackgroundTheme::Black | BackgroundTheme::White => {}
+        }
+    }
+}
+
+/// `color` with its lightness pushed away from the background's, so light
+/// species stay visible on light backgrounds and dark ones on dark.
+pub fn display_color(color: Color, background_lightness: f32) -> Color {
+    let mut oklab = Oklaba::from(color);
+    if (oklab.lightness - background_lightness).abs() < MIN_LIGHTNESS_GAP {
+        oklab.lightness = if background_lightness > 0.5 {
+            (background_lightness - MIN_LIGHTNESS_GAP).max(0.0)
+        } else {
+            (background_lightness + MIN_LIGHTNESS_GAP).min(1.0)
+        };
+    }
+    oklab.into()
+}
+
+/// A two pixel texture stretched over the view; linear filtering turns it
+/// into a vertical gradient.
+#[derive(Component)]
+struct GradientSprite;
+
+fn spawn_gradient_sprite(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let image = images.add(Image::new_fill(
+        Extent3d {
+            width: 1,
+            height: 2,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ));
+    commands.spawn((
+        Sprite::from_image(image),
+        Transform::from_xyz(0.0, 0.0, BACKGROUND_Z),
+        Visibility::Hidden,
+        GradientSprite,
+    ));
+}
+
+fn apply_background(
+    settings: Res<Settings>,
+    mut clear_color: ResMut<ClearColor>,
+    mut images: ResMut<Assets<Image>>,
+    mut sprites: Query<(&Sprite, &mut Visibility), With<GradientSprite>>,
+    mut applied: Local<Option<BackgroundTheme>>,
+) {
+    let theme = settings.background;
+    // The sprite is spawned at startup, so also wait for it before applying
+    if *applied == Some(theme) || sprites.is_empty() {
+        return;
+    }
+    *applied = Some(theme);
+
+    let (top, bottom) = theme.colors();
+    clear_color.0 = bottom;
+    for (sprite, mut visibility) in &mut sprites {
+        let gradient = matches!(theme, BackgroundTheme::Gradient { .. });
+        visibility.set_if_neq(if gradient {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        });
+        if let Some(image) = images.get_mut(&sprite.image) {
+            image.data = [top, bottom]
+                .iter()
+                .flat_map(|color| color.to_srgba().to_u8_array())
+                .collect();
+        }
+    }
+}
+
+type GradientSprites<'w, 's> = Query<
+    'w,
+    's,
+    (&'static mut Transform, &'static mut Sprite),
+    (With<GradientSprite>, Without<Camera>),
+>;
+
+fn follow_camera(
+    camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    mut sprites: GradientSprites,
+) {
+    let Ok((camera_transform, projection)) = camera.get_single() else {
+        return;
+    };
+    let view = lod::camera_view(camera_transform, projection);
+    for (mut transform, mut sprite) in &mut sprites {
+        transform.translation = view.center().extend(BACKGROUND_Z);
+        sprite.custom_size = Some(view.size());
+    }
+}
+
+/// Sets the species materials to the contrast adjusted palette.
+fn apply_contrast(
+    settings: Res<Settings>,
+    particle_system: Res<ParticleSystem>,
+    mut assets: ResMut<ParticleAssets>,
+    mut materials: ResMut<Assets<ParticleMaterial>>,
+) {
+    let lightness = settings.background.lightness();
+    let palette: Vec<Color> = particle_system
+        .colors
+        .iter()
+        .map(|&color| {
+            if settings.contrast_adjust {
+                display_color(color, lightness)
+            } else {
+                color
+            }
+        })
+        .collect();
+    assets.set_colors(&palette, &mut materials);
+}
@@ -0,0 +1,279 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{replay, ui_enabled, update_particles, Particle, ParticleSystem};
+
+/// Stiffness is given per 1/60 s, like species drag, so it does not depend
+/// on the frame rate.
+const REFERENCE_RATE: f32 = 60.0;
+const BOND_ALPHA: f32 = 0.6;
+
+pub struct BondsPlugin;
+
+impl Plugin for BondsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Bonds>().add_systems(
+            Update,
+            (
+                update_bonds
+                    .after(update_particles)
+                    .run_if(not(replay::is_playing_back)),
+                bonds_ui_system.run_if(ui_enabled),
+            ),
+        );
+    }
+}
+
+/// Which species pairs may bond.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum BondPairing {
+    /// Pairs that attract each other both ways in the behavior matrix.
+    #[default]
+    MutualAttraction,
+    SameSpecies,
+    All,
+}
+
+impl BondPairing {
+    const ALL: [BondPairing; 3] = [
+        BondPairing::MutualAttraction,
+        BondPairing::SameSpecies,
+        BondPairing::All,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            BondPairing::MutualAttraction => "Mutual attraction",
+            BondPairing::SameSpecies => "Same species",
+            BondPairing::All => "Any species",
+        }
+    }
+
+    fn allows(self, particle_system: &ParticleSystem, a: usize, b: usize) -> bool {
+        match self {
+            BondPairing::MutualAttraction => {
+                particle_system.get_behavior(a, b) > 0.0 && particle_system.get_behavior(b, a) > 0.0
+            }
+            BondPairing::SameSpecies => a == b,
+            BondPairing::All => true,
+        }
+    }
+}
+
+/// When particles bond and how the bonds behave. Part of the rules, so
+/// presets and saves carry it.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BondRules {
+    pub enabled: bool,
+    pub pairing: BondPairing,
+    /// Distance in world units two particles must stay within to bond.
+    pub distance: f32,
+    /// Consecutive ticks a pair must stay close before it bonds.
+    pub ticks: u32,
+    /// Fraction of a bond's stretch corrected every 1/60 s.
+    pub stiffness: f32,
+    /// Bonds break once stretched beyond this multiple of their rest length.
+    pub break_stretch: f32,
+    pub max_per_particle: usize,
+}
+
+impl Default for BondRules {
+    fn default() -> Self {
+        BondRules {
+            enabled: false,
+            pairing: BondPairing::default(),
+            distance: 8.0,
+            ticks: 30,
+            stiffness: 0.3,
+            break_stretch: 3.0,
+            max_per_particle: 2,
+        }
+    }
+}
+
+struct Bond {
+    a: Entity,
+    b: Entity,
+    rest: f32,
+}
+
+/// Live bonds and how long close pairs have been waiting to bond.
+#[derive(Resource, Default)]
+pub struct Bonds {
+    bonds: Vec<Bond>,
+    contacts: HashMap<(Entity, Entity), u32>,
+}
+
+/// Forms bonds between pairs that stayed close, pulls bonded particles toward
+/// their rest length, breaks overstretched bonds and draws the rest.
+/// Particles by grid cell, with their position and species.
+type ContactGrid = HashMap<(i32, i32), Vec<(Entity, Vec2, usize)>>;
+
+fn update_bonds(
+    time: Res<Time>,
+    particle_system: Res<ParticleSystem>,
+    mut bonds: ResMut<Bonds>,
+    mut gizmos: Gizmos,
+    mut particles: Query<(Entity, &mut Transform, Ref<Particle>)>,
+) {
+    let rules = &particle_system.bonds;
+    if !rules.enabled {
+        if !bonds.bonds.is_empty() || !bonds.contacts.is_empty() {
+            bonds.bonds.clear();
+            bonds.contacts.clear();
+        }
+        return;
+    }
+    let bonds = &mut *bonds;
+
+    // Drop bonds to particles that are gone or whose entity was reused
+    bonds.bonds.retain(|bond| {
+        particles
+            .get_many([bond.a, bond.b])
+            .is_ok_and(|[(_, _, a), (_, _, b)]| !a.is_added() && !b.is_added())
+    });
+    let mut bond_counts: HashMap<Entity, usize> = HashMap::new();
+    for bond in &bonds.bonds {
+        *bond_counts.entry(bond.a).or_default() += 1;
+        *bond_counts.entry(bond.b).or_default() += 1;
+    }
+
+    // Count how long each close, compatible pair has been in contact
+    let distance = rules.distance.max(1.0);
+    let mut grid: ContactGrid = HashMap::new();
+    for (entity, transform, particle) in &particles {
+        let pos = transform.translation.truncate();
+        let cell = (pos / distance).floor().as_ivec2();
+        grid.entry((cell.x, cell.y))
+            .or_default()
+            .push((entity, pos, particle.color_id));
+    }
+    let free = |entity: &Entity, counts: &HashMap<Entity, usize>| {
+        counts.get(entity).copied().unwrap_or(0) < rules.max_per_particle
+    };
+    let mut contacts = HashMap::new();
+    for (&(x, y), cell) in &grid {
+        for &(a, pos_a, species_a) in cell {
+            if !free(&a, &bond_counts) {
+                continue;
+            }
+            for neighbor in [
+                (x, y),
+                (x + 1, y - 1),
+                (x + 1, y),
+                (x + 1, y + 1),
+                (x, y + 1),
+            ] {
+                let Some(other_cell) = grid.get(&neighbor) else {
+                    continue;
+                };
+                for &(b, pos_b, species_b) in other_cell {
+                    // Pairs within one cell are visited from both sides
+                    if neighbor == (x, y) && b <= a {
+                        continue;
+                    }
+                    if pos_a.distance(pos_b) > distance
+                        || !free(&b, &bond_counts)
+                        || !rules.pairing.allows(&particle_system, species_a, species_b)
+                    {
+                        continue;
+                    }
+                    let key = (a.min(b), a.max(b));
+                    let ticks = bonds.contacts.get(&key).copied().unwrap_or(0) + 1;
+                    contacts.insert(key, ticks);
+                }
+            }
+        }
+    }
+
+    // Bond pairs that stayed close long enough, while both have room
+    for (&(a, b), &ticks) in &contacts {
+        if ticks < rules.ticks
+            || !free(&a, &bond_counts)
+            || !free(&b, &bond_counts)
+            || bonds.bonds.iter().any(|bond| (bond.a, bond.b) == (a, b))
+        {
+            continue;
+        }
+        let Ok([(_, transform_a, _), (_, transform_b, _)]) = particles.get_many([a, b]) else {
+            continue;
+        };
+        let rest = transform_a
+            .translation
+            .truncate()
+            .distance(transform_b.translation.truncate());
+        bonds.bonds.push(Bond { a, b, rest });
+        *bond_counts.entry(a).or_default() += 1;
+        *bond_counts.entry(b).or_default() += 1;
+    }
+    contacts.retain(|&(a, b), _| free(&a, &bond_counts) && free(&b, &bond_counts));
+    bonds.contacts = contacts;
+
+    // Springs: move both ends toward the rest length
+    let blend =
+        1.0 - (1.0 - rules.stiffness.clamp(0.0, 1.0)).powf(time.delta_secs() * REFERENCE_RATE);
+    bonds.bonds.retain(|bond| {
+        let Ok([(_, mut transform_a, particle_a), (_, mut transform_b, particle_b)]) =
+            particles.get_many_mut([bond.a, bond.b])
+        else {
+            return false;
+        };
+        let (pos_a, pos_b) = (
+            transform_a.translation.truncate(),
+            transform_b.translation.truncate(),
+        );
+        let length = pos_a.distance(pos_b);
+        if length > bond.rest.max(1.0) * rules.break_stretch {
+            return false;
+        }
+        let correction = (pos_b - pos_a).normalize_or_zero() * (length - bond.rest) * blend / 2.0;
+        transform_a.translation += correction.extend(0.0);
+        transform_b.translation -= correction.extend(0.0);
+
+        let color_a = particle_system.colors.get(particle_a.color_id).copied();
+        let color_b = particle_system.colors.get(particle_b.color_id).copied();
+        let color = match (color_a, color_b) {
+            (Some(a), Some(b)) => a.mix(&b, 0.5),
+            _ => Color::WHITE,
+        };
+        gizmos.line_2d(
+            pos_a + correction,
+            pos_b - correction,
+            color.with_alpha(BOND_ALPHA),
+        );
+        true
+    });
+}
+
+fn bonds_ui_system(
+    mut contexts: EguiContexts,
+    mut particle_system: ResMut<ParticleSystem>,
+    bonds: Res<Bonds>,
+) {
+    egui::Window::new("Bonds")
+        .default_pos([400.0, 700.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Particles that stay close bond into springs that break when overstretched.");
+            ui.add_space(5.0);
+
+            let rules = &mut particle_system.bonds;
+            ui.checkbox(&mut rules.enabled, "Bonds");
+            egui::ComboBox::from_label("Pairs")
+                .selected_text(rules.pairing.label())
+                .show_ui(ui, |ui| {
+                    for pairing in BondPairing::ALL {
+                        ui.selectable_value(&mut rules.pairing, pairing, pairing.label());
+                    }
+                });
+            ui.add(egui::Slider::new(&mut rules.distance, 2.0..=40.0).text("distance"));
+            ui.add(egui::Slider::new(&mut rules.ticks, 1..=300).text("ticks to bond"));
+            ui.add(egui::Slider::new(&mut rules.stiffness, 0.0..=1.0).text("stiffness"));
+            ui.add(egui::Slider::new(&mut rules.break_stretch, 1.1..=10.0).text("break stretch"));
+            ui.add(egui::Slider::new(&mut rules.max_per_particle, 1..=6).text("max per particle"));
+            ui.label(format!("{} bonds", bonds.bonds.len()));
+        });
+}
@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+
+/// Index of a particle's slot in [`ParticleBuffers`].
+#[derive(Component, Default)]
+pub struct BufferIndex(pub usize);
+
+/// Persistent simulation storage. Forces are computed from `front` into
+/// `back`, the buffers are swapped, and Transforms are written from the
+/// result. Nothing here is reallocated per tick once capacity is reached,
+/// and Transforms are only read back when another system moved a particle.
+#[derive(Resource, Default)]
+pub struct ParticleBuffers {
+    pub front: Vec<Vec2>,
+    pub back: Vec<Vec2>,
+    pub species: Vec<usize>,
+    /// `front` and `species` without hidden species, the particles the
+    /// others can feel. Only filled while a species is hidden.
+    pub visible: Vec<Vec2>,
+    pub visible_species: Vec<usize>,
+    /// Per sub-step scratch: positions of the particles that move, the rule
+    /// zone each is in, and the force sum and neighbor count for each.
+    pub queries: Vec<Vec2>,
+    pub query_zones: Vec<Option<usize>>,
+    pub forces: Vec<(Vec2, f32)>,
+}
+
+impl ParticleBuffers {
+    pub fn len(&self) -> usize {
+        self.front.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.front.clear();
+        self.back.clear();
+        self.species.clear();
+    }
+
+    /// Appends a particle and returns its slot index.
+    pub fn push(&mut self, pos: Vec2, species: usize) -> usize {
+        self.front.push(pos);
+        self.back.push(pos);
+        self.species.push(species);
+        self.front.len() - 1
+    }
+
+    pub fn swap(&mut self) {
+        std::mem::swap(&mut self.front, &mut self.back);
+    }
+}
@@ -0,0 +1,365 @@
+use bevy::{
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+        renderer::RenderDevice,
+        view::screenshot::{save_to_disk, Screenshot},
+    },
+    time::TimeUpdateStrategy,
+};
+use bevy_egui::{egui, EguiContexts};
+use std::{path::PathBuf, time::Duration};
+
+use crate::{determinism, settings::Settings, ui_enabled, MainCamera};
+
+const CAPTURE_DIR: &str = "exports";
+const MAX_SIZE: u32 = 8192;
+const STILL_SCALES: std::ops::RangeInclusive<u32> = 4..=8;
+
+/// Renders the simulation offscreen at a chosen resolution, independent of
+/// the window. An image sequence steps the simulation by a fixed tick per
+/// frame instead of by wall time, so a slow machine still writes every frame
+/// of a smooth video; it only takes longer. A high-resolution still renders
+/// the current view at several times the window's resolution, for print.
+pub struct CapturePlugin;
+
+impl Plugin for CapturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Capture>().add_systems(
+            PostUpdate,
+            (
+                capture_ui_system.run_if(ui_enabled),
+                capture_sequence_frame,
+                render_still,
+            )
+                .chain(),
+        );
+    }
+}
+
+#[derive(Resource)]
+struct Capture {
+    width: u32,
+    height: u32,
+    /// Simulated seconds in an image sequence.
+    duration: f32,
+    /// Frames per simulated second, one tick each.
+    fps: u32,
+    sequence: Option<Sequence>,
+    /// Multiple of the window's resolution a still is rendered at.
+    still_scale: u32,
+    still_requested: bool,
+    still: Option<Still>,
+    message: Option<String>,
+}
+
+impl Default for Capture {
+    fn default() -> Self {
+        Capture {
+            width: 1920,
+            height: 1080,
+            duration: 10.0,
+            fps: 60,
+            sequence: None,
+            still_scale: 4,
+            still_requested: false,
+            still: None,
+            message: None,
+        }
+    }
+}
+
+/// An image sequence being written.
+struct Sequence {
+    dir: PathBuf,
+    target: OffscreenTarget,
+    frame: u32,
+    frames: u32,
+}
+
+/// A high-resolution still. It is captured the frame after its camera
+/// appears, and the camera removed the frame after that.
+struct Still {
+    target: OffscreenTarget,
+    path: PathBuf,
+    captured: bool,
+}
+
+/// A camera rendering the main camera's view into an image.
+struct OffscreenTarget {
+    camera: Entity,
+    image: Handle<Image>,
+}
+
+/// Marks offscreen capture cameras, which follow the main camera.
+#[derive(Component)]
+struct CaptureCamera;
+
+/// Spawns a camera showing what the main camera shows, rendered into a new
+/// `width` × `height` image.
+fn spawn_offscreen(
+    commands: &mut Commands,
+    images: &mut Assets<Image>,
+    main: (&Camera, &Transform, &OrthographicProjection),
+    width: u32,
+    height: u32,
+) -> OffscreenTarget {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Bgra8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    // Screenshots copy out of the texture
+    image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+        | TextureUsages::COPY_SRC
+        | TextureUsages::COPY_DST
+        | TextureUsages::RENDER_ATTACHMENT;
+    let image = images.add(image);
+
+    let (camera, transform, projection) = main;
+    // Same world width across the image as across the window
+    let view_width = camera
+        .logical_viewport_size()
+        .map_or(width as f32, |size| size.x);
+    let camera = commands
+        .spawn((
+            Camera2d,
+            Camera {
+                target: RenderTarget::Image(image.clone()),
+                order: -1,
+                ..default()
+            },
+            *transform,
+            OrthographicProjection {
+                scale: projection.scale * view_width / width as f32,
+                ..OrthographicProjection::default_2d()
+            },
+            CaptureCamera,
+        ))
+        .id();
+    OffscreenTarget { camera, image }
+}
+
+/// Time steps by a fixed tick per frame during a sequence, and goes back to
+/// what deterministic mode asks for afterwards.
+fn time_strategy(sequence_fps: Option<u32>, settings: &Settings) -> TimeUpdateStrategy {
+    match sequence_fps {
+        Some(fps) => TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / fps as f64)),
+        None if settings.deterministic => {
+            TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(determinism::FIXED_STEP))
+        }
+        None => TimeUpdateStrategy::Automatic,
+    }
+}
+
+fn unique_path(prefix: &str) -> PathBuf {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    PathBuf::from(CAPTURE_DIR).join(format!("{}_{}", prefix, stamp))
+}
+
+/// Writes the frame rendered last into the sequence and ends it after the
+/// last one.
+fn capture_sequence_frame(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut capture: ResMut<Capture>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+    main: Query<&Transform, (With<MainCamera>, Without<CaptureCamera>)>,
+    mut cameras: Query<&mut Transform, With<CaptureCamera>>,
+) {
+    let Some(sequence) = &mut capture.sequence else {
+        return;
+    };
+    // Follow the main camera so the view can be steered while exporting
+    if let Ok(main) = main.get_single() {
+        for mut transform in &mut cameras {
+            *transform = *main;
+        }
+    }
+    if sequence.frame < sequence.frames {
+        let path = sequence
+            .dir
+            .join(format!("frame_{:05}.png", sequence.frame));
+        commands
+            .spawn(Screenshot::image(sequence.target.image.clone()))
+            .observe(save_to_disk(path));
+        sequence.frame += 1;
+        return;
+    }
+    commands.entity(sequence.target.camera).despawn();
+    *strategy = time_strategy(None, &settings);
+    let message = format!(
+        "Wrote {} frames to {}",
+        sequence.frames,
+        sequence.dir.display()
+    );
+    info!("{}", message);
+    capture.message = Some(message);
+    capture.sequence = None;
+}
+
+/// `scale` times `window`, shrunk to keep both sides within `max`.
+fn still_size(window: UVec2, scale: u32, max: u32) -> UVec2 {
+    let scale = (scale as f32).min(max as f32 / window.max_element().max(1) as f32);
+    (window.as_vec2() * scale)
+        .round()
+        .as_uvec2()
+        .max(UVec2::ONE)
+}
+
+fn render_still(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut capture: ResMut<Capture>,
+    device: Option<Res<RenderDevice>>,
+    main: Query<(&Camera, &Transform, &OrthographicProjection), With<MainCamera>>,
+) {
+    if let Some(still) = &mut capture.still {
+        if still.captured {
+            commands.entity(still.target.camera).despawn();
+            capture.still = None;
+        } else {
+            commands
+                .spawn(Screenshot::image(still.target.image.clone()))
+                .observe(save_to_disk(still.path.clone()));
+            still.captured = true;
+        }
+        return;
+    }
+    if !std::mem::take(&mut capture.still_requested) {
+        return;
+    }
+    let Ok(main) = main.get_single() else {
+        return;
+    };
+    let Some(window) = main.0.physical_viewport_size() else {
+        return;
+    };
+    let max = device.map_or(MAX_SIZE, |device| device.limits().max_texture_dimension_2d);
+    let size = still_size(window, capture.still_scale, max);
+    let path = unique_path("still").with_extension("png");
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Err(error) = std::fs::create_dir_all(CAPTURE_DIR) {
+        capture.message = Some(format!("Export failed: {}", error));
+        return;
+    }
+    let target = spawn_offscreen(&mut commands, &mut images, main, size.x, size.y);
+    let message = format!("Rendering {} × {} to {}", size.x, size.y, path.display());
+    info!("{}", message);
+    capture.message = Some(message);
+    capture.still = Some(Still {
+        target,
+        path,
+        captured: false,
+    });
+}
+
+fn capture_ui_system(
+    mut contexts: EguiContexts,
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    settings: Res<Settings>,
+    mut capture: ResMut<Capture>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+    main: Query<(&Camera, &Transform, &OrthographicProjection), With<MainCamera>>,
+) {
+    egui::Window::new("Capture")
+        .default_pos([10.0, 980.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let capture = &mut *capture;
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::Slider::new(&mut capture.still_scale, STILL_SCALES)
+                        .text("× window resolution"),
+                );
+                let button = ui.add_enabled(
+                    capture.still.is_none(),
+                    egui::Button::new("Render High-Res Still"),
+                );
+                if button.clicked() {
+                    capture.still_requested = true;
+                }
+            });
+
+            ui.separator();
+            ui.label("Image sequence, one tick per frame whatever the frame rate");
+            ui.horizontal(|ui| {
+                ui.label("Resolution");
+                ui.add(egui::DragValue::new(&mut capture.width).range(16..=MAX_SIZE));
+                ui.label("×");
+                ui.add(egui::DragValue::new(&mut capture.height).range(16..=MAX_SIZE));
+            });
+            ui.add(
+                egui::Slider::new(&mut capture.duration, 1.0..=600.0)
+                    .logarithmic(true)
+                    .text("simulated seconds"),
+            );
+            ui.add(egui::Slider::new(&mut capture.fps, 1..=120).text("frames per second"));
+            let frames = (capture.duration * capture.fps as f32).round() as u32;
+            match &capture.sequence {
+                Some(sequence) => {
+                    ui.add(
+                        egui::ProgressBar::new(sequence.frame as f32 / sequence.frames as f32)
+                            .text(format!("frame {} of {}", sequence.frame, sequence.frames)),
+                    );
+                    if ui.button("Stop").clicked() {
+                        // The next frame finishes as if it were the last
+                        if let Some(sequence) = &mut capture.sequence {
+                            sequence.frames = sequence.frame;
+                        }
+                    }
+                }
+                None => {
+                    let button = ui.add_enabled(
+                        cfg!(not(target_arch = "wasm32")),
+                        egui::Button::new(format!("Export {} PNG Frames", frames)),
+                    );
+                    if cfg!(target_arch = "wasm32") {
+                        ui.label("Image sequences need the desktop build.");
+                    }
+                    if button.clicked() {
+                        if let Ok(main) = main.get_single() {
+                            let dir = unique_path("sequence");
+                            match std::fs::create_dir_all(&dir) {
+                                Ok(()) => {
+                                    let target = spawn_offscreen(
+                                        &mut commands,
+                                        &mut images,
+                                        main,
+                                        capture.width,
+                                        capture.height,
+                                    );
+                                    *strategy = time_strategy(Some(capture.fps), &settings);
+                                    info!("Exporting {} frames to {}", frames, dir.display());
+                                    capture.sequence = Some(Sequence {
+                                        dir,
+                                        target,
+                                        frame: 0,
+                                        frames,
+                                    });
+                                }
+                                Err(error) => {
+                                    capture.message = Some(format!("Export failed: {}", error));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            if let Some(message) = &capture.message {
+                ui.label(message);
+            }
+        });
+}
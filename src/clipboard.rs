@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    pool::ParticleSpawner,
+    spawn::{self, WorldCursor},
+    ui_enabled, Particle, ParticleSystem,
+};
+
+const SELECTION_COLOR: Color = Color::srgba(0.4, 0.8, 1.0, 0.8);
+/// Drags shorter than this in world units copy nothing.
+const MIN_SELECTION_SIZE: f32 = 5.0;
+
+pub struct ClipboardPlugin;
+
+impl Plugin for ClipboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ParticleClipboard>().add_systems(
+            Update,
+            (
+                clipboard_with_mouse
+                    .before(spawn::spawn_at_cursor)
+                    .run_if(ui_enabled),
+                clipboard_ui_system.run_if(ui_enabled),
+            ),
+        );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ClipboardMode {
+    /// The next left drag selects a rectangle and copies its particles.
+    Copy,
+    /// Every left click pastes the clipboard centered on the cursor.
+    Paste,
+}
+
+/// Particles copied from a region, to duplicate interesting structures.
+#[derive(Resource, Default)]
+struct ParticleClipboard {
+    mode: Option<ClipboardMode>,
+    start: Option<Vec2>,
+    /// Offset from the selection's center and species of each particle.
+    particles: Vec<(Vec2, usize)>,
+    /// Size of the copied selection, outlined while pasting.
+    size: Vec2,
+}
+
+fn clipboard_with_mouse(
+    mut contexts: EguiContexts,
+    (mut buttons, keyboard): (ResMut<ButtonInput<MouseButton>>, Res<ButtonInput<KeyCode>>),
+    cursor: WorldCursor,
+    mut clipboard: ResMut<ParticleClipboard>,
+    (particle_system, particles): (Res<ParticleSystem>, Query<(&Transform, &Particle)>),
+    mut spawner: ParticleSpawner,
+    mut gizmos: Gizmos,
+) {
+    let Some(mode) = clipboard.mode else {
+        return;
+    };
+    if keyboard.just_pressed(KeyCode::Escape) {
+        clipboard.mode = None;
+        clipboard.start = None;
+        return;
+    }
+    let Some(cursor) = cursor.position() else {
+        return;
+    };
+    let clicked =
+        buttons.just_pressed(MouseButton::Left) && !contexts.ctx_mut().wants_pointer_input();
+    if clicked {
+        // The click belongs to the tool instead of adding a particle
+        buttons.clear_just_pressed(MouseButton::Left);
+    }
+
+    match mode {
+        ClipboardMode::Copy => {
+            if clicked {
+                clipboard.start = Some(cursor);
+            }
+            let Some(start) = clipboard.start else {
+                return;
+            };
+            let selection = Rect::from_corners(start, cursor);
+            if !buttons.just_released(MouseButton::Left) {
+                gizmos.rect_2d(
+                    Isometry2d::from_translation(selection.center()),
+                    selection.size(),
+                    SELECTION_COLOR,
+                );
+                return;
+            }
+            clipboard.start = None;
+            if selection.size().min_element() < MIN_SELECTION_SIZE {
+                return;
+            }
+            let center = selection.center();
+            clipboard.particles = particles
+                .iter()
+                .map(|(transform, particle)| (transform.translation.truncate(), particle.color_id))
+                .filter(|(pos, _)| selection.contains(*pos))
+                .map(|(pos, species)| (pos - center, species))
+                .collect();
+            clipboard.size = selection.size();
+            info!("Copied {} particles", clipboard.particles.len());
+            // Pasting is what a copy is usually followed by
+            clipboard.mode = Some(ClipboardMode::Paste);
+        }
+        ClipboardMode::Paste => {
+            gizmos.rect_2d(
+                Isometry2d::from_translation(cursor),
+                clipboard.size,
+                SELECTION_COLOR,
+            );
+            if !clicked {
+                return;
+            }
+            let n = particle_system.colors.len();
+            for &(offset, species) in &clipboard.particles {
+                // Species beyond the current palette wrap around
+                if n > 0 {
+                    let color_id = species % n;
+                    spawner.spawn(cursor + offset, color_id, particle_system.colors[color_id]);
+                }
+            }
+            debug!(particles = clipboard.particles.len(), "Pasted particles");
+        }
+    }
+}
+
+fn clipboard_ui_system(mut contexts: EguiContexts, mut clipboard: ResMut<ParticleClipboard>) {
+    egui::Window::new("Clipboard")
+        .default_pos([620.0, 990.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Copies the particles in a region to paste them elsewhere.");
+            ui.horizontal(|ui| {
+                for (mode, label) in [
+                    (ClipboardMode::Copy, "Select & Copy"),
+                    (ClipboardMode::Paste, "Paste"),
+                ] {
+                    let enabled = mode == ClipboardMode::Copy || !clipboard.particles.is_empty();
+                    if ui
+                        .add_enabled(
+                            enabled,
+                            egui::SelectableLabel::new(clipboard.mode == Some(mode), label),
+                        )
+                        .clicked()
+                    {
+                        clipboard.mode = (clipboard.mode != Some(mode)).then_some(mode);
+                        clipboard.start = None;
+                    }
+                }
+            });
+            match clipboard.mode {
+                Some(ClipboardMode::Copy) => {
+                    ui.label("Drag in the world to select, Escape to cancel.");
+                }
+                Some(ClipboardMode::Paste) => {
+                    ui.label("Click in the world to paste, Escape to stop.");
+                }
+                None => {}
+            }
+            ui.label(format!(
+                "{} particle(s) on the clipboard",
+                clipboard.particles.len()
+            ));
+        });
+}
@@ -0,0 +1,369 @@
+use bevy::{
+    prelude::*,
+    render::{
+        camera::{ClearColorConfig, Viewport},
+        view::RenderLayers,
+    },
+};
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    backend::{GridBackend, SimulationBackend},
+    particle_assets::ParticleAssets,
+    replay,
+    spawn::SpawnRequest,
+    ui_enabled, update_particles, ControlledCamera, MainCamera, Particle, ParticleCount,
+    ParticleSystem, Velocity, PERTURB_EPSILON,
+};
+
+/// Render layer seen only by the right-hand camera.
+const COMPARISON_LAYER: usize = 1;
+
+pub struct ComparisonPlugin;
+
+impl Plugin for ComparisonPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Comparison>().add_systems(
+            Update,
+            (
+                sync_comparison_particles,
+                update_comparison
+                    .after(update_particles)
+                    .run_if(not(replay::is_playing_back)),
+                layout_cameras,
+                comparison_ui_system.run_if(ui_enabled),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// A particle of the right-hand simulation, only visible to its camera.
+#[derive(Component)]
+struct ComparisonParticle {
+    color_id: usize,
+}
+
+#[derive(Component)]
+struct ComparisonCamera;
+
+/// Split-screen A/B mode: the main simulation on the left, and on the right a
+/// copy started from the same positions whose rules can be changed, to see
+/// what a single change does. Only the matrix forces and species motion run
+/// on the right.
+#[derive(Resource)]
+pub struct Comparison {
+    pub enabled: bool,
+    /// Rules of the right-hand side.
+    pub system: ParticleSystem,
+    /// The right camera follows the left one.
+    pub link_cameras: bool,
+    /// Keyboard pans and zooms the right camera while unlinked.
+    pub control_right: bool,
+    /// Copy the main particles on the next update.
+    respawn: bool,
+    /// Copy them once the main simulation has respawned.
+    awaiting_reset: bool,
+    materials: Vec<Handle<ColorMaterial>>,
+    positions: Vec<Vec2>,
+    species: Vec<usize>,
+    grid: GridBackend,
+}
+
+impl Default for Comparison {
+    fn default() -> Self {
+        Comparison {
+            enabled: false,
+            system: ParticleSystem::new(),
+            link_cameras: true,
+            control_right: false,
+            respawn: false,
+            awaiting_reset: false,
+            materials: Vec::new(),
+            positions: Vec::new(),
+            species: Vec::new(),
+            grid: GridBackend::default(),
+        }
+    }
+}
+
+/// Replaces the right-hand particles with copies of the main ones when asked
+/// to, and removes them when the mode is off.
+fn sync_comparison_particles(
+    mut commands: Commands,
+    mut comparison: ResMut<Comparison>,
+    (particle_system, assets): (Res<ParticleSystem>, Res<ParticleAssets>),
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    main: Query<(&Transform, &Particle)>,
+    added: Query<(), Added<Particle>>,
+    particles: Query<Entity, With<ComparisonParticle>>,
+) {
+    if !comparison.enabled {
+        for entity in &particles {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    let comparison = &mut *comparison;
+    if comparison.awaiting_reset && !added.is_empty() {
+        comparison.awaiting_reset = false;
+        comparison.respawn = true;
+    }
+    if !comparison.respawn {
+        return;
+    }
+    comparison.respawn = false;
+    for entity in &particles {
+        commands.entity(entity).despawn();
+    }
+    // Rules for another species count cannot apply to the copied particles
+    if comparison.system.colors.len() != particle_system.colors.len() {
+        comparison.system = particle_system.clone();
+    }
+
+    comparison.materials.clear();
+    comparison.materials.extend(
+        comparison
+            .system
+            .colors
+            .iter()
+            .map(|&color| materials.add(ColorMaterial::from(color))),
+    );
+    for (transform, particle) in &main {
+        let color_id = particle.color_id;
+        commands.spawn((
+            Mesh2d(assets.mesh.clone()),
+            MeshMaterial2d(comparison.materials[color_id].clone()),
+            *transform,
+            ComparisonParticle { color_id },
+            Velocity::default(),
+            RenderLayers::layer(COMPARISON_LAYER),
+        ));
+    }
+}
+
+fn update_comparison(
+    time: Res<Time>,
+    mut comparison: ResMut<Comparison>,
+    mut particles: Query<(&mut Transform, &ComparisonParticle, &mut Velocity)>,
+) {
+    if !comparison.enabled {
+        return;
+    }
+    let step = time.delta_secs() * comparison.system.time_scale;
+    let Comparison {
+        system,
+        positions,
+        species,
+        grid,
+        ..
+    } = &mut *comparison;
+
+    positions.clear();
+    species.clear();
+    for (transform, particle, _) in &particles {
+        positions.push(transform.translation.truncate());
+        species.push(particle.color_id);
+    }
+    grid.prepare(positions, species, system.attraction_radius);
+    let profile = system.force_profile.profile(system.beta, system.gamma);
+
+    for (index, (mut transform, particle, mut velocity)) in particles.iter_mut().enumerate() {
+        let pos = positions[index];
+        let force = crate::layers::system_force(
+            grid,
+            system,
+            &*profile,
+            pos,
+            particle.color_id,
+            positions,
+            species,
+        );
+        let motion = system
+            .species_motion
+            .get(particle.color_id)
+            .copied()
+            .unwrap_or_default();
+        velocity.0 = motion.integrate(velocity.0, force * system.force_strength, step);
+        let z = transform.translation.z;
+        transform.translation = (pos + velocity.0 * step).extend(z);
+    }
+}
+
+/// Writes a viewport only when it differs, so cameras are not marked changed
+/// every frame.
+fn set_viewport(camera: &mut Mut<Camera>, viewport: Option<(UVec2, UVec2)>) {
+    let current = camera
+        .viewport
+        .as_ref()
+        .map(|viewport| (viewport.physical_position, viewport.physical_size));
+    if current == viewport {
+        return;
+    }
+    camera.viewport = viewport.map(|(physical_position, physical_size)| Viewport {
+        physical_position,
+        physical_size,
+        ..default()
+    });
+}
+
+type MainCameras<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Camera,
+        &'static Transform,
+        Has<ControlledCamera>,
+    ),
+    (With<MainCamera>, Without<ComparisonCamera>),
+>;
+
+type ComparisonCameras<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Camera,
+        &'static mut Transform,
+        Has<ControlledCamera>,
+    ),
+    With<ComparisonCamera>,
+>;
+
+/// Splits the render target between the main camera and the right-hand one, spawning
+/// or removing the latter and handing keyboard control between them.
+fn layout_cameras(
+    mut commands: Commands,
+    comparison: Res<Comparison>,
+    mut main: MainCameras,
+    mut right: ComparisonCameras,
+) {
+    let Ok((main_entity, mut main_camera, main_transform, main_controlled)) = main.get_single_mut()
+    else {
+        return;
+    };
+    let right = right.get_single_mut().ok();
+    if !comparison.enabled {
+        set_viewport(&mut main_camera, None);
+        if let Some((entity, ..)) = right {
+            commands.entity(entity).despawn();
+        }
+        if !main_controlled {
+            commands.entity(main_entity).insert(ControlledCamera);
+        }
+        return;
+    }
+    let Some((right_entity, mut right_camera, mut right_transform, right_controlled)) = right
+    else {
+        commands.spawn((
+            Camera2d,
+            Camera {
+                order: 1,
+                // The main camera already cleared the window or image
+                clear_color: ClearColorConfig::None,
+                target: main_camera.target.clone(),
+                ..default()
+            },
+            *main_transform,
+            RenderLayers::layer(COMPARISON_LAYER),
+            ComparisonCamera,
+        ));
+        return;
+    };
+
+    if let Some(size) = main_camera.physical_target_size() {
+        let half = UVec2::new(size.x / 2, size.y);
+        if half.x > 0 && half.y > 0 {
+            set_viewport(&mut main_camera, Some((UVec2::ZERO, half)));
+            set_viewport(&mut right_camera, Some((UVec2::new(half.x, 0), half)));
+        }
+    }
+
+    let control_right = comparison.control_right && !comparison.link_cameras;
+    if control_right != right_controlled {
+        if control_right {
+            commands.entity(main_entity).remove::<ControlledCamera>();
+            commands.entity(right_entity).insert(ControlledCamera);
+        } else {
+            commands.entity(right_entity).remove::<ControlledCamera>();
+        }
+    }
+    if !control_right && !main_controlled {
+        commands.entity(main_entity).insert(ControlledCamera);
+    }
+    if comparison.link_cameras {
+        *right_transform = *main_transform;
+    }
+}
+
+fn comparison_ui_system(
+    mut contexts: EguiContexts,
+    mut comparison: ResMut<Comparison>,
+    particle_system: Res<ParticleSystem>,
+    particle_count: Res<ParticleCount>,
+    mut spawn_requests: EventWriter<SpawnRequest>,
+) {
+    egui::Window::new("Split Screen")
+        .default_pos([400.0, 1000.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Runs a copy of the simulation on the right, from the same start, with its own matrix.");
+            ui.add_space(5.0);
+
+            let comparison = &mut *comparison;
+            if ui.checkbox(&mut comparison.enabled, "Split screen").changed()
+                && comparison.enabled
+            {
+                comparison.system = particle_system.clone();
+                comparison.respawn = true;
+            }
+            ui.add_enabled_ui(comparison.enabled, |ui| {
+                ui.checkbox(&mut comparison.link_cameras, "Link cameras");
+                ui.add_enabled_ui(!comparison.link_cameras, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Keyboard moves");
+                        ui.selectable_value(&mut comparison.control_right, false, "Left");
+                        ui.selectable_value(&mut comparison.control_right, true, "Right");
+                    });
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Restart Both").clicked() {
+                        spawn_requests.send(SpawnRequest::Reshuffle {
+                            count: particle_count.count,
+                        });
+                        comparison.awaiting_reset = true;
+                    }
+                    if ui.button("Copy Rules From Left").clicked() {
+                        comparison.system = particle_system.clone();
+                        comparison.respawn = true;
+                    }
+                    if ui.button("Perturb Right Matrix").clicked() {
+                        comparison.system.perturb_matrix(PERTURB_EPSILON);
+                    }
+                });
+                ui.add(
+                    egui::Slider::new(&mut comparison.system.force_strength, 0.0..=3200.0)
+                        .text("right force strength"),
+                );
+
+                ui.add_space(5.0);
+                ui.label("Right matrix, entries that differ from the left highlighted");
+                let n = comparison.system.colors.len();
+                egui::Grid::new("comparison_matrix").show(ui, |ui| {
+                    for i in 0..n {
+                        for j in 0..n {
+                            let left = particle_system.get_behavior(i, j);
+                            let value = &mut comparison.system.behavior_matrix[i][j];
+                            let response = ui
+                                .add(egui::DragValue::new(value).speed(0.01).range(-1.0..=1.0));
+                            if (*value - left).abs() > f32::EPSILON {
+                                response.highlight();
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+}
@@ -0,0 +1,263 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    sync::{mpsc, Mutex, PoisonError},
+    thread,
+};
+
+use crate::{
+    events::{ClearParticles, RegenerateMatrix, RegeneratePalette, SetBehavior, SetParameter},
+    preset::{LoadPreset, PRESETS_DIR},
+    spawn::SpawnRequest,
+    stats::SimulationStats,
+    Particle, ParticleCount, ParticleSystem, NUM_COLORS,
+};
+
+/// Lets external tools drive the simulation over TCP: each line sent is a
+/// JSON command and is answered with one JSON line. Enabled with
+/// `--control <address>`.
+pub struct ControlPlugin;
+
+impl Plugin for ControlPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(address) = address_from_args() else {
+            return;
+        };
+        match listen(&address) {
+            Ok(requests) => {
+                info!("Remote control listening on {}", address);
+                app.insert_resource(RemoteControl {
+                    requests: Mutex::new(requests),
+                })
+                .add_systems(Update, handle_requests);
+            }
+            Err(error) => error!(
+                "Could not listen for remote control on {}: {}",
+                address, error
+            ),
+        }
+    }
+}
+
+fn address_from_args() -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|arg| arg == "--control")?;
+    args.get(index + 1).cloned()
+}
+
+/// A remote command, e.g. `{"cmd": "set", "name": "speed", "value": 300}`.
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum Command {
+    /// Sets a parameter by its scripting name, e.g. `speed` or `radius`.
+    Set {
+        name: String,
+        value: f32,
+    },
+    Get {
+        name: String,
+    },
+    SetBehavior {
+        from: usize,
+        to: usize,
+        value: f32,
+    },
+    /// Loads a preset file from the presets directory.
+    LoadPreset {
+        name: String,
+    },
+    /// New palette, matrix and particles, like the restart key.
+    Regenerate,
+    /// New random matrix values, keeping the palette.
+    Reroll,
+    /// Scatters the particles again under the same rules.
+    Respawn,
+    /// Removes every particle.
+    Clear,
+    Pause,
+    Resume,
+    /// The latest statistics sample.
+    Stats,
+}
+
+struct Request {
+    command: Command,
+    reply: mpsc::Sender<Value>,
+}
+
+#[derive(Resource)]
+struct RemoteControl {
+    requests: Mutex<mpsc::Receiver<Request>>,
+}
+
+fn success() -> Value {
+    json!({ "ok": true })
+}
+
+fn failure(error: impl ToString) -> Value {
+    json!({ "ok": false, "error": error.to_string() })
+}
+
+/// Accepts connections on a background thread, each served by its own
+/// thread that hands commands to [`handle_requests`].
+fn listen(address: &str) -> io::Result<mpsc::Receiver<Request>> {
+    let listener = TcpListener::bind(address)?;
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                if let Err(error) = serve_connection(stream, sender) {
+                    debug!("Remote control connection closed: {}", error);
+                }
+            });
+        }
+    });
+    Ok(receiver)
+}
+
+fn serve_connection(stream: TcpStream, requests: mpsc::Sender<Request>) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str(&line) {
+            Ok(command) => {
+                let (reply, answer) = mpsc::channel();
+                // Both fail only once the app has exited
+                if requests.send(Request { command, reply }).is_err() {
+                    break;
+                }
+                let Ok(reply) = answer.recv() else {
+                    break;
+                };
+                reply
+            }
+            Err(error) => failure(format!("invalid command: {}", error)),
+        };
+        writeln!(writer, "{}", reply)?;
+    }
+    Ok(())
+}
+
+fn read_preset(name: &str) -> Result<LoadPreset, String> {
+    // Only bare file names, so remote peers cannot read elsewhere
+    if Path::new(name).file_name().and_then(|file| file.to_str()) != Some(name) {
+        return Err(format!("'{}' is not a file name", name));
+    }
+    let text = std::fs::read_to_string(Path::new(PRESETS_DIR).join(name))
+        .map_err(|error| format!("could not read {}: {}", name, error))?;
+    Ok(LoadPreset {
+        name: name.to_string(),
+        text,
+    })
+}
+
+fn handle_requests(
+    control: Res<RemoteControl>,
+    (stats, particle_count, particles): (
+        Res<SimulationStats>,
+        Res<ParticleCount>,
+        Query<(), With<Particle>>,
+    ),
+    mut time: ResMut<Time<Virtual>>,
+    particle_system: Res<ParticleSystem>,
+    mut spawn_requests: EventWriter<SpawnRequest>,
+    (mut presets, mut parameters, mut behaviors): (
+        EventWriter<LoadPreset>,
+        EventWriter<SetParameter>,
+        EventWriter<SetBehavior>,
+    ),
+    (mut matrix_events, mut palette_events, mut clear_events): (
+        EventWriter<RegenerateMatrix>,
+        EventWriter<RegeneratePalette>,
+        EventWriter<ClearParticles>,
+    ),
+) {
+    let requests = control
+        .requests
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    for request in requests.try_iter() {
+        let n = particle_system.colors.len();
+        let reply = match request.command {
+            Command::Set { name, value } => {
+                // Only checks the name; the change is applied with the event
+                if particle_system.parameter(&name).is_some() {
+                    parameters.send(SetParameter { name, value });
+                    success()
+                } else {
+                    failure(format!("unknown parameter '{}'", name))
+                }
+            }
+            Command::Get { name } => match particle_system.parameter(&name) {
+                Some(value) => json!({ "ok": true, "value": value }),
+                None => failure(format!("unknown parameter '{}'", name)),
+            },
+            Command::SetBehavior { from, to, value } if from < n && to < n => {
+                behaviors.send(SetBehavior { from, to, value });
+                success()
+            }
+            Command::SetBehavior { .. } => failure(format!("species out of range 0..{}", n)),
+            Command::LoadPreset { name } => match read_preset(&name) {
+                Ok(preset) => {
+                    presets.send(preset);
+                    success()
+                }
+                Err(error) => failure(error),
+            },
+            Command::Regenerate => {
+                palette_events.send(RegeneratePalette {
+                    species: NUM_COLORS,
+                    respawn: Some(particle_count.count),
+                });
+                success()
+            }
+            Command::Reroll => {
+                matrix_events.send(RegenerateMatrix::Reroll);
+                success()
+            }
+            Command::Respawn => {
+                spawn_requests.send(SpawnRequest::Reshuffle {
+                    count: particle_count.count,
+                });
+                success()
+            }
+            Command::Clear => {
+                clear_events.send(ClearParticles);
+                success()
+            }
+            Command::Pause => {
+                time.pause();
+                success()
+            }
+            Command::Resume => {
+                time.unpause();
+                success()
+            }
+            Command::Stats => {
+                let mut reply = json!({
+                    "ok": true,
+                    "paused": time.is_paused(),
+                    "particles": particles.iter().len(),
+                    "species": n,
+                });
+                if let Some(sample) = stats.latest() {
+                    reply["time"] = json!(sample.time);
+                    reply["species_counts"] = json!(sample.species_counts);
+                    reply["average_speed"] = json!(sample.average_speed);
+                    reply["cluster_count"] = json!(sample.cluster_count);
+                }
+                reply
+            }
+        };
+        // The peer may have disconnected meanwhile
+        let _ = request.reply.send(reply);
+    }
+}
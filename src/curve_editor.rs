@@ -0,0 +1,204 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use egui_plot::{Line, Plot, PlotPoint, PlotPoints, Points};
+
+use crate::{
+    egui_color,
+    force::{ForceProfile, ForceShape},
+    ui_enabled, ParticleSystem,
+};
+
+const CURVE_SAMPLES: usize = 200;
+/// Screen distance within which a drag picks up a control point.
+const GRAB_RADIUS: f32 = 12.0;
+/// Smallest allowed gap between beta, gamma and the edge of the radius.
+const MIN_GAP: f32 = 0.01;
+
+pub struct CurveEditorPlugin;
+
+impl Plugin for CurveEditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CurveEditor>()
+            .add_systems(Update, curve_editor_ui_system.run_if(ui_enabled));
+    }
+}
+
+/// A draggable point on the force curve and what dragging it changes.
+#[derive(Clone, Copy, PartialEq)]
+enum Handle {
+    /// End of the repulsion zone of the linear peak.
+    Beta,
+    /// Peak position and the pair's behavior.
+    Peak,
+    /// Zero crossing of the Lennard-Jones curve.
+    Sigma,
+    /// Bottom of the Lennard-Jones well, i.e. the pair's behavior.
+    Well,
+    Sample(usize),
+}
+
+#[derive(Resource, Default)]
+struct CurveEditor {
+    from: usize,
+    to: usize,
+    dragging: Option<Handle>,
+}
+
+fn handles(particle_system: &ParticleSystem, behavior: f32) -> Vec<(Handle, [f64; 2])> {
+    let behavior = behavior as f64;
+    match &particle_system.force_profile {
+        ForceShape::LinearPeak => vec![
+            (Handle::Beta, [particle_system.beta as f64, 0.0]),
+            (Handle::Peak, [particle_system.gamma as f64, behavior]),
+        ],
+        ForceShape::LennardJones(profile) => vec![
+            (Handle::Sigma, [profile.sigma as f64, 0.0]),
+            (
+                Handle::Well,
+                [profile.sigma as f64 * 2f64.powf(1.0 / 6.0), behavior],
+            ),
+        ],
+        ForceShape::InverseSquare(profile) => vec![(Handle::Peak, [profile.core as f64, behavior])],
+        ForceShape::Table(table) => {
+            let last = table.samples.len().saturating_sub(1).max(1);
+            (0..table.samples.len())
+                .map(|i| {
+                    let x = i as f32 / last as f32;
+                    let y = table.force(x, behavior as f32);
+                    (Handle::Sample(i), [x as f64, y as f64])
+                })
+                .collect()
+        }
+    }
+}
+
+fn drag(
+    handle: Handle,
+    point: PlotPoint,
+    particle_system: &mut ParticleSystem,
+    pair: (usize, usize),
+) {
+    let x = point.x as f32;
+    let y = (point.y as f32).clamp(-1.0, 1.0);
+    let (from, to) = pair;
+    let behavior = particle_system.behavior_matrix[from][to];
+    match (handle, &mut particle_system.force_profile) {
+        (Handle::Beta, ForceShape::LinearPeak) => {
+            particle_system.beta = x.min(particle_system.gamma - MIN_GAP).max(MIN_GAP);
+        }
+        (Handle::Peak, ForceShape::LinearPeak) => {
+            particle_system.gamma = x.max(particle_system.beta + MIN_GAP).min(1.0 - MIN_GAP);
+            particle_system.behavior_matrix[from][to] = y;
+        }
+        (Handle::Peak, ForceShape::InverseSquare(profile)) => {
+            profile.core = x.clamp(0.05, 0.9);
+            particle_system.behavior_matrix[from][to] = y;
+        }
+        (Handle::Sigma, ForceShape::LennardJones(profile)) => {
+            profile.sigma = x.clamp(0.05, 0.9);
+        }
+        (Handle::Well, ForceShape::LennardJones(_)) => {
+            particle_system.behavior_matrix[from][to] = y;
+        }
+        // Positive samples are scaled by the pair's behavior, so undo that
+        (Handle::Sample(index), ForceShape::Table(table)) => {
+            if let Some(sample) = table.samples.get_mut(index) {
+                *sample = if y > 0.0 && behavior > 0.0 {
+                    (y / behavior).min(1.0)
+                } else {
+                    y
+                };
+            }
+        }
+        // The profile was switched mid-drag
+        _ => {}
+    }
+}
+
+fn curve_editor_ui_system(
+    mut contexts: EguiContexts,
+    mut editor: ResMut<CurveEditor>,
+    mut particle_system: ResMut<ParticleSystem>,
+) {
+    egui::Window::new("Force Curve")
+        .default_pos([320.0, 10.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let n = particle_system.colors.len();
+            if n == 0 {
+                return;
+            }
+            editor.from = editor.from.min(n - 1);
+            editor.to = editor.to.min(n - 1);
+            ui.horizontal(|ui| {
+                ui.label("Species");
+                ui.add(egui::DragValue::new(&mut editor.from).range(0..=n - 1));
+                ui.colored_label(egui_color(particle_system.colors[editor.from]), "⏺");
+                ui.label("reacting to");
+                ui.add(egui::DragValue::new(&mut editor.to).range(0..=n - 1));
+                ui.colored_label(egui_color(particle_system.colors[editor.to]), "⏺");
+            });
+
+            let pair = (editor.from, editor.to);
+            let behavior = particle_system.get_behavior(pair.0, pair.1);
+            let curve: PlotPoints = {
+                let profile = particle_system
+                    .force_profile
+                    .profile(particle_system.beta, particle_system.gamma);
+                (0..=CURVE_SAMPLES)
+                    .map(|i| {
+                        let distance = i as f32 / CURVE_SAMPLES as f32;
+                        [distance as f64, profile.force(distance, behavior) as f64]
+                    })
+                    .collect()
+            };
+            let handles = handles(&particle_system, behavior);
+            let color = egui_color(particle_system.colors[pair.0]);
+
+            let response = Plot::new("force_curve")
+                .height(200.0)
+                .allow_drag(false)
+                .allow_zoom(false)
+                .allow_scroll(false)
+                .allow_boxed_zoom(false)
+                .allow_double_click_reset(false)
+                .include_x(0.0)
+                .include_x(1.0)
+                .include_y(-1.0)
+                .include_y(1.0)
+                .x_axis_label("distance / radius")
+                .y_axis_label("force")
+                .show(ui, |plot_ui| {
+                    plot_ui.line(Line::new(curve).color(color));
+                    let points: Vec<[f64; 2]> = handles.iter().map(|(_, point)| *point).collect();
+                    plot_ui.points(Points::new(points).radius(5.0).color(egui::Color32::WHITE));
+                });
+
+            let pointer = response.response.interact_pointer_pos();
+            if response.response.drag_started() {
+                editor.dragging = pointer.and_then(|pointer| {
+                    handles
+                        .iter()
+                        .map(|&(handle, [x, y])| {
+                            let position = response
+                                .transform
+                                .position_from_point(&PlotPoint::new(x, y));
+                            (handle, position.distance(pointer))
+                        })
+                        .filter(|&(_, distance)| distance < GRAB_RADIUS)
+                        .min_by(|a, b| a.1.total_cmp(&b.1))
+                        .map(|(handle, _)| handle)
+                });
+            }
+            if response.response.dragged() {
+                if let (Some(handle), Some(pointer)) = (editor.dragging, pointer) {
+                    let point = response.transform.value_from_position(pointer);
+                    drag(handle, point, &mut particle_system, pair);
+                }
+            }
+            if response.response.drag_stopped() {
+                editor.dragging = None;
+            }
+            ui.label("Drag the points to reshape the curve; changes apply live.");
+        });
+}
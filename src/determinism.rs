@@ -0,0 +1,120 @@
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use rand::{rngs::StdRng, SeedableRng};
+use std::time::Duration;
+
+use crate::settings::Settings;
+
+/// Simulated time per frame in deterministic mode.
+pub const FIXED_STEP: f64 = 1.0 / 60.0;
+/// Fixed-point positions are multiples of `1 / FIXED_POINT_SCALE` world
+/// units. A power of two, so snapping rounds exactly once.
+const FIXED_POINT_SCALE: f32 = 256.0;
+/// Mixed into the seed for the noise and life streams of [`SpawnRng`].
+const NOISE_STREAM: u64 = 0x9e37_79b9_7f4a_7c15;
+const LIFE_STREAM: u64 = 0xd1b5_4a32_d192_ed03;
+
+/// Deterministic mode, turned on in the Settings window: every frame
+/// simulates [`FIXED_STEP`] whatever the frame rate, shortcuts that depend on
+/// timing or the camera (the quality governor, level of detail, the
+/// simulation thread) are skipped, and resets lay out the world from
+/// [`Settings::seed`]. The same seed and inputs then give bit-identical runs,
+/// on any platform, which replays and networked sessions rely on.
+///
+/// The force code itself never uses fast-math or fused operations, and the
+/// few transcendental functions it needs come from `libm`, which computes
+/// them the same way everywhere rather than deferring to the platform.
+pub struct DeterminismPlugin;
+
+impl Plugin for DeterminismPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpawnRng>()
+            .add_systems(First, apply_fixed_step.before(bevy::time::TimeSystem))
+            .add_systems(
+                Update,
+                sync_seed
+                    .run_if(resource_changed::<Settings>)
+                    .before(crate::spawn::handle_spawn_requests),
+            );
+    }
+}
+
+/// Random sources for spawning and for the stochastic rules (Brownian noise,
+/// births and deaths). In deterministic mode every reset restarts them from
+/// the seed, so the same seed always lays out and evolves the same world.
+/// Each user draws from its own stream, so the order those systems run in
+/// doesn't change what any of them gets.
+#[derive(Resource)]
+pub struct SpawnRng {
+    rng: StdRng,
+    noise: StdRng,
+    life: StdRng,
+    seed: Option<u64>,
+}
+
+impl Default for SpawnRng {
+    fn default() -> Self {
+        SpawnRng {
+            rng: StdRng::from_os_rng(),
+            noise: StdRng::from_os_rng(),
+            life: StdRng::from_os_rng(),
+            seed: None,
+        }
+    }
+}
+
+impl SpawnRng {
+    /// Called at the start of a reset.
+    pub fn restart(&mut self) {
+        if let Some(seed) = self.seed {
+            self.rng = StdRng::seed_from_u64(seed);
+            self.noise = StdRng::seed_from_u64(seed ^ NOISE_STREAM);
+            self.life = StdRng::seed_from_u64(seed ^ LIFE_STREAM);
+        }
+    }
+
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    /// Stream for the temperature's Brownian noise.
+    pub fn noise(&mut self) -> &mut StdRng {
+        &mut self.noise
+    }
+
+    /// Stream for the life rules' births and deaths.
+    pub fn life(&mut self) -> &mut StdRng {
+        &mut self.life
+    }
+}
+
+/// `pos` rounded to the fixed-point grid when fixed-point positions are on.
+pub fn snap(settings: &Settings, pos: Vec2) -> Vec2 {
+    if settings.deterministic && settings.fixed_point {
+        (pos * FIXED_POINT_SCALE).round() / FIXED_POINT_SCALE
+    } else {
+        pos
+    }
+}
+
+/// Switches time to fixed steps and back. Only touches the strategy when the
+/// mode changes, so test harnesses that set their own keep it.
+fn apply_fixed_step(
+    settings: Res<Settings>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+    mut applied: Local<bool>,
+) {
+    if settings.deterministic == *applied {
+        return;
+    }
+    *applied = settings.deterministic;
+    *strategy = if settings.deterministic {
+        info!("Deterministic mode: {} s per frame", FIXED_STEP);
+        TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(FIXED_STEP))
+    } else {
+        TimeUpdateStrategy::Automatic
+    };
+}
+
+fn sync_seed(settings: Res<Settings>, mut spawn_rng: ResMut<SpawnRng>) {
+    spawn_rng.seed = settings.deterministic.then_some(settings.seed);
+}
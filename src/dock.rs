@@ -0,0 +1,217 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_egui::{egui, EguiContext};
+use egui_dock::{DockArea, DockState, NodeIndex, Style, TabViewer};
+use serde::{Deserialize, Serialize};
+
+use crate::{explain, preset, settings, stats, tutorial::Tutorial, ui_enabled};
+
+const DOCK_WIDTH: f32 = 380.0;
+const HIGHLIGHT: egui::Color32 = egui::Color32::from_rgb(255, 200, 60);
+
+/// Docks the main panels into a resizable side area whose tabs can be
+/// dragged, split, undocked or hidden. The arrangement is kept in
+/// [`settings::Settings::dock_layout`].
+pub struct DockPlugin;
+
+impl Plugin for DockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DockLayout>()
+            .add_systems(Update, dock_ui_system.run_if(ui_enabled));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Panel {
+    Controls,
+    Matrix,
+    Statistics,
+    Palette,
+    Presets,
+    Rules,
+}
+
+impl Panel {
+    const ALL: [Panel; 6] = [
+        Panel::Controls,
+        Panel::Matrix,
+        Panel::Statistics,
+        Panel::Palette,
+        Panel::Presets,
+        Panel::Rules,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Panel::Controls => "Simulation Controls",
+            Panel::Matrix => "Matrix Editor",
+            Panel::Statistics => "Statistics",
+            Panel::Palette => "Palette",
+            Panel::Presets => "Presets",
+            Panel::Rules => "Explain Rules",
+        }
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize)]
+struct DockLayout {
+    /// Hides the whole dock, e.g. to watch the simulation unobstructed.
+    shown: bool,
+    state: DockState<Panel>,
+    /// Panel last brought forward for the tutorial.
+    #[serde(skip)]
+    revealed: Option<Panel>,
+}
+
+impl DockLayout {
+    /// The arrangement before any saved layout, and after "Reset Layout".
+    fn initial() -> Self {
+        let mut state = DockState::new(vec![Panel::Controls, Panel::Matrix]);
+        state.main_surface_mut().split_below(
+            NodeIndex::root(),
+            0.6,
+            vec![
+                Panel::Statistics,
+                Panel::Palette,
+                Panel::Presets,
+                Panel::Rules,
+            ],
+        );
+        DockLayout {
+            shown: true,
+            state,
+            revealed: None,
+        }
+    }
+
+    /// Shows `panel`, docking it again if it was closed, and makes it the
+    /// active tab.
+    fn reveal(&mut self, panel: Panel) {
+        self.shown = true;
+        match self.state.find_tab(&panel) {
+            Some(location) => self.state.set_active_tab(location),
+            None => self.state.push_to_focused_leaf(panel),
+        }
+    }
+}
+
+impl FromWorld for DockLayout {
+    fn from_world(world: &mut World) -> Self {
+        let saved = world
+            .get_resource::<settings::Settings>()
+            .and_then(|settings| settings.dock_layout.clone());
+        match saved.map(|text| ron::from_str(&text)) {
+            Some(Ok(layout)) => layout,
+            Some(Err(error)) => {
+                warn!("Ignoring unreadable dock layout: {}", error);
+                DockLayout::initial()
+            }
+            None => DockLayout::initial(),
+        }
+    }
+}
+
+/// Shows each tab by running its panel system with the tab's `Ui`.
+struct PanelViewer<'a> {
+    world: &'a mut World,
+}
+
+impl TabViewer for PanelViewer<'_> {
+    type Tab = Panel;
+
+    fn title(&mut self, tab: &mut Panel) -> egui::WidgetText {
+        tab.title().into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Panel) {
+        let result = match tab {
+            Panel::Controls => self.world.run_system_cached_with(crate::controls_panel, ui),
+            Panel::Matrix => self.world.run_system_cached_with(crate::matrix_panel, ui),
+            Panel::Statistics => self.world.run_system_cached_with(stats::stats_panel, ui),
+            Panel::Palette => self
+                .world
+                .run_system_cached_with(settings::palette_panel, ui),
+            Panel::Presets => self.world.run_system_cached_with(preset::presets_panel, ui),
+            Panel::Rules => self.world.run_system_cached_with(explain::rules_panel, ui),
+        };
+        if let Err(error) = result {
+            warn!("Could not show the {} panel: {}", tab.title(), error);
+        }
+        if self.world.resource::<Tutorial>().highlighted() == Some(*tab) {
+            ui.painter().rect_stroke(
+                ui.max_rect(),
+                4.0,
+                egui::Stroke::new(2.0, HIGHLIGHT),
+                egui::StrokeKind::Inside,
+            );
+        }
+    }
+}
+
+/// Exclusive because the panels are systems of their own, run from inside
+/// the dock.
+fn dock_ui_system(world: &mut World) {
+    let ctx = match world
+        .query_filtered::<&mut EguiContext, With<PrimaryWindow>>()
+        .get_single_mut(world)
+    {
+        Ok(mut context) => context.get_mut().clone(),
+        Err(_) => return,
+    };
+
+    world.resource_scope(|world, mut layout: Mut<DockLayout>| {
+        let layout = &mut *layout;
+        let highlighted = world.resource::<Tutorial>().highlighted();
+        if highlighted != layout.revealed {
+            if let Some(panel) = highlighted {
+                layout.reveal(panel);
+            }
+            layout.revealed = highlighted;
+        }
+        egui::TopBottomPanel::top("view_menu").show(&ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button("View", |ui| {
+                    ui.checkbox(&mut layout.shown, "Show dock");
+                    ui.separator();
+                    for panel in Panel::ALL {
+                        let found = layout.state.find_tab(&panel);
+                        let mut open = found.is_some();
+                        if !ui.checkbox(&mut open, panel.title()).changed() {
+                            continue;
+                        }
+                        match found {
+                            Some(location) => {
+                                layout.state.remove_tab(location);
+                            }
+                            None => layout.state.push_to_focused_leaf(panel),
+                        }
+                    }
+                    ui.separator();
+                    if ui.button("Reset Layout").clicked() {
+                        *layout = DockLayout::initial();
+                    }
+                });
+            });
+        });
+
+        if layout.shown {
+            egui::SidePanel::left("dock")
+                .resizable(true)
+                .default_width(DOCK_WIDTH)
+                .show(&ctx, |ui| {
+                    DockArea::new(&mut layout.state)
+                        .style(Style::from_egui(ui.style().as_ref()))
+                        .show_inside(ui, &mut PanelViewer { world: &mut *world });
+                });
+        }
+
+        match ron::to_string(&*layout) {
+            Ok(text) => {
+                let mut settings = world.resource_mut::<settings::Settings>();
+                if settings.dock_layout.as_ref() != Some(&text) {
+                    settings.dock_layout = Some(text);
+                }
+            }
+            Err(error) => warn!("Failed to serialize dock layout: {}", error),
+        }
+    });
+}
@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+
+use crate::{
+    loading, pool::ParticleSpawner, settings::Settings, spawn::SpawnRequest, Particle,
+    ParticleSystem,
+};
+
+/// Commands to the simulation, sent by keyboard, UI, remote control and OSC
+/// alike and applied by the systems here, so none of the senders needs to
+/// know how the rules are stored. Particles are created through
+/// `SpawnRequest`, the spawning counterpart of these events.
+pub struct SimulationEventsPlugin;
+
+impl Plugin for SimulationEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RegenerateMatrix>()
+            .add_event::<RegeneratePalette>()
+            .add_event::<ClearParticles>()
+            .add_event::<SetParameter>()
+            .add_event::<SetBehavior>()
+            .add_systems(
+                Update,
+                (
+                    apply_matrix_events,
+                    apply_palette_events,
+                    apply_parameter_events,
+                    apply_behavior_events,
+                    clear_particles,
+                )
+                    .before(crate::update_particles),
+            );
+    }
+}
+
+/// Changes to the behavior matrix or the interaction constants.
+#[derive(Event, Clone, Copy)]
+pub enum RegenerateMatrix {
+    /// New behaviors from scratch.
+    Regenerate,
+    /// New random matrix values, keeping the palette and species count.
+    Reroll,
+    /// Nudges every entry by up to `epsilon` in either direction.
+    Perturb { epsilon: f32 },
+    /// Resets beta, gamma and the attraction radius.
+    Constants,
+}
+
+/// New palette of `species` colors from the palette settings, with a new
+/// matrix and constants to match, like the restart key.
+#[derive(Event, Clone, Copy)]
+pub struct RegeneratePalette {
+    pub species: usize,
+    /// Lays out this many particles afresh, since the old ones may belong to
+    /// species that no longer exist.
+    pub respawn: Option<usize>,
+}
+
+/// Removes every particle, keeping the rules.
+#[derive(Event, Clone, Copy)]
+pub struct ClearParticles;
+
+/// Sets a parameter by its scripting name, e.g. `speed` or `radius`.
+#[derive(Event, Clone)]
+pub struct SetParameter {
+    pub name: String,
+    pub value: f32,
+}
+
+/// Sets how species `from` reacts to species `to`, clamped to -1..1.
+#[derive(Event, Clone, Copy)]
+pub struct SetBehavior {
+    pub from: usize,
+    pub to: usize,
+    pub value: f32,
+}
+
+fn apply_matrix_events(
+    mut events: EventReader<RegenerateMatrix>,
+    mut particle_system: ResMut<ParticleSystem>,
+) {
+    for &event in events.read() {
+        match event {
+            RegenerateMatrix::Regenerate => particle_system.regenerate_matrix(),
+            RegenerateMatrix::Reroll => particle_system.reroll_matrix(),
+            RegenerateMatrix::Perturb { epsilon } => particle_system.perturb_matrix(epsilon),
+            RegenerateMatrix::Constants => particle_system.regenerate_constants(),
+        }
+    }
+}
+
+fn apply_palette_events(
+    mut events: EventReader<RegeneratePalette>,
+    settings: Res<Settings>,
+    mut particle_system: ResMut<ParticleSystem>,
+    mut spawn_requests: EventWriter<SpawnRequest>,
+) {
+    for &RegeneratePalette { species, respawn } in events.read() {
+        particle_system.regenerate_all(settings.palette.generate(species));
+        if let Some(count) = respawn {
+            spawn_requests.send(SpawnRequest::Reset { count });
+        }
+    }
+}
+
+fn apply_parameter_events(
+    mut events: EventReader<SetParameter>,
+    mut particle_system: ResMut<ParticleSystem>,
+) {
+    for SetParameter { name, value } in events.read() {
+        match particle_system.parameter_mut(name) {
+            Some(parameter) => *parameter = *value,
+            None => warn!("Ignoring unknown parameter '{}'", name),
+        }
+    }
+}
+
+fn apply_behavior_events(
+    mut events: EventReader<SetBehavior>,
+    mut particle_system: ResMut<ParticleSystem>,
+) {
+    for &SetBehavior { from, to, value } in events.read() {
+        // The palette may have shrunk since the event was sent
+        match particle_system
+            .behavior_matrix
+            .get_mut(from)
+            .and_then(|row| row.get_mut(to))
+        {
+            Some(behavior) => *behavior = value.clamp(-1.0, 1.0),
+            None => warn!("Ignoring behavior for species {} -> {}", from, to),
+        }
+    }
+}
+
+fn clear_particles(
+    mut events: EventReader<ClearParticles>,
+    mut spawner: ParticleSpawner,
+    mut pending: ResMut<loading::PendingSpawns>,
+    particles: Query<Entity, With<Particle>>,
+) {
+    if events.read().count() == 0 {
+        return;
+    }
+    pending.cancel();
+    for entity in &particles {
+        spawner.despawn(entity);
+    }
+    info!("Cleared all particles");
+}
@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use rand::Rng;
+
+use crate::{egui_color, stats::SimulationStats, ui_enabled, Particle, ParticleSystem};
+
+const EVOLUTION_INTERVAL: f32 = 0.5;
+const DOMINANT_SPECIES_SHOWN: usize = 5;
+
+pub struct EvolutionPlugin;
+
+impl Plugin for EvolutionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Evolution::default())
+            .add_systems(Update, (evolve, evolution_ui_system.run_if(ui_enabled)));
+    }
+}
+
+#[derive(Resource)]
+pub struct Evolution {
+    pub enabled: bool,
+    /// Probability per second that a particle turns into a neighboring species.
+    pub species_mutation_rate: f32,
+    /// Maximum change per second of each behavior matrix entry.
+    pub matrix_drift_rate: f32,
+    pub mutations: u64,
+    timer: Timer,
+}
+
+impl Default for Evolution {
+    fn default() -> Self {
+        Evolution {
+            enabled: false,
+            species_mutation_rate: 0.001,
+            matrix_drift_rate: 0.01,
+            mutations: 0,
+            timer: Timer::from_seconds(EVOLUTION_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+fn evolve(
+    time: Res<Time>,
+    mut particle_system: ResMut<ParticleSystem>,
+    mut evolution: ResMut<Evolution>,
+    mut particles: Query<&mut Particle>,
+) {
+    if !evolution.enabled || !evolution.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let mut rng = rand::rng();
+
+    let n = particle_system.colors.len();
+    let probability = evolution.species_mutation_rate * EVOLUTION_INTERVAL;
+    if n > 1 && probability > 0.0 {
+        for mut particle in &mut particles {
+            if rng.random::<f32>() >= probability {
+                continue;
+            }
+            particle.color_id = if rng.random_bool(0.5) {
+                (particle.color_id + 1) % n
+            } else {
+                (particle.color_id + n - 1) % n
+            };
+            evolution.mutations += 1;
+        }
+    }
+
+    let step = evolution.matrix_drift_rate * EVOLUTION_INTERVAL;
+    if step > 0.0 {
+        for row in &mut particle_system.behavior_matrix {
+            for value in row {
+                *value = (*value + rng.random_range(-step..=step)).clamp(-1.0, 1.0);
+            }
+        }
+    }
+}
+
+fn evolution_ui_system(
+    mut contexts: EguiContexts,
+    particle_system: Res<ParticleSystem>,
+    stats: Res<SimulationStats>,
+    mut evolution: ResMut<Evolution>,
+) {
+    egui::Window::new("Evolution")
+        .default_pos([400.0, 600.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut evolution.enabled, "Enable evolution");
+            ui.horizontal(|ui| {
+                ui.label("Species mutation rate:");
+                ui.add(
+                    egui::Slider::new(&mut evolution.species_mutation_rate, 0.0..=0.1)
+                        .logarithmic(true),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Matrix drift rate:");
+                ui.add(
+                    egui::Slider::new(&mut evolution.matrix_drift_rate, 0.0..=0.5)
+                        .logarithmic(true),
+                );
+            });
+            ui.label(format!("Mutations so far: {}", evolution.mutations));
+
+            let Some(latest) = stats.latest() else {
+                return;
+            };
+            let total: usize = latest.species_counts.iter().sum();
+            if total == 0 {
+                return;
+            }
+            let mut ranking: Vec<(usize, usize)> =
+                latest.species_counts.iter().copied().enumerate().collect();
+            ranking.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+
+            ui.add_space(5.0);
+            ui.label("Dominant species");
+            for (species, count) in ranking.into_iter().take(DOMINANT_SPECIES_SHOWN) {
+                let Some(color) = particle_system.colors.get(species) else {
+                    continue;
+                };
+                ui.horizontal(|ui| {
+                    let (rect, _) =
+                        ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                    ui.painter().rect_filled(rect, 2.0, egui_color(*color));
+                    ui.label(format!(
+                        "#{}: {} ({:.1}%)",
+                        species,
+                        count,
+                        count as f32 / total as f32 * 100.0
+                    ));
+                });
+            }
+        });
+}
@@ -0,0 +1,221 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+
+use crate::{egui_color, ParticleSystem};
+
+/// Magnitudes from which a relationship is called strong, and below which
+/// weak.
+const STRONG: f32 = 0.7;
+const WEAK: f32 = 0.35;
+
+/// Options of the "Explain Rules" panel.
+pub struct ExplainOptions {
+    /// Weakest entry worth mentioning.
+    threshold: f32,
+    /// Most relationships listed.
+    limit: usize,
+}
+
+impl Default for ExplainOptions {
+    fn default() -> Self {
+        ExplainOptions {
+            threshold: 0.3,
+            limit: 20,
+        }
+    }
+}
+
+/// A piece of a description: a species, drawn in its color, or plain text.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Part {
+    Species(usize),
+    Text(String),
+}
+
+/// One sentence about a species, or about a pair in both directions.
+pub struct Relationship {
+    /// Strongest matrix entry the sentence is about.
+    pub magnitude: f32,
+    pub parts: Vec<Part>,
+}
+
+impl Relationship {
+    pub fn text(&self) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Species(species) => format!("Species {}", species + 1),
+                Part::Text(text) => text.clone(),
+            })
+            .collect()
+    }
+}
+
+fn adverb(value: f32) -> &'static str {
+    match value.abs() {
+        magnitude if magnitude >= STRONG => "strongly ",
+        magnitude if magnitude < WEAK => "weakly ",
+        _ => "",
+    }
+}
+
+/// Every entry of at least `threshold` in words, strongest first. A pair
+/// whose species react to each other the same way gets one mutual sentence;
+/// otherwise each direction gets a clause. `matrix[i][j]` is how `i` reacts
+/// to `j`.
+pub fn relationships(matrix: &[Vec<f32>], threshold: f32) -> Vec<Relationship> {
+    let n = matrix.len();
+    let mut relationships = Vec::new();
+    for i in 0..n {
+        let own = matrix[i][i];
+        if own.abs() >= threshold {
+            let verb = if own > 0.0 {
+                "clumps together"
+            } else {
+                "spreads out"
+            };
+            relationships.push(Relationship {
+                magnitude: own.abs(),
+                parts: vec![
+                    Part::Species(i),
+                    Part::Text(format!(" {}{}", adverb(own), verb)),
+                ],
+            });
+        }
+        for j in i + 1..n {
+            let mut directions: Vec<(usize, usize, f32)> = [(i, j), (j, i)]
+                .into_iter()
+                .map(|(from, to)| (from, to, matrix[from][to]))
+                .filter(|&(.., value)| value.abs() >= threshold)
+                .collect();
+            let Some(magnitude) = directions
+                .iter()
+                .map(|&(.., value)| value.abs())
+                .reduce(f32::max)
+            else {
+                continue;
+            };
+            directions.sort_by(|a, b| b.2.abs().total_cmp(&a.2.abs()));
+            let parts = match directions[..] {
+                [(_, _, first), (_, _, second)] if first.signum() == second.signum() => {
+                    let verb = if first > 0.0 { "attract" } else { "avoid" };
+                    vec![
+                        Part::Species(i),
+                        Part::Text(" and ".into()),
+                        Part::Species(j),
+                        Part::Text(format!(" {}{} each other", adverb(magnitude), verb)),
+                    ]
+                }
+                _ => {
+                    let mut parts = Vec::new();
+                    for &(from, to, value) in &directions {
+                        if !parts.is_empty() {
+                            parts.push(Part::Text("; ".into()));
+                        }
+                        let verb = if value > 0.0 { "chases" } else { "flees" };
+                        parts.extend([
+                            Part::Species(from),
+                            Part::Text(format!(" {}{} ", adverb(value), verb)),
+                            Part::Species(to),
+                        ]);
+                    }
+                    parts
+                }
+            };
+            relationships.push(Relationship { magnitude, parts });
+        }
+    }
+    relationships.sort_by(|a, b| b.magnitude.total_cmp(&a.magnitude));
+    relationships
+}
+
+/// The "Explain Rules" dock panel: the strongest relationships in the matrix
+/// in plain words, to make sense of big matrices.
+pub fn rules_panel(
+    InMut(ui): InMut<egui::Ui>,
+    particle_system: Res<ParticleSystem>,
+    mut options: Local<ExplainOptions>,
+) {
+    ui.add(egui::Slider::new(&mut options.threshold, 0.05..=1.0).text("weakest entry shown"));
+    ui.add(egui::Slider::new(&mut options.limit, 1..=200).text("most relationships"));
+    let relationships = relationships(&particle_system.behavior_matrix, options.threshold);
+    let shown = relationships.len().min(options.limit);
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "{} of {} relationships",
+            shown,
+            relationships.len()
+        ));
+        if ui.button("Copy as Text").clicked() {
+            let text: Vec<String> = relationships[..shown]
+                .iter()
+                .map(Relationship::text)
+                .collect();
+            ui.ctx().copy_text(text.join("\n"));
+        }
+    });
+    ui.separator();
+
+    let colors = &particle_system.colors;
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for relationship in &relationships[..shown] {
+            ui.horizontal_wrapped(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                for part in &relationship.parts {
+                    match part {
+                        Part::Species(species) => {
+                            let mut name =
+                                egui::RichText::new(format!("Species {}", species + 1)).strong();
+                            if let Some(&color) = colors.get(*species) {
+                                name = name.color(egui_color(color));
+                            }
+                            ui.label(name);
+                        }
+                        Part::Text(text) => {
+                            ui.label(text);
+                        }
+                    }
+                }
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_chasing_and_fleeing_strongest_first() {
+        let matrix = vec![
+            vec![0.0, 0.0, 0.0],
+            vec![0.0, 0.5, 0.9],
+            vec![0.0, -0.4, -0.1],
+        ];
+        let text: Vec<String> = relationships(&matrix, 0.3)
+            .iter()
+            .map(Relationship::text)
+            .collect();
+        assert_eq!(
+            text,
+            vec![
+                "Species 2 strongly chases Species 3; Species 3 flees Species 2",
+                "Species 2 clumps together",
+            ]
+        );
+    }
+
+    #[test]
+    fn mutual_relationships_share_a_sentence() {
+        let matrix = vec![vec![0.0, -0.2], vec![-0.8, 0.0]];
+        let text: Vec<String> = relationships(&matrix, 0.1)
+            .iter()
+            .map(Relationship::text)
+            .collect();
+        assert_eq!(
+            text,
+            vec!["Species 1 and Species 2 strongly avoid each other"]
+        );
+        assert!(relationships(&matrix, 0.9).is_empty());
+    }
+}
@@ -0,0 +1,278 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{fs::OpenOptions, io::Write};
+
+use crate::{
+    spawn::SpawnRequest,
+    stats::{SimulationStats, StatsSample},
+    ui_enabled, ParticleCount, ParticleSystem,
+};
+
+const LOG_PATH: &str = "explore_log.txt";
+/// Average speeds, in world units per second, that count as lively: slower
+/// rules have frozen, faster ones are boiling noise.
+const SPEED_BAND: (f32, f32) = (5.0, 400.0);
+/// Cluster counts that count as structured rather than one blob or dust.
+const CLUSTER_BAND: (usize, usize) = (3, 300);
+const BEST_SHOWN: usize = 10;
+
+pub struct ExplorePlugin;
+
+impl Plugin for ExplorePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AutoExplore::default()).add_systems(
+            Update,
+            (explore, explore_ui_system.run_if(ui_enabled)).chain(),
+        );
+    }
+}
+
+/// Behavior matrix for `n` species drawn from `seed`, so logged seeds can be
+/// replayed.
+pub fn seeded_matrix(n: usize, seed: u64) -> Vec<Vec<f32>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..n)
+        .map(|_| (0..n).map(|_| rng.random_range(-1.0..=1.0)).collect())
+        .collect()
+}
+
+/// How interesting a run looked, from 0 to 1, over stats samples taken after
+/// its warm-up. Structured runs keep a steady number of clusters, a stable
+/// population and a moderate kinetic energy.
+pub fn score(samples: &[&StatsSample]) -> f32 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let clusters: Vec<f32> = samples.iter().map(|s| s.cluster_count as f32).collect();
+    let (cluster_mean, cluster_spread) = mean_and_variation(&clusters);
+    let structured = (CLUSTER_BAND.0 as f32..=CLUSTER_BAND.1 as f32).contains(&cluster_mean);
+    let cluster_term = if structured {
+        (1.0 - cluster_spread).max(0.0)
+    } else {
+        0.0
+    };
+
+    let speed = samples.iter().map(|s| s.average_speed).sum::<f32>() / samples.len() as f32;
+    let speed_term = if speed < SPEED_BAND.0 {
+        speed / SPEED_BAND.0
+    } else if speed > SPEED_BAND.1 {
+        SPEED_BAND.1 / speed
+    } else {
+        1.0
+    };
+
+    // Mean variation of each species' count over the window
+    let species = samples[0].species_counts.len();
+    let population_term = if species == 0 {
+        1.0
+    } else {
+        let variation: f32 = (0..species)
+            .map(|index| {
+                let counts: Vec<f32> = samples
+                    .iter()
+                    .map(|s| s.species_counts.get(index).copied().unwrap_or(0) as f32)
+                    .collect();
+                mean_and_variation(&counts).1
+            })
+            .sum::<f32>()
+            / species as f32;
+        (1.0 - variation).max(0.0)
+    };
+
+    cluster_term * speed_term * population_term
+}
+
+/// Mean and coefficient of variation.
+fn mean_and_variation(values: &[f32]) -> (f32, f32) {
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    if mean <= 0.0 {
+        return (mean, 0.0);
+    }
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f32>()
+        / values.len() as f32;
+    (mean, variance.sqrt() / mean)
+}
+
+/// Tries random matrices one after another: each is scored after a warm-up,
+/// boring ones are dropped right away and interesting ones stay on screen
+/// longer and have their seed logged.
+#[derive(Resource)]
+pub struct AutoExplore {
+    pub enabled: bool,
+    /// Seconds before scoring starts, so the start-up transient is ignored.
+    pub warmup: f32,
+    /// Seconds of stats samples the score is taken over.
+    pub window: f32,
+    /// Scores at or above this keep the matrix.
+    pub threshold: f32,
+    /// Seconds an interesting matrix stays before the next one.
+    pub keep_time: f32,
+    seed: u64,
+    started: f32,
+    score: Option<f32>,
+    tried: u32,
+    best: Vec<(u64, f32)>,
+    replay_seed: u64,
+    message: Option<String>,
+}
+
+impl Default for AutoExplore {
+    fn default() -> Self {
+        AutoExplore {
+            enabled: false,
+            warmup: 10.0,
+            window: 5.0,
+            threshold: 0.5,
+            keep_time: 60.0,
+            seed: 0,
+            started: 0.0,
+            score: None,
+            tried: 0,
+            best: Vec::new(),
+            replay_seed: 0,
+            message: None,
+        }
+    }
+}
+
+impl AutoExplore {
+    fn record(&mut self, score: f32, species: usize) {
+        self.best.push((self.seed, score));
+        self.best.sort_by(|a, b| b.1.total_cmp(&a.1));
+        self.best.truncate(BEST_SHOWN);
+        let line = format!("seed {} species {} score {:.3}", self.seed, species, score);
+        info!("Interesting rules: {}", line);
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(LOG_PATH)
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(error) = result {
+            self.message = Some(format!("Could not write {}: {}", LOG_PATH, error));
+        }
+    }
+}
+
+fn explore(
+    time: Res<Time>,
+    stats: Res<SimulationStats>,
+    particle_count: Res<ParticleCount>,
+    mut explorer: ResMut<AutoExplore>,
+    mut particle_system: ResMut<ParticleSystem>,
+    mut spawn_requests: EventWriter<SpawnRequest>,
+) {
+    if !explorer.enabled {
+        explorer.score = None;
+        return;
+    }
+    let now = time.elapsed_secs();
+    let age = now - explorer.started;
+    let current = explorer.score;
+    let next = match current {
+        // Not started yet, or the previous candidate is done
+        None if explorer.tried == 0 => true,
+        Some(score) => score < explorer.threshold || age >= explorer.keep_time,
+        None => {
+            if age < explorer.warmup + explorer.window {
+                false
+            } else {
+                let scoring_from = explorer.started + explorer.warmup;
+                let samples: Vec<&StatsSample> = stats
+                    .samples
+                    .iter()
+                    .filter(|sample| sample.time >= scoring_from)
+                    .collect();
+                let score = score(&samples);
+                explorer.score = Some(score);
+                if score >= explorer.threshold {
+                    explorer.record(score, particle_system.colors.len());
+                }
+                false
+            }
+        }
+    };
+    if !next {
+        return;
+    }
+
+    explorer.seed = rand::rng().random();
+    explorer.started = now;
+    explorer.score = None;
+    explorer.tried += 1;
+    let n = particle_system.colors.len();
+    particle_system.behavior_matrix = seeded_matrix(n, explorer.seed);
+    spawn_requests.send(SpawnRequest::Reshuffle {
+        count: particle_count.count,
+    });
+}
+
+fn explore_ui_system(
+    mut contexts: EguiContexts,
+    time: Res<Time>,
+    mut explorer: ResMut<AutoExplore>,
+    mut particle_system: ResMut<ParticleSystem>,
+) {
+    egui::Window::new("Auto Explore")
+        .default_pos([400.0, 940.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Tries random matrices, skipping boring ones and logging good seeds.");
+            ui.add_space(5.0);
+            let explorer = &mut *explorer;
+            if ui.checkbox(&mut explorer.enabled, "Explore").changed() {
+                explorer.tried = 0;
+            }
+            ui.add(egui::Slider::new(&mut explorer.warmup, 1.0..=60.0).text("warm-up (s)"));
+            ui.add(egui::Slider::new(&mut explorer.window, 2.0..=30.0).text("scoring window (s)"));
+            ui.add(egui::Slider::new(&mut explorer.threshold, 0.0..=1.0).text("threshold"));
+            ui.add(egui::Slider::new(&mut explorer.keep_time, 10.0..=600.0).text("keep (s)"));
+
+            if explorer.enabled {
+                let age = time.elapsed_secs() - explorer.started;
+                match explorer.score {
+                    Some(score) => ui.label(format!(
+                        "Candidate {} (seed {}): score {:.2}",
+                        explorer.tried, explorer.seed, score
+                    )),
+                    None => ui.label(format!(
+                        "Candidate {} (seed {}): scoring in {:.0} s",
+                        explorer.tried,
+                        explorer.seed,
+                        (explorer.warmup + explorer.window - age).max(0.0)
+                    )),
+                };
+            }
+
+            ui.add_space(5.0);
+            ui.label(format!("Best seeds (also appended to {})", LOG_PATH));
+            let mut chosen = None;
+            for &(seed, score) in &explorer.best {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} ({:.2})", seed, score));
+                    if ui.small_button("Load").clicked() {
+                        chosen = Some(seed);
+                    }
+                });
+            }
+            ui.horizontal(|ui| {
+                ui.label("Seed");
+                ui.add(egui::DragValue::new(&mut explorer.replay_seed));
+                if ui.button("Load").clicked() {
+                    chosen = Some(explorer.replay_seed);
+                }
+            });
+            if let Some(seed) = chosen {
+                // Stop exploring so the loaded rules stay
+                explorer.enabled = false;
+                let n = particle_system.colors.len();
+                particle_system.behavior_matrix = seeded_matrix(n, seed);
+            }
+            if let Some(message) = &explorer.message {
+                ui.label(message);
+            }
+        });
+}
@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{settings::Settings, ui_enabled, Particle, Velocity};
+
+const EXPORT_DIR: &str = "exports";
+const CSV_HEADER: &str = "tick,time,entity,species,x,y,vx,vy";
+
+pub struct ExportPlugin;
+
+impl Plugin for ExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ExportSettings::default()).add_systems(
+            Update,
+            (export_particles, export_ui_system.run_if(ui_enabled)),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct ExportSettings {
+    /// Write one row per particle every `interval` ticks while recording.
+    pub recording: bool,
+    pub interval: u32,
+    tick: u64,
+    snapshot_requested: bool,
+    recording_path: Option<PathBuf>,
+    last_message: Option<String>,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        ExportSettings {
+            recording: false,
+            interval: 60,
+            tick: 0,
+            snapshot_requested: false,
+            recording_path: None,
+            last_message: None,
+        }
+    }
+}
+
+fn export_particles(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    key_settings: Res<Settings>,
+    time: Res<Time>,
+    mut settings: ResMut<ExportSettings>,
+    particles: Query<(Entity, &Transform, &Particle, &Velocity)>,
+) {
+    settings.tick += 1;
+    let tick = settings.tick;
+    let elapsed = time.elapsed_secs();
+
+    if keyboard.just_pressed(key_settings.keys.export) {
+        settings.snapshot_requested = true;
+    }
+
+    if settings.snapshot_requested {
+        settings.snapshot_requested = false;
+        let path = PathBuf::from(EXPORT_DIR).join(format!("particles_{}.csv", tick));
+        let result = create_csv(&path).and_then(|mut writer| {
+            write_rows(&mut writer, tick, elapsed, &particles)?;
+            writer.flush()
+        });
+        settings.last_message = Some(report(&path, result));
+    }
+
+    if !settings.recording {
+        settings.recording_path = None;
+        return;
+    }
+    if !tick.is_multiple_of(settings.interval.max(1) as u64) {
+        return;
+    }
+
+    let result = match settings.recording_path.clone() {
+        Some(path) => OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .map(BufWriter::new)
+            .map(|writer| (path, writer)),
+        None => {
+            let path = PathBuf::from(EXPORT_DIR).join(format!("recording_{}.csv", tick));
+            create_csv(&path).map(|writer| (path, writer))
+        }
+    };
+    match result {
+        Ok((path, mut writer)) => {
+            if let Err(err) =
+                write_rows(&mut writer, tick, elapsed, &particles).and_then(|_| writer.flush())
+            {
+                settings.recording = false;
+                settings.last_message = Some(report(&path, Err(err)));
+            }
+            settings.recording_path = Some(path);
+        }
+        Err(err) => {
+            settings.recording = false;
+            settings.last_message = Some(format!("Export failed: {}", err));
+        }
+    }
+}
+
+fn create_csv(path: &Path) -> io::Result<BufWriter<File>> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "{}", CSV_HEADER)?;
+    Ok(writer)
+}
+
+fn write_rows(
+    writer: &mut impl Write,
+    tick: u64,
+    time: f32,
+    particles: &Query<(Entity, &Transform, &Particle, &Velocity)>,
+) -> io::Result<()> {
+    for (entity, transform, particle, velocity) in particles {
+        let pos = transform.translation;
+        writeln!(
+            writer,
+            "{},{:.4},{},{},{:.3},{:.3},{:.3},{:.3}",
+            tick,
+            time,
+            entity.index(),
+            particle.color_id,
+            pos.x,
+            pos.y,
+            velocity.0.x,
+            velocity.0.y
+        )?;
+    }
+    Ok(())
+}
+
+fn report(path: &Path, result: io::Result<()>) -> String {
+    match result {
+        Ok(()) => {
+            info!("Exported particles to {}", path.display());
+            format!("Exported to {}", path.display())
+        }
+        Err(err) => {
+            error!("Failed to export particles to {}: {}", path.display(), err);
+            format!("Export failed: {}", err)
+        }
+    }
+}
+
+fn export_ui_system(mut contexts: EguiContexts, mut settings: ResMut<ExportSettings>) {
+    egui::Window::new("Export")
+        .default_pos([10.0, 940.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            if ui.button("Export Snapshot (X)").clicked() {
+                settings.snapshot_requested = true;
+            }
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut settings.recording, "Record every");
+                ui.add(egui::DragValue::new(&mut settings.interval).range(1..=10000));
+                ui.label("ticks");
+            });
+            if let Some(message) = &settings.last_message {
+                ui.label(message);
+            }
+        });
+}
@@ -0,0 +1,297 @@
+use bevy::{
+    image::{CompressedImageFormats, ImageSampler, ImageType},
+    prelude::*,
+    render::render_asset::RenderAssetUsages,
+    window::FileDragAndDrop,
+};
+use bevy_egui::{egui, EguiContexts};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::f32::consts::TAU;
+
+use crate::{freeze, replay, settings::Settings, spawn, ui_enabled, update_particles, Particle};
+
+/// World units between the arrows drawn for the field.
+const ARROW_SPACING: f32 = 80.0;
+const ARROW_COLOR: Color = Color::srgba(0.6, 0.8, 1.0, 0.4);
+
+pub struct FlowPlugin;
+
+impl Plugin for FlowPlugin {
+    fn build(&self, app: &mut App) {
+        let mut field = FlowField::default();
+        if let Some(path) = image_from_args() {
+            field.load_image(&path);
+        }
+        app.insert_resource(field).add_systems(
+            Update,
+            (
+                toggle_flow,
+                read_dropped_images,
+                advect_particles
+                    .after(update_particles)
+                    .run_if(not(replay::is_playing_back)),
+                draw_flow,
+                flow_ui_system.run_if(ui_enabled),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Whether a dropped file should become the flow image.
+pub fn is_image(name: &str) -> bool {
+    name.to_lowercase().ends_with(".png")
+}
+
+fn image_from_args() -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|arg| arg == "--flow-image")?;
+    args.get(index + 1).cloned()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FlowSource {
+    Noise,
+    /// Brightness of the loaded image picks the flow direction.
+    Image,
+}
+
+/// A background velocity field that carries particles along, independent of
+/// their interactions.
+#[derive(Resource)]
+pub struct FlowField {
+    pub enabled: bool,
+    pub source: FlowSource,
+    /// Flow speed in world units per second.
+    pub strength: f32,
+    /// Noise features per 1000 world units.
+    pub frequency: f32,
+    /// How fast the noise pattern drifts, in noise units per second.
+    pub drift: f32,
+    pub show: bool,
+    noise: Perlin,
+    seed: u64,
+    image: Option<Image>,
+    status: Option<String>,
+    elapsed: f32,
+}
+
+impl Default for FlowField {
+    fn default() -> Self {
+        FlowField {
+            enabled: false,
+            source: FlowSource::Noise,
+            strength: 60.0,
+            frequency: 2.0,
+            drift: 0.05,
+            show: false,
+            noise: Perlin::new(0),
+            seed: 0,
+            image: None,
+            status: None,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl FlowField {
+    /// Flow velocity at `pos`. The image is stretched over the world bounds.
+    pub fn velocity(&self, pos: Vec2) -> Vec2 {
+        let angle = match (self.source, &self.image) {
+            (FlowSource::Image, Some(image)) => {
+                let bounds = spawn::world_bounds();
+                let uv = ((pos - bounds.min) / bounds.size()).clamp(Vec2::ZERO, Vec2::ONE);
+                let size = image.size();
+                let x = ((uv.x * size.x as f32) as u32).min(size.x - 1);
+                let y = (((1.0 - uv.y) * size.y as f32) as u32).min(size.y - 1);
+                let brightness = image
+                    .get_color_at(x, y)
+                    .map(|color| color.luminance())
+                    .unwrap_or(0.0);
+                brightness * TAU
+            }
+            (FlowSource::Image, None) => return Vec2::ZERO,
+            (FlowSource::Noise, _) => {
+                let point = pos * self.frequency / 1000.0 + Vec2::splat(self.elapsed * self.drift);
+                // Noise rarely leaves -0.5..0.5, so stretch it to all directions
+                self.noise.sample(point) * 2.0 * TAU
+            }
+        };
+        Vec2::from_angle(angle) * self.strength
+    }
+
+    fn load_image(&mut self, path: &str) {
+        let extension = path.rsplit('.').next().unwrap_or_default().to_lowercase();
+        let image = std::fs::read(path)
+            .map_err(|error| error.to_string())
+            .and_then(|bytes| {
+                Image::from_buffer(
+                    &bytes,
+                    ImageType::Extension(&extension),
+                    CompressedImageFormats::NONE,
+                    false,
+                    ImageSampler::Default,
+                    RenderAssetUsages::MAIN_WORLD,
+                )
+                .map_err(|error| error.to_string())
+            });
+        match image {
+            Ok(image) => {
+                info!("Loaded flow image {}", path);
+                self.image = Some(image);
+                self.source = FlowSource::Image;
+                self.status = Some(format!("Loaded {}", path));
+            }
+            Err(error) => {
+                warn!("Could not load flow image {}: {}", path, error);
+                self.status = Some(format!("Could not load {}: {}", path, error));
+            }
+        }
+    }
+}
+
+/// Classic 2D gradient noise over a seeded permutation table.
+struct Perlin {
+    permutation: [u8; 512],
+}
+
+impl Perlin {
+    fn new(seed: u64) -> Self {
+        let mut table: Vec<u8> = (0..=255).collect();
+        table.shuffle(&mut StdRng::seed_from_u64(seed));
+        let mut permutation = [0; 512];
+        for (index, value) in permutation.iter_mut().enumerate() {
+            *value = table[index % 256];
+        }
+        Perlin { permutation }
+    }
+
+    fn gradient(&self, x: i32, y: i32) -> Vec2 {
+        let hash =
+            self.permutation[self.permutation[(x & 255) as usize] as usize + (y & 255) as usize];
+        Vec2::from_angle(hash as f32 / 256.0 * TAU)
+    }
+
+    /// Within about -0.7..0.7 and mostly -0.5..0.5, zero at every lattice point.
+    fn sample(&self, point: Vec2) -> f32 {
+        let cell = point.floor();
+        let (x, y) = (cell.x as i32, cell.y as i32);
+        let offset = point - cell;
+        let fade = offset * offset * offset * (offset * (offset * 6.0 - 15.0) + 10.0);
+        let corner = |dx: i32, dy: i32| {
+            self.gradient(x + dx, y + dy)
+                .dot(offset - Vec2::new(dx as f32, dy as f32))
+        };
+        let bottom = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * fade.x;
+        let top = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * fade.x;
+        bottom + (top - bottom) * fade.y
+    }
+}
+
+fn toggle_flow(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    mut field: ResMut<FlowField>,
+) {
+    if keyboard.just_pressed(settings.keys.toggle_flow) {
+        field.enabled = !field.enabled;
+    }
+}
+
+fn read_dropped_images(mut drops: EventReader<FileDragAndDrop>, mut field: ResMut<FlowField>) {
+    for drop in drops.read() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = drop else {
+            continue;
+        };
+        let path = path_buf.to_string_lossy();
+        if is_image(&path) {
+            field.load_image(&path);
+        }
+    }
+}
+
+/// Moves particles with the flow. `update_particles` picks the new positions
+/// up like any other moved particle.
+fn advect_particles(
+    time: Res<Time>,
+    mut field: ResMut<FlowField>,
+    mut particles: Query<&mut Transform, (With<Particle>, Without<freeze::Frozen>)>,
+) {
+    if !field.enabled {
+        return;
+    }
+    let step = time.delta_secs();
+    field.elapsed += step;
+    for mut transform in &mut particles {
+        let flow = field.velocity(transform.translation.truncate());
+        transform.translation += (flow * step).extend(0.0);
+    }
+}
+
+fn draw_flow(field: Res<FlowField>, mut gizmos: Gizmos) {
+    if !field.enabled || !field.show || field.strength <= 0.0 {
+        return;
+    }
+    let bounds = spawn::world_bounds();
+    let length = ARROW_SPACING * 0.6;
+    let mut y = bounds.min.y + ARROW_SPACING / 2.0;
+    while y < bounds.max.y {
+        let mut x = bounds.min.x + ARROW_SPACING / 2.0;
+        while x < bounds.max.x {
+            let start = Vec2::new(x, y);
+            let direction = field.velocity(start).normalize_or_zero();
+            gizmos.arrow_2d(start, start + direction * length, ARROW_COLOR);
+            x += ARROW_SPACING;
+        }
+        y += ARROW_SPACING;
+    }
+}
+
+fn flow_ui_system(
+    mut contexts: EguiContexts,
+    settings: Res<Settings>,
+    mut field: ResMut<FlowField>,
+) {
+    egui::Window::new("Flow Field")
+        .default_pos([400.0, 760.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let field = &mut *field;
+            ui.checkbox(
+                &mut field.enabled,
+                format!("Carry particles along ({:?})", settings.keys.toggle_flow),
+            );
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut field.source, FlowSource::Noise, "Noise");
+                ui.add_enabled_ui(field.image.is_some(), |ui| {
+                    ui.selectable_value(&mut field.source, FlowSource::Image, "Image");
+                });
+            });
+            ui.add(egui::Slider::new(&mut field.strength, 0.0..=500.0).text("strength"));
+            if field.source == FlowSource::Noise {
+                ui.add(
+                    egui::Slider::new(&mut field.frequency, 0.1..=20.0)
+                        .logarithmic(true)
+                        .text("frequency"),
+                );
+                ui.add(egui::Slider::new(&mut field.drift, 0.0..=1.0).text("drift"));
+                ui.horizontal(|ui| {
+                    ui.label("Seed");
+                    if ui.add(egui::DragValue::new(&mut field.seed)).changed() {
+                        field.noise = Perlin::new(field.seed);
+                    }
+                });
+            } else {
+                ui.label("Brightness sets the direction, from east through a full turn.");
+            }
+            ui.checkbox(&mut field.show, "Show arrows");
+            if cfg!(target_arch = "wasm32") {
+                ui.label("Flow images can be loaded in the desktop build.");
+            } else {
+                ui.label("Drop a PNG on the window to flow along its brightness.");
+            }
+            if let Some(status) = &field.status {
+                ui.label(status);
+            }
+        });
+}
@@ -0,0 +1,351 @@
+use bevy::math::Vec2;
+use bevy_egui::egui;
+use serde::{Deserialize, Serialize};
+
+/// Samples in a newly created force table.
+const TABLE_SAMPLES: usize = 16;
+
+/// Distance → force shape shared by every species pair. `distance` is a
+/// fraction of the attraction radius in 0..1 and `behavior` the pair's matrix
+/// entry; positive results attract and negative ones repel. Shared across
+/// threads by the parallel backend.
+pub trait ForceProfile: Sync {
+    fn force(&self, distance: f32, behavior: f32) -> f32;
+}
+
+/// The classic particle life shape: universal repulsion below `beta`, then a
+/// tent that peaks at `behavior` at `gamma` and falls back to zero at 1.
+pub struct LinearPeak {
+    pub beta: f32,
+    pub gamma: f32,
+}
+
+impl ForceProfile for LinearPeak {
+    fn force(&self, distance: f32, behavior: f32) -> f32 {
+        force_magnitude(distance, self.beta, self.gamma, behavior)
+    }
+}
+
+/// The linear peak force at `distance` (a fraction of the radius): -1 at
+/// contact rising to 0 at `beta`, then `behavior` at `gamma` and 0 again at 1.
+pub fn force_magnitude(distance: f32, beta: f32, gamma: f32, behavior: f32) -> f32 {
+    if distance < beta {
+        -1.0 + (distance / beta)
+    } else if distance < gamma {
+        behavior * ((distance - beta) / (gamma - beta))
+    } else {
+        behavior * ((1.0 - distance) / (1.0 - gamma))
+    }
+}
+
+/// Lennard-Jones style: a steep core below `sigma` and an attractive well
+/// scaled by `behavior`, normalized so the well bottoms out at 1.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LennardJones {
+    pub sigma: f32,
+}
+
+impl ForceProfile for LennardJones {
+    fn force(&self, distance: f32, behavior: f32) -> f32 {
+        // Multiplied out: powi may round differently on each target
+        let ratio = self.sigma / distance.max(f32::EPSILON);
+        let squared = ratio * ratio;
+        let s = squared * (squared * squared);
+        let force = 4.0 * (s - s * s);
+        if force < 0.0 {
+            force.max(-1.0)
+        } else {
+            force * behavior
+        }
+    }
+}
+
+/// Repulsion inside `core`, then `behavior` falling off with the inverse
+/// square of distance, tapered to zero at the edge of the radius.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct InverseSquare {
+    pub core: f32,
+}
+
+impl ForceProfile for InverseSquare {
+    fn force(&self, distance: f32, behavior: f32) -> f32 {
+        if distance < self.core {
+            -1.0 + (distance / self.core)
+        } else {
+            let ratio = self.core / distance;
+            let falloff = ratio * ratio;
+            behavior * falloff * (1.0 - distance) / (1.0 - self.core)
+        }
+    }
+}
+
+/// User-drawn curve sampled evenly over 0..1 and linearly interpolated.
+/// Negative samples are universal repulsion; positive samples are scaled by
+/// `behavior`.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForceTable {
+    pub samples: Vec<f32>,
+}
+
+impl ForceTable {
+    /// Samples `profile` with a behavior of 1, as a starting point for editing.
+    pub fn sampled(profile: &dyn ForceProfile) -> Self {
+        ForceTable {
+            samples: (0..TABLE_SAMPLES)
+                .map(|i| profile.force(i as f32 / (TABLE_SAMPLES - 1) as f32, 1.0))
+                .collect(),
+        }
+    }
+}
+
+impl ForceProfile for ForceTable {
+    fn force(&self, distance: f32, behavior: f32) -> f32 {
+        let last = self.samples.len().saturating_sub(1);
+        if last == 0 {
+            return 0.0;
+        }
+        let position = distance.clamp(0.0, 1.0) * last as f32;
+        let index = (position as usize).min(last - 1);
+        let t = position - index as f32;
+        let force = self.samples[index] + (self.samples[index + 1] - self.samples[index]) * t;
+        if force < 0.0 {
+            force
+        } else {
+            force * behavior
+        }
+    }
+}
+
+/// The force profile a simulation uses, as stored in presets and saves.
+/// Linear peak takes its parameters from the simulation's beta and gamma.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum ForceShape {
+    #[default]
+    LinearPeak,
+    LennardJones(LennardJones),
+    InverseSquare(InverseSquare),
+    Table(ForceTable),
+}
+
+impl ForceShape {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ForceShape::LinearPeak => "Linear peak",
+            ForceShape::LennardJones(_) => "Lennard-Jones",
+            ForceShape::InverseSquare(_) => "Inverse square",
+            ForceShape::Table(_) => "Table",
+        }
+    }
+
+    pub fn profile(&self, beta: f32, gamma: f32) -> Box<dyn ForceProfile + '_> {
+        match self {
+            ForceShape::LinearPeak => Box::new(LinearPeak { beta, gamma }),
+            ForceShape::LennardJones(profile) => Box::new(*profile),
+            ForceShape::InverseSquare(profile) => Box::new(*profile),
+            ForceShape::Table(table) => Box::new(TableRef(table)),
+        }
+    }
+
+    /// Shape picker plus the parameters of the selected shape.
+    pub fn settings_ui(&mut self, ui: &mut egui::Ui, beta: f32, gamma: f32) {
+        let mut selected = self.label();
+        egui::ComboBox::from_label("Force Profile")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for label in ["Linear peak", "Lennard-Jones", "Inverse square", "Table"] {
+                    ui.selectable_value(&mut selected, label, label);
+                }
+            });
+        if selected != self.label() {
+            let shape = match selected {
+                "Lennard-Jones" => ForceShape::LennardJones(LennardJones { sigma: beta }),
+                "Inverse square" => ForceShape::InverseSquare(InverseSquare { core: beta }),
+                // Start the table from whatever shape was selected before
+                "Table" => ForceShape::Table(ForceTable::sampled(&*self.profile(beta, gamma))),
+                _ => ForceShape::LinearPeak,
+            };
+            *self = shape;
+        }
+
+        match self {
+            ForceShape::LinearPeak => {
+                ui.label("Shaped by beta and gamma.");
+            }
+            ForceShape::LennardJones(profile) => {
+                ui.add(egui::Slider::new(&mut profile.sigma, 0.05..=0.9).text("sigma"));
+            }
+            ForceShape::InverseSquare(profile) => {
+                ui.add(egui::Slider::new(&mut profile.core, 0.05..=0.9).text("core"));
+            }
+            ForceShape::Table(table) => {
+                ui.collapsing("Samples", |ui| {
+                    for sample in &mut table.samples {
+                        ui.add(egui::Slider::new(sample, -1.0..=1.0));
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Caps on the forces particles feel, so a single close encounter cannot
+/// fling a particle off-screen. Both are off by default.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForceLimit {
+    /// Largest length of a particle's total force; 0 leaves it unclamped.
+    pub max_force: f32,
+    /// Clamp every neighbor's contribution to -1..1 before averaging, so
+    /// scaled zones and steep profiles cannot let one pair dominate.
+    pub normalize_neighbors: bool,
+}
+
+impl Default for ForceLimit {
+    fn default() -> Self {
+        ForceLimit {
+            max_force: 0.0,
+            normalize_neighbors: false,
+        }
+    }
+}
+
+impl ForceLimit {
+    pub fn neighbor(&self, force: f32) -> f32 {
+        if self.normalize_neighbors {
+            force.clamp(-1.0, 1.0)
+        } else {
+            force
+        }
+    }
+
+    pub fn total(&self, force: Vec2) -> Vec2 {
+        if self.max_force > 0.0 {
+            force.clamp_length_max(self.max_force)
+        } else {
+            force
+        }
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mut clamped = self.max_force > 0.0;
+            if ui.checkbox(&mut clamped, "Clamp force").changed() {
+                self.max_force = if clamped { 1.0 } else { 0.0 };
+            }
+            if clamped {
+                ui.add(egui::Slider::new(&mut self.max_force, 0.05..=5.0).text("max"));
+            }
+        });
+        ui.checkbox(
+            &mut self.normalize_neighbors,
+            "Normalize each neighbor's force",
+        );
+    }
+}
+
+/// Borrows a table so building the profile each tick does not copy samples.
+struct TableRef<'a>(&'a ForceTable);
+
+impl ForceProfile for TableRef<'_> {
+    fn force(&self, distance: f32, behavior: f32) -> f32 {
+        self.0.force(distance, behavior)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEHAVIORS: [f32; 5] = [-1.0, -0.4, 0.0, 0.6, 1.0];
+    const STEPS: usize = 1000;
+
+    fn distances() -> impl Iterator<Item = f32> {
+        (0..=STEPS).map(|i| i as f32 / STEPS as f32)
+    }
+
+    #[test]
+    fn linear_peak_hits_its_anchor_points() {
+        for behavior in BEHAVIORS {
+            assert_eq!(force_magnitude(0.0, 0.25, 0.75, behavior), -1.0);
+            assert_eq!(force_magnitude(0.25, 0.25, 0.75, behavior), 0.0);
+            assert_eq!(force_magnitude(0.75, 0.25, 0.75, behavior), behavior);
+            assert!(force_magnitude(1.0, 0.25, 0.75, behavior).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn linear_peak_is_continuous_at_beta_and_gamma() {
+        let epsilon = 1e-4;
+        for (beta, gamma) in [(0.1, 0.5), (0.25, 0.75), (0.3, 0.9)] {
+            for behavior in BEHAVIORS {
+                for edge in [beta, gamma] {
+                    let below = force_magnitude(edge - epsilon, beta, gamma, behavior);
+                    let above = force_magnitude(edge + epsilon, beta, gamma, behavior);
+                    assert!(
+                        (below - above).abs() < 0.01,
+                        "jump of {} at {} (beta {}, gamma {}, behavior {})",
+                        above - below,
+                        edge,
+                        beta,
+                        gamma,
+                        behavior
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn linear_peak_repels_at_short_range_whatever_the_behavior() {
+        for behavior in BEHAVIORS {
+            for distance in distances().filter(|&distance| distance < 0.25) {
+                assert!(force_magnitude(distance, 0.25, 0.75, behavior) < 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn profiles_stay_within_unit_range() {
+        let table = ForceTable::sampled(&LinearPeak {
+            beta: 0.3,
+            gamma: 0.7,
+        });
+        let shapes = [
+            ForceShape::LinearPeak,
+            ForceShape::LennardJones(LennardJones { sigma: 0.3 }),
+            ForceShape::InverseSquare(InverseSquare { core: 0.2 }),
+            ForceShape::Table(table),
+        ];
+        for shape in &shapes {
+            let profile = shape.profile(0.25, 0.75);
+            for behavior in BEHAVIORS {
+                for distance in distances() {
+                    let force = profile.force(distance, behavior);
+                    assert!(
+                        force.is_finite() && (-1.0..=1.0).contains(&force),
+                        "{} gives {} at distance {} with behavior {}",
+                        shape.label(),
+                        force,
+                        distance,
+                        behavior
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sampled_table_matches_its_profile_at_the_samples() {
+        let profile = LinearPeak {
+            beta: 0.3,
+            gamma: 0.7,
+        };
+        let table = ForceTable::sampled(&profile);
+        assert_eq!(table.samples.len(), TABLE_SAMPLES);
+        for (i, &sample) in table.samples.iter().enumerate() {
+            let distance = i as f32 / (TABLE_SAMPLES - 1) as f32;
+            assert!((table.force(distance, 1.0) - sample).abs() < 1e-5);
+            assert!((profile.force(distance, 1.0) - sample).abs() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use bevy::{prelude::*, window::WindowOccluded};
+use std::time::Duration;
+
+use crate::{settings::Settings, stress::StressConfig, Headless};
+
+/// Frame rate, and so tick rate, while the app is in the background.
+const BACKGROUND_FPS: f64 = 4.0;
+
+/// Caps the frame rate at [`Settings::fps_cap`], e.g. 30 FPS to save battery
+/// during long ambient runs, and slows the app to a trickle while it is in the
+/// background. Stress runs and headless runs are never capped.
+pub struct FramePacePlugin;
+
+impl Plugin for FramePacePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Background>()
+            .add_systems(PreUpdate, detect_background);
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Last, limit_frame_rate);
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(Last, apply_frame_cap);
+    }
+}
+
+/// Whether nobody is looking: the window is unfocused or occluded on
+/// desktop, or the tab is hidden in the browser.
+#[derive(Resource, Default)]
+struct Background {
+    occluded: bool,
+    active: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn unseen(windows: &Query<&Window>) -> bool {
+    !windows.is_empty() && windows.iter().all(|window| !window.focused)
+}
+
+/// The canvas loses focus to the rest of the page while still visible, so
+/// browsers only go by the tab's visibility.
+#[cfg(target_arch = "wasm32")]
+fn unseen(_windows: &Query<&Window>) -> bool {
+    web_sys::window()
+        .and_then(|window| window.document())
+        .is_some_and(|document| document.hidden())
+}
+
+fn detect_background(
+    settings: Res<Settings>,
+    windows: Query<&Window>,
+    mut occlusions: EventReader<WindowOccluded>,
+    mut background: ResMut<Background>,
+) {
+    for event in occlusions.read() {
+        background.occluded = event.occluded;
+    }
+    let active = settings.background_throttle && (background.occluded || unseen(&windows));
+    if active != background.active {
+        if active {
+            info!("In the background, slowing to {} FPS", BACKGROUND_FPS);
+        } else {
+            info!("Back in the foreground, resuming full speed");
+        }
+        background.active = active;
+    }
+}
+
+/// Shortest frame allowed by the cap or the background throttle, if any.
+fn min_frame_time(settings: &Settings, background: &Background) -> Option<Duration> {
+    if background.active {
+        Some(Duration::from_secs_f64(1.0 / BACKGROUND_FPS))
+    } else {
+        (settings.fps_cap > 0).then(|| Duration::from_secs_f64(1.0 / settings.fps_cap as f64))
+    }
+}
+
+/// Sleeps away whatever is left of the frame's share of time.
+#[cfg(not(target_arch = "wasm32"))]
+fn limit_frame_rate(
+    settings: Res<Settings>,
+    background: Res<Background>,
+    stress: Option<Res<StressConfig>>,
+    headless: Option<Res<Headless>>,
+    mut last_frame: Local<Option<bevy::utils::Instant>>,
+) {
+    let now = bevy::utils::Instant::now();
+    let uncapped = stress.is_some() || headless.is_some();
+    if let (Some(min_frame), Some(last), false) = (
+        min_frame_time(&settings, &background),
+        *last_frame,
+        uncapped,
+    ) {
+        let elapsed = now.duration_since(last);
+        if elapsed < min_frame {
+            std::thread::sleep(min_frame - elapsed);
+        }
+    }
+    *last_frame = Some(bevy::utils::Instant::now());
+}
+
+/// Browsers cannot block the main thread, so the cap instead has winit wait
+/// between updates. Input still wakes the app early, so the cap is a ceiling
+/// only while the user is idle.
+#[cfg(target_arch = "wasm32")]
+fn apply_frame_cap(
+    settings: Res<Settings>,
+    background: Res<Background>,
+    stress: Option<Res<StressConfig>>,
+    headless: Option<Res<Headless>>,
+    winit: Option<ResMut<bevy::winit::WinitSettings>>,
+) {
+    let Some(mut winit) = winit else {
+        return;
+    };
+    if !settings.is_changed() && !background.is_changed() {
+        return;
+    }
+    let cap =
+        min_frame_time(&settings, &background).filter(|_| stress.is_none() && headless.is_none());
+    let mode = match cap {
+        Some(wait) => bevy::winit::UpdateMode::reactive(wait),
+        None => bevy::winit::UpdateMode::Continuous,
+    };
+    winit.focused_mode = mode;
+    winit.unfocused_mode = mode;
+}
@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    spawn::{self, WorldCursor},
+    ui_enabled, Particle, Velocity,
+};
+
+const REGION_COLOR: Color = Color::srgba(0.6, 0.85, 1.0, 0.6);
+const DRAG_COLOR: Color = Color::srgba(1.0, 0.85, 0.3, 0.8);
+/// Drags shorter than this in world units freeze nothing.
+const MIN_REGION_SIZE: f32 = 5.0;
+
+pub struct FreezePlugin;
+
+impl Plugin for FreezePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FreezeTool>().add_systems(
+            Update,
+            (
+                freeze_with_mouse
+                    .before(spawn::spawn_at_cursor)
+                    .run_if(ui_enabled),
+                // Before the tool, so a region frozen this frame is not
+                // cleared while its particles are still being marked
+                draw_frozen_regions.before(freeze_with_mouse),
+                freeze_ui_system.run_if(ui_enabled),
+            ),
+        );
+    }
+}
+
+/// Marks a particle pinned in place by the freeze tool. It is skipped by
+/// integration and noise but still pushes and pulls the others.
+#[derive(Component)]
+pub struct Frozen;
+
+/// State of the freeze tool and the regions frozen so far.
+#[derive(Resource, Default)]
+struct FreezeTool {
+    /// The next left drag selects a region to freeze.
+    active: bool,
+    start: Option<Vec2>,
+    /// Outlined so users can see what is pinned.
+    regions: Vec<Rect>,
+}
+
+type UnfrozenParticles<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static Transform, &'static mut Velocity),
+    (With<Particle>, Without<Frozen>),
+>;
+
+fn freeze_with_mouse(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    (mut buttons, keyboard): (ResMut<ButtonInput<MouseButton>>, Res<ButtonInput<KeyCode>>),
+    cursor: WorldCursor,
+    mut tool: ResMut<FreezeTool>,
+    mut particles: UnfrozenParticles,
+    mut gizmos: Gizmos,
+) {
+    if !tool.active {
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::Escape) {
+        tool.active = false;
+        tool.start = None;
+        return;
+    }
+    let cursor = cursor.position();
+    if buttons.just_pressed(MouseButton::Left) && !contexts.ctx_mut().wants_pointer_input() {
+        tool.start = cursor;
+        // The click selects the region instead of adding a particle
+        buttons.clear_just_pressed(MouseButton::Left);
+    }
+    let (Some(start), Some(end)) = (tool.start, cursor) else {
+        return;
+    };
+    let region = Rect::from_corners(start, end);
+    if !buttons.just_released(MouseButton::Left) {
+        gizmos.rect_2d(
+            Isometry2d::from_translation(region.center()),
+            region.size(),
+            DRAG_COLOR,
+        );
+        return;
+    }
+    tool.start = None;
+    if region.size().min_element() < MIN_REGION_SIZE {
+        return;
+    }
+    let mut frozen = 0;
+    for (entity, transform, mut velocity) in &mut particles {
+        if region.contains(transform.translation.truncate()) {
+            velocity.0 = Vec2::ZERO;
+            commands.entity(entity).insert(Frozen);
+            frozen += 1;
+        }
+    }
+    tool.regions.push(region);
+    info!("Froze {} particles", frozen);
+}
+
+fn draw_frozen_regions(
+    mut tool: ResMut<FreezeTool>,
+    frozen: Query<(), With<Frozen>>,
+    mut gizmos: Gizmos,
+) {
+    // Respawns and removals take the frozen particles with them
+    if frozen.is_empty() {
+        if !tool.regions.is_empty() {
+            tool.regions.clear();
+        }
+        return;
+    }
+    for region in &tool.regions {
+        gizmos.rect_2d(
+            Isometry2d::from_translation(region.center()),
+            region.size(),
+            REGION_COLOR,
+        );
+    }
+}
+
+fn freeze_ui_system(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut tool: ResMut<FreezeTool>,
+    frozen: Query<Entity, With<Frozen>>,
+) {
+    egui::Window::new("Freeze Region")
+        .default_pos([620.0, 1060.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Pins the particles in a region; they keep acting on the others.");
+            if ui.selectable_label(tool.active, "Select Region").clicked() {
+                tool.active = !tool.active;
+                tool.start = None;
+            }
+            if tool.active {
+                ui.label("Drag in the world to freeze, Escape to stop.");
+            }
+            let count = frozen.iter().len();
+            ui.label(format!("{} particle(s) frozen", count));
+            if count > 0 && ui.button("Unfreeze All").clicked() {
+                for entity in &frozen {
+                    commands.entity(entity).remove::<Frozen>();
+                }
+            }
+        });
+}
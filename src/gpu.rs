@@ -0,0 +1,298 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{
+            BindGroup, BindGroupEntry, BindGroupLayout, BindGroupLayoutEntry, BindingType, Buffer,
+            BufferBindingType, BufferDescriptor, BufferUsages, CommandEncoderDescriptor,
+            ComputePassDescriptor, ComputePipeline, DownlevelFlags, Maintain, MapMode,
+            PipelineLayoutDescriptor, RawComputePipelineDescriptor, ShaderModuleDescriptor,
+            ShaderSource, ShaderStages,
+        },
+        renderer::{RenderAdapter, RenderDevice, RenderQueue},
+    },
+};
+use std::sync::{
+    atomic::{AtomicU8, Ordering},
+    Arc,
+};
+
+/// Neighbor slots per particle in the lists read back.
+pub const MAX_NEIGHBORS: usize = 64;
+const WORKGROUP_SIZE: usize = 64;
+
+const PENDING: u8 = 0;
+const MAPPED: u8 = 1;
+const FAILED: u8 = 2;
+
+/// GPU buffers for up to `capacity` particles.
+struct SearchBuffers {
+    capacity: usize,
+    params: Buffer,
+    positions: Buffer,
+    counts: Buffer,
+    neighbors: Buffer,
+    /// Counts followed by neighbor lists, copied here to be mapped.
+    staging: Buffer,
+    bind_group: BindGroup,
+}
+
+/// A search whose results are being mapped for reading.
+struct Readback {
+    count: usize,
+    state: Arc<AtomicU8>,
+}
+
+/// Lists the neighbors of every particle in a compute shader, see
+/// `neighbors.wgsl`. One search is in flight at a time.
+pub struct GpuNeighborSearch {
+    device: RenderDevice,
+    queue: RenderQueue,
+    layout: BindGroupLayout,
+    pipeline: ComputePipeline,
+    buffers: Option<SearchBuffers>,
+    in_flight: Option<Readback>,
+}
+
+impl GpuNeighborSearch {
+    /// None when the adapter cannot run compute shaders, e.g. under WebGL2.
+    pub fn new(device: RenderDevice, queue: RenderQueue, adapter: &RenderAdapter) -> Option<Self> {
+        let compute = adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(DownlevelFlags::COMPUTE_SHADERS);
+        if !compute || device.limits().max_storage_buffers_per_shader_stage < 3 {
+            return None;
+        }
+
+        let module = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("neighbor search"),
+            source: ShaderSource::Wgsl(include_str!("neighbors.wgsl").into()),
+        });
+        let buffer_entry = |binding, ty| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(
+            "neighbor search",
+            &[
+                buffer_entry(0, BufferBindingType::Uniform),
+                buffer_entry(1, BufferBindingType::Storage { read_only: true }),
+                buffer_entry(2, BufferBindingType::Storage { read_only: false }),
+                buffer_entry(3, BufferBindingType::Storage { read_only: false }),
+            ],
+        );
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("neighbor search"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&RawComputePipelineDescriptor {
+            label: Some("neighbor search"),
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: default(),
+            cache: None,
+        });
+
+        Some(GpuNeighborSearch {
+            device,
+            queue,
+            layout,
+            pipeline,
+            buffers: None,
+            in_flight: None,
+        })
+    }
+
+    /// None when the lists would not fit in one storage buffer.
+    fn create_buffers(&self, capacity: usize) -> Option<SearchBuffers> {
+        let limits = self.device.limits();
+        let neighbors_size = (capacity * MAX_NEIGHBORS * 4) as u64;
+        if neighbors_size > u64::from(limits.max_storage_buffer_binding_size)
+            || neighbors_size + capacity as u64 * 4 > limits.max_buffer_size
+        {
+            return None;
+        }
+        let buffer = |label, size, usage| {
+            self.device.create_buffer(&BufferDescriptor {
+                label: Some(label),
+                size,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        let params = buffer(
+            "neighbor search params",
+            16,
+            BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        );
+        let positions = buffer(
+            "neighbor search positions",
+            capacity as u64 * 8,
+            BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        );
+        let counts = buffer(
+            "neighbor search counts",
+            capacity as u64 * 4,
+            BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+        );
+        let neighbors = buffer(
+            "neighbor search lists",
+            neighbors_size,
+            BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+        );
+        let staging = buffer(
+            "neighbor search readback",
+            neighbors_size + capacity as u64 * 4,
+            BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        );
+        let bind_group = self.device.create_bind_group(
+            "neighbor search",
+            &self.layout,
+            &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: params.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: positions.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: counts.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: neighbors.as_entire_binding(),
+                },
+            ],
+        );
+        Some(SearchBuffers {
+            capacity,
+            params,
+            positions,
+            counts,
+            neighbors,
+            staging,
+            bind_group,
+        })
+    }
+
+    /// Starts listing the others within `radius` of every position, unless a
+    /// search is still in flight. Natively this waits for the GPU, so the
+    /// lists can be read right after; on the web they arrive a later tick.
+    pub fn dispatch(&mut self, positions: &[Vec2], radius: f32) {
+        if self.in_flight.is_some() || positions.is_empty() {
+            return;
+        }
+        let count = positions.len();
+        if self
+            .buffers
+            .as_ref()
+            .is_none_or(|buffers| buffers.capacity < count)
+        {
+            self.buffers = self.create_buffers(count.next_power_of_two());
+        }
+        let Some(buffers) = &self.buffers else {
+            return;
+        };
+
+        let mut params = Vec::with_capacity(16);
+        for word in [
+            count as u32,
+            MAX_NEIGHBORS as u32,
+            (radius * radius).to_bits(),
+            0,
+        ] {
+            params.extend_from_slice(&word.to_le_bytes());
+        }
+        let mut position_bytes = Vec::with_capacity(count * 8);
+        for pos in positions {
+            position_bytes.extend_from_slice(&pos.x.to_le_bytes());
+            position_bytes.extend_from_slice(&pos.y.to_le_bytes());
+        }
+        self.queue.write_buffer(&buffers.params, 0, &params);
+        self.queue
+            .write_buffer(&buffers.positions, 0, &position_bytes);
+
+        let counts_size = count as u64 * 4;
+        let neighbors_size = counts_size * MAX_NEIGHBORS as u64;
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("neighbor search"),
+            });
+        {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("neighbor search"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &*buffers.bind_group, &[]);
+            pass.dispatch_workgroups(count.div_ceil(WORKGROUP_SIZE) as u32, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&buffers.counts, 0, &buffers.staging, 0, counts_size);
+        encoder.copy_buffer_to_buffer(
+            &buffers.neighbors,
+            0,
+            &buffers.staging,
+            counts_size,
+            neighbors_size,
+        );
+        let submission = self.queue.submit([encoder.finish()]);
+
+        let state = Arc::new(AtomicU8::new(PENDING));
+        let mapped = state.clone();
+        buffers
+            .staging
+            .slice(..counts_size + neighbors_size)
+            .map_async(MapMode::Read, move |result| {
+                mapped.store(
+                    if result.is_ok() { MAPPED } else { FAILED },
+                    Ordering::Release,
+                );
+            });
+        self.device
+            .poll(Maintain::WaitForSubmissionIndex(submission));
+        self.in_flight = Some(Readback { count, state });
+    }
+
+    /// Copies out the lists of a finished search: the number of neighbors
+    /// found per particle, then [`MAX_NEIGHBORS`] slots per particle. Returns
+    /// how many particles were searched, or None while nothing new is ready.
+    pub fn read(&mut self, counts: &mut Vec<u32>, neighbors: &mut Vec<u32>) -> Option<usize> {
+        let readback = self.in_flight.as_ref()?;
+        match readback.state.load(Ordering::Acquire) {
+            PENDING => return None,
+            FAILED => {
+                self.in_flight = None;
+                return None;
+            }
+            _ => {}
+        }
+        let count = readback.count;
+        self.in_flight = None;
+        let staging = &self.buffers.as_ref()?.staging;
+        {
+            let size = (count * (1 + MAX_NEIGHBORS) * 4) as u64;
+            let bytes = staging.slice(..size).get_mapped_range();
+            let mut words = bytes
+                .chunks_exact(4)
+                .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]));
+            counts.clear();
+            counts.extend(words.by_ref().take(count));
+            neighbors.clear();
+            neighbors.extend(words);
+        }
+        staging.unmap();
+        Some(count)
+    }
+}
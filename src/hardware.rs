@@ -0,0 +1,166 @@
+use bevy::{
+    prelude::*,
+    render::renderer::{RenderAdapterInfo, RenderDevice},
+};
+
+use crate::{
+    backend::{threads_available, SimulationBackends, GRID, PARALLEL_GRID},
+    quality::QualityGovernor,
+    settings::Settings,
+    stress::StressConfig,
+    ParticleCount, NUM_PARTICLES,
+};
+
+/// Largest 2D texture below which a GPU counts as weak; WebGL2 guarantees
+/// only 2048.
+const WEAK_GPU_TEXTURE_SIZE: u32 = 8192;
+/// Adapter names of CPU rasterizers, which draw a few thousand circles slowly.
+const SOFTWARE_RENDERERS: [&str; 3] = ["llvmpipe", "swiftshader", "basic render"];
+
+/// Probes the machine at startup and picks the default particle count to
+/// match, so phones and old laptops do not start at a slideshow and desktops
+/// are not left idle. Turned off in the Settings window.
+pub struct HardwarePlugin;
+
+impl Plugin for HardwarePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HardwareProfile>()
+            .add_systems(Startup, probe_hardware.before(crate::setup));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub enum HardwareTier {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+/// What the probe found and the defaults it chose.
+#[derive(Resource, Default)]
+pub struct HardwareProfile {
+    pub threads: Option<usize>,
+    pub max_texture_size: Option<u32>,
+    /// Reported by browsers only, rounded down to a power of two.
+    pub device_memory_gb: Option<f32>,
+    pub tier: HardwareTier,
+}
+
+impl HardwareProfile {
+    /// Particles a world starts with on this machine.
+    pub fn default_particles(&self) -> usize {
+        match self.tier {
+            HardwareTier::Low => 2000,
+            HardwareTier::Medium => NUM_PARTICLES,
+            HardwareTier::High => 10000,
+        }
+    }
+
+    /// One line for the controls panel.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(threads) = self.threads {
+            parts.push(format!("{} threads", threads));
+        }
+        if let Some(size) = self.max_texture_size {
+            parts.push(format!("{} px textures", size));
+        }
+        if let Some(memory) = self.device_memory_gb {
+            parts.push(format!("{} GB", memory));
+        }
+        format!(
+            "{:?} tier ({}), {} particles by default",
+            self.tier,
+            parts.join(", "),
+            self.default_particles()
+        )
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn threads() -> Option<usize> {
+    std::thread::available_parallelism().ok().map(usize::from)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn threads() -> Option<usize> {
+    let concurrency = web_sys::window()?.navigator().hardware_concurrency();
+    (concurrency > 0.0).then_some(concurrency as usize)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn device_memory_gb() -> Option<f32> {
+    None
+}
+
+/// `navigator.deviceMemory`, which only Chromium browsers expose.
+#[cfg(target_arch = "wasm32")]
+fn device_memory_gb() -> Option<f32> {
+    let navigator = web_sys::window()?.navigator();
+    let memory = js_sys::Reflect::get(&navigator, &"deviceMemory".into()).ok()?;
+    memory.as_f64().map(|memory| memory as f32)
+}
+
+fn tier(
+    threads: Option<usize>,
+    max_texture_size: Option<u32>,
+    software_gpu: bool,
+    memory: Option<f32>,
+) -> HardwareTier {
+    // The weakest part decides
+    let mut tier = match threads {
+        Some(0..=2) => HardwareTier::Low,
+        Some(3..=4) | None => HardwareTier::Medium,
+        Some(_) => HardwareTier::High,
+    };
+    if software_gpu || memory.is_some_and(|memory| memory < 4.0) {
+        tier = HardwareTier::Low;
+    }
+    if max_texture_size.is_some_and(|size| size < WEAK_GPU_TEXTURE_SIZE) {
+        tier = tier.min(HardwareTier::Medium);
+    }
+    tier
+}
+
+fn probe_hardware(
+    settings: Res<Settings>,
+    stress: Option<Res<StressConfig>>,
+    (adapter, device): (Option<Res<RenderAdapterInfo>>, Option<Res<RenderDevice>>),
+    mut profile: ResMut<HardwareProfile>,
+    mut particle_count: ResMut<ParticleCount>,
+    mut backends: ResMut<SimulationBackends>,
+    mut quality: ResMut<QualityGovernor>,
+) {
+    let software_gpu = adapter.is_some_and(|adapter| {
+        let name = adapter.name.to_lowercase();
+        SOFTWARE_RENDERERS
+            .iter()
+            .any(|renderer| name.contains(renderer))
+    });
+    profile.threads = threads();
+    profile.max_texture_size = device.map(|device| device.limits().max_texture_dimension_2d);
+    profile.device_memory_gb = device_memory_gb();
+    profile.tier = tier(
+        profile.threads,
+        profile.max_texture_size,
+        software_gpu,
+        profile.device_memory_gb,
+    );
+    info!("Hardware: {}", profile.summary());
+
+    // Stress runs ask for their own setup, and a saved count other than the
+    // stock default was picked by the user
+    if !settings.hardware_defaults || stress.is_some() || settings.particle_count != NUM_PARTICLES {
+        return;
+    }
+    particle_count.count = profile.default_particles();
+    // The grid backend scales best with count, spread over the cores where
+    // there are several; weak machines also get the quality governor to
+    // keep the frame rate up
+    let parallel = threads_available() && profile.threads.is_some_and(|threads| threads > 2);
+    backends.select(if parallel { PARALLEL_GRID } else { GRID });
+    if profile.tier == HardwareTier::Low {
+        quality.enabled = true;
+    }
+}
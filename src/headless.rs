@@ -0,0 +1,96 @@
+use bevy::{diagnostic::DiagnosticsPlugin, prelude::*, time::TimeUpdateStrategy};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::time::Duration;
+
+use crate::{
+    backend, buffers, interpolation, layers, lod, matrix_presets,
+    palette::{PaletteSettings, PaletteStrategy},
+    pheromone, quality, settings, species, substep, update_particles, Particle, ParticleSystem,
+};
+
+/// Length of one [`HeadlessSimulation::step`].
+const TICK: f64 = 1.0 / 60.0;
+
+/// The force simulation alone, without rendering, input or UI, advanced a
+/// fixed tick at a time. Every random choice is drawn from the seed, so two
+/// runs with the same arguments match exactly.
+pub struct HeadlessSimulation {
+    app: App,
+    /// Particles in spawn order.
+    particles: Vec<Entity>,
+}
+
+impl HeadlessSimulation {
+    /// `particles` particles of `species` species scattered over a square of
+    /// half-width `extent`, with a random matrix, searched by the backend at
+    /// index `backend` of [`HeadlessSimulation::backends`].
+    pub fn new(seed: u64, species: usize, particles: usize, extent: f32, backend: usize) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut particle_system = ParticleSystem::with_colors(
+            PaletteSettings {
+                strategy: PaletteStrategy::EvenHues,
+                seed: Some(seed),
+            }
+            .generate(species),
+        );
+        matrix_presets::reroll(&mut particle_system.behavior_matrix, &mut rng);
+        let mut backends = backend::SimulationBackends::default();
+        backends.select(backend);
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, DiagnosticsPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+                TICK,
+            )))
+            .insert_resource(particle_system)
+            .insert_resource(backends)
+            .init_resource::<settings::Settings>()
+            .init_resource::<quality::QualityGovernor>()
+            .init_resource::<lod::LodSettings>()
+            .init_resource::<substep::Substepping>()
+            .init_resource::<species::SpeciesToggles>()
+            .init_resource::<buffers::ParticleBuffers>()
+            .init_resource::<layers::OverlayLayer>()
+            .init_resource::<pheromone::PheromoneField>()
+            .init_resource::<interpolation::TickClock>()
+            .add_systems(Update, update_particles);
+
+        let particles = (0..particles)
+            .map(|index| {
+                let pos = Vec2::new(
+                    rng.random_range(-extent..extent),
+                    rng.random_range(-extent..extent),
+                );
+                app.world_mut()
+                    .spawn((
+                        Transform::from_translation(pos.extend(0.0)),
+                        Particle {
+                            color_id: index % species,
+                        },
+                    ))
+                    .id()
+            })
+            .collect();
+        HeadlessSimulation { app, particles }
+    }
+
+    /// Names of the CPU backends, by index.
+    pub fn backends() -> Vec<&'static str> {
+        backend::SimulationBackends::default().names()
+    }
+
+    pub fn step(&mut self) {
+        self.app.update();
+    }
+
+    /// Every particle's position, in spawn order.
+    pub fn positions(&self) -> Vec<Vec2> {
+        self.particles
+            .iter()
+            .map(|&entity| {
+                let transform = self.app.world().get::<Transform>(entity).unwrap();
+                transform.translation.truncate()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,329 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    lod, point_sprites::PointSprites, settings::Settings, species::SpeciesToggles, ui_enabled,
+    MainCamera, Particle, ParticleSystem,
+};
+
+/// Drawn above the particles, which are hidden anyway while the heatmap shows.
+const HEATMAP_Z: f32 = 10.0;
+const MAX_HEIGHT: u32 = 1024;
+/// sRGB stops of the heat palette, from empty to densest.
+const HEAT_STOPS: [Vec3; 5] = [
+    Vec3::new(0.0, 0.0, 0.0),
+    Vec3::new(0.35, 0.05, 0.45),
+    Vec3::new(0.85, 0.15, 0.15),
+    Vec3::new(1.0, 0.7, 0.1),
+    Vec3::new(1.0, 1.0, 0.9),
+];
+
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Heatmap::default())
+            .add_systems(Startup, spawn_heatmap_sprite)
+            .add_systems(
+                Update,
+                (
+                    toggle_heatmap,
+                    sync_particle_visibility,
+                    render_heatmap,
+                    heatmap_ui_system.run_if(ui_enabled),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Density render mode: particles in view are splatted into a low resolution
+/// grid on the CPU, blurred, and shown as one texture instead of as circles.
+#[derive(Resource)]
+pub struct Heatmap {
+    pub enabled: bool,
+    /// Texture width in cells; the height follows the view's aspect ratio.
+    pub width: u32,
+    /// Box blur radius in cells, applied twice for a near-Gaussian falloff.
+    pub blur: usize,
+    /// How quickly density saturates, relative to the average density.
+    pub intensity: f32,
+    /// Tint each cell with the mix of species in it instead of a heat palette.
+    pub species_colors: bool,
+    image: Handle<Image>,
+    /// Summed sRGB species color in xyz and density in w, per cell.
+    cells: Vec<Vec4>,
+    scratch: Vec<Vec4>,
+    prefix: Vec<Vec4>,
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Heatmap {
+            enabled: false,
+            width: 320,
+            blur: 3,
+            intensity: 1.5,
+            species_colors: true,
+            image: Handle::default(),
+            cells: Vec::new(),
+            scratch: Vec::new(),
+            prefix: Vec::new(),
+        }
+    }
+}
+
+#[derive(Component)]
+struct HeatmapSprite;
+
+fn spawn_heatmap_sprite(
+    mut commands: Commands,
+    mut heatmap: ResMut<Heatmap>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    heatmap.image = images.add(Image::new_fill(
+        Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ));
+    commands.spawn((
+        Sprite::from_image(heatmap.image.clone()),
+        Transform::from_xyz(0.0, 0.0, HEATMAP_Z),
+        Visibility::Hidden,
+        HeatmapSprite,
+    ));
+}
+
+fn toggle_heatmap(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    mut heatmap: ResMut<Heatmap>,
+) {
+    if keyboard.just_pressed(settings.keys.toggle_heatmap) {
+        heatmap.enabled = !heatmap.enabled;
+    }
+}
+
+/// Hides the particle meshes while the heatmap or point sprites are shown,
+/// and those of hidden species, including particles spawned or recolored in
+/// the meantime.
+fn sync_particle_visibility(
+    heatmap: Res<Heatmap>,
+    toggles: Res<SpeciesToggles>,
+    sprites: Res<PointSprites>,
+    mut was_hidden: Local<bool>,
+    mut particles: Query<(&mut Visibility, Ref<Particle>)>,
+) {
+    let all_hidden = heatmap.enabled || sprites.active;
+    let toggled = all_hidden != *was_hidden || toggles.is_changed();
+    *was_hidden = all_hidden;
+    for (mut particle_visibility, particle) in &mut particles {
+        if toggled || particle.is_changed() {
+            let visibility = if all_hidden || toggles.is_hidden(particle.color_id) {
+                Visibility::Hidden
+            } else {
+                Visibility::Inherited
+            };
+            particle_visibility.set_if_neq(visibility);
+        }
+    }
+}
+
+/// Box blur along one axis of the grid, using a prefix sum per line so the
+/// cost does not depend on the radius.
+fn blur_pass(
+    src: &[Vec4],
+    dst: &mut [Vec4],
+    prefix: &mut Vec<Vec4>,
+    (lines, len): (usize, usize),
+    index: impl Fn(usize, usize) -> usize,
+    radius: usize,
+) {
+    let window = (2 * radius + 1) as f32;
+    prefix.resize(len + 1, Vec4::ZERO);
+    for line in 0..lines {
+        for i in 0..len {
+            prefix[i + 1] = prefix[i] + src[index(line, i)];
+        }
+        for i in 0..len {
+            let low = i.saturating_sub(radius);
+            let high = (i + radius + 1).min(len);
+            dst[index(line, i)] = (prefix[high] - prefix[low]) / window;
+        }
+    }
+}
+
+fn heat_color(level: f32) -> Vec3 {
+    let position = level.clamp(0.0, 1.0) * (HEAT_STOPS.len() - 1) as f32;
+    let index = (position as usize).min(HEAT_STOPS.len() - 2);
+    HEAT_STOPS[index].lerp(HEAT_STOPS[index + 1], position - index as f32)
+}
+
+type HeatmapSprites<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Transform,
+        &'static mut Sprite,
+        &'static mut Visibility,
+    ),
+    (With<HeatmapSprite>, Without<Particle>, Without<Camera>),
+>;
+
+fn render_heatmap(
+    mut heatmap: ResMut<Heatmap>,
+    particle_system: Res<ParticleSystem>,
+    mut images: ResMut<Assets<Image>>,
+    camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    particles: Query<(&Transform, &Particle)>,
+    mut sprite: HeatmapSprites,
+) {
+    let Ok((mut sprite_transform, mut sprite, mut sprite_visibility)) = sprite.get_single_mut()
+    else {
+        return;
+    };
+    let Ok((camera_transform, projection)) = camera.get_single() else {
+        return;
+    };
+    if !heatmap.enabled {
+        sprite_visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+    sprite_visibility.set_if_neq(Visibility::Visible);
+
+    let view = lod::camera_view(camera_transform, projection);
+    if view.width() <= 0.0 || view.height() <= 0.0 {
+        return;
+    }
+    sprite_transform.translation = view.center().extend(HEATMAP_Z);
+    sprite.custom_size = Some(view.size());
+
+    let width = heatmap.width.max(1);
+    let height =
+        ((width as f32 * view.height() / view.width()).round() as u32).clamp(1, MAX_HEIGHT);
+    let (w, h) = (width as usize, height as usize);
+
+    // Splat each particle bilinearly into the four nearest cells
+    let palette: Vec<Vec3> = particle_system
+        .colors
+        .iter()
+        .map(|color| {
+            let srgba = color.to_srgba();
+            Vec3::new(srgba.red, srgba.green, srgba.blue)
+        })
+        .collect();
+    let heatmap = &mut *heatmap;
+    heatmap.cells.clear();
+    heatmap.cells.resize(w * h, Vec4::ZERO);
+    let mut total = 0.0;
+    for (transform, particle) in &particles {
+        let pos = transform.translation.truncate();
+        if !view.contains(pos) {
+            continue;
+        }
+        let color = palette
+            .get(particle.color_id)
+            .copied()
+            .unwrap_or(Vec3::ONE)
+            .extend(1.0);
+        let u = (pos.x - view.min.x) / view.width() * w as f32 - 0.5;
+        let v = (view.max.y - pos.y) / view.height() * h as f32 - 0.5;
+        let (x0, y0) = (u.floor(), v.floor());
+        let (fx, fy) = (u - x0, v - y0);
+        for (dx, dy, weight) in [
+            (0, 0, (1.0 - fx) * (1.0 - fy)),
+            (1, 0, fx * (1.0 - fy)),
+            (0, 1, (1.0 - fx) * fy),
+            (1, 1, fx * fy),
+        ] {
+            let (x, y) = (x0 as i32 + dx, y0 as i32 + dy);
+            if x >= 0 && y >= 0 && (x as usize) < w && (y as usize) < h {
+                heatmap.cells[y as usize * w + x as usize] += color * weight;
+            }
+        }
+        total += 1.0;
+    }
+
+    // Two separable box blurs approximate a Gaussian
+    heatmap.scratch.resize(w * h, Vec4::ZERO);
+    for _ in 0..2 {
+        if heatmap.blur == 0 {
+            break;
+        }
+        let Heatmap {
+            cells,
+            scratch,
+            prefix,
+            blur,
+            ..
+        } = &mut *heatmap;
+        blur_pass(cells, scratch, prefix, (h, w), |y, x| y * w + x, *blur);
+        blur_pass(scratch, cells, prefix, (w, h), |x, y| y * w + x, *blur);
+    }
+
+    // Density saturates relative to the average over the view
+    let gain = heatmap.intensity * (w * h) as f32 / f32::max(total, 1.0);
+    let Some(image) = images.get_mut(&heatmap.image) else {
+        return;
+    };
+    let size = Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+    if image.texture_descriptor.size != size {
+        image.resize(size);
+    }
+    for (pixel, cell) in image.data.chunks_exact_mut(4).zip(&heatmap.cells) {
+        let density = cell.w;
+        let level = 1.0 - (-density * gain).exp();
+        let color = if heatmap.species_colors && density > 0.0 {
+            cell.truncate() / density * level
+        } else {
+            heat_color(level)
+        };
+        let [r, g, b] = color
+            .to_array()
+            .map(|channel| (channel.clamp(0.0, 1.0) * 255.0) as u8);
+        pixel.copy_from_slice(&[r, g, b, 255]);
+    }
+}
+
+fn heatmap_ui_system(
+    mut contexts: EguiContexts,
+    settings: Res<Settings>,
+    mut heatmap: ResMut<Heatmap>,
+) {
+    egui::Window::new("Heatmap")
+        .default_pos([400.0, 580.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(
+                &mut heatmap.enabled,
+                format!(
+                    "Show density instead of particles ({:?})",
+                    settings.keys.toggle_heatmap
+                ),
+            );
+            ui.add(egui::Slider::new(&mut heatmap.width, 64..=640).text("resolution"));
+            ui.add(egui::Slider::new(&mut heatmap.blur, 0..=12).text("blur"));
+            ui.add(
+                egui::Slider::new(&mut heatmap.intensity, 0.1..=10.0)
+                    .logarithmic(true)
+                    .text("intensity"),
+            );
+            ui.checkbox(&mut heatmap.species_colors, "Species colors");
+        });
+}
@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{settings::Settings, tutorial::Tutorial, ui_enabled};
+
+/// Mouse and species controls, which are not rebindable.
+const MOUSE_CONTROLS: [(&str, &str); 5] = [
+    ("1-9", "Hide a species"),
+    ("Shift+1-9", "Freeze a species"),
+    ("Left click", "Add a particle"),
+    ("Right drag", "Spray or paint species with the brush"),
+    ("Middle click", "Push particles (shared world client)"),
+];
+
+pub struct HelpPlugin;
+
+impl Plugin for HelpPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HelpOverlay>().add_systems(
+            Update,
+            (toggle_help, help_overlay_system.run_if(ui_enabled)).chain(),
+        );
+    }
+}
+
+/// Lists every control with its current binding. Opens by itself until it has
+/// been dismissed once.
+#[derive(Resource)]
+struct HelpOverlay {
+    visible: bool,
+}
+
+impl FromWorld for HelpOverlay {
+    fn from_world(world: &mut World) -> Self {
+        let seen = world
+            .get_resource::<Settings>()
+            .is_some_and(|settings| settings.seen_help);
+        HelpOverlay { visible: !seen }
+    }
+}
+
+/// `KeyR` reads better as `R`, `Digit1` as `1`.
+pub fn key_name(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    ["Key", "Digit"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .map_or_else(|| name.clone(), str::to_string)
+}
+
+fn toggle_help(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut overlay: ResMut<HelpOverlay>,
+) {
+    if keyboard.just_pressed(settings.keys.toggle_help) {
+        overlay.visible = !overlay.visible;
+    }
+    if !overlay.visible && !settings.seen_help {
+        settings.seen_help = true;
+    }
+}
+
+fn help_overlay_system(
+    mut contexts: EguiContexts,
+    settings: Res<Settings>,
+    mut overlay: ResMut<HelpOverlay>,
+    mut tutorial: ResMut<Tutorial>,
+) {
+    if !overlay.visible {
+        return;
+    }
+    let title = if settings.seen_help {
+        "Controls"
+    } else {
+        "Welcome to Particle Life"
+    };
+    egui::Window::new(title)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            if !settings.seen_help {
+                ui.label(
+                    "Colored particles attract or repel each other by species. \
+                     Edit the rules in the panels on the left, or try the keys below.",
+                );
+                ui.add_space(5.0);
+            }
+            egui::Grid::new("help_controls")
+                .striped(true)
+                .show(ui, |ui| {
+                    for (name, key) in settings.keys.entries() {
+                        ui.monospace(key_name(key));
+                        ui.label(name);
+                        ui.end_row();
+                    }
+                    for (button, action) in MOUSE_CONTROLS {
+                        ui.monospace(button);
+                        ui.label(action);
+                        ui.end_row();
+                    }
+                });
+            ui.add_space(5.0);
+            ui.label(format!(
+                "Press {} to show this again. Keys can be rebound in the Settings window.",
+                key_name(settings.keys.toggle_help)
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("Got it").clicked() {
+                    overlay.visible = false;
+                }
+                if ui.button("Start Tutorial").clicked() {
+                    tutorial.start(settings.tutorial_progress);
+                    overlay.visible = false;
+                }
+            });
+        });
+}
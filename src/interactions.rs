@@ -0,0 +1,245 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::collections::HashMap;
+
+use crate::{egui_color, quality::QualityGovernor, ui_enabled, Particle, ParticleSystem};
+
+/// Lines drawn at most per frame, so dense clusters stay readable and cheap.
+const MAX_LINES: usize = 4000;
+const ATTRACT_COLOR: Vec3 = Vec3::new(0.3, 1.0, 0.4);
+const REPEL_COLOR: Vec3 = Vec3::new(1.0, 0.3, 0.3);
+
+pub struct InteractionsPlugin;
+
+impl Plugin for InteractionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PairHighlight>().add_systems(
+            Update,
+            (
+                draw_pair_interactions.after(crate::update_particles),
+                interactions_ui_system.run_if(ui_enabled),
+            ),
+        );
+    }
+}
+
+/// Debug view of one matrix entry: every `from` particle is joined to the
+/// `to` particles within their interaction radius, green where the pair
+/// attracts and red where it repels, more opaque the stronger the force.
+#[derive(Resource, Default)]
+pub struct PairHighlight {
+    pub enabled: bool,
+    pub from: usize,
+    pub to: usize,
+    /// Lines drawn last frame, and whether the cap cut some off.
+    drawn: usize,
+    capped: bool,
+}
+
+fn draw_pair_interactions(
+    particle_system: Res<ParticleSystem>,
+    quality: Res<QualityGovernor>,
+    mut highlight: ResMut<PairHighlight>,
+    mut gizmos: Gizmos,
+    particles: Query<(&Transform, &Particle)>,
+) {
+    let (from, to) = (highlight.from, highlight.to);
+    let species = particle_system.colors.len();
+    if !highlight.enabled || from >= species || to >= species {
+        return;
+    }
+    let radius = particle_system.interaction_radius(from, to) * quality.radius_scale;
+    if radius <= 0.0 {
+        return;
+    }
+    let behavior = particle_system.get_behavior(from, to);
+    let profile = particle_system
+        .force_profile
+        .profile(particle_system.beta, particle_system.gamma);
+
+    // Bucket the targets by radius-sized cells so each source only checks
+    // its own and the neighboring cells
+    let mut sources = Vec::new();
+    let mut targets: HashMap<IVec2, Vec<Vec2>> = HashMap::new();
+    for (transform, particle) in &particles {
+        let pos = transform.translation.truncate();
+        if particle.color_id == from {
+            sources.push(pos);
+        }
+        if particle.color_id == to {
+            let cell = (pos / radius).floor().as_ivec2();
+            targets.entry(cell).or_default().push(pos);
+        }
+    }
+
+    let mut drawn = 0;
+    let mut capped = false;
+    'sources: for &pos in &sources {
+        let cell = (pos / radius).floor().as_ivec2();
+        for offset in [-1, 0, 1]
+            .into_iter()
+            .flat_map(|x| [-1, 0, 1].map(|y| IVec2::new(x, y)))
+        {
+            let Some(cell_targets) = targets.get(&(cell + offset)) else {
+                continue;
+            };
+            for &other in cell_targets {
+                let distance = pos.distance(other) / radius;
+                if other == pos || distance >= 1.0 {
+                    continue;
+                }
+                if drawn == MAX_LINES {
+                    capped = true;
+                    break 'sources;
+                }
+                let force = profile.force(distance, behavior);
+                let tint = if force >= 0.0 {
+                    ATTRACT_COLOR
+                } else {
+                    REPEL_COLOR
+                };
+                let alpha = 0.15 + 0.85 * force.abs().min(1.0);
+                gizmos.line_2d(pos, other, Color::srgba(tint.x, tint.y, tint.z, alpha));
+                drawn += 1;
+            }
+        }
+    }
+    highlight.drawn = drawn;
+    highlight.capped = capped;
+}
+
+/// Pairs of a `from` and a `to` particle closer than `radius`, each pair
+/// counted once.
+pub fn pairs_in_range<'a>(
+    particles: impl Iterator<Item = (&'a Transform, &'a Particle)>,
+    (from, to): (usize, usize),
+    radius: f32,
+) -> usize {
+    if radius <= 0.0 {
+        return 0;
+    }
+    // Same bucketing as the line drawing; indices keep a species paired with
+    // itself from counting each pair twice
+    let mut sources = Vec::new();
+    let mut targets: HashMap<IVec2, Vec<(usize, Vec2)>> = HashMap::new();
+    for (index, (transform, particle)) in particles.enumerate() {
+        let pos = transform.translation.truncate();
+        if particle.color_id == from {
+            sources.push((index, pos));
+        }
+        if particle.color_id == to {
+            let cell = (pos / radius).floor().as_ivec2();
+            targets.entry(cell).or_default().push((index, pos));
+        }
+    }
+    let mut pairs = 0;
+    for &(index, pos) in &sources {
+        let cell = (pos / radius).floor().as_ivec2();
+        for offset in [-1, 0, 1]
+            .into_iter()
+            .flat_map(|x| [-1, 0, 1].map(|y| IVec2::new(x, y)))
+        {
+            let Some(cell_targets) = targets.get(&(cell + offset)) else {
+                continue;
+            };
+            pairs += cell_targets
+                .iter()
+                .filter(|&&(other_index, other)| {
+                    (from != to || other_index > index) && pos.distance(other) < radius
+                })
+                .count();
+        }
+    }
+    pairs
+}
+
+/// Tooltip for matrix entry `(from, to)`: both species, the coefficient and
+/// how many pairs it currently acts on.
+pub fn pair_tooltip(
+    ui: &mut egui::Ui,
+    particle_system: &ParticleSystem,
+    radius_scale: f32,
+    particles: &Query<(&Transform, &Particle)>,
+    (from, to): (usize, usize),
+) {
+    let swatch = |ui: &mut egui::Ui, species: usize| {
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+        ui.painter()
+            .rect_filled(rect, 2.0, egui_color(particle_system.colors[species]));
+        ui.label(format!("Species {}", species + 1));
+    };
+    ui.horizontal(|ui| {
+        swatch(ui, from);
+        ui.label("reacting to");
+        swatch(ui, to);
+    });
+    let behavior = particle_system.get_behavior(from, to);
+    let effect = match behavior {
+        b if b > 0.0 => "attracts",
+        b if b < 0.0 => "repels",
+        _ => "no effect",
+    };
+    ui.label(format!("Coefficient {:+.3} ({})", behavior, effect));
+    let radius = particle_system.interaction_radius(from, to) * radius_scale;
+    let pairs = pairs_in_range(particles.iter(), (from, to), radius);
+    ui.label(format!("{} pair(s) within range ({:.0})", pairs, radius));
+}
+
+/// Species picker with each entry in its own color.
+pub fn species_combo(ui: &mut egui::Ui, label: &str, selected: &mut usize, colors: &[Color]) {
+    let name = |species: usize| {
+        egui::RichText::new(format!("Species {}", species + 1)).color(egui_color(colors[species]))
+    };
+    egui::ComboBox::from_label(label)
+        .selected_text(name(*selected))
+        .show_ui(ui, |ui| {
+            for species in 0..colors.len() {
+                ui.selectable_value(selected, species, name(species));
+            }
+        });
+}
+
+fn interactions_ui_system(
+    mut contexts: EguiContexts,
+    mut particle_system: ResMut<ParticleSystem>,
+    mut highlight: ResMut<PairHighlight>,
+) {
+    egui::Window::new("Interactions")
+        .default_pos([620.0, 780.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Draws the pairs one matrix entry acts on, to see what it does.");
+            ui.checkbox(&mut highlight.enabled, "Highlight interactions");
+            let colors = particle_system.colors.clone();
+            if colors.is_empty() {
+                return;
+            }
+            let highlight = &mut *highlight;
+            highlight.from = highlight.from.min(colors.len() - 1);
+            highlight.to = highlight.to.min(colors.len() - 1);
+            species_combo(
+                ui,
+                "From (is pushed or pulled)",
+                &mut highlight.from,
+                &colors,
+            );
+            species_combo(ui, "To (acts on it)", &mut highlight.to, &colors);
+
+            // The entry itself, so its effect can be watched while editing it
+            let (from, to) = (highlight.from, highlight.to);
+            if let Some(value) = particle_system
+                .behavior_matrix
+                .get_mut(from)
+                .and_then(|row| row.get_mut(to))
+            {
+                ui.add(egui::Slider::new(value, -1.0..=1.0).text("behavior"));
+            }
+            if highlight.enabled {
+                ui.label(format!(
+                    "{} line(s){}",
+                    highlight.drawn,
+                    if highlight.capped { ", capped" } else { "" }
+                ));
+            }
+        });
+}
@@ -0,0 +1,132 @@
+use bevy::{prelude::*, transform::TransformSystem};
+
+use crate::{settings::Settings, Particle};
+
+/// Smooths motion when the simulation ticks less often than frames are drawn,
+/// because of [`Settings::tick_rate`] or the quality governor's stride.
+/// Between ticks each particle is drawn part way from its position at the
+/// previous tick to its position at the latest one, one tick behind the
+/// simulation. The blended position only lives in `Transform` from
+/// `PostUpdate` until the start of the next frame, so every simulation
+/// system still sees the simulated one.
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TickClock>()
+            .add_systems(First, restore_transforms)
+            .add_systems(
+                PostUpdate,
+                interpolate_transforms.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// When the simulation last ticked, kept by `update_particles`.
+#[derive(Resource)]
+pub struct TickClock {
+    ticked: bool,
+    /// Frames the last tick covered.
+    frames_per_tick: u32,
+    /// Seconds the last tick covered.
+    interval: f32,
+    since_tick: f32,
+}
+
+impl Default for TickClock {
+    fn default() -> Self {
+        TickClock {
+            ticked: false,
+            frames_per_tick: 1,
+            interval: 0.0,
+            since_tick: 0.0,
+        }
+    }
+}
+
+impl TickClock {
+    /// Records a tick covering `frames` frames and `elapsed` seconds.
+    pub fn tick(&mut self, frames: u32, elapsed: f32) {
+        self.ticked = true;
+        self.frames_per_tick = frames;
+        self.interval = elapsed;
+    }
+
+    /// How far the frame being drawn is from the previous tick to the next.
+    fn alpha(&self) -> f32 {
+        if self.interval > 0.0 {
+            (self.since_tick / self.interval).min(1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A particle's simulated positions at the last two ticks, and the position
+/// it was last drawn at if that differed.
+#[derive(Component)]
+pub struct TickPositions {
+    previous: Vec2,
+    current: Vec2,
+    drawn: Option<Vec2>,
+}
+
+impl TickPositions {
+    pub fn at(pos: Vec2) -> Self {
+        TickPositions {
+            previous: pos,
+            current: pos,
+            drawn: None,
+        }
+    }
+}
+
+/// Puts the simulated positions back before anything else runs.
+fn restore_transforms(mut particles: Query<(&mut Transform, &mut TickPositions)>) {
+    for (mut transform, mut positions) in &mut particles {
+        let Some(drawn) = positions.drawn.take() else {
+            continue;
+        };
+        if transform.translation.truncate() == drawn {
+            let current = positions.current;
+            transform.translation = current.extend(transform.translation.z);
+        }
+    }
+}
+
+pub fn interpolate_transforms(
+    settings: Res<Settings>,
+    time: Res<Time>,
+    mut clock: ResMut<TickClock>,
+    mut particles: Query<(&mut Transform, &mut TickPositions), With<Particle>>,
+) {
+    let ticked = std::mem::take(&mut clock.ticked);
+    if ticked {
+        clock.since_tick = 0.0;
+    } else {
+        clock.since_tick += time.delta_secs();
+    }
+    let blend = settings.interpolate && clock.frames_per_tick > 1;
+    let alpha = clock.alpha();
+    for (mut transform, mut positions) in &mut particles {
+        let pos = transform.translation.truncate();
+        if ticked {
+            positions.previous = positions.current;
+            positions.current = pos;
+        } else {
+            // Moved by something other than a tick, e.g. dragged or
+            // respawned; carry the whole blend along
+            let offset = pos - positions.current;
+            positions.previous += offset;
+            positions.current = pos;
+        }
+        if !blend {
+            continue;
+        }
+        let drawn = positions.previous.lerp(positions.current, alpha);
+        if drawn != pos {
+            transform.translation = drawn.extend(transform.translation.z);
+            positions.drawn = Some(drawn);
+        }
+    }
+}
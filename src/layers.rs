@@ -0,0 +1,318 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    backend::{GridBackend, SimulationBackend},
+    buffers::ParticleBuffers,
+    force::ForceProfile,
+    palette::PaletteSettings,
+    particle_assets::ParticleAssets,
+    replay,
+    settings::Settings,
+    spawn, ui_enabled, update_particles, ParticleSystem, Velocity,
+};
+
+/// Overlay particles are drawn above the base layer and below the heatmap.
+const OVERLAY_Z: f32 = 1.0;
+const OVERLAY_COLORS: usize = 6;
+const OVERLAY_PARTICLES: usize = 1000;
+
+pub struct LayersPlugin;
+
+impl Plugin for LayersPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OverlayLayer>().add_systems(
+            Update,
+            (
+                sync_overlay_particles,
+                update_overlay
+                    .after(update_particles)
+                    .run_if(not(replay::is_playing_back)),
+                layers_ui_system.run_if(ui_enabled),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// A particle of the overlay layer. It is not a [`crate::Particle`], so the
+/// base layer's systems never see it.
+#[derive(Component)]
+pub struct OverlayParticle {
+    color_id: usize,
+}
+
+/// A second particle system with its own matrix and palette, simulated next
+/// to the base one and drawn on its own z-layer, e.g. an environment the
+/// base layer's organisms move through.
+#[derive(Resource)]
+pub struct OverlayLayer {
+    pub enabled: bool,
+    pub system: ParticleSystem,
+    pub count: usize,
+    /// Behavior of every pair across the layers, felt by both sides. Zero
+    /// keeps the layers independent.
+    pub coupling: f32,
+    respawn: bool,
+    materials: Vec<Handle<ColorMaterial>>,
+    /// Overlay positions as of its last step, and a grid over them sized by
+    /// the overlay's attraction radius.
+    positions: Vec<Vec2>,
+    species: Vec<usize>,
+    grid: GridBackend,
+    /// Base layer positions, bucketed by the same radius.
+    base_grid: GridBackend,
+}
+
+impl Default for OverlayLayer {
+    fn default() -> Self {
+        let mut system =
+            ParticleSystem::with_colors(PaletteSettings::default().generate(OVERLAY_COLORS));
+        system.reroll_matrix();
+        OverlayLayer {
+            enabled: false,
+            system,
+            count: OVERLAY_PARTICLES,
+            coupling: 0.0,
+            respawn: true,
+            materials: Vec::new(),
+            positions: Vec::new(),
+            species: Vec::new(),
+            grid: GridBackend::default(),
+            base_grid: GridBackend::default(),
+        }
+    }
+}
+
+impl OverlayLayer {
+    fn coupled(&self) -> bool {
+        self.enabled && self.coupling != 0.0
+    }
+
+    /// Average force the overlay exerts on a base particle at `pos`, shaped by
+    /// the base layer's `profile`.
+    pub fn coupling_force(&mut self, pos: Vec2, profile: &dyn ForceProfile) -> Vec2 {
+        if !self.coupled() {
+            return Vec2::ZERO;
+        }
+        cross_force(
+            &mut self.grid,
+            pos,
+            self.system.attraction_radius,
+            &self.positions,
+            &self.species,
+            profile,
+            self.coupling,
+        )
+    }
+}
+
+/// Average force on a particle of `color_id` at `pos` from the particles
+/// bucketed in `grid`, under `system`'s matrix and its global radius.
+pub fn system_force(
+    grid: &mut GridBackend,
+    system: &ParticleSystem,
+    profile: &dyn ForceProfile,
+    pos: Vec2,
+    color_id: usize,
+    positions: &[Vec2],
+    species: &[usize],
+) -> Vec2 {
+    let (force, count) = grid.accumulate(
+        pos,
+        system.attraction_radius,
+        positions,
+        species,
+        &|distance, other| {
+            let force = profile.force(distance, system.get_behavior(color_id, other));
+            system.force_limit.neighbor(force)
+        },
+    );
+    if count > 0.0 {
+        system.force_limit.total(force / count)
+    } else {
+        Vec2::ZERO
+    }
+}
+
+fn cross_force(
+    grid: &mut GridBackend,
+    pos: Vec2,
+    radius: f32,
+    positions: &[Vec2],
+    species: &[usize],
+    profile: &dyn ForceProfile,
+    coupling: f32,
+) -> Vec2 {
+    let (force, count) = grid.accumulate(pos, radius, positions, species, &|distance, _| {
+        profile.force(distance, coupling)
+    });
+    if count > 0.0 {
+        force / count
+    } else {
+        Vec2::ZERO
+    }
+}
+
+/// Spawns or removes overlay particles to match the layer's settings and keeps
+/// its species materials in line with its palette.
+fn sync_overlay_particles(
+    mut commands: Commands,
+    mut layer: ResMut<OverlayLayer>,
+    assets: Res<ParticleAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    particles: Query<Entity, With<OverlayParticle>>,
+) {
+    let live = particles.iter().len();
+    let wanted = if layer.enabled { layer.count } else { 0 };
+    if live == wanted && !layer.respawn {
+        return;
+    }
+    let layer = &mut *layer;
+    layer.respawn = false;
+    for entity in &particles {
+        commands.entity(entity).despawn();
+    }
+
+    layer.materials.clear();
+    layer.materials.extend(
+        layer
+            .system
+            .colors
+            .iter()
+            .map(|&color| materials.add(ColorMaterial::from(color))),
+    );
+    let n = layer.system.colors.len();
+    let bounds = spawn::world_bounds();
+    let mut rng = rand::rng();
+    for index in 0..wanted {
+        let color_id = index % n;
+        commands.spawn((
+            Mesh2d(assets.mesh.clone()),
+            MeshMaterial2d(layer.materials[color_id].clone()),
+            Transform::from_translation(spawn::uniform_in(bounds, &mut rng).extend(OVERLAY_Z)),
+            OverlayParticle { color_id },
+            Velocity::default(),
+        ));
+    }
+}
+
+fn update_overlay(
+    time: Res<Time>,
+    buffers: Res<ParticleBuffers>,
+    mut layer: ResMut<OverlayLayer>,
+    mut particles: Query<(&mut Transform, &OverlayParticle, &mut Velocity)>,
+) {
+    if !layer.enabled {
+        return;
+    }
+    let step = time.delta_secs() * layer.system.time_scale;
+    let coupled = layer.coupled();
+    let OverlayLayer {
+        system,
+        coupling,
+        positions,
+        species,
+        grid,
+        base_grid,
+        ..
+    } = &mut *layer;
+
+    positions.clear();
+    species.clear();
+    for (transform, particle, _) in &particles {
+        positions.push(transform.translation.truncate());
+        species.push(particle.color_id);
+    }
+    let radius = system.attraction_radius;
+    grid.prepare(positions, species, radius);
+    if coupled {
+        base_grid.prepare(&buffers.front, &buffers.species, radius);
+    }
+    let profile = system.force_profile.profile(system.beta, system.gamma);
+
+    for (index, (mut transform, particle, mut velocity)) in particles.iter_mut().enumerate() {
+        let pos = positions[index];
+        let mut force = system_force(
+            grid,
+            system,
+            &*profile,
+            pos,
+            particle.color_id,
+            positions,
+            species,
+        );
+        if coupled {
+            force += cross_force(
+                base_grid,
+                pos,
+                radius,
+                &buffers.front,
+                &buffers.species,
+                &*profile,
+                *coupling,
+            );
+        }
+
+        let motion = system
+            .species_motion
+            .get(particle.color_id)
+            .copied()
+            .unwrap_or_default();
+        velocity.0 = motion.integrate(velocity.0, force * system.force_strength, step);
+        transform.translation = (pos + velocity.0 * step).extend(OVERLAY_Z);
+    }
+}
+
+fn layers_ui_system(
+    mut contexts: EguiContexts,
+    settings: Res<Settings>,
+    mut layer: ResMut<OverlayLayer>,
+) {
+    egui::Window::new("Layers")
+        .default_pos([400.0, 420.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("A second particle system with its own rules, drawn above the first.");
+            ui.add_space(5.0);
+
+            let layer = &mut *layer;
+            ui.checkbox(&mut layer.enabled, "Overlay layer");
+            ui.add_enabled_ui(layer.enabled, |ui| {
+                ui.add(egui::Slider::new(&mut layer.count, 100..=5000).text("particles"));
+                let mut colors = layer.system.colors.len();
+                if ui
+                    .add(egui::Slider::new(&mut colors, 1..=20).text("colors"))
+                    .changed()
+                {
+                    layer.system.colors = settings.palette.generate(colors);
+                    layer.system.regenerate_matrix();
+                    layer.system.reroll_matrix();
+                    layer.respawn = true;
+                }
+                ui.add(
+                    egui::Slider::new(&mut layer.system.force_strength, 0.0..=3200.0)
+                        .text("force strength"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut layer.system.attraction_radius, 10.0..=200.0)
+                        .text("radius"),
+                );
+                ui.add(egui::Slider::new(&mut layer.coupling, -1.0..=1.0).text("coupling"))
+                    .on_hover_text("Positive attracts the layers to each other, negative repels");
+                ui.horizontal(|ui| {
+                    if ui.button("Reroll Matrix").clicked() {
+                        layer.system.reroll_matrix();
+                    }
+                    if ui.button("New Palette").clicked() {
+                        let n = layer.system.colors.len();
+                        layer.system.colors = settings.palette.generate(n);
+                        layer.respawn = true;
+                    }
+                    if ui.button("Respawn").clicked() {
+                        layer.respawn = true;
+                    }
+                });
+            });
+        });
+}
@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    egui_color,
+    interactions::PairHighlight,
+    settings::Settings,
+    species::{self, SpeciesToggles},
+    ui_enabled, Particle, ParticleSystem,
+};
+
+/// Tallest the species list gets before it scrolls, in points.
+const MAX_LIST_HEIGHT: f32 = 320.0;
+
+pub struct LegendPlugin;
+
+impl Plugin for LegendPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Legend>().add_systems(
+            Update,
+            (toggle_legend, legend_system.run_if(ui_enabled)).chain(),
+        );
+    }
+}
+
+/// Corner list of the species with their colors and live populations.
+/// Clicking one selects it for freezing or hiding; Shift+clicking a second
+/// highlights the pair's interactions from the selected one.
+#[derive(Resource)]
+struct Legend {
+    visible: bool,
+    selected: Option<usize>,
+}
+
+impl Default for Legend {
+    fn default() -> Self {
+        Legend {
+            visible: true,
+            selected: None,
+        }
+    }
+}
+
+fn toggle_legend(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    mut legend: ResMut<Legend>,
+) {
+    if keyboard.just_pressed(settings.keys.toggle_legend) {
+        legend.visible = !legend.visible;
+    }
+}
+
+fn legend_system(
+    mut contexts: EguiContexts,
+    (particle_system, settings): (Res<ParticleSystem>, Res<Settings>),
+    mut legend: ResMut<Legend>,
+    mut toggles: ResMut<SpeciesToggles>,
+    mut highlight: ResMut<PairHighlight>,
+    particles: Query<&Particle>,
+    mut populations: Local<Vec<usize>>,
+) {
+    if !legend.visible {
+        return;
+    }
+    let colors = &particle_system.colors;
+    populations.clear();
+    populations.resize(colors.len(), 0);
+    for particle in &particles {
+        if let Some(population) = populations.get_mut(particle.color_id) {
+            *population += 1;
+        }
+    }
+    if legend
+        .selected
+        .is_some_and(|selected| selected >= colors.len())
+    {
+        legend.selected = None;
+    }
+
+    egui::Area::new(egui::Id::new("species_legend"))
+        .anchor(egui::Align2::LEFT_BOTTOM, [10.0, -10.0])
+        .show(contexts.ctx_mut(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(MAX_LIST_HEIGHT)
+                    .show(ui, |ui| {
+                        egui::Grid::new("species_legend_grid").show(ui, |ui| {
+                            for (species, &color) in colors.iter().enumerate() {
+                                let hidden = toggles.is_hidden(species);
+                                let (rect, swatch) = ui.allocate_exact_size(
+                                    egui::vec2(12.0, 12.0),
+                                    egui::Sense::click(),
+                                );
+                                let mut fill = egui_color(color);
+                                if hidden {
+                                    fill = fill.gamma_multiply(0.3);
+                                }
+                                ui.painter().rect_filled(rect, 2.0, fill);
+                                // Particles are drawn with their shape, so name them with it
+                                let shape = particle_system
+                                    .shapes
+                                    .get(species)
+                                    .filter(|_| settings.species_shapes);
+                                let mut name = egui::RichText::new(match shape {
+                                    Some(shape) => format!("{} {}", shape.glyph(), species + 1),
+                                    None => format!("{}", species + 1),
+                                });
+                                if hidden {
+                                    name = name.weak();
+                                }
+                                let label =
+                                    ui.selectable_label(legend.selected == Some(species), name);
+                                ui.label(populations[species].to_string());
+                                let pair = highlight.enabled.then(|| {
+                                    match (species == highlight.from, species == highlight.to) {
+                                        (true, true) => "from, to",
+                                        (true, false) => "from",
+                                        (false, true) => "to",
+                                        (false, false) => "",
+                                    }
+                                });
+                                ui.weak(pair.unwrap_or_default());
+                                ui.end_row();
+
+                                if !(swatch | label).clicked() {
+                                    continue;
+                                }
+                                let shift = ui.input(|input| input.modifiers.shift);
+                                match legend.selected {
+                                    Some(from) if shift => {
+                                        highlight.from = from;
+                                        highlight.to = species;
+                                        highlight.enabled = true;
+                                    }
+                                    Some(selected) if selected == species => {
+                                        legend.selected = None;
+                                    }
+                                    _ => legend.selected = Some(species),
+                                }
+                            }
+                        });
+                    });
+
+                let Some(selected) = legend.selected else {
+                    ui.weak("Click to select, Shift+click a second to highlight the pair");
+                    return;
+                };
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(format!("Species {}", selected + 1));
+                    let state = species::state_checkboxes(ui, toggles.state(selected));
+                    if state != toggles.state(selected) {
+                        toggles.set(selected, state);
+                    }
+                });
+                if highlight.enabled && ui.button("Stop highlighting").clicked() {
+                    highlight.enabled = false;
+                }
+            });
+        });
+}
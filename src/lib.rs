@@ -1,174 +1,192 @@
+mod audio;
+mod autosave;
+mod backend;
+mod background;
+mod bonds;
+mod buffers;
+mod capture;
+mod clipboard;
+mod comparison;
+#[cfg(not(target_arch = "wasm32"))]
+mod control;
+mod curve_editor;
+mod determinism;
+mod dock;
+pub mod events;
+mod evolution;
+mod explain;
+mod explore;
+mod export;
+mod flow;
+mod force;
+mod framepace;
+mod freeze;
+mod gpu;
+mod hardware;
+pub mod headless;
+mod heatmap;
+mod help;
+mod interactions;
+mod interpolation;
+mod layers;
+mod legend;
+mod life;
+mod loading;
+mod lod;
+mod logging;
+mod matrix_presets;
+mod menu;
+#[cfg(not(target_arch = "wasm32"))]
+mod metrics;
+mod microphone;
+mod midi;
+mod minimap;
+mod motion;
+mod network;
+#[cfg(not(target_arch = "wasm32"))]
+mod osc;
+mod palette;
+mod particle_assets;
+mod perf;
+mod pheromone;
+mod physics;
+mod point_sprites;
+mod pool;
+mod preset;
+mod quadtree;
+mod quality;
+mod renderer;
+mod replay;
+mod screensaver;
+mod scripting;
+mod settings;
+mod shapes;
+mod share;
+mod simthread;
+mod slots;
+mod snapshot;
+mod soak;
+mod spawn;
+mod species;
+mod stamp;
+mod stats;
+mod stress;
+mod substep;
+mod svg;
+mod temperature;
+mod timeline;
+mod tutorial;
+mod warmup;
+mod zones;
+
 use bevy::{
-    color::palettes::css,
-    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
-    input::mouse::MouseWheel,
+    app::ScheduleRunnerPlugin,
+    diagnostic::{Diagnostics, DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    ecs::system::SystemParam,
+    input::mouse::{MouseScrollUnit, MouseWheel},
     prelude::*,
-    window::WindowResolution,
+    render::{
+        camera::RenderTarget,
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+        renderer::RenderAdapterInfo,
+        settings::WgpuSettings,
+        RenderPlugin,
+    },
+    utils::Instant,
+    window::{ExitCondition, WindowResolution},
+    winit::WinitPlugin,
 };
 use bevy_egui::{egui, EguiContexts, EguiPlugin};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
-#[derive(Resource)]
-struct ColorCount {
-    count: usize,
-}
+/// Starts the web worker pool of the parallel backend. The page must await
+/// `initThreadPool(navigator.hardwareConcurrency)` before starting the app,
+/// and only when `crossOriginIsolated` is true.
+#[cfg(all(target_arch = "wasm32", feature = "wasm-threads"))]
+pub use wasm_bindgen_rayon::init_thread_pool;
 
 #[derive(Component)]
+#[require(Velocity, life::Age, lod::LodClock, buffers::BufferIndex)]
 struct Particle {
     color_id: usize,
 }
 
+/// World units per second applied to the particle during the last update.
+#[derive(Component, Default)]
+struct Velocity(Vec2);
+
+/// Particles a reset lays out: the slider's target, updated to the live
+/// count whenever the user restarts or respawns.
 #[derive(Resource)]
 struct ParticleCount {
     count: usize,
 }
 
+impl ParticleCount {
+    /// Adopts the number of particles alive now, so a restart keeps what was
+    /// added or removed since the last one. Particles the quality governor
+    /// removed still count; an empty world keeps the current target.
+    fn adopt_live(&mut self, alive: usize, quality: &quality::QualityGovernor) -> usize {
+        if alive > 0 {
+            self.count = alive + quality.removed_particles;
+        }
+        self.count
+    }
+}
+
+/// The camera showing the main simulation.
+#[derive(Component)]
+struct MainCamera;
+
+/// The camera panned and zoomed by the keyboard, normally the main one.
+#[derive(Component)]
+struct ControlledCamera;
+
+/// Present when running without a window or renderer; UI systems are skipped.
 #[derive(Resource)]
+struct Headless;
+
+#[derive(Resource, Clone, Serialize, Deserialize)]
+#[serde(default)]
 struct ParticleSystem {
     colors: Vec<Color>,
     behavior_matrix: Vec<Vec<f32>>,
-    speed: f32,
+    /// Scales every force, and with it how fast particles move; older files
+    /// call it speed.
+    #[serde(alias = "speed")]
+    force_strength: f32,
+    /// Simulated seconds per real second. Slowing time keeps the
+    /// interactions as strong; lowering the force strength does not.
+    time_scale: f32,
     beta: f32,
     gamma: f32,
     attraction_radius: f32,
+    /// Per-pair interaction cutoffs in world units; the global
+    /// `attraction_radius` applies to every pair when absent.
+    radius_matrix: Option<Vec<Vec<f32>>>,
+    force_profile: force::ForceShape,
+    force_limit: force::ForceLimit,
+    temperature: f32,
+    annealing: bool,
+    annealing_rate: f32,
+    life_enabled: bool,
+    life_rules: Vec<life::LifeRule>,
+    species_motion: Vec<motion::SpeciesMotion>,
+    shapes: Vec<shapes::SpeciesShape>,
+    spawn_weights: spawn::SpawnWeights,
+    pheromones: pheromone::PheromoneRules,
+    bonds: bonds::BondRules,
+    zones: Vec<zones::RuleZone>,
 }
 
 impl ParticleSystem {
     fn new() -> Self {
-        let all_colors = vec![
-            // Reds
-            css::RED,
-            css::CRIMSON,
-            css::DARK_RED,
-            css::FIRE_BRICK,
-            css::INDIAN_RED,
-            css::LIGHT_CORAL,
-            css::SALMON,
-            css::DARK_SALMON,
-            css::LIGHT_SALMON,
-            css::ORANGE_RED,
-            // Oranges
-            css::ORANGE_RED,
-            css::TOMATO,
-            css::DARK_ORANGE,
-            css::ORANGE,
-            css::GOLD,
-            css::DARK_GOLDENROD,
-            css::GOLDENROD,
-            css::PALE_GOLDENROD,
-            css::PEACHPUFF,
-            css::NAVAJO_WHITE,
-            // Yellows
-            css::YELLOW,
-            css::LIGHT_YELLOW,
-            css::LEMON_CHIFFON,
-            css::LIGHT_GOLDENROD_YELLOW,
-            css::PAPAYA_WHIP,
-            css::MOCCASIN,
-            css::KHAKI,
-            css::DARK_KHAKI,
-            css::YELLOW_GREEN,
-            css::OLIVE,
-            // Greens
-            css::LIME,
-            css::LIMEGREEN,
-            css::LAWN_GREEN,
-            css::CHARTREUSE,
-            css::GREEN_YELLOW,
-            css::SPRING_GREEN,
-            css::MEDIUM_SPRING_GREEN,
-            css::LIGHT_GREEN,
-            css::PALE_GREEN,
-            css::DARK_SEA_GREEN,
-            css::MEDIUM_SEA_GREEN,
-            css::SEA_GREEN,
-            css::FOREST_GREEN,
-            css::GREEN,
-            css::DARK_GREEN,
-            // Cyans
-            css::MEDIUM_AQUAMARINE,
-            css::AQUA,
-            css::DARK_CYAN,
-            css::LIGHT_CYAN,
-            css::PALE_TURQUOISE,
-            css::AQUAMARINE,
-            css::TURQUOISE,
-            css::MEDIUM_TURQUOISE,
-            css::DARK_TURQUOISE,
-            css::LIGHT_SEA_GREEN,
-            // Blues
-            css::DEEP_SKY_BLUE,
-            css::LIGHT_BLUE,
-            css::SKY_BLUE,
-            css::LIGHT_SKY_BLUE,
-            css::STEEL_BLUE,
-            css::ALICE_BLUE,
-            css::DODGER_BLUE,
-            css::ROYAL_BLUE,
-            css::BLUE,
-            css::MEDIUM_BLUE,
-            css::DARK_BLUE,
-            css::NAVY,
-            css::MIDNIGHT_BLUE,
-            css::CORNFLOWER_BLUE,
-            css::SLATE_BLUE,
-            // Purples
-            css::MEDIUM_SLATE_BLUE,
-            css::DARK_SLATE_BLUE,
-            css::LAVENDER,
-            css::THISTLE,
-            css::PLUM,
-            css::VIOLET,
-            css::ORCHID,
-            css::MAGENTA,
-            css::MEDIUM_ORCHID,
-            css::MEDIUM_PURPLE,
-            css::BLUE_VIOLET,
-            css::DARK_VIOLET,
-            css::DARK_ORCHID,
-            css::DARK_MAGENTA,
-            css::PURPLE,
-            // Pinks
-            css::INDIGO,
-            css::MEDIUM_VIOLET_RED,
-            css::PALE_VIOLETRED,
-            css::DEEP_PINK,
-            css::HOT_PINK,
-            css::LIGHT_PINK,
-            css::PINK,
-            css::ANTIQUE_WHITE,
-            css::BEIGE,
-            css::BISQUE,
-            // Browns
-            css::SADDLE_BROWN,
-            css::SIENNA,
-            css::CHOCOLATE,
-            css::PERU,
-            css::SANDY_BROWN,
-            css::BURLYWOOD,
-            css::TAN,
-            css::ROSY_BROWN,
-            css::MOCCASIN,
-            css::NAVAJO_WHITE,
-            // Grays and others
-            css::MAROON,
-            css::BROWN,
-            css::DARK_OLIVEGREEN,
-            css::OLIVE_DRAB,
-            css::DARK_CYAN,
-            css::TEAL,
-            css::DARK_SLATE_GRAY,
-            css::SLATE_GRAY,
-            css::LIGHT_SLATE_GRAY,
-            css::DIM_GRAY,
-        ];
-
-        let num_colors = 50; // Fixed number of colors for simplicity
-        let colors: Vec<Color> = all_colors
-            .into_iter()
-            .take(num_colors)
-            .map(Color::from)
-            .collect();
+        Self::with_colors(palette::PaletteSettings::default().generate(NUM_COLORS))
+    }
+
+    fn with_colors(colors: Vec<Color>) -> Self {
         let n = colors.len();
 
         let behavior_matrix = vec![vec![0.0; n]; n]; // Initialize with zeros
@@ -180,19 +198,141 @@ impl ParticleSystem {
         ParticleSystem {
             colors,
             behavior_matrix,
-            speed: BASE_SPEED,
+            force_strength: BASE_SPEED,
+            time_scale: 1.0,
             beta,
             gamma,
             attraction_radius,
+            radius_matrix: None,
+            force_profile: force::ForceShape::default(),
+            force_limit: force::ForceLimit::default(),
+            temperature: 0.0,
+            annealing: false,
+            annealing_rate: 0.1,
+            life_enabled: false,
+            life_rules: vec![life::LifeRule::default(); n],
+            species_motion: vec![motion::SpeciesMotion::default(); n],
+            shapes: shapes::generate(n),
+            spawn_weights: spawn::SpawnWeights::default(),
+            pheromones: pheromone::PheromoneRules::default(),
+            bonds: bonds::BondRules::default(),
+            zones: Vec::new(),
         }
     }
 
     fn get_behavior(&self, from_color: usize, to_color: usize) -> f32 {
         self.behavior_matrix[from_color][to_color]
     }
+    /// Cutoff for `from_color` reacting to `to_color`, falling back to the
+    /// global radius for pairs outside the radius matrix.
+    fn interaction_radius(&self, from_color: usize, to_color: usize) -> f32 {
+        self.radius_matrix
+            .as_ref()
+            .and_then(|radii| radii.get(from_color)?.get(to_color).copied())
+            .unwrap_or(self.attraction_radius)
+    }
+    /// A tunable scalar by the name scripts and remote control use for it.
+    fn parameter(&self, name: &str) -> Option<f32> {
+        match name {
+            "speed" | "force_strength" => Some(self.force_strength),
+            "time_scale" => Some(self.time_scale),
+            "beta" => Some(self.beta),
+            "gamma" => Some(self.gamma),
+            "radius" => Some(self.attraction_radius),
+            "temperature" => Some(self.temperature),
+            _ => None,
+        }
+    }
+    /// Writable form of [`ParticleSystem::parameter`].
+    fn parameter_mut(&mut self, name: &str) -> Option<&mut f32> {
+        match name {
+            "speed" | "force_strength" => Some(&mut self.force_strength),
+            "time_scale" => Some(&mut self.time_scale),
+            "beta" => Some(&mut self.beta),
+            "gamma" => Some(&mut self.gamma),
+            "radius" => Some(&mut self.attraction_radius),
+            "temperature" => Some(&mut self.temperature),
+            _ => None,
+        }
+    }
+    /// Every parameter [`parameter_mut`](Self::parameter_mut) knows, by the
+    /// name it is set with.
+    fn parameters(&self) -> [(&'static str, f32); 6] {
+        [
+            ("speed", self.force_strength),
+            ("time_scale", self.time_scale),
+            ("beta", self.beta),
+            ("gamma", self.gamma),
+            ("radius", self.attraction_radius),
+            ("temperature", self.temperature),
+        ]
+    }
+    /// The rule zone `pos` falls in, if any.
+    fn zone_at(&self, pos: Vec2) -> Option<&zones::RuleZone> {
+        self.zones.iter().find(|zone| zone.contains(pos))
+    }
+    fn zone_index_at(&self, pos: Vec2) -> Option<usize> {
+        self.zones.iter().position(|zone| zone.contains(pos))
+    }
+    /// The highest force strength in effect anywhere, zones included.
+    fn fastest_speed(&self) -> f32 {
+        self.zones
+            .iter()
+            .filter_map(|zone| zone.speed)
+            .fold(self.force_strength, f32::max)
+    }
+    /// The largest cutoff of any pair, which sizes the neighbor search.
+    fn max_interaction_radius(&self) -> f32 {
+        match &self.radius_matrix {
+            Some(radii) if !radii.iter().all(Vec::is_empty) => {
+                radii.iter().flatten().copied().fold(0.0, f32::max)
+            }
+            _ => self.attraction_radius,
+        }
+    }
     fn regenerate_matrix(&mut self) {
+        self.behavior_matrix.clear();
+        if let Some(radii) = &mut self.radius_matrix {
+            radii.clear();
+        }
+        self.fit_to_palette();
+    }
+    /// Sizes every per-species table to the palette, keeping the entries that
+    /// still apply, so rules read from a file or received from elsewhere can
+    /// be indexed by any species.
+    fn fit_to_palette(&mut self) {
         let n = self.colors.len();
-        self.behavior_matrix = vec![vec![0.0; n]; n]; // Initialize with zeros
+        self.behavior_matrix.resize(n, Vec::new());
+        for row in &mut self.behavior_matrix {
+            row.resize(n, 0.0);
+        }
+        if let Some(radii) = &mut self.radius_matrix {
+            radii.resize(n, Vec::new());
+            for row in radii {
+                row.resize(n, self.attraction_radius);
+            }
+        }
+        self.life_rules.resize(n, life::LifeRule::default());
+        self.species_motion
+            .resize(n, motion::SpeciesMotion::default());
+        if self.shapes.len() != n {
+            self.shapes = shapes::generate(n);
+        }
+    }
+    /// New colors, shapes, matrix and constants, as on a restart.
+    fn regenerate_all(&mut self, colors: Vec<Color>) {
+        self.shapes = shapes::generate(colors.len());
+        self.colors = colors;
+        self.regenerate_matrix();
+        self.regenerate_constants();
+    }
+    /// New random matrix values, keeping the palette and species count.
+    fn reroll_matrix(&mut self) {
+        matrix_presets::reroll(&mut self.behavior_matrix, &mut rand::rng());
+    }
+    /// Nudges every matrix entry by up to `epsilon` in either direction.
+    fn perturb_matrix(&mut self, epsilon: f32) {
+        matrix_presets::perturb(&mut self.behavior_matrix, epsilon, &mut rand::rng());
     }
     fn regenerate_constants(&mut self) {
         self.beta = 0.25;
@@ -201,732 +341,1031 @@ impl ParticleSystem {
     }
 }
 
+impl Default for ParticleSystem {
+    fn default() -> Self {
+        ParticleSystem::new()
+    }
+}
+
 const WINDOW_WIDTH: f32 = 1920.0;
 const WINDOW_HEIGHT: f32 = 1080.0;
 const PARTICLE_SIZE: f32 = 5.0;
 const NUM_PARTICLES: usize = 5000;
+const NUM_COLORS: usize = 50;
 const BASE_SPEED: f32 = 1600.0;
 const CAMERA_SPEED: f32 = 500.0;
+/// Zoom factor of one scroll wheel notch.
+const ZOOM_STEP: f32 = 1.1;
+/// Zoom rate while a zoom key is held, as a natural log per second.
+const ZOOM_SPEED: f32 = 1.5;
+/// Pixel scrolling (touchpads) that counts as one wheel notch.
+const PIXELS_PER_LINE: f32 = 50.0;
+/// Largest change per entry when perturbing the matrix.
+const PERTURB_EPSILON: f32 = 0.05;
+/// Particles added by the "Add to View" button.
+const VIEW_SCATTER: usize = 500;
+
+/// Builds the app from the command line and saved settings, and runs it.
+pub fn run() {
+    let mut app = App::new();
+
+    let soak_config = soak::SoakConfig::from_args();
+    let settings = match soak_config {
+        Some(_) => settings::Settings::default(),
+        None => settings::Settings::load(),
+    };
 
-fn main() {
-    App::new()
-        .add_plugins((
-            DefaultPlugins.set(WindowPlugin {
-                primary_window: Some(Window {
-                    title: "Particle Life".to_string(),
-                    resolution: WindowResolution::new(WINDOW_WIDTH, WINDOW_HEIGHT),
+    #[cfg(not(target_arch = "wasm32"))]
+    preset::hot_reload::register_source(&mut app);
+
+    if let Some(config) = soak_config {
+        app.add_plugins((
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    ..Default::default()
+                })
+                .set(RenderPlugin {
+                    render_creation: WgpuSettings {
+                        backends: None,
+                        ..Default::default()
+                    }
+                    .into(),
                     ..Default::default()
-                }),
-                ..Default::default()
-            }),
+                })
+                .set(logging::log_plugin())
+                .disable::<WinitPlugin>(),
+            ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(1.0 / 60.0)),
+            FrameTimeDiagnosticsPlugin,
+            soak::SoakPlugin { config },
+        ));
+    } else {
+        app.add_plugins((
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: Some(Window {
+                        title: "Particle Life".to_string(),
+                        resolution: WindowResolution::new(WINDOW_WIDTH, WINDOW_HEIGHT),
+                        present_mode: settings.present_mode(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+                .set(RenderPlugin {
+                    render_creation: renderer::wgpu_settings().into(),
+                    ..Default::default()
+                })
+                .set(logging::log_plugin()),
             FrameTimeDiagnosticsPlugin,
             EguiPlugin,
-            LogDiagnosticsPlugin::default(),
-        ))
-        .insert_resource(ParticleSystem::new())
-        .insert_resource(ParticleCount { count: 5000 }) // Initial particle count
-        .add_systems(Startup, setup)
-        .add_systems(
-            Update,
-            (
-                update_particles,
-                move_camera,
-                handle_matrix_regeneration,
-                adjust_speed,
-                ui_system,
-            ),
-        )
+            settings::SettingsPlugin,
+        ));
+        if menu::shown_at_launch() {
+            app.insert_state(menu::AppState::Menu);
+        }
+    }
+
+    app.insert_resource(settings)
+        .add_plugins(ParticleLifePlugin::default())
         .run();
 }
 
-fn setup(
-    mut commands: Commands,
-    particle_system: Res<ParticleSystem>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    commands.spawn(Camera2d::default());
+/// The whole simulation with its UI, for `main` and for host applications
+/// that embed it. Needs `DefaultPlugins`, plus `EguiPlugin` when the UI is
+/// shown. Settings are loaded from disk unless the app already has them.
+pub struct ParticleLifePlugin {
+    /// Draw into this image instead of the primary window, e.g. one made
+    /// with [`embed_image`], so the host can show it as a texture.
+    pub render_target: Option<Handle<Image>>,
+    pub show_ui: bool,
+}
 
-    dbg!(&particle_system.behavior_matrix);
+impl Default for ParticleLifePlugin {
+    fn default() -> Self {
+        ParticleLifePlugin {
+            render_target: None,
+            show_ui: true,
+        }
+    }
+}
 
-    let grid_size = (NUM_PARTICLES as f32).sqrt().ceil() as usize;
-    let spacing_x = WINDOW_WIDTH / grid_size as f32;
-    let spacing_y = WINDOW_HEIGHT / grid_size as f32;
+impl Plugin for ParticleLifePlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world().contains_resource::<settings::Settings>() {
+            app.insert_resource(settings::Settings::load());
+        }
+        if let Some(target) = &self.render_target {
+            app.insert_resource(EmbedTarget(target.clone()));
+        }
+        if !self.show_ui {
+            app.insert_resource(Headless);
+        }
+        let settings = app.world().resource::<settings::Settings>();
+        let colors = settings.palette.generate(NUM_COLORS);
+        let stress = stress::StressConfig::from_args();
+        let count = stress
+            .as_ref()
+            .map_or(settings.particle_count, |config| config.particles);
 
-    for i in 0..grid_size {
-        for j in 0..grid_size {
-            if i * grid_size + j >= NUM_PARTICLES {
-                break;
-            }
-            let mut x = i as f32 * spacing_x - WINDOW_WIDTH / 2.0 + spacing_x / 2.0;
-            let mut y = j as f32 * spacing_y - WINDOW_HEIGHT / 2.0 + spacing_y / 2.0;
-            if x % 2.0 == 0.0 {
-                x += 5.0;
-            } else {
-                y += 2.0;
-            }
-            let color_id = (i * grid_size + j) % particle_system.colors.len();
+        if physics::requested() {
+            #[cfg(feature = "avian")]
+            app.add_plugins(physics::PhysicsPlugin);
+        }
+        if network::requested() {
+            #[cfg(feature = "network")]
+            app.add_plugins(network::NetworkPlugin);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_plugins((
+            control::ControlPlugin,
+            osc::OscPlugin,
+            metrics::MetricsPlugin,
+        ));
+        if let Some(config) = stress {
+            app.add_plugins(stress::StressPlugin { config });
+        }
 
-            commands.spawn((
-                Mesh2d(meshes.add(Circle::new(PARTICLE_SIZE / 2.0))),
-                MeshMaterial2d(
-                    materials.add(ColorMaterial::from(particle_system.colors[color_id])),
+        app.init_resource::<buffers::ParticleBuffers>()
+            .init_resource::<CameraMotion>()
+            .init_resource::<pool::ParticlePool>()
+            .add_plugins((
+                stats::StatsPlugin,
+                export::ExportPlugin,
+                replay::ReplayPlugin,
+                life::LifePlugin,
+                evolution::EvolutionPlugin,
+                temperature::TemperaturePlugin,
+                quality::QualityPlugin,
+                lod::LodPlugin,
+                backend::BackendPlugin,
+                particle_assets::ParticleAssetsPlugin,
+                spawn::SpawnPlugin,
+            ))
+            .add_plugins((
+                slots::SlotsPlugin,
+                preset::PresetPlugin,
+                share::SharePlugin,
+                audio::AudioSynthPlugin,
+                microphone::MicrophonePlugin,
+                midi::MidiPlugin,
+                timeline::TimelinePlugin,
+                scripting::ScriptingPlugin,
+                curve_editor::CurveEditorPlugin,
+                motion::MotionPlugin,
+                heatmap::HeatmapPlugin,
+                background::BackgroundPlugin,
+                shapes::ShapesPlugin,
+                perf::PerfPlugin,
+                logging::LoggingPlugin,
+            ))
+            .add_plugins((
+                layers::LayersPlugin,
+                pheromone::PheromonePlugin,
+                bonds::BondsPlugin,
+                flow::FlowPlugin,
+                zones::ZonesPlugin,
+                screensaver::ScreensaverPlugin,
+                explore::ExplorePlugin,
+                comparison::ComparisonPlugin,
+                dock::DockPlugin,
+                help::HelpPlugin,
+                tutorial::TutorialPlugin,
+                minimap::MinimapPlugin,
+                substep::SubstepPlugin,
+                warmup::WarmupPlugin,
+                species::SpeciesPlugin,
+            ))
+            .add_plugins((
+                interactions::InteractionsPlugin,
+                autosave::AutosavePlugin,
+                stamp::StampPlugin,
+                clipboard::ClipboardPlugin,
+                freeze::FreezePlugin,
+                events::SimulationEventsPlugin,
+                menu::MenuPlugin,
+                loading::LoadingPlugin,
+                framepace::FramePacePlugin,
+                hardware::HardwarePlugin,
+                simthread::SimThreadPlugin,
+                determinism::DeterminismPlugin,
+                capture::CapturePlugin,
+                svg::SvgPlugin,
+            ))
+            .add_plugins((
+                point_sprites::PointSpritePlugin,
+                interpolation::InterpolationPlugin,
+                legend::LegendPlugin,
+            ))
+            .insert_resource(ParticleSystem::with_colors(colors))
+            .insert_resource(ParticleCount { count })
+            .add_systems(Startup, setup)
+            .add_systems(
+                Update,
+                (
+                    update_particles
+                        .run_if(in_state(menu::AppState::Running))
+                        .run_if(not(simthread::is_active))
+                        .run_if(not(replay::is_playing_back))
+                        .run_if(not(network::is_remote)),
+                    move_camera,
+                    handle_matrix_regeneration,
+                    adjust_speed,
                 ),
-                Transform::from_xyz(x, y, 0.0),
-                Particle { color_id },
-            ));
+            );
+    }
+}
+
+/// The image the main camera renders into when embedded.
+#[derive(Resource)]
+struct EmbedTarget(Handle<Image>);
+
+/// A blank image usable as [`ParticleLifePlugin::render_target`] and as a
+/// sprite or UI texture.
+pub fn embed_image(images: &mut Assets<Image>, width: u32, height: u32) -> Handle<Image> {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Bgra8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage =
+        TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+    images.add(image)
+}
+
+/// Whether the simulation's own UI is shown: never when headless, and not
+/// behind the main menu.
+fn ui_enabled(headless: Option<Res<Headless>>, state: Option<Res<State<menu::AppState>>>) -> bool {
+    headless.is_none() && state.is_none_or(|state| *state.get() != menu::AppState::Menu)
+}
+
+fn setup(
+    mut commands: Commands,
+    particle_count: Res<ParticleCount>,
+    mut spawn_requests: EventWriter<spawn::SpawnRequest>,
+    embed: Option<Res<EmbedTarget>>,
+    state: Res<State<menu::AppState>>,
+) {
+    let mut camera = commands.spawn((Camera2d, MainCamera, ControlledCamera));
+    if let Some(EmbedTarget(image)) = embed.as_deref() {
+        camera.insert(Camera {
+            target: RenderTarget::Image(image.clone()),
+            ..Default::default()
+        });
+    }
+    // The main menu spawns the first world once it is configured
+    if *state.get() != menu::AppState::Menu {
+        spawn_requests.send(spawn::SpawnRequest::Reset {
+            count: particle_count.count,
+        });
+    }
+}
+
+/// The rules and force shape of one tick, shared by every particle pair.
+struct PairForces<'a> {
+    rules: &'a ParticleSystem,
+    profile: &'a dyn force::ForceProfile,
+    /// Search radius of the neighbor lookup, in world units.
+    attraction_radius: f32,
+    /// Multiplier on every cutoff, from the quality governor.
+    radius_scale: f32,
+}
+
+impl PairForces<'_> {
+    /// Force on a particle of `color_id` in `zone` from a neighbor of
+    /// `other_color_id` at `distance`, a fraction of the search radius.
+    fn force(
+        &self,
+        zone: Option<&zones::RuleZone>,
+        color_id: usize,
+        other_color_id: usize,
+        distance: f32,
+    ) -> f32 {
+        let rules = self.rules;
+        let behavior = rules.get_behavior(color_id, other_color_id);
+        let behavior = zone.map_or(behavior, |zone| {
+            zone.behavior(behavior, color_id, other_color_id)
+        });
+        if rules.radius_matrix.is_none() {
+            return rules
+                .force_limit
+                .neighbor(self.profile.force(distance, behavior));
+        }
+        // Rescale from the search radius to this pair's own cutoff
+        let pair_radius = rules.interaction_radius(color_id, other_color_id) * self.radius_scale;
+        let distance = distance * self.attraction_radius / pair_radius;
+        if distance < 1.0 {
+            rules
+                .force_limit
+                .neighbor(self.profile.force(distance, behavior))
+        } else {
+            0.0
         }
     }
-    
 }
 
+/// A particle integrated this sub-step, with its slot in the buffers.
+struct Mover {
+    index: usize,
+    species: usize,
+    /// Time since it last moved, longer for particles skipped by level of
+    /// detail.
+    elapsed: f32,
+}
+
+/// Rules and settings `update_particles` reads.
+#[derive(SystemParam)]
+struct SimulationInputs<'w> {
+    particle_system: Res<'w, ParticleSystem>,
+    quality: Res<'w, quality::QualityGovernor>,
+    lod: Res<'w, lod::LodSettings>,
+    substepping: Res<'w, substep::Substepping>,
+    toggles: Res<'w, species::SpeciesToggles>,
+    settings: Res<'w, settings::Settings>,
+    time: Res<'w, Time>,
+}
+
+/// Forces from outside the particle rules.
+#[derive(SystemParam)]
+struct ExternalForces<'w> {
+    overlay: ResMut<'w, layers::OverlayLayer>,
+    pheromones: Res<'w, pheromone::PheromoneField>,
+    rigid_bodies: Option<Res<'w, physics::RigidBodies>>,
+}
+
+/// What `update_particles` keeps between ticks.
+#[derive(SystemParam)]
+struct SimulationState<'w, 's> {
+    backends: ResMut<'w, backend::SimulationBackends>,
+    buffers: ResMut<'w, buffers::ParticleBuffers>,
+    clock: ResMut<'w, interpolation::TickClock>,
+    diagnostics: Diagnostics<'w, 's>,
+    /// Frames and seconds since the last tick.
+    pending: Local<'s, (u32, f32)>,
+    tick: Local<'s, u64>,
+    movers: Local<'s, Vec<Mover>>,
+}
+
+type CameraView<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Transform, &'static OrthographicProjection),
+    (With<MainCamera>, Without<Particle>),
+>;
+
+type SimulatedParticles<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Transform,
+        Ref<'static, Particle>,
+        &'static mut Velocity,
+        &'static mut lod::LodClock,
+        &'static mut buffers::BufferIndex,
+        Has<freeze::Frozen>,
+    ),
+    Without<Camera>,
+>;
+
 fn update_particles(
-    particle_system: Res<ParticleSystem>,
-    time: Res<Time>,
-    mut particle_query: Query<(&mut Transform, &Particle)>,
+    inputs: SimulationInputs,
+    external: ExternalForces,
+    state: SimulationState,
+    camera_query: CameraView,
+    added: Query<(), Added<Particle>>,
+    mut removed: RemovedComponents<Particle>,
+    mut particle_query: SimulatedParticles,
 ) {
-    dbg!(particle_query.iter().count());
-    let dt = time.delta_secs() * particle_system.speed;
-    let beta = particle_system.beta;
-    let gamma = particle_system.gamma;
-    let gamma_beta_diff = gamma - beta;
-    let one_minus_gamma = 1.0 - gamma;
-    let attraction_radius = particle_system.attraction_radius;
-
-    // Create a spatial grid for faster neighbor lookups
-    let cell_size = attraction_radius;
-    let mut grid: HashMap<(i32, i32), Vec<(Vec3, usize)>> = HashMap::new();
-
-    // Populate the grid
-    for (transform, particle) in particle_query.iter() {
-        let pos = transform.translation;
-        let cell_x = (pos.x / cell_size).floor() as i32;
-        let cell_y = (pos.y / cell_size).floor() as i32;
-        grid.entry((cell_x, cell_y))
-            .or_default()
-            .push((pos, particle.color_id));
-    }
-
-    // Update particles
-    for (mut transform, particle) in &mut particle_query {
-        let pos = transform.translation;
-        let cell_x = (pos.x / cell_size).floor() as i32;
-        let cell_y = (pos.y / cell_size).floor() as i32;
-
-        let mut force = Vec2::ZERO;
-        let mut count = 0.0;
-
-        // Check neighboring cells
-        for dx in -1..=1 {
-            for dy in -1..=1 {
-                if let Some(cell_particles) = grid.get(&(cell_x + dx, cell_y + dy)) {
-                    for &(other_pos, other_color_id) in cell_particles {
-                        if pos == other_pos {
-                            continue;
-                        }
+    let SimulationInputs {
+        particle_system,
+        quality,
+        lod,
+        substepping,
+        toggles,
+        settings,
+        time,
+    } = inputs;
+    let ExternalForces {
+        mut overlay,
+        pheromones,
+        rigid_bodies,
+    } = external;
+    let SimulationState {
+        mut backends,
+        mut buffers,
+        mut clock,
+        mut diagnostics,
+        mut pending,
+        mut tick,
+        mut movers,
+    } = state;
 
-                        let to_other = other_pos - pos;
-                        let distance = to_other.length() / attraction_radius;
+    // Accumulate time over frames skipped by the quality governor or the
+    // tick rate, which deterministic mode leaves out along with everything
+    // else that depends on the frame rate or the camera
+    let strict = settings.deterministic;
+    let (frames, elapsed) = &mut *pending;
+    *frames += 1;
+    *elapsed += time.delta_secs();
+    let tick_interval = match settings.tick_rate {
+        0 => 0.0,
+        rate => 1.0 / rate as f32,
+    };
+    if !strict && (*frames < quality.update_stride || *elapsed < tick_interval) {
+        return;
+    }
+    clock.tick(*frames, *elapsed);
+    let step = *elapsed * particle_system.time_scale;
+    *pending = (0, 0.0);
+    *tick += 1;
 
-                        if distance < 1.0 {
-                            let direction = to_other.truncate().normalize();
-                            let behavior =
-                                particle_system.get_behavior(particle.color_id, other_color_id);
+    // Refill the buffers when particles were added or removed, otherwise only
+    // pick up particles that other systems moved or recolored since last tick
+    let structural_change = removed.read().count() > 0 || !added.is_empty();
+    if structural_change || buffers.len() != particle_query.iter().len() {
+        buffers.clear();
+        for (transform, particle, _, _, mut index, _) in &mut particle_query {
+            index.0 = buffers.push(transform.translation.truncate(), particle.color_id);
+        }
+    } else {
+        for (transform, particle, _, _, index, _) in &mut particle_query {
+            if transform.is_changed() {
+                buffers.front[index.0] = transform.translation.truncate();
+            }
+            if particle.is_changed() {
+                buffers.species[index.0] = particle.color_id;
+            }
+        }
+    }
 
-                            let force_magnitude = if distance < beta {
-                                -1.0 + (distance / beta)
-                            } else if distance < gamma {
-                                behavior * ((distance - beta) / gamma_beta_diff)
-                            } else {
-                                behavior * ((1.0 - distance) / one_minus_gamma)
-                            };
+    let view = camera_query
+        .get_single()
+        .ok()
+        .filter(|_| lod.enabled && !strict)
+        .map(|(transform, projection)| lod::camera_view(transform, projection));
 
-                            force += direction * force_magnitude;
-                            count += 1.0;
-                        }
-                    }
+    let profile = particle_system
+        .force_profile
+        .profile(particle_system.beta, particle_system.gamma);
+    let radius_scale = if strict { 1.0 } else { quality.radius_scale };
+    let attraction_radius = particle_system.max_interaction_radius() * radius_scale;
+    let limit = particle_system.force_limit;
+    let pair_forces = PairForces {
+        rules: &particle_system,
+        profile: &*profile,
+        attraction_radius,
+        radius_scale,
+    };
+
+    // Shorter sub-steps when one step would move particles too far
+    let substeps = substepping.count(step, particle_system.fastest_speed(), attraction_radius);
+    let step = step / substeps as f32;
+    let started = backends.start_timing();
+    let mut spatial_ms = 0.0;
+    let mut simulation_ms = 0.0;
+    for _ in 0..substeps {
+        let backend = backends.active_mut();
+        let buffers::ParticleBuffers {
+            front,
+            back,
+            species,
+            visible,
+            visible_species,
+            queries,
+            query_zones,
+            forces,
+        } = &mut *buffers;
+        let spatial_started = Instant::now();
+
+        // Hidden species have no effect on the others, so leave them out of
+        // the neighbor search rather than letting them count toward the average
+        let (neighbors, neighbor_species) = if toggles.any_hidden() {
+            visible.clear();
+            visible_species.clear();
+            for (&pos, &other_species) in front.iter().zip(species.iter()) {
+                if !toggles.is_hidden(other_species) {
+                    visible.push(pos);
+                    visible_species.push(other_species);
+                }
+            }
+            (&visible[..], &visible_species[..])
+        } else {
+            (&front[..], &species[..])
+        };
+        debug_span!("spatial_build", backend = backend.name()).in_scope(|| {
+            backend.prepare(neighbors, neighbor_species, attraction_radius);
+        });
+        spatial_ms += perf::elapsed_ms(spatial_started);
+        let simulation_started = Instant::now();
+        let forces_span = debug_span!("forces", particles = front.len()).entered();
+
+        // Pick the particles that move this sub-step, so the backend can
+        // compute all their forces in one batch
+        movers.clear();
+        for (_, particle, _, mut clock, index, frozen) in &mut particle_query {
+            let pos = front[index.0];
+            back[index.0] = pos;
+            if frozen || !toggles.moves(particle.color_id) {
+                continue;
+            }
+
+            // Skip particles whose level of detail tier is not due this tick
+            clock.0 += step;
+            if let Some(view) = view {
+                if *tick % lod.stride(pos, view) != 0 {
+                    continue;
                 }
             }
+            movers.push(Mover {
+                index: index.0,
+                species: particle.color_id,
+                elapsed: clock.0,
+            });
+            clock.0 = 0.0;
         }
+        queries.clear();
+        queries.extend(movers.iter().map(|mover| front[mover.index]));
+        query_zones.clear();
+        query_zones.extend(
+            queries
+                .iter()
+                .map(|&pos| particle_system.zone_index_at(pos)),
+        );
+        let zones = &particle_system.zones;
+        let zone_of = |query: usize| query_zones[query].map(|zone| &zones[zone]);
 
-        if count > 0.0 {
-            force /= count;
+        backend.accumulate_batch(
+            queries,
+            attraction_radius,
+            neighbors,
+            neighbor_species,
+            &|query, distance, other_color_id| {
+                pair_forces.force(
+                    zone_of(query),
+                    movers[query].species,
+                    other_color_id,
+                    distance,
+                )
+            },
+            forces,
+        );
+
+        // Integrate in the same order the movers were picked
+        let mut next = 0;
+        for (mut transform, _, mut velocity, _, index, _) in &mut particle_query {
+            let Some(mover) = movers.get(next).filter(|mover| mover.index == index.0) else {
+                continue;
+            };
+            let (pos, zone, (mut force, count)) = (queries[next], zone_of(next), forces[next]);
+            next += 1;
+
+            if count > 0.0 {
+                force /= count;
+            }
+            force += overlay.coupling_force(pos, &*profile);
+            force += pheromones.gradient_force(pos, &particle_system.pheromones, mover.species);
+            let force = limit.total(force);
+
+            let motion = particle_system
+                .species_motion
+                .get(mover.species)
+                .copied()
+                .unwrap_or_default();
+            let speed = zone
+                .and_then(|zone| zone.speed)
+                .unwrap_or(particle_system.force_strength);
+            velocity.0 = motion.integrate(velocity.0, force * speed, mover.elapsed);
+            // Rigid bodies are moved by the physics engine toward this velocity
+            if rigid_bodies.is_some() {
+                continue;
+            }
+            let new_pos = determinism::snap(&settings, pos + velocity.0 * mover.elapsed);
+            back[index.0] = new_pos;
+            transform.translation = new_pos.extend(transform.translation.z);
         }
 
-        transform.translation += force.extend(0.0) * dt;
+        forces_span.exit();
+        simulation_ms += perf::elapsed_ms(simulation_started);
+        buffers.swap();
     }
+    diagnostics.add_measurement(&perf::SPATIAL_BUILD, || spatial_ms);
+    diagnostics.add_measurement(&perf::SIMULATION, || simulation_ms);
+    backends.finish_timing(started);
+}
+
+/// Camera movement requested but not yet applied. Each frame a share of it is
+/// applied that depends only on the elapsed time, so the camera eases toward
+/// its target the same way at any frame rate, and moves made by other systems
+/// are kept.
+#[derive(Resource, Default)]
+struct CameraMotion {
+    translation: Vec2,
+    /// Natural log of the remaining zoom factor; positive zooms in.
+    zoom: f32,
 }
 
 fn move_camera(
-    mut commands: Commands,
     keyboard: Res<ButtonInput<KeyCode>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    time: Res<Time>,
-    particle_system: Res<ParticleSystem>,
-    mut query: Query<&mut Transform, With<Camera>>,
+    mut wheel: EventReader<MouseWheel>,
+    mut contexts: EguiContexts,
+    settings: Res<settings::Settings>,
+    // Real time, so the camera still moves while the simulation is paused
+    time: Res<Time<Real>>,
+    mut motion: ResMut<CameraMotion>,
+    mut query: Query<&mut Transform, With<ControlledCamera>>,
 ) {
-    let mut camera_transform = query.single_mut();
-    let mut direction = Vec3::ZERO;
+    let Ok(mut camera_transform) = query.get_single_mut() else {
+        return;
+    };
+    let mut direction = Vec2::ZERO;
+    let keys = &settings.keys;
+    let delta = time.delta_secs();
 
-    if keyboard.pressed(KeyCode::KeyA) {
+    if keyboard.pressed(keys.camera_left) {
         direction.x -= 1.0;
     }
-    if keyboard.pressed(KeyCode::KeyD) {
+    if keyboard.pressed(keys.camera_right) {
         direction.x += 1.0;
     }
-    if keyboard.pressed(KeyCode::KeyW) {
+    if keyboard.pressed(keys.camera_up) {
         direction.y += 1.0;
     }
-    if keyboard.pressed(KeyCode::KeyS) {
+    if keyboard.pressed(keys.camera_down) {
         direction.y -= 1.0;
     }
-    if keyboard.just_pressed(KeyCode::ArrowUp) {
-        camera_transform.scale /= 1.1;
+    if keyboard.pressed(keys.zoom_in) {
+        motion.zoom += ZOOM_SPEED * delta;
     }
-    if keyboard.just_pressed(KeyCode::ArrowDown) {
-        camera_transform.scale *= 1.1;
+    if keyboard.pressed(keys.zoom_out) {
+        motion.zoom -= ZOOM_SPEED * delta;
+    }
+    let over_ui = contexts
+        .try_ctx_mut()
+        .is_some_and(|ctx| ctx.is_pointer_over_area());
+    for event in wheel.read() {
+        if over_ui {
+            continue;
+        }
+        let notches = match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        };
+        motion.zoom += notches * ZOOM_STEP.ln();
     }
 
-    if direction != Vec3::ZERO {
-        direction = direction.normalize();
-        let scale = camera_transform.scale;
-        camera_transform.translation += direction * CAMERA_SPEED * time.delta_secs() * scale;
+    if direction != Vec2::ZERO {
+        let scale = camera_transform.scale.x;
+        motion.translation += direction.normalize() * CAMERA_SPEED * delta * scale;
     }
+
+    let share = if settings.camera_smoothing > 0.0 {
+        1.0 - (-delta / settings.camera_smoothing).exp()
+    } else {
+        1.0
+    };
+    let translation = motion.translation * share;
+    let zoom = motion.zoom * share;
+    motion.translation -= translation;
+    motion.zoom -= zoom;
+    camera_transform.translation += translation.extend(0.0);
+    camera_transform.scale /= zoom.exp();
 }
 
+/// Turns the rule keys into simulation events.
 fn handle_matrix_regeneration(
     keyboard: Res<ButtonInput<KeyCode>>,
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut particle_system: ResMut<ParticleSystem>,
-    particles: Query<Entity, With<Particle>>,
+    settings: Res<settings::Settings>,
+    quality: Res<quality::QualityGovernor>,
+    mut particle_count: ResMut<ParticleCount>,
+    mut spawn_requests: EventWriter<spawn::SpawnRequest>,
+    (mut palette_events, mut matrix_events): (
+        EventWriter<events::RegeneratePalette>,
+        EventWriter<events::RegenerateMatrix>,
+    ),
+    particles: Query<(), With<Particle>>,
 ) {
-    if keyboard.just_pressed(KeyCode::KeyR) {
-        // Clear all existing particles
-        for entity in &particles {
-            commands.entity(entity).despawn();
-        }
-
-        // Generate new colors and matrix
-        let all_colors = vec![
-            // Reds
-            css::RED,
-            css::CRIMSON,
-            css::DARK_RED,
-            css::FIRE_BRICK,
-            css::INDIAN_RED,
-            css::LIGHT_CORAL,
-            css::SALMON,
-            css::DARK_SALMON,
-            css::LIGHT_SALMON,
-            css::ORANGE_RED,
-            // Oranges
-            css::ORANGE_RED,
-            css::TOMATO,
-            css::DARK_ORANGE,
-            css::ORANGE,
-            css::GOLD,
-            css::DARK_GOLDENROD,
-            css::GOLDENROD,
-            css::PALE_GOLDENROD,
-            css::PEACHPUFF,
-            css::NAVAJO_WHITE,
-            // Yellows
-            css::YELLOW,
-            css::LIGHT_YELLOW,
-            css::LEMON_CHIFFON,
-            css::LIGHT_GOLDENROD_YELLOW,
-            css::PAPAYA_WHIP,
-            css::MOCCASIN,
-            css::KHAKI,
-            css::DARK_KHAKI,
-            css::YELLOW_GREEN,
-            css::OLIVE,
-            // Greens
-            css::LIME,
-            css::LIMEGREEN,
-            css::LAWN_GREEN,
-            css::CHARTREUSE,
-            css::GREEN_YELLOW,
-            css::SPRING_GREEN,
-            css::MEDIUM_SPRING_GREEN,
-            css::LIGHT_GREEN,
-            css::PALE_GREEN,
-            css::DARK_SEA_GREEN,
-            css::MEDIUM_SEA_GREEN,
-            css::SEA_GREEN,
-            css::FOREST_GREEN,
-            css::GREEN,
-            css::DARK_GREEN,
-            // Cyans
-            css::MEDIUM_AQUAMARINE,
-            css::AQUA,
-            css::DARK_CYAN,
-            css::LIGHT_CYAN,
-            css::PALE_TURQUOISE,
-            css::AQUAMARINE,
-            css::TURQUOISE,
-            css::MEDIUM_TURQUOISE,
-            css::DARK_TURQUOISE,
-            css::LIGHT_SEA_GREEN,
-            // Blues
-            css::DEEP_SKY_BLUE,
-            css::LIGHT_BLUE,
-            css::SKY_BLUE,
-            css::LIGHT_SKY_BLUE,
-            css::STEEL_BLUE,
-            css::ALICE_BLUE,
-            css::DODGER_BLUE,
-            css::ROYAL_BLUE,
-            css::BLUE,
-            css::MEDIUM_BLUE,
-            css::DARK_BLUE,
-            css::NAVY,
-            css::MIDNIGHT_BLUE,
-            css::CORNFLOWER_BLUE,
-            css::SLATE_BLUE,
-            // Purples
-            css::MEDIUM_SLATE_BLUE,
-            css::DARK_SLATE_BLUE,
-            css::LAVENDER,
-            css::THISTLE,
-            css::PLUM,
-            css::VIOLET,
-            css::ORCHID,
-            css::MAGENTA,
-            css::MEDIUM_ORCHID,
-            css::MEDIUM_PURPLE,
-            css::BLUE_VIOLET,
-            css::DARK_VIOLET,
-            css::DARK_ORCHID,
-            css::DARK_MAGENTA,
-            css::PURPLE,
-            // Pinks
-            css::INDIGO,
-            css::MEDIUM_VIOLET_RED,
-            css::PALE_VIOLETRED,
-            css::DEEP_PINK,
-            css::HOT_PINK,
-            css::LIGHT_PINK,
-            css::PINK,
-            css::ANTIQUE_WHITE,
-            css::BEIGE,
-            css::BISQUE,
-            // Browns
-            css::SADDLE_BROWN,
-            css::SIENNA,
-            css::CHOCOLATE,
-            css::PERU,
-            css::SANDY_BROWN,
-            css::BURLYWOOD,
-            css::TAN,
-            css::ROSY_BROWN,
-            css::MOCCASIN,
-            css::NAVAJO_WHITE,
-            // Grays and others
-            css::MAROON,
-            css::BROWN,
-            css::DARK_OLIVEGREEN,
-            css::OLIVE_DRAB,
-            css::DARK_CYAN,
-            css::TEAL,
-            css::DARK_SLATE_GRAY,
-            css::SLATE_GRAY,
-            css::LIGHT_SLATE_GRAY,
-            css::DIM_GRAY,
-        ];
-
-        let num_colors = 50; // Fixed number of colors for simplicity
-        let colors: Vec<Color> = all_colors
-            .into_iter()
-            .take(num_colors)
-            .map(Color::from)
-            .collect();
-        // Update ParticleSystem
-        particle_system.colors = colors;
-        particle_system.regenerate_matrix();
-        particle_system.regenerate_constants();
-
-        // Spawn new particles
-        let grid_size = (NUM_PARTICLES as f32).sqrt().ceil() as usize;
-        let spacing_x = WINDOW_WIDTH / grid_size as f32;
-        let spacing_y = WINDOW_HEIGHT / grid_size as f32;
-
-        for i in 0..grid_size {
-            for j in 0..grid_size {
-                if i * grid_size + j >= NUM_PARTICLES {
-                    break;
-                }
-                let x = i as f32 * spacing_x - WINDOW_WIDTH / 2.0 + spacing_x / 2.0;
-                let y = j as f32 * spacing_y - WINDOW_HEIGHT / 2.0 + spacing_y / 2.0;
-
-                let color_id = (i * grid_size + j) % particle_system.colors.len();
-
-                commands.spawn((
-                    Mesh2d(meshes.add(Circle::new(PARTICLE_SIZE / 2.0))),
-                    MeshMaterial2d(
-                        materials.add(ColorMaterial::from(particle_system.colors[color_id])),
-                    ),
-                    Transform::from_xyz(x, y, 0.0),
-                    Particle { color_id },
-                ));
-            }
-        }
+    if keyboard.just_pressed(settings.keys.restart) {
+        palette_events.send(events::RegeneratePalette {
+            species: NUM_COLORS,
+            respawn: Some(particle_count.adopt_live(particles.iter().len(), &quality)),
+        });
     }
-    if keyboard.just_pressed(KeyCode::KeyQ) {
-        particle_system.regenerate_matrix();
+    if keyboard.just_pressed(settings.keys.respawn) {
+        spawn_requests.send(spawn::SpawnRequest::Reshuffle {
+            count: particle_count.adopt_live(particles.iter().len(), &quality),
+        });
     }
-    if keyboard.just_pressed(KeyCode::KeyT) {
-        particle_system.regenerate_constants();
+    for (key, event) in [
+        (
+            settings.keys.regenerate_behaviors,
+            events::RegenerateMatrix::Regenerate,
+        ),
+        (
+            settings.keys.reroll_matrix,
+            events::RegenerateMatrix::Reroll,
+        ),
+        (
+            settings.keys.perturb_matrix,
+            events::RegenerateMatrix::Perturb {
+                epsilon: PERTURB_EPSILON,
+            },
+        ),
+        (
+            settings.keys.regenerate_constants,
+            events::RegenerateMatrix::Constants,
+        ),
+    ] {
+        if keyboard.just_pressed(key) {
+            matrix_events.send(event);
+        }
     }
 }
 
-fn adjust_speed(keyboard: Res<ButtonInput<KeyCode>>, mut particle_system: ResMut<ParticleSystem>) {
-    if keyboard.just_pressed(KeyCode::ArrowRight) {
-        particle_system.speed *= 2.0;
-    } else if keyboard.just_pressed(KeyCode::ArrowLeft) {
-        particle_system.speed /= 2.0;
-    }
+fn adjust_speed(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<settings::Settings>,
+    particle_system: Res<ParticleSystem>,
+    mut parameters: EventWriter<events::SetParameter>,
+) {
+    let factor = if keyboard.just_pressed(settings.keys.speed_up) {
+        2.0
+    } else if keyboard.just_pressed(settings.keys.slow_down) {
+        0.5
+    } else {
+        return;
+    };
+    parameters.send(events::SetParameter {
+        name: "time_scale".to_string(),
+        value: particle_system.time_scale * factor,
+    });
 }
 
-fn ui_system(
-    mut contexts: EguiContexts,
+fn egui_color(color: Color) -> egui::Color32 {
+    let [r, g, b, a] = color.to_srgba().to_u8_array();
+    egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+/// The "Simulation Controls" dock panel.
+fn controls_panel(
+    InMut(ui): InMut<egui::Ui>,
     mut particle_system: ResMut<ParticleSystem>,
     mut particle_count: ResMut<ParticleCount>,
-    diagnostics: Res<DiagnosticsStore>,
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    particles: Query<Entity, With<Particle>>,
+    (diagnostics, adapter_info, hardware): (
+        Res<DiagnosticsStore>,
+        Option<Res<RenderAdapterInfo>>,
+        Res<hardware::HardwareProfile>,
+    ),
+    (settings, quality, particles): (
+        Res<settings::Settings>,
+        Res<quality::QualityGovernor>,
+        Query<(), With<Particle>>,
+    ),
+    mut spawn_pattern: ResMut<spawn::SpawnPattern>,
+    (mut spawn_requests, mut palette_events, mut matrix_events, mut clear_events): (
+        EventWriter<spawn::SpawnRequest>,
+        EventWriter<events::RegeneratePalette>,
+        EventWriter<events::RegenerateMatrix>,
+        EventWriter<events::ClearParticles>,
+    ),
 ) {
-    egui::Window::new("Particle Life Controls")
-        .default_pos([10.0, 10.0])
-        .show(contexts.ctx_mut(), |ui| {
-            // FPS Display
-            if let Some(fps) = diagnostics.get(&FrameTimeDiagnosticsPlugin::FPS) {
-                if let Some(fps_value) = fps.smoothed() {
-                    ui.label(format!("FPS: {:.1}", fps_value));
-                }
-            }
+    // FPS Display
+    if let Some(fps) = diagnostics.get(&FrameTimeDiagnosticsPlugin::FPS) {
+        if let Some(fps_value) = fps.smoothed() {
+            ui.label(format!("FPS: {:.1}", fps_value));
+        }
+    }
+    if let Some(adapter_info) = &adapter_info {
+        ui.label(format!("Renderer: {}", renderer::describe(adapter_info)));
+    }
+    ui.label(format!("Hardware: {}", hardware.summary()));
 
-            ui.add_space(10.0);
-            ui.heading("Simulation Parameters");
-
-            // Particle count control
-            let mut count = particle_count.count as i32;
-            ui.horizontal(|ui| {
-                ui.label("Particle Count:");
-                if ui
-                    .add(egui::Slider::new(&mut count, 100..=20000).text("count"))
-                    .changed()
-                {
-                    particle_count.count = count as usize;
-                    // Clear existing particles
-                    for entity in &particles {
-                        commands.entity(entity).despawn();
-                    }
-                    // Spawn new particles
-                    let grid_size = (particle_count.count as f32).sqrt().ceil() as usize;
-                    let spacing_x = WINDOW_WIDTH / grid_size as f32;
-                    let spacing_y = WINDOW_HEIGHT / grid_size as f32;
-
-                    for i in 0..grid_size {
-                        for j in 0..grid_size {
-                            if i * grid_size + j >= particle_count.count {
-                                break;
-                            }
-                            let x = i as f32 * spacing_x - WINDOW_WIDTH / 2.0 + spacing_x / 2.0;
-                            let y = j as f32 * spacing_y - WINDOW_HEIGHT / 2.0 + spacing_y / 2.0;
-
-                            let color_id = (i * grid_size + j) % particle_system.colors.len();
-
-                            commands.spawn((
-                                Mesh2d(meshes.add(Circle::new(PARTICLE_SIZE / 2.0))),
-                                MeshMaterial2d(
-                                    materials
-                                        .add(ColorMaterial::from(particle_system.colors[color_id])),
-                                ),
-                                Transform::from_xyz(x, y, 0.0),
-                                Particle { color_id },
-                            ));
-                        }
-                    }
-                }
-            });
+    ui.add_space(10.0);
+    ui.heading("Simulation Parameters");
 
-            // Speed control
-            ui.horizontal(|ui| {
-                ui.label("Speed:");
-                ui.add(egui::Slider::new(&mut particle_system.speed, 0.0..=3200.0));
+    // Particle count control
+    let mut count = particle_count.count as i32;
+    // Stress runs start above the slider's usual range
+    let max_count = count.max(20000);
+    ui.horizontal(|ui| {
+        ui.label("Particle Count:");
+        if ui
+            .add(egui::Slider::new(&mut count, 100..=max_count).text("count"))
+            .changed()
+        {
+            particle_count.count = count as usize;
+            spawn_requests.send(spawn::SpawnRequest::Reset {
+                count: particle_count.count,
             });
-
-            // Beta control
-            ui.horizontal(|ui| {
-                ui.label("Beta:");
-                ui.add(egui::Slider::new(&mut particle_system.beta, 0.0..=1.0));
+        }
+        let default_count = if settings.hardware_defaults {
+            hardware.default_particles()
+        } else {
+            NUM_PARTICLES
+        };
+        if particle_count.count != default_count && ui.button("Default Count").clicked() {
+            particle_count.count = default_count;
+            spawn_requests.send(spawn::SpawnRequest::Reset {
+                count: default_count,
             });
+        }
+    });
 
-            // Gamma control
-            ui.horizontal(|ui| {
-                ui.label("Gamma:");
-                ui.add(egui::Slider::new(&mut particle_system.gamma, 0.0..=1.0));
-            });
+    // Time scale and force strength are separate: slowing time keeps the
+    // interactions as they are
+    ui.horizontal(|ui| {
+        ui.label("Time Scale:");
+        ui.add(egui::Slider::new(&mut particle_system.time_scale, 0.0..=8.0).logarithmic(true));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Force Strength:");
+        ui.add(egui::Slider::new(
+            &mut particle_system.force_strength,
+            0.0..=3200.0,
+        ));
+    });
 
-            // Attraction radius control
-            ui.horizontal(|ui| {
-                ui.label("Attraction Radius:");
-                ui.add(egui::Slider::new(
-                    &mut particle_system.attraction_radius,
-                    10.0..=200.0,
-                ));
+    // Beta control
+    ui.horizontal(|ui| {
+        ui.label("Beta:");
+        ui.add(egui::Slider::new(&mut particle_system.beta, 0.0..=1.0));
+    });
+
+    // Gamma control
+    ui.horizontal(|ui| {
+        ui.label("Gamma:");
+        ui.add(egui::Slider::new(&mut particle_system.gamma, 0.0..=1.0));
+    });
+
+    // Force profile selection
+    let (beta, gamma) = (particle_system.beta, particle_system.gamma);
+    particle_system.force_profile.settings_ui(ui, beta, gamma);
+    particle_system.force_limit.settings_ui(ui);
+
+    // Attraction radius control
+    ui.horizontal(|ui| {
+        ui.label("Attraction Radius:");
+        ui.add(egui::Slider::new(
+            &mut particle_system.attraction_radius,
+            10.0..=200.0,
+        ));
+    });
+
+    // Temperature control
+    ui.horizontal(|ui| {
+        ui.label("Temperature:");
+        ui.add(egui::Slider::new(
+            &mut particle_system.temperature,
+            0.0..=50.0,
+        ));
+    });
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut particle_system.annealing, "Annealing");
+        ui.add_enabled(
+            particle_system.annealing,
+            egui::Slider::new(&mut particle_system.annealing_rate, 0.01..=1.0)
+                .logarithmic(true)
+                .text("decay/s"),
+        );
+    });
+
+    // Color count control
+    let mut color_count = particle_system.colors.len() as i32;
+    ui.horizontal(|ui| {
+        ui.label("Color Count:");
+        if ui
+            .add(egui::Slider::new(&mut color_count, 1..=100).text("colors"))
+            .changed()
+        {
+            palette_events.send(events::RegeneratePalette {
+                species: color_count as usize,
+                respawn: Some(particle_count.count),
             });
+        }
+    });
 
-            // Color count control
-            let mut color_count = particle_system.colors.len() as i32;
-            ui.horizontal(|ui| {
-                ui.label("Color Count:");
-                if ui
-                    .add(egui::Slider::new(&mut color_count, 1..=100).text("colors"))
-                    .changed()
-                {
-                    // Clear existing particles
-                    for entity in &particles {
-                        commands.entity(entity).despawn();
-                    }
-                    // Update ParticleSystem with new color count
-                    let all_colors = vec![
-                        // Reds
-                        css::RED,
-                        css::CRIMSON,
-                        css::DARK_RED,
-                        css::FIRE_BRICK,
-                        css::INDIAN_RED,
-                        css::LIGHT_CORAL,
-                        css::SALMON,
-                        css::DARK_SALMON,
-                        css::LIGHT_SALMON,
-                        css::ORANGE_RED,
-                        // Oranges
-                        css::ORANGE_RED,
-                        css::TOMATO,
-                        css::DARK_ORANGE,
-                        css::ORANGE,
-                        css::GOLD,
-                        css::DARK_GOLDENROD,
-                        css::GOLDENROD,
-                        css::PALE_GOLDENROD,
-                        css::PEACHPUFF,
-                        css::NAVAJO_WHITE,
-                        // Yellows
-                        css::YELLOW,
-                        css::LIGHT_YELLOW,
-                        css::LEMON_CHIFFON,
-                        css::LIGHT_GOLDENROD_YELLOW,
-                        css::PAPAYA_WHIP,
-                        css::MOCCASIN,
-                        css::KHAKI,
-                        css::DARK_KHAKI,
-                        css::YELLOW_GREEN,
-                        css::OLIVE,
-                        // Greens
-                        css::LIME,
-                        css::LIMEGREEN,
-                        css::LAWN_GREEN,
-                        css::CHARTREUSE,
-                        css::GREEN_YELLOW,
-                        css::SPRING_GREEN,
-                        css::MEDIUM_SPRING_GREEN,
-                        css::LIGHT_GREEN,
-                        css::PALE_GREEN,
-                        css::DARK_SEA_GREEN,
-                        css::MEDIUM_SEA_GREEN,
-                        css::SEA_GREEN,
-                        css::FOREST_GREEN,
-                        css::GREEN,
-                        css::DARK_GREEN,
-                        // Cyans
-                        css::MEDIUM_AQUAMARINE,
-                        css::AQUA,
-                        css::DARK_CYAN,
-                        css::LIGHT_CYAN,
-                        css::PALE_TURQUOISE,
-                        css::AQUAMARINE,
-                        css::TURQUOISE,
-                        css::MEDIUM_TURQUOISE,
-                        css::DARK_TURQUOISE,
-                        css::LIGHT_SEA_GREEN,
-                        // Blues
-                        css::DEEP_SKY_BLUE,
-                        css::LIGHT_BLUE,
-                        css::SKY_BLUE,
-                        css::LIGHT_SKY_BLUE,
-                        css::STEEL_BLUE,
-                        css::ALICE_BLUE,
-                        css::DODGER_BLUE,
-                        css::ROYAL_BLUE,
-                        css::BLUE,
-                        css::MEDIUM_BLUE,
-                        css::DARK_BLUE,
-                        css::NAVY,
-                        css::MIDNIGHT_BLUE,
-                        css::CORNFLOWER_BLUE,
-                        css::SLATE_BLUE,
-                        // Purples
-                        css::MEDIUM_SLATE_BLUE,
-                        css::DARK_SLATE_BLUE,
-                        css::LAVENDER,
-                        css::THISTLE,
-                        css::PLUM,
-                        css::VIOLET,
-                        css::ORCHID,
-                        css::MAGENTA,
-                        css::MEDIUM_ORCHID,
-                        css::MEDIUM_PURPLE,
-                        css::BLUE_VIOLET,
-                        css::DARK_VIOLET,
-                        css::DARK_ORCHID,
-                        css::DARK_MAGENTA,
-                        css::PURPLE,
-                        // Pinks
-                        css::INDIGO,
-                        css::MEDIUM_VIOLET_RED,
-                        css::PALE_VIOLETRED,
-                        css::DEEP_PINK,
-                        css::HOT_PINK,
-                        css::LIGHT_PINK,
-                        css::PINK,
-                        css::ANTIQUE_WHITE,
-                        css::BEIGE,
-                        css::BISQUE,
-                        // Browns
-                        css::SADDLE_BROWN,
-                        css::SIENNA,
-                        css::CHOCOLATE,
-                        css::PERU,
-                        css::SANDY_BROWN,
-                        css::BURLYWOOD,
-                        css::TAN,
-                        css::ROSY_BROWN,
-                        css::MOCCASIN,
-                        css::NAVAJO_WHITE,
-                        // Grays and others
-                        css::MAROON,
-                        css::BROWN,
-                        css::DARK_OLIVEGREEN,
-                        css::OLIVE_DRAB,
-                        css::DARK_CYAN,
-                        css::TEAL,
-                        css::DARK_SLATE_GRAY,
-                        css::SLATE_GRAY,
-                        css::LIGHT_SLATE_GRAY,
-                        css::DIM_GRAY,
-                    ];
-
-                    let colors: Vec<Color> = all_colors
-                        .into_iter()
-                        .take(color_count as usize)
-                        .map(Color::from)
-                        .collect();
-
-                    particle_system.colors = colors;
-                    particle_system.regenerate_matrix();
-                    particle_system.regenerate_constants();
-
-                    // Spawn new particles
-                    let grid_size = (particle_count.count as f32).sqrt().ceil() as usize;
-                    let spacing_x = WINDOW_WIDTH / grid_size as f32;
-                    let spacing_y = WINDOW_HEIGHT / grid_size as f32;
-
-                    for i in 0..grid_size {
-                        for j in 0..grid_size {
-                            if i * grid_size + j >= particle_count.count {
-                                break;
-                            }
-                            let x = i as f32 * spacing_x - WINDOW_WIDTH / 2.0 + spacing_x / 2.0;
-                            let y = j as f32 * spacing_y - WINDOW_HEIGHT / 2.0 + spacing_y / 2.0;
-
-                            let color_id = (i * grid_size + j) % particle_system.colors.len();
-
-                            commands.spawn((
-                                Mesh2d(meshes.add(Circle::new(PARTICLE_SIZE / 2.0))),
-                                MeshMaterial2d(
-                                    materials
-                                        .add(ColorMaterial::from(particle_system.colors[color_id])),
-                                ),
-                                Transform::from_xyz(x, y, 0.0),
-                                Particle { color_id },
-                            ));
-                        }
-                    }
-                }
+    // Species mix of new particles
+    let colors = particle_system.colors.clone();
+    particle_system.spawn_weights.settings_ui(ui, &colors);
+    spawn_pattern.settings_ui(ui);
+
+    // Matrix regeneration controls
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        for (label, event) in [
+            ("Regenerate Matrix", events::RegenerateMatrix::Regenerate),
+            ("Reroll Matrix", events::RegenerateMatrix::Reroll),
+            (
+                "Perturb Matrix",
+                events::RegenerateMatrix::Perturb {
+                    epsilon: PERTURB_EPSILON,
+                },
+            ),
+            ("Regenerate Constants", events::RegenerateMatrix::Constants),
+        ] {
+            if ui.button(label).clicked() {
+                matrix_events.send(event);
+            }
+        }
+        if ui.button("Add to View").clicked() {
+            spawn_requests.send(spawn::SpawnRequest::Scatter {
+                count: VIEW_SCATTER,
+            });
+        }
+        if ui.button("Clear Particles").clicked() {
+            clear_events.send(events::ClearParticles);
+        }
+        if ui.button("Respawn Particles").clicked() {
+            spawn_requests.send(spawn::SpawnRequest::Reshuffle {
+                count: particle_count.adopt_live(particles.iter().len(), &quality),
+            });
+        }
+        if ui.button("Reset Simulation").clicked() {
+            palette_events.send(events::RegeneratePalette {
+                species: NUM_COLORS,
+                respawn: Some(particle_count.adopt_live(particles.iter().len(), &quality)),
             });
+        }
+    });
+}
 
-            // Matrix regeneration controls
-            ui.add_space(10.0);
-            ui.horizontal(|ui| {
-                if ui.button("Regenerate Matrix").clicked() {
-                    particle_system.regenerate_matrix();
-                }
-                if ui.button("Regenerate Constants").clicked() {
-                    particle_system.regenerate_constants();
-                }
-                if ui.button("Reset Simulation").clicked() {
-                    // Clear existing particles
-                    for entity in &particles {
-                        commands.entity(entity).despawn();
-                    }
-                    // Generate new colors and matrix
-                    *particle_system = ParticleSystem::new();
-                    // Spawn new particles
-                    let grid_size = (particle_count.count as f32).sqrt().ceil() as usize;
-                    let spacing_x = WINDOW_WIDTH / grid_size as f32;
-                    let spacing_y = WINDOW_HEIGHT / grid_size as f32;
-
-                    for i in 0..grid_size {
-                        for j in 0..grid_size {
-                            if i * grid_size + j >= particle_count.count {
-                                break;
-                            }
-                            let x = i as f32 * spacing_x - WINDOW_WIDTH / 2.0 + spacing_x / 2.0;
-                            let y = j as f32 * spacing_y - WINDOW_HEIGHT / 2.0 + spacing_y / 2.0;
-
-                            let color_id = (i * grid_size + j) % particle_system.colors.len();
-
-                            commands.spawn((
-                                Mesh2d(meshes.add(Circle::new(PARTICLE_SIZE / 2.0))),
-                                MeshMaterial2d(
-                                    materials
-                                        .add(ColorMaterial::from(particle_system.colors[color_id])),
-                                ),
-                                Transform::from_xyz(x, y, 0.0),
-                                Particle { color_id },
-                            ));
-                        }
+/// The "Matrix Editor" dock panel: behavior matrix and per-pair radii.
+fn matrix_panel(
+    InMut(ui): InMut<egui::Ui>,
+    mut particle_system: ResMut<ParticleSystem>,
+    (mut matrix_generator, mut constraints, mut tools): (
+        Local<matrix_presets::MatrixGenerator>,
+        Local<matrix_presets::RandomConstraints>,
+        Local<matrix_presets::MatrixTools>,
+    ),
+    quality: Res<quality::QualityGovernor>,
+    particles: Query<(&Transform, &Particle)>,
+) {
+    let size = particle_system.colors.len();
+    if let Some(matrix) = matrix_generator.settings_ui(ui, size) {
+        particle_system.behavior_matrix = matrix;
+    }
+    if ui.button("Randomize with Constraints…").clicked() {
+        constraints.open = true;
+    }
+    if let Some(matrix) = constraints.dialog(ui.ctx(), size) {
+        particle_system.behavior_matrix = matrix;
+    }
+    ui.collapsing("Simplify", |ui| {
+        let colors = particle_system.colors.clone();
+        if let Some(matrix) = tools.tools_ui(ui, &particle_system.behavior_matrix, &colors) {
+            particle_system.behavior_matrix = matrix;
+        }
+    });
+    ui.separator();
+    egui::ScrollArea::both().show(ui, |ui| {
+        egui::Grid::new("behavior_matrix_grid")
+            .spacing([4.0, 4.0])
+            .show(ui, |ui| {
+                for i in 0..size {
+                    for j in 0..size {
+                        let value = &mut particle_system.behavior_matrix[i][j];
+                        ui.add(egui::Slider::new(value, -1.0..=1.0))
+                            .on_hover_ui(|ui| {
+                                interactions::pair_tooltip(
+                                    ui,
+                                    &particle_system,
+                                    quality.radius_scale,
+                                    &particles,
+                                    (i, j),
+                                );
+                            });
                     }
+                    ui.end_row();
                 }
             });
-        });
 
-    // Matrix visualization and editing window
-    egui::Window::new("Behavior Matrix")
-        .default_pos([WINDOW_WIDTH - 300.0, 10.0])
-        .default_size([280.0, 300.0])
-        .show(contexts.ctx_mut(), |ui| {
-            egui::ScrollArea::both().show(ui, |ui| {
-                let size = particle_system.colors.len();
-                egui::Grid::new("behavior_matrix_grid")
-                    .spacing([4.0, 4.0])
-                    .show(ui, |ui| {
-                        for i in 0..size {
-                            for j in 0..size {
-                                let value = &mut particle_system.behavior_matrix[i][j];
-                                ui.add(egui::Slider::new(value, -1.0..=1.0));
-                            }
-                            ui.end_row();
+        // Per-pair cutoffs
+        ui.add_space(10.0);
+        let mut per_pair = particle_system.radius_matrix.is_some();
+        if ui.checkbox(&mut per_pair, "Per-pair radii").changed() {
+            particle_system.radius_matrix =
+                per_pair.then(|| vec![vec![particle_system.attraction_radius; size]; size]);
+        }
+        let fallback = particle_system.attraction_radius;
+        if let Some(radii) = &mut particle_system.radius_matrix {
+            radii.resize(size, Vec::new());
+            egui::Grid::new("radius_matrix_grid")
+                .spacing([4.0, 4.0])
+                .show(ui, |ui| {
+                    for row in radii.iter_mut() {
+                        row.resize(size, fallback);
+                        for value in row.iter_mut() {
+                            ui.add(egui::Slider::new(value, 10.0..=400.0));
                         }
-                    });
-            });
-        });
+                        ui.end_row();
+                    }
+                });
+        }
+    });
 }
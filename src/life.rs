@@ -0,0 +1,212 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    determinism::SpawnRng, egui_color, pool::ParticleSpawner, ui_enabled, Particle, ParticleSystem,
+    PARTICLE_SIZE,
+};
+
+const LIFE_INTERVAL: f32 = 0.25;
+const MAX_PARTICLES: usize = 20000;
+
+pub struct LifePlugin;
+
+impl Plugin for LifePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LifeTimer(Timer::from_seconds(
+            LIFE_INTERVAL,
+            TimerMode::Repeating,
+        )))
+        .add_systems(
+            Update,
+            (
+                age_particles,
+                apply_life_rules.after(crate::spawn::handle_spawn_requests),
+                life_ui_system.run_if(ui_enabled),
+            ),
+        );
+    }
+}
+
+/// Seconds since the particle was spawned.
+#[derive(Component, Default)]
+pub struct Age(pub f32);
+
+/// Birth and death rules for one species. Neighbors are counted within a
+/// fraction of the attraction radius; rates are probabilities per second.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LifeRule {
+    pub birth_neighbors: usize,
+    pub birth_rate: f32,
+    pub isolation_threshold: usize,
+    pub overcrowding_threshold: usize,
+    pub death_rate: f32,
+    /// Maximum age in seconds, 0 means particles never die of old age.
+    pub lifespan: f32,
+}
+
+impl Default for LifeRule {
+    fn default() -> Self {
+        LifeRule {
+            birth_neighbors: 6,
+            birth_rate: 0.02,
+            isolation_threshold: 1,
+            overcrowding_threshold: 30,
+            death_rate: 0.05,
+            lifespan: 0.0,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct LifeTimer(Timer);
+
+fn age_particles(time: Res<Time>, mut particles: Query<&mut Age, With<Particle>>) {
+    let dt = time.delta_secs();
+    for mut age in &mut particles {
+        age.0 += dt;
+    }
+}
+
+fn apply_life_rules(
+    mut spawner: ParticleSpawner,
+    time: Res<Time>,
+    mut timer: ResMut<LifeTimer>,
+    particle_system: Res<ParticleSystem>,
+    mut spawn_rng: ResMut<SpawnRng>,
+    particles: Query<(Entity, &Transform, &Particle, &Age)>,
+) {
+    if !particle_system.life_enabled || !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let neighbor_radius = particle_system.attraction_radius * particle_system.beta;
+    let cell_size = neighbor_radius.max(PARTICLE_SIZE);
+    let mut grid: HashMap<(i32, i32), Vec<(Vec3, usize)>> = HashMap::new();
+    for (_, transform, particle, _) in &particles {
+        let pos = transform.translation;
+        let cell = (
+            (pos.x / cell_size).floor() as i32,
+            (pos.y / cell_size).floor() as i32,
+        );
+        grid.entry(cell).or_default().push((pos, particle.color_id));
+    }
+
+    let rng = spawn_rng.life();
+    let mut population = particles.iter().count();
+    let dt = LIFE_INTERVAL;
+
+    for (entity, transform, particle, age) in &particles {
+        let (Some(rule), Some(&color)) = (
+            particle_system.life_rules.get(particle.color_id),
+            particle_system.colors.get(particle.color_id),
+        ) else {
+            continue;
+        };
+        let pos = transform.translation;
+        let cell_x = (pos.x / cell_size).floor() as i32;
+        let cell_y = (pos.y / cell_size).floor() as i32;
+
+        let mut same = 0;
+        let mut total = 0;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let Some(cell_particles) = grid.get(&(cell_x + dx, cell_y + dy)) else {
+                    continue;
+                };
+                for &(other_pos, other_color_id) in cell_particles {
+                    if other_pos == pos || other_pos.distance(pos) > neighbor_radius {
+                        continue;
+                    }
+                    total += 1;
+                    if other_color_id == particle.color_id {
+                        same += 1;
+                    }
+                }
+            }
+        }
+
+        let too_old = rule.lifespan > 0.0 && age.0 > rule.lifespan;
+        let stressed = same < rule.isolation_threshold || total > rule.overcrowding_threshold;
+        if too_old || (stressed && rng.random::<f32>() < rule.death_rate * dt) {
+            spawner.despawn(entity);
+            population -= 1;
+            continue;
+        }
+
+        if same >= rule.birth_neighbors
+            && population < MAX_PARTICLES
+            && rng.random::<f32>() < rule.birth_rate * dt
+        {
+            let offset = Vec2::from_angle(rng.random_range(0.0..std::f32::consts::TAU))
+                * PARTICLE_SIZE
+                * 2.0;
+            spawner.spawn(pos.truncate() + offset, particle.color_id, color);
+            population += 1;
+        }
+    }
+}
+
+fn life_ui_system(mut contexts: EguiContexts, mut particle_system: ResMut<ParticleSystem>) {
+    egui::Window::new("Life Rules")
+        .default_pos([400.0, 300.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut particle_system.life_enabled, "Enable birth and death");
+            ui.label("Neighbors are counted within beta × attraction radius.");
+            ui.add_space(5.0);
+
+            let particle_system = &mut *particle_system;
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("life_rules_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("");
+                        ui.label("Birth at");
+                        ui.label("Birth rate");
+                        ui.label("Isolated below");
+                        ui.label("Crowded above");
+                        ui.label("Death rate");
+                        ui.label("Lifespan");
+                        ui.end_row();
+
+                        for (color, rule) in particle_system
+                            .colors
+                            .iter()
+                            .zip(particle_system.life_rules.iter_mut())
+                        {
+                            let (rect, _) = ui
+                                .allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                            ui.painter().rect_filled(rect, 2.0, egui_color(*color));
+                            ui.add(egui::DragValue::new(&mut rule.birth_neighbors).range(0..=100));
+                            ui.add(
+                                egui::DragValue::new(&mut rule.birth_rate)
+                                    .range(0.0..=1.0)
+                                    .speed(0.001),
+                            );
+                            ui.add(
+                                egui::DragValue::new(&mut rule.isolation_threshold).range(0..=100),
+                            );
+                            ui.add(
+                                egui::DragValue::new(&mut rule.overcrowding_threshold)
+                                    .range(0..=500),
+                            );
+                            ui.add(
+                                egui::DragValue::new(&mut rule.death_rate)
+                                    .range(0.0..=1.0)
+                                    .speed(0.001),
+                            );
+                            ui.add(
+                                egui::DragValue::new(&mut rule.lifespan)
+                                    .range(0.0..=3600.0)
+                                    .suffix("s"),
+                            );
+                            ui.end_row();
+                        }
+                    });
+            });
+        });
+}
@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{menu::AppState, pool::ParticleSpawner, ui_enabled, ParticleSystem};
+
+/// Layouts with more particles than this are spawned over several frames
+/// behind a progress bar instead of in one frozen frame.
+pub const LOADING_THRESHOLD: usize = 20000;
+/// Particles spawned per frame while loading.
+const SPAWN_BUDGET: usize = 5000;
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingSpawns>().add_systems(
+            Update,
+            (
+                spawn_pending.after(crate::spawn::handle_spawn_requests),
+                loading_ui_system.run_if(in_state(AppState::Loading).and(ui_enabled)),
+            ),
+        );
+    }
+}
+
+/// A large layout still being spawned, as position and species per particle.
+#[derive(Resource, Default)]
+pub struct PendingSpawns {
+    layout: Vec<(Vec2, usize)>,
+    spawned: usize,
+}
+
+impl PendingSpawns {
+    /// Replaces whatever was still loading with `layout`.
+    pub fn start(&mut self, layout: Vec<(Vec2, usize)>) {
+        self.layout = layout;
+        self.spawned = 0;
+    }
+
+    pub fn cancel(&mut self) {
+        self.layout.clear();
+        self.spawned = 0;
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.spawned < self.layout.len()
+    }
+
+    fn progress(&self) -> f32 {
+        self.spawned as f32 / self.layout.len().max(1) as f32
+    }
+}
+
+/// Spawns the next budget of pending particles. A running simulation waits
+/// in the loading state until all of them exist; a paused one stays paused.
+fn spawn_pending(
+    mut pending: ResMut<PendingSpawns>,
+    mut spawner: ParticleSpawner,
+    particle_system: Res<ParticleSystem>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !pending.is_loading() {
+        // Also leaves loading when a clear or a small reset cancelled it
+        if *state.get() == AppState::Loading {
+            next_state.set(AppState::Running);
+        }
+        return;
+    }
+    if *state.get() == AppState::Running {
+        next_state.set(AppState::Loading);
+    }
+    let end = (pending.spawned + SPAWN_BUDGET).min(pending.layout.len());
+    for &(position, color_id) in &pending.layout[pending.spawned..end] {
+        // A palette shrunk mid-load drops the species it no longer has
+        if let Some(&color) = particle_system.colors.get(color_id) {
+            spawner.spawn(position, color_id, color);
+        }
+    }
+    pending.spawned = end;
+    if !pending.is_loading() {
+        debug!(
+            particles = pending.layout.len(),
+            "Finished loading particles"
+        );
+        pending.cancel();
+    }
+}
+
+fn loading_ui_system(mut contexts: EguiContexts, pending: Res<PendingSpawns>) {
+    egui::Window::new("Loading")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false)
+        .title_bar(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(format!("Spawning {} particles...", pending.layout.len()));
+            ui.add(
+                egui::ProgressBar::new(pending.progress())
+                    .desired_width(300.0)
+                    .show_percentage(),
+            );
+        });
+}
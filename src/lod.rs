@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::ui_enabled;
+
+pub struct LodPlugin;
+
+impl Plugin for LodPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LodSettings::default())
+            .add_systems(Update, lod_ui_system.run_if(ui_enabled));
+    }
+}
+
+/// Simulation time a particle has not been integrated for yet. Skipped
+/// particles accumulate here so each one still advances by the full elapsed
+/// time once it is updated, just in fewer, larger steps.
+#[derive(Component, Default)]
+pub struct LodClock(pub f32);
+
+/// Level-of-detail tiers relative to the visible world rect. Particles inside
+/// the view (plus `margin`) update every tick, within `far_distance` view
+/// sizes every `mid_stride` ticks, and beyond that every `far_stride` ticks.
+#[derive(Resource)]
+pub struct LodSettings {
+    pub enabled: bool,
+    pub margin: f32,
+    pub far_distance: f32,
+    pub mid_stride: u64,
+    pub far_stride: u64,
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        LodSettings {
+            enabled: false,
+            margin: 200.0,
+            far_distance: 1.0,
+            mid_stride: 2,
+            far_stride: 4,
+        }
+    }
+}
+
+impl LodSettings {
+    /// How many ticks apart a particle at `pos` should be integrated.
+    pub fn stride(&self, pos: Vec2, view: Rect) -> u64 {
+        let near = view.inflate(self.margin);
+        if near.contains(pos) {
+            return 1;
+        }
+        let mid = view.inflate(self.margin + view.size().max_element() * self.far_distance);
+        if mid.contains(pos) {
+            self.mid_stride
+        } else {
+            self.far_stride
+        }
+    }
+}
+
+/// Visible world rect of a 2D camera whose zoom is stored in its scale.
+pub fn camera_view(transform: &Transform, projection: &OrthographicProjection) -> Rect {
+    let scale = transform.scale.truncate();
+    let center = transform.translation.truncate();
+    Rect::from_corners(
+        center + projection.area.min * scale,
+        center + projection.area.max * scale,
+    )
+}
+
+fn lod_ui_system(mut contexts: EguiContexts, mut lod: ResMut<LodSettings>) {
+    egui::Window::new("Level of Detail")
+        .default_pos([400.0, 520.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut lod.enabled, "Update off-screen particles less often");
+            ui.add(egui::Slider::new(&mut lod.margin, 0.0..=1000.0).text("full-rate margin"));
+            ui.add(egui::Slider::new(&mut lod.far_distance, 0.0..=4.0).text("mid range (views)"));
+            ui.add(egui::Slider::new(&mut lod.mid_stride, 1..=8).text("mid stride"));
+            ui.add(egui::Slider::new(&mut lod.far_stride, 1..=16).text("far stride"));
+        });
+}
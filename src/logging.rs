@@ -0,0 +1,102 @@
+use bevy::{
+    log::{
+        tracing_subscriber::{filter::LevelFilter, reload, Registry},
+        BoxedLayer, Level, LogPlugin,
+    },
+    prelude::*,
+};
+
+use crate::settings::Settings;
+
+/// Verbosities the cycle key steps through, quietest first.
+const LEVELS: [LevelFilter; 5] = [
+    LevelFilter::ERROR,
+    LevelFilter::WARN,
+    LevelFilter::INFO,
+    LevelFilter::DEBUG,
+    LevelFilter::TRACE,
+];
+
+/// Bevy's log plugin with everything let through its static filter, so the
+/// verbosity can be raised and lowered at runtime by [`LogVerbosity`].
+pub fn log_plugin() -> LogPlugin {
+    LogPlugin {
+        level: Level::TRACE,
+        custom_layer: verbosity_layer,
+        ..Default::default()
+    }
+}
+
+pub struct LoggingPlugin;
+
+impl Plugin for LoggingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, cycle_verbosity);
+    }
+}
+
+/// Current maximum log level, starting from `--log-level <level>` or info.
+#[derive(Resource)]
+pub struct LogVerbosity {
+    level: LevelFilter,
+    handle: reload::Handle<LevelFilter, Registry>,
+}
+
+impl LogVerbosity {
+    pub fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    pub fn set(&mut self, level: LevelFilter) {
+        if level == self.level {
+            return;
+        }
+        match self.handle.reload(level) {
+            Ok(()) => {
+                self.level = level;
+                info!("Log level set to {}", level);
+            }
+            Err(error) => warn!("Failed to change log level: {}", error),
+        }
+    }
+}
+
+fn level_from_args() -> LevelFilter {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(index) = args.iter().position(|arg| arg == "--log-level") else {
+        return LevelFilter::INFO;
+    };
+    match args.get(index + 1).map(|value| value.parse()) {
+        Some(Ok(level)) => level,
+        _ => {
+            eprintln!("--log-level expects one of off, error, warn, info, debug or trace");
+            LevelFilter::INFO
+        }
+    }
+}
+
+/// Runs while `LogPlugin` builds, before any other plugin, and leaves the
+/// reload handle behind as a resource.
+fn verbosity_layer(app: &mut App) -> Option<BoxedLayer> {
+    let level = level_from_args();
+    let (layer, handle) = reload::Layer::new(level);
+    app.insert_resource(LogVerbosity { level, handle });
+    Some(Box::new(layer))
+}
+
+fn cycle_verbosity(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    verbosity: Option<ResMut<LogVerbosity>>,
+) {
+    let Some(mut verbosity) = verbosity else {
+        return;
+    };
+    if keyboard.just_pressed(settings.keys.cycle_log_level) {
+        let current = LEVELS
+            .iter()
+            .position(|&level| level == verbosity.level())
+            .unwrap_or(2);
+        verbosity.set(LEVELS[(current + 1) % LEVELS.len()]);
+    }
+}
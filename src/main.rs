@@ -0,0 +1,3 @@
+fn main() {
+    particle_life_rust::run();
+}
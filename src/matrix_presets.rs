@@ -0,0 +1,575 @@
+use bevy::prelude::Color;
+use bevy_egui::egui;
+use rand::Rng;
+
+use crate::interactions::species_combo;
+
+/// Tool actions that can be undone.
+const MAX_UNDO: usize = 32;
+/// Values entries are quantized to.
+const QUANTIZE_STEP: f32 = 0.5;
+
+/// Structured interaction patterns. `matrix[i][j]` is how species `i` reacts
+/// to species `j`.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatrixPreset {
+    /// No interactions besides the background.
+    Neutral,
+    /// Each species chases the next one, which flees, closing into a cycle.
+    #[default]
+    PredatorPreyChain,
+    /// Each species and its ring neighbors attract each other.
+    SymbiosisRing,
+    /// Species attract in pairs and keep away from everyone else.
+    MutualisticPairs,
+    /// Odd species cling to the even species before them, which back away.
+    Parasites,
+}
+
+impl MatrixPreset {
+    const ALL: [MatrixPreset; 5] = [
+        MatrixPreset::Neutral,
+        MatrixPreset::PredatorPreyChain,
+        MatrixPreset::SymbiosisRing,
+        MatrixPreset::MutualisticPairs,
+        MatrixPreset::Parasites,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            MatrixPreset::Neutral => "Neutral",
+            MatrixPreset::PredatorPreyChain => "Predator-prey chain",
+            MatrixPreset::SymbiosisRing => "Symbiosis ring",
+            MatrixPreset::MutualisticPairs => "Mutualistic pairs",
+            MatrixPreset::Parasites => "Parasites",
+        }
+    }
+
+    /// The structured entry for `i` reacting to `j`, or `None` where the
+    /// background applies.
+    fn entry(self, i: usize, j: usize, n: usize, strength: f32) -> Option<f32> {
+        let next = (i + 1) % n;
+        let previous = (i + n - 1) % n;
+        match self {
+            MatrixPreset::Neutral => None,
+            MatrixPreset::PredatorPreyChain => {
+                if i == j {
+                    Some(strength * 0.5)
+                } else if j == next {
+                    Some(strength)
+                } else if j == previous {
+                    Some(-strength)
+                } else {
+                    None
+                }
+            }
+            MatrixPreset::SymbiosisRing => {
+                if i == j {
+                    Some(strength * 0.5)
+                } else if j == next || j == previous {
+                    Some(strength)
+                } else {
+                    None
+                }
+            }
+            MatrixPreset::MutualisticPairs => {
+                if i / 2 == j / 2 {
+                    Some(strength)
+                } else {
+                    Some(-strength * 0.25)
+                }
+            }
+            MatrixPreset::Parasites => {
+                let host = i.is_multiple_of(2);
+                if i == j {
+                    Some(if host { strength * 0.5 } else { 0.0 })
+                } else if !host && j == i - 1 {
+                    Some(strength)
+                } else if host && j == i + 1 {
+                    Some(-strength * 0.5)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Replaces every entry with a uniform random value in -1..=1.
+pub fn reroll(matrix: &mut [Vec<f32>], rng: &mut impl Rng) {
+    for value in matrix.iter_mut().flatten() {
+        *value = rng.random_range(-1.0..=1.0);
+    }
+}
+
+/// Nudges every entry by up to `epsilon` in either direction, staying within
+/// -1..=1.
+pub fn perturb(matrix: &mut [Vec<f32>], epsilon: f32, rng: &mut impl Rng) {
+    for value in matrix.iter_mut().flatten() {
+        *value = (*value + rng.random_range(-epsilon..=epsilon)).clamp(-1.0, 1.0);
+    }
+}
+
+/// Zeroes every entry weaker than `threshold`.
+pub fn zero_below(matrix: &mut [Vec<f32>], threshold: f32) {
+    for value in matrix.iter_mut().flatten() {
+        if value.abs() < threshold {
+            *value = 0.0;
+        }
+    }
+}
+
+/// Rounds every entry to the nearest of -1, -0.5, 0, 0.5 and 1.
+pub fn quantize(matrix: &mut [Vec<f32>]) {
+    for value in matrix.iter_mut().flatten() {
+        *value = ((*value / QUANTIZE_STEP).round() * QUANTIZE_STEP).clamp(-1.0, 1.0);
+    }
+}
+
+/// Multiplies how species `row` reacts to everyone by `factor`.
+pub fn scale_row(matrix: &mut [Vec<f32>], row: usize, factor: f32) {
+    for value in matrix.get_mut(row).into_iter().flatten() {
+        *value = (*value * factor).clamp(-1.0, 1.0);
+    }
+}
+
+/// Multiplies how everyone reacts to species `column` by `factor`.
+pub fn scale_column(matrix: &mut [Vec<f32>], column: usize, factor: f32) {
+    for value in matrix.iter_mut().filter_map(|row| row.get_mut(column)) {
+        *value = (*value * factor).clamp(-1.0, 1.0);
+    }
+}
+
+/// Swaps how `i` reacts to `j` with how `j` reacts to `i`, for every pair.
+pub fn transpose(matrix: &mut [Vec<f32>]) {
+    for i in 0..matrix.len() {
+        for j in i + 1..matrix.len() {
+            let (upper, lower) = matrix.split_at_mut(j);
+            std::mem::swap(&mut upper[i][j], &mut lower[0][i]);
+        }
+    }
+}
+
+/// A tool's change to the matrix, applied after the buttons are drawn.
+type MatrixEdit = Box<dyn Fn(&mut [Vec<f32>])>;
+
+/// Operations that simplify the current matrix into something explainable,
+/// each undoable from the Matrix Editor.
+pub struct MatrixTools {
+    threshold: f32,
+    /// Species whose row or column is scaled.
+    species: usize,
+    factor: f32,
+    /// Matrices from before each tool action, newest last.
+    undo: Vec<Vec<Vec<f32>>>,
+}
+
+impl Default for MatrixTools {
+    fn default() -> Self {
+        MatrixTools {
+            threshold: 0.2,
+            species: 0,
+            factor: 0.5,
+            undo: Vec::new(),
+        }
+    }
+}
+
+impl MatrixTools {
+    /// Returns the matrix a tool or undo turns `matrix` into, if one was
+    /// used.
+    pub fn tools_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        matrix: &[Vec<f32>],
+        colors: &[Color],
+    ) -> Option<Vec<Vec<f32>>> {
+        // Undo steps from before the species count changed no longer fit
+        if self
+            .undo
+            .last()
+            .is_some_and(|last| last.len() != matrix.len())
+        {
+            self.undo.clear();
+        }
+        let mut action: Option<MatrixEdit> = None;
+        ui.horizontal(|ui| {
+            ui.add(egui::Slider::new(&mut self.threshold, 0.0..=1.0).text("threshold"));
+            if ui.button("Zero Below").clicked() {
+                let threshold = self.threshold;
+                action = Some(Box::new(move |matrix: &mut [Vec<f32>]| {
+                    zero_below(matrix, threshold)
+                }));
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Quantize").clicked() {
+                action = Some(Box::new(quantize));
+            }
+            if ui.button("Transpose").clicked() {
+                action = Some(Box::new(transpose));
+            }
+        });
+        if !colors.is_empty() {
+            self.species = self.species.min(colors.len() - 1);
+            species_combo(ui, "species", &mut self.species, colors);
+            ui.horizontal(|ui| {
+                ui.add(egui::Slider::new(&mut self.factor, -2.0..=2.0).text("factor"));
+                let (species, factor) = (self.species, self.factor);
+                if ui.button("Scale Row").clicked() {
+                    action = Some(Box::new(move |matrix: &mut [Vec<f32>]| {
+                        scale_row(matrix, species, factor)
+                    }));
+                }
+                if ui.button("Scale Column").clicked() {
+                    action = Some(Box::new(move |matrix: &mut [Vec<f32>]| {
+                        scale_column(matrix, species, factor)
+                    }));
+                }
+            });
+        }
+        let undo = ui.add_enabled(
+            !self.undo.is_empty(),
+            egui::Button::new(format!("Undo ({})", self.undo.len())),
+        );
+        if undo.clicked() {
+            return self.undo.pop();
+        }
+
+        let action = action?;
+        if self.undo.len() == MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.undo.push(matrix.to_vec());
+        let mut changed = matrix.to_vec();
+        action(&mut changed);
+        Some(changed)
+    }
+}
+
+/// Sign forced on a group of matrix entries.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignRule {
+    #[default]
+    Any,
+    Attract,
+    Repel,
+}
+
+impl SignRule {
+    const ALL: [SignRule; 3] = [SignRule::Any, SignRule::Attract, SignRule::Repel];
+
+    fn label(self) -> &'static str {
+        match self {
+            SignRule::Any => "Any sign",
+            SignRule::Attract => "Always attract",
+            SignRule::Repel => "Always repel",
+        }
+    }
+
+    fn sign(self, rng: &mut impl Rng) -> f32 {
+        match self {
+            SignRule::Any if rng.random_bool(0.5) => 1.0,
+            SignRule::Any | SignRule::Repel => -1.0,
+            SignRule::Attract => 1.0,
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, label: &str) {
+        egui::ComboBox::from_label(label)
+            .selected_text(self.label())
+            .show_ui(ui, |ui| {
+                for rule in SignRule::ALL {
+                    ui.selectable_value(self, rule, rule.label());
+                }
+            });
+    }
+}
+
+/// Random matrices held to a shape, since uniform values in -1..=1 rarely
+/// give structured ecosystems. Set in the "Randomize with Constraints"
+/// dialog of the Matrix Editor.
+pub struct RandomConstraints {
+    pub open: bool,
+    /// Smallest and largest magnitude of a nonzero entry.
+    magnitude: (f32, f32),
+    /// Sign of each species' reaction to its own kind.
+    diagonal: SignRule,
+    /// Sign of every reaction to another species.
+    off_diagonal: SignRule,
+    /// Fraction of the entries between different species that are zero.
+    sparsity: f32,
+    /// Each pair reacts to one another the same way.
+    symmetric: bool,
+}
+
+impl Default for RandomConstraints {
+    fn default() -> Self {
+        RandomConstraints {
+            open: false,
+            magnitude: (0.2, 1.0),
+            diagonal: SignRule::Attract,
+            off_diagonal: SignRule::Any,
+            sparsity: 0.3,
+            symmetric: false,
+        }
+    }
+}
+
+impl RandomConstraints {
+    pub fn generate_with(&self, n: usize, rng: &mut impl Rng) -> Vec<Vec<f32>> {
+        let (low, high) = (
+            self.magnitude.0.min(self.magnitude.1),
+            self.magnitude.0.max(self.magnitude.1),
+        );
+        let mut matrix = vec![vec![0.0; n]; n];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                // Symmetric matrices mirror the upper triangle below
+                if self.symmetric && j < i {
+                    continue;
+                }
+                let rule = if i == j {
+                    self.diagonal
+                } else if rng.random::<f32>() < self.sparsity {
+                    continue;
+                } else {
+                    self.off_diagonal
+                };
+                let magnitude = rng.random_range(low..=high);
+                *value = (rule.sign(rng) * magnitude).clamp(-1.0, 1.0);
+            }
+        }
+        if self.symmetric {
+            let upper = matrix.clone();
+            for (i, row) in matrix.iter_mut().enumerate() {
+                for (j, value) in row.iter_mut().enumerate().take(i) {
+                    *value = upper[j][i];
+                }
+            }
+        }
+        matrix
+    }
+
+    /// Shows the dialog while it is open. Returns a new matrix for `n`
+    /// species when the user asks for one.
+    pub fn dialog(&mut self, ctx: &egui::Context, n: usize) -> Option<Vec<Vec<f32>>> {
+        let mut open = self.open;
+        let mut generated = None;
+        egui::Window::new("Randomize with Constraints")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let (low, high) = &mut self.magnitude;
+                ui.add(egui::Slider::new(low, 0.0..=1.0).text("smallest magnitude"));
+                ui.add(egui::Slider::new(high, 0.0..=1.0).text("largest magnitude"));
+                self.diagonal.ui(ui, "own species");
+                self.off_diagonal.ui(ui, "other species");
+                ui.add(
+                    egui::Slider::new(&mut self.sparsity, 0.0..=1.0)
+                        .text("zero entries (other species)"),
+                );
+                ui.checkbox(&mut self.symmetric, "Symmetric");
+                if ui.button("Regenerate").clicked() {
+                    generated = Some(self.generate_with(n, &mut rand::rng()));
+                }
+            });
+        self.open = open;
+        generated
+    }
+}
+
+/// Builds structured behavior matrices from the UI.
+pub struct MatrixGenerator {
+    preset: MatrixPreset,
+    /// Magnitude of the structured entries.
+    strength: f32,
+    /// Largest random value given to the unstructured entries.
+    background: f32,
+}
+
+impl Default for MatrixGenerator {
+    fn default() -> Self {
+        MatrixGenerator {
+            preset: MatrixPreset::default(),
+            strength: 0.8,
+            background: 0.0,
+        }
+    }
+}
+
+impl MatrixGenerator {
+    pub fn generate(&self, n: usize) -> Vec<Vec<f32>> {
+        self.generate_with(n, &mut rand::rng())
+    }
+
+    /// [`Self::generate`] drawing the background values from `rng`.
+    pub fn generate_with(&self, n: usize, rng: &mut impl Rng) -> Vec<Vec<f32>> {
+        (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| match self.preset.entry(i, j, n, self.strength) {
+                        Some(value) => value.clamp(-1.0, 1.0),
+                        None if self.background > 0.0 => {
+                            rng.random_range(-self.background..=self.background)
+                        }
+                        None => 0.0,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns a new matrix for `n` species when the user asks for one.
+    pub fn settings_ui(&mut self, ui: &mut egui::Ui, n: usize) -> Option<Vec<Vec<f32>>> {
+        egui::ComboBox::from_label("Structure")
+            .selected_text(self.preset.label())
+            .show_ui(ui, |ui| {
+                for preset in MatrixPreset::ALL {
+                    ui.selectable_value(&mut self.preset, preset, preset.label());
+                }
+            });
+        ui.add(egui::Slider::new(&mut self.strength, 0.0..=1.0).text("strength"));
+        ui.add(egui::Slider::new(&mut self.background, 0.0..=1.0).text("background"));
+        ui.button("Generate Structured Matrix")
+            .clicked()
+            .then(|| self.generate(n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn generators() -> impl Iterator<Item = MatrixGenerator> {
+        MatrixPreset::ALL.into_iter().flat_map(|preset| {
+            [(0.8, 0.0), (1.0, 0.3), (2.0, 1.0)].map(|(strength, background)| MatrixGenerator {
+                preset,
+                strength,
+                background,
+            })
+        })
+    }
+
+    #[test]
+    fn generated_matrices_are_square_and_in_range() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for generator in generators() {
+            for n in 0..12 {
+                let matrix = generator.generate_with(n, &mut rng);
+                assert_eq!(matrix.len(), n);
+                for row in &matrix {
+                    assert_eq!(row.len(), n);
+                    assert!(row.iter().all(|value| (-1.0..=1.0).contains(value)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn predator_prey_chain_chases_the_next_species() {
+        let generator = MatrixGenerator {
+            preset: MatrixPreset::PredatorPreyChain,
+            strength: 0.8,
+            background: 0.0,
+        };
+        let n = 5;
+        let matrix = generator.generate_with(n, &mut StdRng::seed_from_u64(1));
+        for i in 0..n {
+            assert_eq!(matrix[i][(i + 1) % n], 0.8);
+            assert_eq!(matrix[i][(i + n - 1) % n], -0.8);
+        }
+    }
+
+    #[test]
+    fn neutral_without_background_is_all_zero() {
+        let generator = MatrixGenerator {
+            preset: MatrixPreset::Neutral,
+            strength: 1.0,
+            background: 0.0,
+        };
+        let matrix = generator.generate_with(6, &mut StdRng::seed_from_u64(1));
+        assert!(matrix.iter().flatten().all(|&value| value == 0.0));
+    }
+
+    #[test]
+    fn constrained_matrices_keep_signs_and_symmetry() {
+        let constraints = RandomConstraints {
+            open: false,
+            magnitude: (0.3, 0.6),
+            diagonal: SignRule::Attract,
+            off_diagonal: SignRule::Repel,
+            sparsity: 0.5,
+            symmetric: true,
+        };
+        let n = 10;
+        let matrix = constraints.generate_with(n, &mut StdRng::seed_from_u64(2));
+        for (i, row) in matrix.iter().enumerate() {
+            assert!((0.3..=0.6).contains(&row[i]));
+            for (j, &value) in row.iter().enumerate() {
+                assert_eq!(value, matrix[j][i]);
+                if i != j {
+                    assert!(value == 0.0 || (-0.6..=-0.3).contains(&value));
+                }
+            }
+        }
+        let zeros = matrix
+            .iter()
+            .flatten()
+            .filter(|&&value| value == 0.0)
+            .count();
+        assert!(zeros > 0 && zeros < n * (n - 1));
+    }
+
+    #[test]
+    fn tools_simplify_the_matrix() {
+        let mut matrix = vec![
+            vec![0.1, -0.7, 0.3],
+            vec![0.9, -0.2, 0.0],
+            vec![-0.4, 0.6, 1.0],
+        ];
+        transpose(&mut matrix);
+        assert_eq!(matrix[0], vec![0.1, 0.9, -0.4]);
+        assert_eq!(matrix[1][0], -0.7);
+        zero_below(&mut matrix, 0.25);
+        assert_eq!(matrix[0][0], 0.0);
+        quantize(&mut matrix);
+        assert!(matrix
+            .iter()
+            .flatten()
+            .all(|&value| [-1.0, -0.5, 0.0, 0.5, 1.0].contains(&value)));
+        scale_row(&mut matrix, 2, 2.0);
+        assert_eq!(matrix[2], vec![1.0, 0.0, 1.0]);
+        scale_column(&mut matrix, 1, -1.0);
+        assert_eq!(matrix[0][1], -1.0);
+    }
+
+    #[test]
+    fn reroll_keeps_dimensions_and_range() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut matrix = vec![vec![0.0; 9]; 9];
+        reroll(&mut matrix, &mut rng);
+        assert_eq!(matrix.len(), 9);
+        assert!(matrix.iter().all(|row| row.len() == 9));
+        assert!(matrix
+            .iter()
+            .flatten()
+            .all(|value| (-1.0..=1.0).contains(value)));
+        assert!(matrix.iter().flatten().any(|&value| value != 0.0));
+    }
+
+    #[test]
+    fn perturb_moves_entries_by_at_most_epsilon() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut matrix = vec![vec![0.0; 8]; 8];
+        reroll(&mut matrix, &mut rng);
+        matrix[0][0] = 1.0;
+        matrix[0][1] = -1.0;
+        let before = matrix.clone();
+        perturb(&mut matrix, 0.05, &mut rng);
+        for (after, before) in matrix.iter().flatten().zip(before.iter().flatten()) {
+            assert!((after - before).abs() <= 0.05 + 1e-6);
+            assert!((-1.0..=1.0).contains(after));
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    events::RegeneratePalette,
+    network,
+    preset::{self, LoadPreset},
+    settings::Settings,
+    spawn::SpawnRequest,
+    ui_enabled, ParticleCount, NUM_COLORS,
+};
+
+/// Largest particle count offered by the main menu.
+const MAX_MENU_PARTICLES: usize = 20000;
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<AppState>()
+            .init_resource::<NewWorld>()
+            .add_systems(OnEnter(AppState::Running), resume_time)
+            .add_systems(OnEnter(AppState::Menu), pause_time)
+            .add_systems(OnEnter(AppState::Paused), pause_time)
+            .add_systems(OnEnter(AppState::Loading), pause_time)
+            .add_systems(
+                Update,
+                (
+                    toggle_pause.run_if(ui_enabled),
+                    main_menu_system.run_if(in_state(AppState::Menu)),
+                    pause_menu_system.run_if(in_state(AppState::Paused)),
+                    start_with_preset
+                        .after(preset::apply_presets)
+                        .run_if(in_state(AppState::Menu)),
+                ),
+            );
+    }
+}
+
+/// Where the app is: the main menu before a world exists, the running
+/// simulation, the simulation paused behind the pause menu, or a large world
+/// still being spawned. Simulated time only passes while running. Embedded and command line runs start running.
+#[derive(States, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum AppState {
+    Menu,
+    #[default]
+    Running,
+    Paused,
+    Loading,
+}
+
+/// Interactive launches open the main menu; command line modes such as
+/// `--stress` or `--replay` and shared worlds go straight to the simulation.
+pub fn shown_at_launch() -> bool {
+    std::env::args().len() <= 1 && !network::requested()
+}
+
+/// What "Start New World" creates.
+#[derive(Resource)]
+struct NewWorld {
+    species: usize,
+}
+
+impl Default for NewWorld {
+    fn default() -> Self {
+        NewWorld {
+            species: NUM_COLORS,
+        }
+    }
+}
+
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn toggle_pause(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut contexts: EguiContexts,
+    settings: Res<Settings>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !keyboard.just_pressed(settings.keys.pause) || contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    match state.get() {
+        AppState::Running => next_state.set(AppState::Paused),
+        AppState::Paused => next_state.set(AppState::Running),
+        AppState::Menu | AppState::Loading => {}
+    }
+}
+
+/// Expands the collapsed Settings window.
+fn open_settings(ctx: &egui::Context) {
+    let id = egui::Id::new("Settings").with("collapsing");
+    let mut state =
+        egui::collapsing_header::CollapsingState::load_with_default_open(ctx, id, false);
+    state.set_open(true);
+    state.store(ctx);
+}
+
+/// A button per preset file, each loading it.
+#[cfg(not(target_arch = "wasm32"))]
+fn preset_buttons(
+    ui: &mut egui::Ui,
+    files: &mut Option<Vec<String>>,
+    presets: &mut EventWriter<LoadPreset>,
+) {
+    let files = files.get_or_insert_with(|| {
+        let Ok(entries) = std::fs::read_dir(preset::PRESETS_DIR) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| {
+                let name = name.to_lowercase();
+                name.ends_with(".ron") || name.ends_with(".json")
+            })
+            .collect();
+        names.sort();
+        names
+    });
+    if files.is_empty() {
+        ui.label(format!("No presets in {}/ yet.", preset::PRESETS_DIR));
+    }
+    for name in files.iter() {
+        if !ui.button(name).clicked() {
+            continue;
+        }
+        let path = std::path::Path::new(preset::PRESETS_DIR).join(name);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                presets.send(LoadPreset {
+                    name: name.clone(),
+                    text,
+                });
+            }
+            Err(error) => warn!("Could not read {}: {}", path.display(), error),
+        }
+    }
+}
+
+/// Browsers have no presets directory; presets are dropped onto the page.
+#[cfg(target_arch = "wasm32")]
+fn preset_buttons(
+    _ui: &mut egui::Ui,
+    _files: &mut Option<Vec<String>>,
+    _presets: &mut EventWriter<LoadPreset>,
+) {
+}
+
+fn main_menu_system(
+    mut contexts: EguiContexts,
+    mut new_world: ResMut<NewWorld>,
+    mut particle_count: ResMut<ParticleCount>,
+    mut palette_events: EventWriter<RegeneratePalette>,
+    mut presets: EventWriter<LoadPreset>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut files: Local<Option<Vec<String>>>,
+) {
+    let ctx = contexts.ctx_mut().clone();
+    egui::Window::new("Particle Life")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(&ctx, |ui| {
+            ui.heading("New World");
+            ui.add(
+                egui::Slider::new(&mut particle_count.count, 100..=MAX_MENU_PARTICLES)
+                    .text("particles"),
+            );
+            ui.add(egui::Slider::new(&mut new_world.species, 1..=100).text("species"));
+            if ui.button("Start New World").clicked() {
+                palette_events.send(RegeneratePalette {
+                    species: new_world.species,
+                    respawn: Some(particle_count.count),
+                });
+                next_state.set(AppState::Running);
+            }
+
+            ui.add_space(10.0);
+            ui.heading("Load Preset");
+            preset_buttons(ui, &mut files, &mut presets);
+            ui.label("Or drop a preset file onto the window.");
+
+            ui.add_space(10.0);
+            if ui.button("Settings").clicked() {
+                open_settings(&ctx);
+            }
+        });
+}
+
+/// A preset loaded from the main menu, or dropped onto it, starts the world
+/// with its rules once they are in place.
+fn start_with_preset(
+    mut loaded: EventReader<LoadPreset>,
+    particle_count: Res<ParticleCount>,
+    mut spawn_requests: EventWriter<SpawnRequest>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if loaded.read().count() == 0 {
+        return;
+    }
+    spawn_requests.send(SpawnRequest::Reset {
+        count: particle_count.count,
+    });
+    next_state.set(AppState::Running);
+}
+
+fn pause_menu_system(
+    mut contexts: EguiContexts,
+    settings: Res<Settings>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let ctx = contexts.ctx_mut().clone();
+    egui::Window::new("Paused")
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(&ctx, |ui| {
+            ui.label(format!(
+                "Press {} to resume.",
+                crate::help::key_name(settings.keys.pause)
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("Resume").clicked() {
+                    next_state.set(AppState::Running);
+                }
+                if ui.button("Settings").clicked() {
+                    open_settings(&ctx);
+                }
+                if ui.button("Main Menu").clicked() {
+                    next_state.set(AppState::Menu);
+                }
+            });
+        });
+}
@@ -0,0 +1,178 @@
+use bevy::{
+    diagnostic::{DiagnosticPath, DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+use std::{
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+};
+
+use crate::{perf, stats::SimulationStats, Particle};
+
+/// Seconds between refreshes of the served metrics.
+const REFRESH_INTERVAL: f32 = 1.0;
+
+/// Serves live metrics in the Prometheus text format over HTTP, so long
+/// running installations can be scraped by standard monitoring. Enabled with
+/// `--metrics <address>`, e.g. `--metrics 0.0.0.0:9184`.
+pub struct MetricsPlugin;
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(address) = address_from_args() else {
+            return;
+        };
+        let page = Arc::new(Mutex::new(String::new()));
+        match serve(&address, page.clone()) {
+            Ok(()) => {
+                info!("Serving metrics on http://{}/metrics", address);
+                app.insert_resource(MetricsPage(page))
+                    .add_systems(Last, refresh_metrics);
+            }
+            Err(error) => error!("Could not serve metrics on {}: {}", address, error),
+        }
+    }
+}
+
+fn address_from_args() -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|arg| arg == "--metrics")?;
+    args.get(index + 1).cloned()
+}
+
+/// The latest exposition text, shared with the server thread.
+#[derive(Resource)]
+struct MetricsPage(Arc<Mutex<String>>);
+
+/// Answers every request on a background thread; `/metrics` gets the page,
+/// anything else a 404.
+fn serve(address: &str, page: Arc<Mutex<String>>) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let page = page.clone();
+            thread::spawn(move || {
+                if let Err(error) = answer(stream, &page) {
+                    debug!("Metrics request failed: {}", error);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn answer(stream: TcpStream, page: &Mutex<String>) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers; requests to this endpoint carry no body
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let (status, content_type, body) = if path == "/metrics" || path.starts_with("/metrics?") {
+        let body = page.lock().unwrap_or_else(PoisonError::into_inner).clone();
+        ("200 OK", "text/plain; version=0.0.4", body)
+    } else {
+        ("404 Not Found", "text/plain", "Not found\n".to_string())
+    };
+    write!(
+        writer,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    writer.flush()
+}
+
+/// Appends one gauge with its help text and samples, each sample being a
+/// label set (empty for none) and a value.
+fn gauge(out: &mut String, name: &str, help: &str, samples: &[(String, f64)]) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{} {}", name, labels, value);
+    }
+}
+
+fn refresh_metrics(
+    time: Res<Time>,
+    mut timer: Local<Option<Timer>>,
+    page: Res<MetricsPage>,
+    diagnostics: Res<DiagnosticsStore>,
+    stats: Res<SimulationStats>,
+    particles: Query<(), With<Particle>>,
+) {
+    let timer =
+        timer.get_or_insert_with(|| Timer::from_seconds(REFRESH_INTERVAL, TimerMode::Repeating));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let smoothed = |path: &DiagnosticPath| {
+        diagnostics
+            .get(path)
+            .and_then(|diagnostic| diagnostic.smoothed())
+    };
+
+    let mut out = String::new();
+    gauge(
+        &mut out,
+        "particle_life_particles",
+        "Particles currently simulated.",
+        &[(String::new(), particles.iter().len() as f64)],
+    );
+    if let Some(tick_ms) = smoothed(&perf::SPATIAL_BUILD)
+        .zip(smoothed(&perf::SIMULATION))
+        .map(|(spatial, simulation)| spatial + simulation)
+    {
+        gauge(
+            &mut out,
+            "particle_life_tick_seconds",
+            "Spatial build plus force integration per tick, smoothed.",
+            &[(String::new(), tick_ms / 1000.0)],
+        );
+    }
+    if let Some(fps) = smoothed(&FrameTimeDiagnosticsPlugin::FPS) {
+        gauge(
+            &mut out,
+            "particle_life_frames_per_second",
+            "Frames per second, smoothed.",
+            &[(String::new(), fps)],
+        );
+    }
+    if let Some(latest) = stats.latest() {
+        let populations: Vec<_> = latest
+            .species_counts
+            .iter()
+            .enumerate()
+            .map(|(species, &count)| (format!("{{species=\"{}\"}}", species), count as f64))
+            .collect();
+        gauge(
+            &mut out,
+            "particle_life_species_population",
+            "Particles per species at the last statistics sample.",
+            &populations,
+        );
+        gauge(
+            &mut out,
+            "particle_life_clusters",
+            "Clusters found at the last statistics sample.",
+            &[(String::new(), latest.cluster_count as f64)],
+        );
+        gauge(
+            &mut out,
+            "particle_life_average_speed",
+            "Mean particle speed at the last statistics sample.",
+            &[(String::new(), latest.average_speed as f64)],
+        );
+    }
+    *page.0.lock().unwrap_or_else(PoisonError::into_inner) = out;
+}
@@ -0,0 +1,483 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{ui_enabled, ParticleSystem, BASE_SPEED};
+
+/// Per-frame decay of the automatic gain reference, so quiet passages still register.
+const GAIN_DECAY: f32 = 0.995;
+const LEVEL_SMOOTHING: f32 = 0.3;
+const LOW_CUTOFF: f32 = 250.0;
+const HIGH_CUTOFF: f32 = 2000.0;
+
+pub struct MicrophonePlugin;
+
+impl Plugin for MicrophonePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AudioReactive::default())
+            .init_non_send_resource::<capture::Capture>()
+            .add_systems(
+                Update,
+                (
+                    update_capture,
+                    modulate_parameters,
+                    microphone_ui_system.run_if(ui_enabled),
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AudioBand {
+    Off,
+    Loudness,
+    Bass,
+    Mids,
+    Treble,
+}
+
+impl AudioBand {
+    const ALL: [AudioBand; 5] = [
+        AudioBand::Off,
+        AudioBand::Loudness,
+        AudioBand::Bass,
+        AudioBand::Mids,
+        AudioBand::Treble,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            AudioBand::Off => "Off",
+            AudioBand::Loudness => "Loudness",
+            AudioBand::Bass => "Bass",
+            AudioBand::Mids => "Mids",
+            AudioBand::Treble => "Treble",
+        }
+    }
+}
+
+/// Band energies of the input signal, each normalized to 0..1.
+#[derive(Clone, Copy, Default)]
+pub struct AudioLevels {
+    pub loudness: f32,
+    pub bass: f32,
+    pub mids: f32,
+    pub treble: f32,
+}
+
+impl AudioLevels {
+    fn get(&self, band: AudioBand) -> f32 {
+        match band {
+            AudioBand::Off => 0.0,
+            AudioBand::Loudness => self.loudness,
+            AudioBand::Bass => self.bass,
+            AudioBand::Mids => self.mids,
+            AudioBand::Treble => self.treble,
+        }
+    }
+
+    fn max(self, other: AudioLevels) -> AudioLevels {
+        AudioLevels {
+            loudness: self.loudness.max(other.loudness),
+            bass: self.bass.max(other.bass),
+            mids: self.mids.max(other.mids),
+            treble: self.treble.max(other.treble),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Modulation {
+    pub band: AudioBand,
+    /// Offset added at full level, in the parameter's own units.
+    pub amount: f32,
+}
+
+/// Music visualizer mode: microphone levels push temperature, speed and
+/// attraction radius up from wherever the sliders are. Offsets are tracked
+/// and removed again, so the sliders stay usable while it runs.
+#[derive(Resource)]
+pub struct AudioReactive {
+    pub enabled: bool,
+    pub temperature: Modulation,
+    pub speed: Modulation,
+    pub radius: Modulation,
+    pub levels: AudioLevels,
+    peaks: AudioLevels,
+    applied: [f32; 3],
+    status: Option<String>,
+}
+
+impl Default for AudioReactive {
+    fn default() -> Self {
+        AudioReactive {
+            enabled: false,
+            temperature: Modulation {
+                band: AudioBand::Treble,
+                amount: 20.0,
+            },
+            speed: Modulation {
+                band: AudioBand::Loudness,
+                amount: BASE_SPEED,
+            },
+            radius: Modulation {
+                band: AudioBand::Bass,
+                amount: 50.0,
+            },
+            levels: AudioLevels::default(),
+            peaks: AudioLevels {
+                loudness: 1e-4,
+                bass: 1e-4,
+                mids: 1e-4,
+                treble: 1e-4,
+            },
+            applied: [0.0; 3],
+            status: None,
+        }
+    }
+}
+
+fn update_capture(mut reactive: ResMut<AudioReactive>, mut capture: NonSendMut<capture::Capture>) {
+    if !reactive.enabled {
+        capture.stop();
+        reactive.levels = AudioLevels::default();
+        return;
+    }
+    if !capture.is_running() {
+        match capture.start() {
+            Ok(()) => reactive.status = Some("Listening".to_string()),
+            Err(error) => {
+                reactive.status = Some(format!("Microphone unavailable: {}", error));
+                reactive.enabled = false;
+                return;
+            }
+        }
+    }
+    let Some(raw) = capture.levels() else {
+        return;
+    };
+
+    // Automatic gain: normalize against a slowly decaying peak per band
+    let peaks = reactive.peaks;
+    let decayed = AudioLevels {
+        loudness: peaks.loudness * GAIN_DECAY,
+        bass: peaks.bass * GAIN_DECAY,
+        mids: peaks.mids * GAIN_DECAY,
+        treble: peaks.treble * GAIN_DECAY,
+    };
+    let peaks = decayed.max(raw);
+    reactive.peaks = peaks;
+
+    let smooth = |current: f32, raw: f32, peak: f32| {
+        current + ((raw / peak.max(1e-4)).min(1.0) - current) * LEVEL_SMOOTHING
+    };
+    let levels = reactive.levels;
+    reactive.levels = AudioLevels {
+        loudness: smooth(levels.loudness, raw.loudness, peaks.loudness),
+        bass: smooth(levels.bass, raw.bass, peaks.bass),
+        mids: smooth(levels.mids, raw.mids, peaks.mids),
+        treble: smooth(levels.treble, raw.treble, peaks.treble),
+    };
+}
+
+fn modulate_parameters(
+    mut reactive: ResMut<AudioReactive>,
+    mut particle_system: ResMut<ParticleSystem>,
+) {
+    let offsets = if reactive.enabled {
+        let levels = reactive.levels;
+        [reactive.temperature, reactive.speed, reactive.radius]
+            .map(|modulation| levels.get(modulation.band) * modulation.amount)
+    } else {
+        [0.0; 3]
+    };
+    if offsets == reactive.applied {
+        return;
+    }
+    let [temperature, speed, radius] = offsets;
+    let [applied_temperature, applied_speed, applied_radius] = reactive.applied;
+    particle_system.temperature =
+        (particle_system.temperature + temperature - applied_temperature).max(0.0);
+    particle_system.force_strength =
+        (particle_system.force_strength + speed - applied_speed).max(0.0);
+    particle_system.attraction_radius =
+        (particle_system.attraction_radius + radius - applied_radius).max(1.0);
+    reactive.applied = offsets;
+}
+
+fn microphone_ui_system(mut contexts: EguiContexts, mut reactive: ResMut<AudioReactive>) {
+    egui::Window::new("Audio Reactive")
+        .default_pos([620.0, 360.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut reactive.enabled, "React to microphone input");
+            let levels = reactive.levels;
+            for (band, level) in [
+                ("Loudness", levels.loudness),
+                ("Bass", levels.bass),
+                ("Mids", levels.mids),
+                ("Treble", levels.treble),
+            ] {
+                ui.add(egui::ProgressBar::new(level).text(band));
+            }
+            ui.separator();
+
+            let reactive = &mut *reactive;
+            egui::Grid::new("audio_reactive_grid").show(ui, |ui| {
+                for (name, modulation, max) in [
+                    ("Temperature", &mut reactive.temperature, 50.0),
+                    ("Speed", &mut reactive.speed, 3200.0),
+                    ("Radius", &mut reactive.radius, 200.0),
+                ] {
+                    ui.label(name);
+                    egui::ComboBox::from_id_salt(name)
+                        .selected_text(modulation.band.label())
+                        .show_ui(ui, |ui| {
+                            for band in AudioBand::ALL {
+                                ui.selectable_value(&mut modulation.band, band, band.label());
+                            }
+                        });
+                    ui.add(egui::Slider::new(&mut modulation.amount, 0.0..=max).text("amount"));
+                    ui.end_row();
+                }
+            });
+            if let Some(status) = &reactive.status {
+                ui.label(status);
+            }
+        });
+}
+
+/// Native capture through cpal. The stream callback splits the signal into
+/// bands with one-pole filters and publishes the RMS of each per buffer.
+#[cfg(not(target_arch = "wasm32"))]
+mod capture {
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use std::sync::{Arc, Mutex};
+
+    use super::{AudioLevels, HIGH_CUTOFF, LOW_CUTOFF};
+
+    #[derive(Default)]
+    pub struct Capture {
+        stream: Option<cpal::Stream>,
+        levels: Arc<Mutex<Option<AudioLevels>>>,
+    }
+
+    struct BandSplitter {
+        low_alpha: f32,
+        high_alpha: f32,
+        low: f32,
+        below_high: f32,
+    }
+
+    impl BandSplitter {
+        fn new(sample_rate: f32) -> Self {
+            let alpha = |cutoff: f32| 1.0 - (-std::f32::consts::TAU * cutoff / sample_rate).exp();
+            BandSplitter {
+                low_alpha: alpha(LOW_CUTOFF),
+                high_alpha: alpha(HIGH_CUTOFF),
+                low: 0.0,
+                below_high: 0.0,
+            }
+        }
+
+        /// Returns the RMS of the whole signal and of each band.
+        fn process(&mut self, samples: impl Iterator<Item = f32>) -> Option<AudioLevels> {
+            let mut sums = [0.0f32; 4];
+            let mut count = 0;
+            for sample in samples {
+                self.low += (sample - self.low) * self.low_alpha;
+                self.below_high += (sample - self.below_high) * self.high_alpha;
+                let bands = [
+                    sample,
+                    self.low,
+                    self.below_high - self.low,
+                    sample - self.below_high,
+                ];
+                for (sum, band) in sums.iter_mut().zip(bands) {
+                    *sum += band * band;
+                }
+                count += 1;
+            }
+            if count == 0 {
+                return None;
+            }
+            let [loudness, bass, mids, treble] = sums.map(|sum| (sum / count as f32).sqrt());
+            Some(AudioLevels {
+                loudness,
+                bass,
+                mids,
+                treble,
+            })
+        }
+    }
+
+    impl Capture {
+        pub fn is_running(&self) -> bool {
+            self.stream.is_some()
+        }
+
+        pub fn start(&mut self) -> Result<(), String> {
+            let device = cpal::default_host()
+                .default_input_device()
+                .ok_or("no input device")?;
+            let config = device
+                .default_input_config()
+                .map_err(|error| error.to_string())?;
+            let channels = config.channels() as usize;
+            let mut splitter = BandSplitter::new(config.sample_rate().0 as f32);
+            let levels = self.levels.clone();
+            let on_error =
+                |error: cpal::StreamError| bevy::log::warn!("Microphone stream error: {}", error);
+
+            // Only the first channel of each frame is analyzed
+            let stream = match config.sample_format() {
+                cpal::SampleFormat::F32 => device.build_input_stream(
+                    &config.into(),
+                    move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                        let measured = splitter.process(data.iter().step_by(channels).copied());
+                        if let (Some(measured), Ok(mut levels)) = (measured, levels.lock()) {
+                            *levels = Some(measured);
+                        }
+                    },
+                    on_error,
+                    None,
+                ),
+                cpal::SampleFormat::I16 => device.build_input_stream(
+                    &config.into(),
+                    move |data: &[i16], _: &cpal::InputCallbackInfo| {
+                        let samples = data
+                            .iter()
+                            .step_by(channels)
+                            .map(|&sample| sample as f32 / i16::MAX as f32);
+                        let measured = splitter.process(samples);
+                        if let (Some(measured), Ok(mut levels)) = (measured, levels.lock()) {
+                            *levels = Some(measured);
+                        }
+                    },
+                    on_error,
+                    None,
+                ),
+                format => return Err(format!("unsupported sample format {:?}", format)),
+            }
+            .map_err(|error| error.to_string())?;
+            stream.play().map_err(|error| error.to_string())?;
+            self.stream = Some(stream);
+            Ok(())
+        }
+
+        pub fn stop(&mut self) {
+            self.stream = None;
+        }
+
+        /// Levels measured since the last call, if a buffer arrived.
+        pub fn levels(&mut self) -> Option<AudioLevels> {
+            self.levels.lock().ok()?.take()
+        }
+    }
+}
+
+/// Browser capture through getUserMedia and a Web Audio analyser node.
+#[cfg(target_arch = "wasm32")]
+mod capture {
+    use std::{cell::RefCell, rc::Rc};
+
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::{spawn_local, JsFuture};
+    use web_sys::{AnalyserNode, AudioContext, MediaStream, MediaStreamConstraints};
+
+    use super::{AudioLevels, HIGH_CUTOFF, LOW_CUTOFF};
+
+    const FFT_SIZE: u32 = 1024;
+
+    #[derive(Default)]
+    pub struct Capture {
+        /// Filled in once the user grants microphone access.
+        analyser: Rc<RefCell<Option<(AudioContext, AnalyserNode)>>>,
+        requested: bool,
+        bins: Vec<u8>,
+    }
+
+    impl Capture {
+        pub fn is_running(&self) -> bool {
+            self.requested
+        }
+
+        pub fn start(&mut self) -> Result<(), String> {
+            let media_devices = web_sys::window()
+                .ok_or("no window")?
+                .navigator()
+                .media_devices()
+                .map_err(|_| "media devices unavailable")?;
+            let constraints = MediaStreamConstraints::new();
+            constraints.set_audio(&true.into());
+            let request = media_devices
+                .get_user_media_with_constraints(&constraints)
+                .map_err(|_| "getUserMedia unavailable")?;
+            self.requested = true;
+
+            let analyser = self.analyser.clone();
+            spawn_local(async move {
+                let Ok(stream) = JsFuture::from(request).await else {
+                    bevy::log::warn!("Microphone access was denied");
+                    return;
+                };
+                let stream: MediaStream = stream.unchecked_into();
+                let Ok(context) = AudioContext::new() else {
+                    return;
+                };
+                let (Ok(source), Ok(node)) = (
+                    context.create_media_stream_source(&stream),
+                    context.create_analyser(),
+                ) else {
+                    return;
+                };
+                node.set_fft_size(FFT_SIZE);
+                if source.connect_with_audio_node(&node).is_ok() {
+                    *analyser.borrow_mut() = Some((context, node));
+                }
+            });
+            Ok(())
+        }
+
+        pub fn stop(&mut self) {
+            if let Some((context, _)) = self.analyser.borrow_mut().take() {
+                let _ = context.close();
+            }
+            self.requested = false;
+        }
+
+        /// Average magnitude of the analyser's frequency bins per band.
+        pub fn levels(&mut self) -> Option<AudioLevels> {
+            let analyser = self.analyser.borrow();
+            let (context, node) = analyser.as_ref()?;
+            self.bins.resize(node.frequency_bin_count() as usize, 0);
+            node.get_byte_frequency_data(&mut self.bins);
+
+            let bin_width = context.sample_rate() / FFT_SIZE as f32;
+            let mut sums = [0.0f32; 4];
+            let mut counts = [0usize; 4];
+            for (index, &magnitude) in self.bins.iter().enumerate() {
+                let frequency = index as f32 * bin_width;
+                let band = if frequency < LOW_CUTOFF {
+                    1
+                } else if frequency < HIGH_CUTOFF {
+                    2
+                } else {
+                    3
+                };
+                for slot in [0, band] {
+                    sums[slot] += magnitude as f32 / 255.0;
+                    counts[slot] += 1;
+                }
+            }
+            let [loudness, bass, mids, treble] =
+                std::array::from_fn(|slot| sums[slot] / counts[slot].max(1) as f32);
+            Some(AudioLevels {
+                loudness,
+                bass,
+                mids,
+                treble,
+            })
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use midir::{MidiInput, MidiInputConnection};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::{settings::Settings, ui_enabled, ParticleSystem};
+
+const CLIENT_NAME: &str = "particle-life";
+const CONTROL_CHANGE: u8 = 0xB0;
+const MIN_ROW_SCALE: f32 = 0.0;
+const MAX_ROW_SCALE: f32 = 2.0;
+
+pub struct MidiPlugin;
+
+impl Plugin for MidiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MidiState>()
+            .init_non_send_resource::<MidiConnection>()
+            .add_systems(
+                Update,
+                (receive_midi, midi_ui_system.run_if(ui_enabled)).chain(),
+            );
+    }
+}
+
+/// Simulation parameter a MIDI control change can drive.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum MidiTarget {
+    Speed,
+    Beta,
+    Gamma,
+    Radius,
+    /// Multiplies one species' row of the behavior matrix by 0..2.
+    RowScale(usize),
+}
+
+impl MidiTarget {
+    fn label(self) -> String {
+        match self {
+            MidiTarget::Speed => "Speed".to_string(),
+            MidiTarget::Beta => "Beta".to_string(),
+            MidiTarget::Gamma => "Gamma".to_string(),
+            MidiTarget::Radius => "Radius".to_string(),
+            MidiTarget::RowScale(row) => format!("Row {} scale", row),
+        }
+    }
+}
+
+/// A learned CC knob, persisted with the other settings.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MidiBinding {
+    pub channel: u8,
+    pub controller: u8,
+    pub target: MidiTarget,
+}
+
+#[derive(Resource, Default)]
+pub struct MidiState {
+    pub ports: Vec<String>,
+    pub connected: Option<String>,
+    /// The next control change received is bound to this target.
+    pub learning: Option<MidiTarget>,
+    /// Channel, controller and value of the last control change.
+    pub last_message: Option<(u8, u8, u8)>,
+    /// Rows as they were before row scaling started, so scaling is not compounded.
+    row_bases: HashMap<usize, Vec<f32>>,
+    learn_row: usize,
+    request: Option<MidiRequest>,
+    status: Option<String>,
+}
+
+enum MidiRequest {
+    Refresh,
+    Connect(usize),
+    Disconnect,
+}
+
+/// The open input port. midir connections are not `Sync` on every platform,
+/// so this lives on the main thread.
+#[derive(Default)]
+struct MidiConnection {
+    connection: Option<MidiInputConnection<()>>,
+    messages: Arc<Mutex<Vec<[u8; 3]>>>,
+}
+
+fn list_ports() -> Result<Vec<String>, String> {
+    let input = MidiInput::new(CLIENT_NAME).map_err(|error| error.to_string())?;
+    Ok(input
+        .ports()
+        .iter()
+        .map(|port| {
+            input
+                .port_name(port)
+                .unwrap_or_else(|_| "unknown".to_string())
+        })
+        .collect())
+}
+
+fn connect(
+    index: usize,
+    messages: Arc<Mutex<Vec<[u8; 3]>>>,
+) -> Result<MidiInputConnection<()>, String> {
+    let input = MidiInput::new(CLIENT_NAME).map_err(|error| error.to_string())?;
+    let ports = input.ports();
+    let port = ports.get(index).ok_or("port disappeared")?;
+    input
+        .connect(
+            port,
+            "particle-life-input",
+            move |_, message, _| {
+                if let ([status, controller, value], Ok(mut messages)) = (message, messages.lock())
+                {
+                    if status & 0xF0 == CONTROL_CHANGE {
+                        messages.push([*status, *controller, *value]);
+                    }
+                }
+            },
+            (),
+        )
+        .map_err(|error| error.to_string())
+}
+
+fn receive_midi(
+    mut state: ResMut<MidiState>,
+    mut connection: NonSendMut<MidiConnection>,
+    mut settings: ResMut<Settings>,
+    mut particle_system: ResMut<ParticleSystem>,
+) {
+    match state.request.take() {
+        Some(MidiRequest::Refresh) => match list_ports() {
+            Ok(ports) => state.ports = ports,
+            Err(error) => state.status = Some(format!("MIDI unavailable: {}", error)),
+        },
+        Some(MidiRequest::Connect(index)) => {
+            connection.connection = None;
+            match connect(index, connection.messages.clone()) {
+                Ok(opened) => {
+                    connection.connection = Some(opened);
+                    state.connected = state.ports.get(index).cloned();
+                    state.status = None;
+                }
+                Err(error) => state.status = Some(format!("Could not connect: {}", error)),
+            }
+        }
+        Some(MidiRequest::Disconnect) => {
+            connection.connection = None;
+            state.connected = None;
+        }
+        None => {}
+    }
+
+    let messages = match connection.messages.lock() {
+        Ok(mut messages) if !messages.is_empty() => std::mem::take(&mut *messages),
+        _ => return,
+    };
+    for [status, controller, value] in messages {
+        let channel = status & 0x0F;
+        state.last_message = Some((channel, controller, value));
+
+        if let Some(target) = state.learning.take() {
+            let bindings = &mut settings.midi_bindings;
+            bindings.retain(|binding| {
+                binding.target != target
+                    && (binding.channel, binding.controller) != (channel, controller)
+            });
+            bindings.push(MidiBinding {
+                channel,
+                controller,
+                target,
+            });
+            state.row_bases.clear();
+        }
+
+        let normalized = value as f32 / 127.0;
+        for binding in settings.midi_bindings.iter() {
+            if (binding.channel, binding.controller) != (channel, controller) {
+                continue;
+            }
+            match binding.target {
+                MidiTarget::Speed => particle_system.force_strength = normalized * 3200.0,
+                MidiTarget::Beta => particle_system.beta = normalized,
+                MidiTarget::Gamma => particle_system.gamma = normalized,
+                MidiTarget::Radius => particle_system.attraction_radius = 10.0 + normalized * 190.0,
+                MidiTarget::RowScale(row) => {
+                    let Some(current) = particle_system.behavior_matrix.get_mut(row) else {
+                        continue;
+                    };
+                    let base = state
+                        .row_bases
+                        .entry(row)
+                        .or_insert_with(|| current.clone());
+                    let scale = MIN_ROW_SCALE + normalized * (MAX_ROW_SCALE - MIN_ROW_SCALE);
+                    for (value, base) in current.iter_mut().zip(base.iter()) {
+                        *value = (base * scale).clamp(-1.0, 1.0);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn midi_ui_system(
+    mut contexts: EguiContexts,
+    mut state: ResMut<MidiState>,
+    mut settings: ResMut<Settings>,
+    particle_system: Res<ParticleSystem>,
+) {
+    egui::Window::new("MIDI")
+        .default_pos([620.0, 430.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Refresh Ports").clicked() {
+                    state.request = Some(MidiRequest::Refresh);
+                }
+                if state.connected.is_some() && ui.button("Disconnect").clicked() {
+                    state.request = Some(MidiRequest::Disconnect);
+                }
+            });
+            let mut connect = None;
+            for (index, port) in state.ports.iter().enumerate() {
+                let connected = state.connected.as_ref() == Some(port);
+                if ui.selectable_label(connected, port).clicked() && !connected {
+                    connect = Some(index);
+                }
+            }
+            if let Some(index) = connect {
+                state.request = Some(MidiRequest::Connect(index));
+            }
+            if let Some((channel, controller, value)) = state.last_message {
+                ui.label(format!(
+                    "Last CC: channel {} controller {} = {}",
+                    channel + 1,
+                    controller,
+                    value
+                ));
+            }
+
+            ui.separator();
+            ui.label("Learn: click a parameter, then move a knob");
+            let species = particle_system.colors.len().max(1);
+            ui.horizontal(|ui| {
+                for target in [
+                    MidiTarget::Speed,
+                    MidiTarget::Beta,
+                    MidiTarget::Gamma,
+                    MidiTarget::Radius,
+                ] {
+                    let learning = state.learning == Some(target);
+                    if ui.selectable_label(learning, target.label()).clicked() {
+                        state.learning = if learning { None } else { Some(target) };
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut state.learn_row).range(0..=species - 1));
+                let target = MidiTarget::RowScale(state.learn_row);
+                let learning = state.learning == Some(target);
+                if ui.selectable_label(learning, "Row scale").clicked() {
+                    state.learning = if learning { None } else { Some(target) };
+                }
+            });
+
+            ui.separator();
+            let mut removed = None;
+            for (index, binding) in settings.midi_bindings.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "Ch {} CC {} → {}",
+                        binding.channel + 1,
+                        binding.controller,
+                        binding.target.label()
+                    ));
+                    if ui.small_button("✖").clicked() {
+                        removed = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = removed {
+                settings.midi_bindings.remove(index);
+                state.row_bases.clear();
+            }
+            if let Some(status) = &state.status {
+                ui.label(status);
+            }
+        });
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{lod, settings::Settings, spawn, ui_enabled, CameraMotion, ControlledCamera, Particle};
+
+const MAP_WIDTH: f32 = 240.0;
+/// Tallest the map gets for very narrow extents, in points.
+const MAX_MAP_HEIGHT: f32 = 240.0;
+/// Density cells across the map.
+const COLUMNS: usize = 48;
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Minimap>().add_systems(
+            Update,
+            (toggle_minimap, minimap_system.run_if(ui_enabled)).chain(),
+        );
+    }
+}
+
+/// Corner overview of where the particles are, with the camera's view
+/// outlined. Covers the spawn area, every particle and the view.
+#[derive(Resource)]
+struct Minimap {
+    visible: bool,
+    counts: Vec<u32>,
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Minimap {
+            visible: true,
+            counts: Vec::new(),
+        }
+    }
+}
+
+fn toggle_minimap(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    mut minimap: ResMut<Minimap>,
+) {
+    if keyboard.just_pressed(settings.keys.toggle_minimap) {
+        minimap.visible = !minimap.visible;
+    }
+}
+
+fn minimap_system(
+    mut contexts: EguiContexts,
+    mut minimap: ResMut<Minimap>,
+    mut motion: ResMut<CameraMotion>,
+    camera: Query<(&Transform, &OrthographicProjection), With<ControlledCamera>>,
+    particles: Query<&Transform, With<Particle>>,
+) {
+    if !minimap.visible {
+        return;
+    }
+    let Ok((camera_transform, projection)) = camera.get_single() else {
+        return;
+    };
+    let view = lod::camera_view(camera_transform, projection);
+    let extent = particles
+        .iter()
+        .fold(spawn::world_bounds().union(view), |extent, transform| {
+            extent.union_point(transform.translation.truncate())
+        });
+    let map_height = (MAP_WIDTH * extent.height() / extent.width()).min(MAX_MAP_HEIGHT);
+    let rows = ((COLUMNS as f32 * map_height / MAP_WIDTH).ceil() as usize).max(1);
+
+    let counts = &mut minimap.counts;
+    counts.clear();
+    counts.resize(COLUMNS * rows, 0);
+    let cell = extent.size() / Vec2::new(COLUMNS as f32, rows as f32);
+    for transform in &particles {
+        let offset = (transform.translation.truncate() - extent.min) / cell;
+        let column = (offset.x as usize).min(COLUMNS - 1);
+        let row = (offset.y as usize).min(rows - 1);
+        counts[row * COLUMNS + column] += 1;
+    }
+    let max = counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+
+    egui::Area::new(egui::Id::new("minimap"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+        .show(contexts.ctx_mut(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                let (rect, response) = ui.allocate_exact_size(
+                    egui::vec2(MAP_WIDTH, map_height),
+                    egui::Sense::click_and_drag(),
+                );
+                let to_map = |world: Vec2| {
+                    let t = (world - extent.min) / extent.size();
+                    egui::pos2(
+                        rect.min.x + t.x * rect.width(),
+                        rect.max.y - t.y * rect.height(),
+                    )
+                };
+                let painter = ui.painter_at(rect);
+                painter.rect_filled(rect, 0.0, egui::Color32::from_gray(10));
+                let cell_size =
+                    egui::vec2(rect.width() / COLUMNS as f32, rect.height() / rows as f32);
+                for (index, &count) in counts.iter().enumerate() {
+                    if count == 0 {
+                        continue;
+                    }
+                    let (row, column) = (index / COLUMNS, index % COLUMNS);
+                    let min = egui::pos2(
+                        rect.min.x + column as f32 * cell_size.x,
+                        rect.max.y - (row + 1) as f32 * cell_size.y,
+                    );
+                    // Square root so sparse regions still show up
+                    let level = (count as f32 / max).sqrt();
+                    painter.rect_filled(
+                        egui::Rect::from_min_size(min, cell_size),
+                        0.0,
+                        egui::Color32::from_white_alpha((level * 255.0) as u8),
+                    );
+                }
+                painter.rect_stroke(
+                    egui::Rect::from_two_pos(to_map(view.min), to_map(view.max)),
+                    0.0,
+                    egui::Stroke::new(1.5, egui::Color32::from_rgb(255, 200, 60)),
+                    egui::StrokeKind::Inside,
+                );
+
+                // Clicking or dragging moves the camera there
+                if let Some(pointer) = response.interact_pointer_pos() {
+                    let t = Vec2::new(
+                        (pointer.x - rect.min.x) / rect.width(),
+                        (rect.max.y - pointer.y) / rect.height(),
+                    );
+                    let target = extent.min + t * extent.size();
+                    motion.translation = target - camera_transform.translation.truncate();
+                }
+            });
+        });
+}
@@ -0,0 +1,99 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+
+use crate::{egui_color, ui_enabled, ParticleSystem};
+
+/// Drag is specified per frame at this rate so it does not depend on the
+/// actual frame rate.
+const REFERENCE_RATE: f32 = 60.0;
+
+pub struct MotionPlugin;
+
+impl Plugin for MotionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, motion_ui_system.run_if(ui_enabled));
+    }
+}
+
+/// How one species' velocity follows the forces acting on it.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpeciesMotion {
+    /// Fraction of the velocity replaced by the force-driven velocity every
+    /// 1/60 s. At 1 particles have no momentum, the classic update.
+    pub drag: f32,
+    /// Speed cap in world units per second, 0 means unlimited.
+    pub max_speed: f32,
+}
+
+impl Default for SpeciesMotion {
+    fn default() -> Self {
+        SpeciesMotion {
+            drag: 1.0,
+            max_speed: 0.0,
+        }
+    }
+}
+
+impl SpeciesMotion {
+    /// Moves `previous` toward the force-driven `target` velocity over
+    /// `elapsed` seconds, then applies the speed cap.
+    pub fn integrate(&self, previous: Vec2, target: Vec2, elapsed: f32) -> Vec2 {
+        // libm rather than the platform's powf, which differs between
+        // targets in the last bits
+        let kept = libm::powf((1.0 - self.drag).clamp(0.0, 1.0), elapsed * REFERENCE_RATE);
+        let velocity = previous * kept + target * (1.0 - kept);
+        if self.max_speed > 0.0 {
+            velocity.clamp_length_max(self.max_speed)
+        } else {
+            velocity
+        }
+    }
+}
+
+fn motion_ui_system(mut contexts: EguiContexts, mut particle_system: ResMut<ParticleSystem>) {
+    egui::Window::new("Species Motion")
+        .default_pos([400.0, 360.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Drag below 1 keeps momentum; the speed cap stops flung particles.");
+            ui.add_space(5.0);
+
+            let particle_system = &mut *particle_system;
+            let n = particle_system.colors.len();
+            particle_system
+                .species_motion
+                .resize(n, SpeciesMotion::default());
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("species_motion_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("");
+                        ui.label("Drag");
+                        ui.label("Max speed");
+                        ui.end_row();
+
+                        for (color, motion) in particle_system
+                            .colors
+                            .iter()
+                            .zip(particle_system.species_motion.iter_mut())
+                        {
+                            let (rect, _) = ui
+                                .allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                            ui.painter().rect_filled(rect, 2.0, egui_color(*color));
+                            ui.add(
+                                egui::DragValue::new(&mut motion.drag)
+                                    .range(0.01..=1.0)
+                                    .speed(0.005),
+                            );
+                            ui.add(
+                                egui::DragValue::new(&mut motion.max_speed)
+                                    .range(0.0..=100_000.0)
+                                    .speed(10.0),
+                            );
+                            ui.end_row();
+                        }
+                    });
+            });
+        });
+}
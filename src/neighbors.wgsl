@@ -0,0 +1,36 @@
+// Lists, for every particle, the others closer than the search radius. Each
+// particle gets `max_neighbors` slots; `counts` holds the full number found,
+// so the CPU can tell when a list was cut short.
+
+struct Params {
+    count: u32,
+    max_neighbors: u32,
+    radius_squared: f32,
+    _padding: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> positions: array<vec2<f32>>;
+@group(0) @binding(2) var<storage, read_write> counts: array<u32>;
+@group(0) @binding(3) var<storage, read_write> neighbors: array<u32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= params.count {
+        return;
+    }
+    let pos = positions[index];
+    let base = index * params.max_neighbors;
+    var found = 0u;
+    for (var other = 0u; other < params.count; other++) {
+        let offset = positions[other] - pos;
+        if other != index && dot(offset, offset) < params.radius_squared {
+            if found < params.max_neighbors {
+                neighbors[base + found] = other;
+            }
+            found++;
+        }
+    }
+    counts[index] = found;
+}
@@ -0,0 +1,631 @@
+use bevy::prelude::*;
+
+/// Present while this instance shows a world simulated by a server:
+/// `update_particles` then leaves positions to the received frames.
+#[derive(Resource)]
+pub struct RemoteWorld;
+
+pub fn is_remote(remote: Option<Res<RemoteWorld>>) -> bool {
+    remote.is_some()
+}
+
+/// Whether this instance should serve or join a shared world. Without the
+/// `network` feature the request only produces a warning.
+pub fn requested() -> bool {
+    let requested = NetworkRole::from_environment().is_some();
+    if requested && !cfg!(feature = "network") {
+        eprintln!("--serve and --connect need a build with the `network` feature; running alone");
+        return false;
+    }
+    requested
+}
+
+/// Part this instance plays in a shared world.
+#[derive(Clone, PartialEq, Debug)]
+pub enum NetworkRole {
+    /// `--serve <address>`: simulate and broadcast the world to clients.
+    Server(String),
+    /// `--connect <url>`, or `?server=<url>` on the web page: show the
+    /// server's world and send it spawn and push events.
+    Client(String),
+}
+
+impl NetworkRole {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_environment() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        let value = |flag: &str| {
+            let index = args.iter().position(|arg| arg == flag)?;
+            args.get(index + 1).cloned()
+        };
+        value("--serve")
+            .map(NetworkRole::Server)
+            .or_else(|| value("--connect").map(NetworkRole::Client))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn from_environment() -> Option<Self> {
+        let search = web_sys::window()?.location().search().ok()?;
+        let url = search
+            .trim_start_matches('?')
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("server="))?;
+        let url = js_sys::decode_uri_component(url).ok()?.as_string()?;
+        Some(NetworkRole::Client(url))
+    }
+}
+
+#[cfg(feature = "network")]
+pub use session::NetworkPlugin;
+
+#[cfg(feature = "network")]
+mod session {
+    use bevy::prelude::*;
+    use bevy_egui::{egui, EguiContexts};
+    use serde::{Deserialize, Serialize};
+
+    use super::{transport, NetworkRole, RemoteWorld};
+    use crate::{
+        pool::{self, ParticleSpawner},
+        snapshot::ParticleSnapshot,
+        spawn::{self, SpawnRequest},
+        ui_enabled, update_particles, MainCamera, Particle, ParticleSystem,
+    };
+
+    const FRAME_VERSION: u8 = 1;
+    /// Frames per second sent to clients.
+    const BROADCAST_RATE: f32 = 15.0;
+    /// Limits on what a single client event may do to the shared world.
+    const MAX_CLIENT_BURST: usize = 100;
+    const MAX_CLIENT_RADIUS: f32 = 300.0;
+
+    /// Runs this instance as the server or a client of a shared world, as
+    /// asked for by [`NetworkRole::from_environment`].
+    pub struct NetworkPlugin;
+
+    impl Plugin for NetworkPlugin {
+        fn build(&self, app: &mut App) {
+            let Some(role) = NetworkRole::from_environment() else {
+                return;
+            };
+            let mut status = NetworkStatus::default();
+            match role {
+                #[cfg(not(target_arch = "wasm32"))]
+                NetworkRole::Server(address) => match transport::Server::bind(&address) {
+                    Ok(server) => {
+                        info!("Serving the world on ws://{}", address);
+                        status.message = format!("Serving on ws://{}", address);
+                        app.insert_non_send_resource(server)
+                            .add_systems(Update, serve.after(update_particles));
+                    }
+                    Err(error) => {
+                        error!("Could not serve on {}: {}", address, error);
+                        status.message = format!("Could not serve on {}: {}", address, error);
+                    }
+                },
+                #[cfg(target_arch = "wasm32")]
+                NetworkRole::Server(_) => {}
+                NetworkRole::Client(url) => match transport::Client::connect(&url) {
+                    Ok(client) => {
+                        info!("Joining the world at {}", url);
+                        status.message = format!("Connected to {}", url);
+                        app.insert_non_send_resource(client)
+                            .insert_resource(RemoteWorld)
+                            .add_systems(
+                                Update,
+                                (
+                                    forward_input
+                                        .after(spawn::spawn_at_cursor)
+                                        .run_if(ui_enabled),
+                                    mirror,
+                                )
+                                    .chain(),
+                            );
+                    }
+                    Err(error) => {
+                        error!("Could not connect to {}: {}", url, error);
+                        status.message = format!("Could not connect to {}: {}", url, error);
+                    }
+                },
+            }
+            app.insert_resource(status)
+                .add_systems(Update, network_ui_system.run_if(ui_enabled));
+        }
+    }
+
+    /// Sent by clients, as JSON text messages.
+    #[derive(Clone, Copy, Serialize, Deserialize)]
+    pub enum ClientEvent {
+        /// Particles of random species within `radius` of `position`.
+        Spawn {
+            position: Vec2,
+            radius: f32,
+            count: usize,
+        },
+        /// Moves particles within `radius` of `position` away from it by up
+        /// to `strength` world units; negative strengths pull them in.
+        Push {
+            position: Vec2,
+            radius: f32,
+            strength: f32,
+        },
+    }
+
+    /// One broadcast snapshot: the palette, then every particle in the shared
+    /// [`ParticleSnapshot`] format.
+    struct WorldFrame {
+        colors: Vec<[u8; 4]>,
+        particles: Vec<(Vec2, u16)>,
+    }
+
+    impl WorldFrame {
+        fn capture<'a>(
+            particle_system: &ParticleSystem,
+            particles: impl Iterator<Item = (&'a Transform, &'a Particle)>,
+        ) -> Self {
+            WorldFrame {
+                colors: particle_system
+                    .colors
+                    .iter()
+                    .map(|color| color.to_srgba().to_u8_array())
+                    .collect(),
+                particles: particles
+                    .map(|(transform, particle)| {
+                        (transform.translation.truncate(), particle.color_id as u16)
+                    })
+                    .collect(),
+            }
+        }
+
+        fn encode(&self) -> Vec<u8> {
+            let mut bytes = vec![FRAME_VERSION];
+            bytes.extend_from_slice(&(self.colors.len() as u16).to_le_bytes());
+            for color in &self.colors {
+                bytes.extend_from_slice(color);
+            }
+            let mut snapshot = ParticleSnapshot::default();
+            for &(pos, color_id) in &self.particles {
+                snapshot.push(pos, color_id as usize, None);
+            }
+            bytes.extend(snapshot.encode());
+            bytes
+        }
+
+        fn decode(bytes: &[u8]) -> Option<Self> {
+            let (&version, rest) = bytes.split_first()?;
+            if version != FRAME_VERSION {
+                return None;
+            }
+            let (count, rest) = rest.split_at_checked(2)?;
+            let color_count = u16::from_le_bytes(count.try_into().ok()?) as usize;
+            let (colors, rest) = rest.split_at_checked(color_count * 4)?;
+            let snapshot = ParticleSnapshot::decode(rest).ok()?;
+            Some(WorldFrame {
+                colors: colors
+                    .chunks_exact(4)
+                    .map(|color| color.try_into().unwrap())
+                    .collect(),
+                particles: snapshot
+                    .positions
+                    .into_iter()
+                    .zip(snapshot.species)
+                    .collect(),
+            })
+        }
+    }
+
+    #[derive(Resource)]
+    struct NetworkStatus {
+        message: String,
+        clients: usize,
+        frames: u64,
+        frame_bytes: usize,
+        push_radius: f32,
+        push_strength: f32,
+        /// Client events waiting to be sent.
+        outgoing: Vec<ClientEvent>,
+    }
+
+    impl Default for NetworkStatus {
+        fn default() -> Self {
+            NetworkStatus {
+                message: String::new(),
+                clients: 0,
+                frames: 0,
+                frame_bytes: 0,
+                push_radius: 100.0,
+                push_strength: 40.0,
+                outgoing: Vec::new(),
+            }
+        }
+    }
+
+    fn push(
+        particles: &mut Query<(&mut Transform, &Particle)>,
+        center: Vec2,
+        radius: f32,
+        strength: f32,
+    ) {
+        let radius = radius.clamp(1.0, MAX_CLIENT_RADIUS);
+        for (mut transform, _) in particles.iter_mut() {
+            let offset = transform.translation.truncate() - center;
+            let distance = offset.length();
+            if distance < radius {
+                let falloff = 1.0 - distance / radius;
+                transform.translation +=
+                    (offset.normalize_or_zero() * strength * falloff).extend(0.0);
+            }
+        }
+    }
+
+    /// Applies client events and broadcasts the world to every client.
+    fn serve(
+        time: Res<Time>,
+        mut server: NonSendMut<transport::Server>,
+        mut status: ResMut<NetworkStatus>,
+        mut since_broadcast: Local<f32>,
+        particle_system: Res<ParticleSystem>,
+        mut spawn_requests: EventWriter<SpawnRequest>,
+        mut particles: Query<(&mut Transform, &Particle)>,
+    ) {
+        for event in server.receive() {
+            match event {
+                ClientEvent::Spawn {
+                    position,
+                    radius,
+                    count,
+                } => {
+                    spawn_requests.send(SpawnRequest::Burst {
+                        position,
+                        radius: radius.clamp(0.0, MAX_CLIENT_RADIUS),
+                        count: count.min(MAX_CLIENT_BURST),
+                        species: None,
+                    });
+                }
+                ClientEvent::Push {
+                    position,
+                    radius,
+                    strength,
+                } => push(&mut particles, position, radius, strength),
+            }
+        }
+
+        status.clients = server.client_count();
+        *since_broadcast += time.delta_secs();
+        if status.clients == 0 || *since_broadcast < 1.0 / BROADCAST_RATE {
+            return;
+        }
+        *since_broadcast = 0.0;
+        let frame = WorldFrame::capture(&particle_system, particles.iter()).encode();
+        status.frame_bytes = frame.len();
+        status.frames += 1;
+        server.broadcast(&frame);
+    }
+
+    /// Sends the bursts `spawn_at_cursor` asked for to the server, and a push
+    /// on middle click.
+    fn forward_input(
+        mut contexts: EguiContexts,
+        mut requests: EventReader<SpawnRequest>,
+        buttons: Res<ButtonInput<MouseButton>>,
+        windows: Query<&Window>,
+        camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+        mut status: ResMut<NetworkStatus>,
+    ) {
+        for &request in requests.read() {
+            if let SpawnRequest::Burst {
+                position,
+                radius,
+                count,
+                ..
+            } = request
+            {
+                status.outgoing.push(ClientEvent::Spawn {
+                    position,
+                    radius,
+                    count,
+                });
+            }
+        }
+        if !buttons.just_pressed(MouseButton::Middle) || contexts.ctx_mut().wants_pointer_input() {
+            return;
+        }
+        let Some(cursor) = windows.iter().find_map(Window::cursor_position) else {
+            return;
+        };
+        let Ok((camera, camera_transform)) = camera.get_single() else {
+            return;
+        };
+        if let Ok(position) = camera.viewport_to_world_2d(camera_transform, cursor) {
+            let (radius, strength) = (status.push_radius, status.push_strength);
+            status.outgoing.push(ClientEvent::Push {
+                position,
+                radius,
+                strength,
+            });
+        }
+    }
+
+    /// Sends pending events and makes the local particles match the newest
+    /// frame from the server.
+    fn mirror(
+        mut client: NonSendMut<transport::Client>,
+        mut status: ResMut<NetworkStatus>,
+        mut spawner: ParticleSpawner,
+        mut particle_system: ResMut<ParticleSystem>,
+        mut particles: Query<(Entity, &mut Transform, &mut Particle)>,
+    ) {
+        let outgoing = std::mem::take(&mut status.outgoing);
+        client.send(&outgoing);
+        if let Some(error) = client.error() {
+            status.message = error;
+        }
+        let Some(bytes) = client.latest_frame() else {
+            return;
+        };
+        let Some(frame) = WorldFrame::decode(&bytes) else {
+            warn!("Ignoring a malformed world frame of {} bytes", bytes.len());
+            return;
+        };
+        status.frame_bytes = bytes.len();
+        status.frames += 1;
+        if frame.colors.is_empty() {
+            return;
+        }
+
+        let colors: Vec<Color> = frame
+            .colors
+            .iter()
+            .map(|&[r, g, b, a]| Color::srgba_u8(r, g, b, a))
+            .collect();
+        if particle_system.colors != colors {
+            let resized = particle_system.colors.len() != colors.len();
+            particle_system.colors = colors;
+            if resized {
+                particle_system.regenerate_matrix();
+            }
+        }
+
+        let received = frame
+            .particles
+            .iter()
+            .map(|&(pos, color_id)| (pos, color_id as usize));
+        pool::show_particles(&mut spawner, &particle_system, &mut particles, received);
+    }
+
+    fn network_ui_system(
+        mut contexts: EguiContexts,
+        remote: Option<Res<RemoteWorld>>,
+        mut status: ResMut<NetworkStatus>,
+    ) {
+        egui::Window::new("Network")
+            .default_pos([400.0, 1060.0])
+            .default_open(false)
+            .show(contexts.ctx_mut(), |ui| {
+                ui.label(&status.message);
+                if remote.is_some() {
+                    ui.label("Clicks spawn particles in the shared world, middle click pushes.");
+                    ui.add(
+                        egui::Slider::new(&mut status.push_radius, 10.0..=MAX_CLIENT_RADIUS)
+                            .text("push radius"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut status.push_strength, -200.0..=200.0)
+                            .text("push strength"),
+                    )
+                    .on_hover_text("Negative pulls particles toward the cursor");
+                } else {
+                    ui.label(format!("Clients: {}", status.clients));
+                }
+                ui.label(format!(
+                    "Frames: {}, last {:.1} KB",
+                    status.frames,
+                    status.frame_bytes as f32 / 1024.0
+                ));
+            });
+    }
+}
+
+/// Native WebSocket server and client on top of tungstenite, polled without
+/// blocking once connected.
+#[cfg(all(feature = "network", not(target_arch = "wasm32")))]
+mod transport {
+    use bevy::log::{info, warn};
+    use std::{
+        io,
+        net::{TcpListener, TcpStream},
+        sync::{Arc, Mutex},
+        thread,
+    };
+    use tungstenite::{stream::MaybeTlsStream, Message, WebSocket};
+
+    use super::session::ClientEvent;
+
+    fn would_block(error: &tungstenite::Error) -> bool {
+        matches!(error, tungstenite::Error::Io(error) if error.kind() == io::ErrorKind::WouldBlock)
+    }
+
+    /// Accepts clients on a background thread, so slow handshakes never stall
+    /// the simulation.
+    pub struct Server {
+        clients: Arc<Mutex<Vec<WebSocket<TcpStream>>>>,
+    }
+
+    impl Server {
+        pub fn bind(address: &str) -> io::Result<Self> {
+            let listener = TcpListener::bind(address)?;
+            let clients = Arc::new(Mutex::new(Vec::new()));
+            let accepted = Arc::clone(&clients);
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let peer = stream.peer_addr().ok();
+                    match tungstenite::accept(stream) {
+                        Ok(socket) => {
+                            if socket.get_ref().set_nonblocking(true).is_ok() {
+                                info!("Client {:?} joined", peer);
+                                accepted.lock().unwrap().push(socket);
+                            }
+                        }
+                        Err(error) => warn!("Handshake with {:?} failed: {}", peer, error),
+                    }
+                }
+            });
+            Ok(Server { clients })
+        }
+
+        pub fn client_count(&self) -> usize {
+            self.clients.lock().unwrap().len()
+        }
+
+        /// Events clients sent since the last call. Clients whose connection
+        /// broke are dropped.
+        pub fn receive(&mut self) -> Vec<ClientEvent> {
+            let mut events = Vec::new();
+            self.clients.lock().unwrap().retain_mut(|socket| loop {
+                match socket.read() {
+                    Ok(Message::Text(text)) => match serde_json::from_str(text.as_str()) {
+                        Ok(event) => events.push(event),
+                        Err(error) => warn!("Ignoring a client message: {}", error),
+                    },
+                    Ok(_) => {}
+                    Err(error) => break would_block(&error),
+                }
+            });
+            events
+        }
+
+        pub fn broadcast(&mut self, frame: &[u8]) {
+            self.clients.lock().unwrap().retain_mut(|socket| {
+                match socket.send(Message::binary(frame.to_vec())) {
+                    Ok(()) => true,
+                    // Queued, and flushed along with the next frame
+                    Err(error) => would_block(&error),
+                }
+            });
+        }
+    }
+
+    pub struct Client {
+        socket: WebSocket<MaybeTlsStream<TcpStream>>,
+        error: Option<String>,
+    }
+
+    impl Client {
+        pub fn connect(url: &str) -> Result<Self, String> {
+            let (socket, _) = tungstenite::connect(url).map_err(|error| error.to_string())?;
+            if let MaybeTlsStream::Plain(stream) = socket.get_ref() {
+                stream
+                    .set_nonblocking(true)
+                    .map_err(|error| error.to_string())?;
+            }
+            Ok(Client {
+                socket,
+                error: None,
+            })
+        }
+
+        pub fn send(&mut self, events: &[ClientEvent]) {
+            if self.error.is_some() {
+                return;
+            }
+            for event in events {
+                let Ok(text) = serde_json::to_string(event) else {
+                    continue;
+                };
+                if let Err(error) = self.socket.send(Message::text(text)) {
+                    if !would_block(&error) {
+                        self.error = Some(format!("Connection lost: {}", error));
+                        return;
+                    }
+                }
+            }
+        }
+
+        /// The newest frame received since the last call; older ones are
+        /// skipped.
+        pub fn latest_frame(&mut self) -> Option<Vec<u8>> {
+            let mut latest = None;
+            while self.error.is_none() {
+                match self.socket.read() {
+                    Ok(Message::Binary(bytes)) => latest = Some(bytes.to_vec()),
+                    Ok(_) => {}
+                    Err(error) if would_block(&error) => break,
+                    Err(error) => self.error = Some(format!("Connection lost: {}", error)),
+                }
+            }
+            latest
+        }
+
+        pub fn error(&self) -> Option<String> {
+            self.error.clone()
+        }
+    }
+}
+
+/// Browser client on the page's WebSocket.
+#[cfg(all(feature = "network", target_arch = "wasm32"))]
+mod transport {
+    use std::{cell::RefCell, rc::Rc};
+    use wasm_bindgen::{closure::Closure, JsCast};
+    use web_sys::{BinaryType, MessageEvent, WebSocket};
+
+    use super::session::ClientEvent;
+
+    pub struct Client {
+        socket: WebSocket,
+        latest: Rc<RefCell<Option<Vec<u8>>>>,
+        error: Rc<RefCell<Option<String>>>,
+    }
+
+    impl Client {
+        pub fn connect(url: &str) -> Result<Self, String> {
+            let socket = WebSocket::new(url).map_err(|error| format!("{:?}", error))?;
+            socket.set_binary_type(BinaryType::Arraybuffer);
+
+            let latest = Rc::new(RefCell::new(None));
+            let received = Rc::clone(&latest);
+            let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                if let Ok(buffer) = event.data().dyn_into::<js_sys::ArrayBuffer>() {
+                    *received.borrow_mut() = Some(js_sys::Uint8Array::new(&buffer).to_vec());
+                }
+            });
+            socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+            on_message.forget();
+
+            let error = Rc::new(RefCell::new(None));
+            let closed = Rc::clone(&error);
+            let on_close = Closure::<dyn FnMut()>::new(move || {
+                *closed.borrow_mut() = Some("Connection closed".to_string());
+            });
+            socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+            on_close.forget();
+
+            Ok(Client {
+                socket,
+                latest,
+                error,
+            })
+        }
+
+        pub fn send(&mut self, events: &[ClientEvent]) {
+            if self.socket.ready_state() != WebSocket::OPEN {
+                return;
+            }
+            for event in events {
+                if let Ok(text) = serde_json::to_string(event) {
+                    let _ = self.socket.send_with_str(&text);
+                }
+            }
+        }
+
+        /// The newest frame received since the last call; older ones are
+        /// skipped.
+        pub fn latest_frame(&mut self) -> Option<Vec<u8>> {
+            self.latest.borrow_mut().take()
+        }
+
+        pub fn error(&self) -> Option<String> {
+            self.error.borrow().clone()
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use rosc::{OscMessage, OscPacket, OscType};
+use std::net::UdpSocket;
+
+use crate::{
+    events::{RegenerateMatrix, SetBehavior, SetParameter},
+    spawn::SpawnRequest,
+    ui_enabled, ParticleCount, ParticleSystem,
+};
+
+const ADDRESS_PREFIX: &str = "/particlelife/";
+const DEFAULT_PORT: u16 = 9000;
+/// Larger than any OSC packet a controller sends over UDP.
+const MAX_PACKET: usize = 65_536;
+
+pub struct OscPlugin;
+
+impl Plugin for OscPlugin {
+    fn build(&self, app: &mut App) {
+        let mut state = OscState::default();
+        // `--osc <port>` starts listening at launch
+        let args: Vec<String> = std::env::args().collect();
+        if let Some(index) = args.iter().position(|arg| arg == "--osc") {
+            if let Some(port) = args.get(index + 1).and_then(|port| port.parse().ok()) {
+                state.port = port;
+            }
+            state.request = Some(OscRequest::Listen);
+        }
+        app.insert_resource(state).add_systems(
+            Update,
+            (receive_osc, osc_ui_system.run_if(ui_enabled)).chain(),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct OscState {
+    pub port: u16,
+    socket: Option<UdpSocket>,
+    /// Address and arguments of the last message received.
+    last_message: Option<String>,
+    request: Option<OscRequest>,
+    status: Option<String>,
+}
+
+impl Default for OscState {
+    fn default() -> Self {
+        OscState {
+            port: DEFAULT_PORT,
+            socket: None,
+            last_message: None,
+            request: None,
+            status: None,
+        }
+    }
+}
+
+enum OscRequest {
+    Listen,
+    Stop,
+}
+
+fn bind(port: u16) -> std::io::Result<UdpSocket> {
+    let socket = UdpSocket::bind(("0.0.0.0", port))?;
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+/// Messages in `packet`, with bundles flattened.
+fn messages(packet: OscPacket, into: &mut Vec<OscMessage>) {
+    match packet {
+        OscPacket::Message(message) => into.push(message),
+        OscPacket::Bundle(bundle) => {
+            for packet in bundle.content {
+                messages(packet, into);
+            }
+        }
+    }
+}
+
+fn number(argument: &OscType) -> Option<f32> {
+    match *argument {
+        OscType::Float(value) => Some(value),
+        OscType::Double(value) => Some(value as f32),
+        OscType::Int(value) => Some(value as f32),
+        OscType::Long(value) => Some(value as f32),
+        _ => None,
+    }
+}
+
+/// What an OSC address changes.
+enum OscTarget<'a> {
+    /// A parameter by its scripting name.
+    Parameter(&'a str),
+    Behavior(usize, usize),
+    Reroll,
+    Respawn,
+}
+
+impl<'a> OscTarget<'a> {
+    fn parse(address: &'a str) -> Option<Self> {
+        let path = address.strip_prefix(ADDRESS_PREFIX)?;
+        let parts: Vec<&str> = path.split('/').collect();
+        match parts.as_slice() {
+            ["matrix", from, to] => Some(OscTarget::Behavior(from.parse().ok()?, to.parse().ok()?)),
+            ["reroll"] => Some(OscTarget::Reroll),
+            ["respawn"] => Some(OscTarget::Respawn),
+            [name] => Some(OscTarget::Parameter(name)),
+            _ => None,
+        }
+    }
+}
+
+fn receive_osc(
+    mut state: ResMut<OscState>,
+    particle_count: Res<ParticleCount>,
+    particle_system: Res<ParticleSystem>,
+    mut spawn_requests: EventWriter<SpawnRequest>,
+    mut parameters: EventWriter<SetParameter>,
+    mut behaviors: EventWriter<SetBehavior>,
+    mut matrix_events: EventWriter<RegenerateMatrix>,
+) {
+    match state.request.take() {
+        Some(OscRequest::Listen) => match bind(state.port) {
+            Ok(socket) => {
+                info!("Listening for OSC on UDP port {}", state.port);
+                state.socket = Some(socket);
+                state.status = None;
+            }
+            Err(error) => {
+                state.status = Some(format!("Could not listen on {}: {}", state.port, error))
+            }
+        },
+        Some(OscRequest::Stop) => state.socket = None,
+        None => {}
+    }
+    let Some(socket) = &state.socket else {
+        return;
+    };
+
+    let mut received = Vec::new();
+    let mut buffer = vec![0; MAX_PACKET];
+    // Nonblocking, so this ends once the queued packets are read
+    while let Ok((size, _)) = socket.recv_from(&mut buffer) {
+        match rosc::decoder::decode_udp(&buffer[..size]) {
+            Ok((_, packet)) => messages(packet, &mut received),
+            Err(error) => warn!("Ignoring malformed OSC packet: {:?}", error),
+        }
+    }
+
+    for message in received {
+        state.last_message = Some(format!("{} {:?}", message.addr, message.args));
+        let Some(target) = OscTarget::parse(&message.addr) else {
+            state.status = Some(format!("Unknown OSC address {}", message.addr));
+            continue;
+        };
+        let value = message.args.first().and_then(number);
+        let n = particle_system.colors.len();
+        match (target, value) {
+            (OscTarget::Parameter(name), Some(value)) => match particle_system.parameter(name) {
+                // Controllers resend unchanged values, which must not count as rule edits
+                Some(current) if current == value => {}
+                Some(_) => {
+                    parameters.send(SetParameter {
+                        name: name.to_string(),
+                        value,
+                    });
+                }
+                None => state.status = Some(format!("Unknown parameter {}", name)),
+            },
+            (OscTarget::Behavior(from, to), Some(value)) if from < n && to < n => {
+                behaviors.send(SetBehavior { from, to, value });
+            }
+            (OscTarget::Behavior(..), Some(_)) => {
+                state.status = Some(format!("Species out of range 0..{}", n));
+            }
+            (OscTarget::Reroll, _) => {
+                matrix_events.send(RegenerateMatrix::Reroll);
+            }
+            (OscTarget::Respawn, _) => {
+                spawn_requests.send(SpawnRequest::Reshuffle {
+                    count: particle_count.count,
+                });
+            }
+            (_, None) => {
+                state.status = Some(format!("{} needs a number argument", message.addr));
+            }
+        }
+    }
+}
+
+fn osc_ui_system(mut contexts: EguiContexts, mut state: ResMut<OscState>) {
+    egui::Window::new("OSC")
+        .default_pos([620.0, 490.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("UDP port");
+                ui.add_enabled(
+                    state.socket.is_none(),
+                    egui::DragValue::new(&mut state.port).range(1024..=65535),
+                );
+                if state.socket.is_some() {
+                    if ui.button("Stop").clicked() {
+                        state.request = Some(OscRequest::Stop);
+                    }
+                } else if ui.button("Listen").clicked() {
+                    state.request = Some(OscRequest::Listen);
+                }
+            });
+            ui.label("Addresses:");
+            for address in [
+                "/particlelife/speed f",
+                "/particlelife/beta f, /gamma f, /radius f, /temperature f",
+                "/particlelife/matrix/<from>/<to> f",
+                "/particlelife/reroll, /respawn",
+            ] {
+                ui.monospace(address);
+            }
+            if let Some(message) = &state.last_message {
+                ui.label(format!("Last: {}", message));
+            }
+            if let Some(status) = &state.status {
+                ui.label(status);
+            }
+        });
+}
@@ -0,0 +1,260 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Lightness levels cycled through so species with neighboring hues still
+/// differ in brightness once there are more species than distinct hues.
+const LIGHTNESS_LEVELS: [f32; 3] = [0.72, 0.58, 0.86];
+const CHROMA: f32 = 0.16;
+const GOLDEN_ANGLE: f32 = 137.507_76;
+/// Oklab distance the random strategy starts out requiring between species.
+const RANDOM_MIN_DISTANCE: f32 = 0.15;
+const RANDOM_ATTEMPTS: usize = 200;
+/// Okabe–Ito colors, which stay distinct under protanopia, deuteranopia and
+/// tritanopia. Its black is swapped for light grey to show on dark backgrounds.
+const COLORBLIND_SAFE: [[u8; 3]; 8] = [
+    [230, 159, 0],
+    [86, 180, 233],
+    [0, 158, 115],
+    [240, 228, 66],
+    [0, 114, 178],
+    [213, 94, 0],
+    [204, 121, 167],
+    [200, 200, 200],
+];
+/// Lightness shifts for palettes larger than the colorblind-safe set. Repeats
+/// are much harder to tell apart, so species shapes help there.
+const COLORBLIND_SHADES: [f32; 3] = [0.0, -0.2, 0.12];
+
+/// How species colors are chosen.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum PaletteStrategy {
+    /// Hues evenly spaced around the OKLCH wheel.
+    #[default]
+    EvenHues,
+    /// Each hue a golden angle from the previous one, so any prefix of the
+    /// palette is well spread.
+    GoldenAngle,
+    /// Random OKLCH colors kept a minimum perceptual distance apart.
+    Random,
+    /// Colors distinguishable with common color-vision deficiencies.
+    Colorblind,
+}
+
+impl PaletteStrategy {
+    const ALL: [PaletteStrategy; 4] = [
+        PaletteStrategy::EvenHues,
+        PaletteStrategy::GoldenAngle,
+        PaletteStrategy::Random,
+        PaletteStrategy::Colorblind,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            PaletteStrategy::EvenHues => "Even hues",
+            PaletteStrategy::GoldenAngle => "Golden angle",
+            PaletteStrategy::Random => "Random",
+            PaletteStrategy::Colorblind => "Colorblind safe",
+        }
+    }
+}
+
+/// Palette preferences, persisted with the other settings.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct PaletteSettings {
+    pub strategy: PaletteStrategy,
+    /// Fixes the hue offset and random choices; `None` picks new ones each time.
+    pub seed: Option<u64>,
+}
+
+impl PaletteSettings {
+    pub fn generate(&self, count: usize) -> Vec<Color> {
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
+        let offset = if self.seed.is_some() || self.strategy == PaletteStrategy::Random {
+            rng.random_range(0.0..360.0)
+        } else {
+            0.0
+        };
+        match self.strategy {
+            PaletteStrategy::EvenHues => (0..count)
+                .map(|i| cycled(i, offset + 360.0 * i as f32 / count as f32))
+                .collect(),
+            PaletteStrategy::GoldenAngle => (0..count)
+                .map(|i| cycled(i, offset + GOLDEN_ANGLE * i as f32))
+                .collect(),
+            PaletteStrategy::Random => random(count, &mut rng),
+            PaletteStrategy::Colorblind => (0..count).map(colorblind).collect(),
+        }
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        egui::ComboBox::from_label("Palette")
+            .selected_text(self.strategy.label())
+            .show_ui(ui, |ui| {
+                for strategy in PaletteStrategy::ALL {
+                    ui.selectable_value(&mut self.strategy, strategy, strategy.label());
+                }
+            });
+        ui.horizontal(|ui| {
+            let mut seeded = self.seed.is_some();
+            if ui.checkbox(&mut seeded, "Seed").changed() {
+                self.seed = seeded.then_some(0);
+            }
+            if let Some(seed) = &mut self.seed {
+                ui.add(egui::DragValue::new(seed));
+            }
+        });
+    }
+}
+
+fn cycled(index: usize, hue: f32) -> Color {
+    in_gamut(Oklcha::lch(
+        LIGHTNESS_LEVELS[index % LIGHTNESS_LEVELS.len()],
+        CHROMA,
+        hue.rem_euclid(360.0),
+    ))
+}
+
+fn colorblind(index: usize) -> Color {
+    let [r, g, b] = COLORBLIND_SAFE[index % COLORBLIND_SAFE.len()];
+    let shade = COLORBLIND_SHADES[(index / COLORBLIND_SAFE.len()) % COLORBLIND_SHADES.len()];
+    let mut oklab = Oklaba::from(Color::srgb_u8(r, g, b));
+    oklab.lightness = (oklab.lightness + shade).clamp(0.0, 1.0);
+    oklab.into()
+}
+
+/// Rejection sampling; the required distance shrinks whenever no candidate
+/// fits, so large palettes still terminate.
+fn random(count: usize, rng: &mut impl Rng) -> Vec<Color> {
+    let mut accepted: Vec<Oklaba> = Vec::with_capacity(count);
+    let mut min_distance = RANDOM_MIN_DISTANCE;
+    while accepted.len() < count {
+        let mut placed = false;
+        for _ in 0..RANDOM_ATTEMPTS {
+            let candidate = Oklaba::from(in_gamut(Oklcha::lch(
+                rng.random_range(0.5..0.9),
+                rng.random_range(0.08..0.2),
+                rng.random_range(0.0..360.0),
+            )));
+            if accepted
+                .iter()
+                .all(|other| distance(*other, candidate) >= min_distance)
+            {
+                accepted.push(candidate);
+                placed = true;
+                break;
+            }
+        }
+        if !placed {
+            min_distance *= 0.9;
+        }
+    }
+    accepted.into_iter().map(Color::from).collect()
+}
+
+/// Lowers chroma until the color fits in sRGB, keeping lightness and hue.
+fn in_gamut(mut color: Oklcha) -> Color {
+    for _ in 0..20 {
+        let rgb = LinearRgba::from(color);
+        if [rgb.red, rgb.green, rgb.blue]
+            .iter()
+            .all(|channel| (0.0..=1.0).contains(channel))
+        {
+            break;
+        }
+        color.chroma *= 0.85;
+    }
+    color.into()
+}
+
+/// Euclidean distance in Oklab, roughly proportional to perceived difference.
+pub fn distance(a: Oklaba, b: Oklaba) -> f32 {
+    Vec3::new(a.lightness - b.lightness, a.a - b.a, a.b - b.b).length()
+}
+
+/// Perceptual distance between the two most similar colors of a palette.
+pub fn closest_pair(colors: &[Color]) -> Option<f32> {
+    let oklab: Vec<Oklaba> = colors.iter().map(|&color| color.into()).collect();
+    oklab
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| oklab[i + 1..].iter().map(move |b| distance(*a, *b)))
+        .min_by(f32::total_cmp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeded(strategy: PaletteStrategy) -> PaletteSettings {
+        PaletteSettings {
+            strategy,
+            seed: Some(42),
+        }
+    }
+
+    #[test]
+    fn every_strategy_makes_the_requested_number_of_colors() {
+        for strategy in PaletteStrategy::ALL {
+            for count in [0, 1, 2, 7, 8, 9, 50] {
+                assert_eq!(seeded(strategy).generate(count).len(), count);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_hues_are_within_srgb() {
+        // The colorblind set is fixed sRGB colors, only shaded past eight
+        for strategy in [
+            PaletteStrategy::EvenHues,
+            PaletteStrategy::GoldenAngle,
+            PaletteStrategy::Random,
+        ] {
+            for color in seeded(strategy).generate(50) {
+                let srgba = color.to_srgba();
+                for channel in [srgba.red, srgba.green, srgba.blue] {
+                    assert!(
+                        (-1e-3..=1.0 + 1e-3).contains(&channel),
+                        "{} channel out of gamut: {}",
+                        strategy.label(),
+                        channel
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn seeded_palettes_repeat() {
+        for strategy in PaletteStrategy::ALL {
+            assert_eq!(seeded(strategy).generate(12), seeded(strategy).generate(12));
+        }
+    }
+
+    #[test]
+    fn small_palettes_have_distinct_colors() {
+        for strategy in PaletteStrategy::ALL {
+            let colors = seeded(strategy).generate(6);
+            let closest = closest_pair(&colors).unwrap();
+            assert!(
+                closest > 0.03,
+                "{} has colors only {} apart",
+                strategy.label(),
+                closest
+            );
+        }
+    }
+
+    #[test]
+    fn closest_pair_needs_two_colors() {
+        assert_eq!(closest_pair(&[]), None);
+        assert_eq!(closest_pair(&[Color::WHITE]), None);
+        let distance = closest_pair(&[Color::WHITE, Color::WHITE]).unwrap();
+        assert!(distance.abs() < 1e-6);
+    }
+}
@@ -0,0 +1,78 @@
+// Particles are quads; the species shape is drawn inside from a distance
+// field, with an anti-aliased or softened edge and an optional glow around it.
+
+#import bevy_sprite::{
+    mesh2d_functions::{get_world_from_local, mesh2d_position_world_to_clip},
+    mesh2d_view_bindings::view,
+    mesh2d_vertex_output::VertexOutput,
+}
+
+// Half the quad's side in particle radii; the glow fades out at its edge.
+const QUAD_EXTENT: f32 = 2.5;
+
+struct ParticleMaterial {
+    color: vec4<f32>,
+    // x: edge softness in radii, y: glow strength, z: zoom attenuation,
+    // w: shape (0 circle, 1 square, 2 triangle)
+    style: vec4<f32>,
+}
+
+@group(2) @binding(0) var<uniform> material: ParticleMaterial;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    let world_from_local = get_world_from_local(vertex.instance_index);
+    // Zoomed out past one world unit per pixel, particles grow by a power
+    // of the zoom: 0 keeps their world size, 1 their size on screen
+    let units_per_pixel = 2.0 / (view.clip_from_world[0][0] * view.viewport.z);
+    let grow = pow(max(units_per_pixel, 1.0), material.style.z);
+    out.world_position = world_from_local * vec4<f32>(vertex.position * grow, 1.0);
+    out.position = mesh2d_position_world_to_clip(out.world_position);
+    out.uv = vertex.uv;
+    return out;
+}
+
+// Equilateral triangle pointing up with half side `r`, after Inigo Quilez.
+fn triangle_distance(point: vec2<f32>, r: f32) -> f32 {
+    let k = sqrt(3.0);
+    var p = vec2<f32>(abs(point.x) - r, point.y + r / k);
+    if p.x + k * p.y > 0.0 {
+        p = vec2<f32>(p.x - k * p.y, -k * p.x - p.y) / 2.0;
+    }
+    p.x -= clamp(p.x, -2.0 * r, 0.0);
+    return -length(p) * sign(p.y);
+}
+
+// Signed distance to the shape's edge in particle radii, negative inside.
+// Sizes match the meshes the shapes used to be drawn with.
+fn shape_distance(point: vec2<f32>, shape: f32) -> f32 {
+    if shape > 1.5 {
+        // Circumradius 1.34, so half a side of 1.34 * sqrt(3) / 2
+        return triangle_distance(point, 1.16);
+    }
+    if shape > 0.5 {
+        return max(abs(point.x), abs(point.y)) - 0.9;
+    }
+    return length(point) - 1.0;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // UV y points down; flip it so triangles point up
+    let point = (vec2<f32>(in.uv.x, 1.0 - in.uv.y) - 0.5) * 2.0 * QUAD_EXTENT;
+    let distance = shape_distance(point, material.style.w);
+    // At least a pixel of smoothing keeps edges from aliasing
+    let width = max(material.style.x, fwidth(distance));
+    let body = clamp(0.5 - distance / width, 0.0, 1.0);
+    let fade = clamp(1.0 - distance / (QUAD_EXTENT - 1.0), 0.0, 1.0);
+    let glow = material.style.y * fade * fade;
+    let alpha = (body + glow * (1.0 - body)) * material.color.a;
+    return vec4<f32>(material.color.rgb, alpha);
+}
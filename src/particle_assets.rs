@@ -0,0 +1,227 @@
+use bevy::{
+    asset::load_internal_asset,
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderRef},
+    sprite::{AlphaMode2d, Material2d, Material2dPlugin},
+};
+use bevy_egui::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::{settings::Settings, shapes::SpeciesShape, Particle, PARTICLE_SIZE};
+
+const PARTICLE_SHADER: Handle<Shader> =
+    Handle::weak_from_u128(0x7f3c_52e1_9a4d_4b8e_a0c6_1d2e_8b5f_3a91);
+/// Side of the particle quad in particle sizes, leaving room for the glow.
+/// Matches `QUAD_EXTENT` in the shader.
+const QUAD_SIZE: f32 = 2.5;
+
+pub struct ParticleAssetsPlugin;
+
+impl Plugin for ParticleAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, PARTICLE_SHADER, "particle.wgsl", Shader::from_wgsl);
+        app.add_plugins(Material2dPlugin::<ParticleMaterial>::default())
+            .init_resource::<ParticleAssets>()
+            .add_systems(Update, (apply_look, assign_species_materials));
+    }
+}
+
+/// How particles are drawn, set in the Settings window.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ParticleLook {
+    /// Width of the blurred edge in particle radii; 0 only anti-aliases.
+    pub softness: f32,
+    /// Brightness of the halo around each particle.
+    pub glow: f32,
+    /// How far particles keep their size on screen when zoomed out: 0 shrinks
+    /// them with the world, 1 keeps them the same number of pixels.
+    pub zoom_attenuation: f32,
+}
+
+impl ParticleLook {
+    pub fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.add(egui::Slider::new(&mut self.softness, 0.0..=1.0).text("edge softness"));
+        ui.add(egui::Slider::new(&mut self.glow, 0.0..=1.0).text("glow"));
+        ui.add(
+            egui::Slider::new(&mut self.zoom_attenuation, 0.0..=1.0)
+                .text("keep size when zoomed out"),
+        );
+    }
+}
+
+/// One species' particles: a flat color drawn by `particle.wgsl` as the
+/// species shape with a soft edge and glow.
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+pub struct ParticleMaterial {
+    #[uniform(0)]
+    pub color: LinearRgba,
+    /// Softness, glow, zoom attenuation and shape, as the shader reads them.
+    #[uniform(0)]
+    style: Vec4,
+}
+
+impl Material2d for ParticleMaterial {
+    fn vertex_shader() -> ShaderRef {
+        PARTICLE_SHADER.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        PARTICLE_SHADER.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+}
+
+/// The quad every particle is drawn on and one material per species, shared
+/// by all particles instead of a mesh and material each. Material colors are
+/// kept in sync with the palette by `background::apply_contrast`, shapes by
+/// `shapes::apply_shapes`.
+#[derive(Resource)]
+pub struct ParticleAssets {
+    pub quad: Handle<Mesh>,
+    /// A plain circle for flat-colored stand-ins of particles, like the
+    /// comparison world's.
+    pub mesh: Handle<Mesh>,
+    materials: Vec<Handle<ParticleMaterial>>,
+    look: ParticleLook,
+    /// Shape per species; missing entries are circles.
+    shapes: Vec<SpeciesShape>,
+}
+
+impl FromWorld for ParticleAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        ParticleAssets {
+            quad: meshes.add(Rectangle::from_length(PARTICLE_SIZE * QUAD_SIZE)),
+            mesh: meshes.add(Circle::new(PARTICLE_SIZE / 2.0)),
+            materials: Vec::new(),
+            look: ParticleLook::default(),
+            shapes: Vec::new(),
+        }
+    }
+}
+
+impl ParticleAssets {
+    fn style(&self, color_id: usize) -> Vec4 {
+        let shape = self.shapes.get(color_id).copied().unwrap_or_default();
+        Vec4::new(
+            self.look.softness,
+            self.look.glow,
+            self.look.zoom_attenuation,
+            shape.shader_index(),
+        )
+    }
+
+    fn new_material(&self, color_id: usize, color: Color) -> ParticleMaterial {
+        ParticleMaterial {
+            color: color.into(),
+            style: self.style(color_id),
+        }
+    }
+
+    /// Material of species `color_id`, created with `color` if the species is
+    /// new.
+    pub fn material(
+        &mut self,
+        color_id: usize,
+        color: Color,
+        materials: &mut Assets<ParticleMaterial>,
+    ) -> Handle<ParticleMaterial> {
+        while self.materials.len() <= color_id {
+            let material = self.new_material(self.materials.len(), color);
+            self.materials.push(materials.add(material));
+        }
+        self.materials[color_id].clone()
+    }
+
+    /// Adds or drops species materials to match the palette, then sets their
+    /// colors.
+    pub fn set_colors(&mut self, colors: &[Color], materials: &mut Assets<ParticleMaterial>) {
+        self.materials.truncate(colors.len());
+        for (handle, &color) in self.materials.iter().zip(colors) {
+            let color = LinearRgba::from(color);
+            // `get_mut` alone would mark the material for re-upload
+            if materials
+                .get(handle)
+                .is_some_and(|material| material.color != color)
+            {
+                if let Some(material) = materials.get_mut(handle) {
+                    material.color = color;
+                }
+            }
+        }
+        for &color in &colors[self.materials.len()..] {
+            let material = self.new_material(self.materials.len(), color);
+            self.materials.push(materials.add(material));
+        }
+    }
+
+    /// Sets the look and the species shapes of every material.
+    pub fn set_style(
+        &mut self,
+        look: ParticleLook,
+        shapes: Vec<SpeciesShape>,
+        materials: &mut Assets<ParticleMaterial>,
+    ) {
+        self.look = look;
+        self.shapes = shapes;
+        for (color_id, handle) in self.materials.iter().enumerate() {
+            let style = self.style(color_id);
+            if materials
+                .get(handle)
+                .is_some_and(|material| material.style != style)
+            {
+                if let Some(material) = materials.get_mut(handle) {
+                    material.style = style;
+                }
+            }
+        }
+    }
+
+    /// Color each species is drawn in, after contrast adjustment.
+    pub fn colors(&self, materials: &Assets<ParticleMaterial>) -> Vec<LinearRgba> {
+        self.materials
+            .iter()
+            .map(|handle| {
+                materials
+                    .get(handle)
+                    .map_or(LinearRgba::WHITE, |material| material.color)
+            })
+            .collect()
+    }
+
+    pub fn shapes(&self) -> &[SpeciesShape] {
+        &self.shapes
+    }
+}
+
+fn apply_look(
+    settings: Res<Settings>,
+    mut assets: ResMut<ParticleAssets>,
+    mut materials: ResMut<Assets<ParticleMaterial>>,
+) {
+    if settings.particle_look != assets.look {
+        let shapes = assets.shapes.clone();
+        assets.set_style(settings.particle_look, shapes, &mut materials);
+    }
+}
+
+/// Points particles whose species changed at that species' material.
+fn assign_species_materials(
+    mut assets: ResMut<ParticleAssets>,
+    mut materials: ResMut<Assets<ParticleMaterial>>,
+    mut particles: Query<(Ref<Particle>, &mut MeshMaterial2d<ParticleMaterial>)>,
+) {
+    for (particle, mut material) in &mut particles {
+        if !particle.is_changed() {
+            continue;
+        }
+        let handle = assets.material(particle.color_id, Color::WHITE, &mut materials);
+        if material.0 != handle {
+            material.0 = handle;
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use bevy::{
+    diagnostic::{
+        Diagnostic, DiagnosticPath, Diagnostics, DiagnosticsStore, FrameTimeDiagnosticsPlugin,
+        RegisterDiagnostic,
+    },
+    prelude::*,
+    utils::Instant,
+};
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{settings::Settings, ui_enabled};
+
+/// Spatial structure build of the active backend, once per simulation tick.
+pub const SPATIAL_BUILD: DiagnosticPath = DiagnosticPath::const_new("perf/spatial_build");
+/// Force accumulation and integration, excluding the spatial build.
+pub const SIMULATION: DiagnosticPath = DiagnosticPath::const_new("perf/simulation");
+/// Every main world schedule from `First` to `Last`, simulation included.
+const MAIN_WORLD: DiagnosticPath = DiagnosticPath::const_new("perf/main_world");
+const BAR_WIDTH: f32 = 160.0;
+
+pub struct PerfPlugin;
+
+impl Plugin for PerfPlugin {
+    fn build(&self, app: &mut App) {
+        for path in [SPATIAL_BUILD, SIMULATION, MAIN_WORLD] {
+            app.register_diagnostic(Diagnostic::new(path).with_suffix("ms"));
+        }
+        app.init_resource::<PerfOverlay>()
+            .add_systems(First, start_frame)
+            .add_systems(Last, finish_frame)
+            .add_systems(
+                Update,
+                (toggle_overlay, perf_overlay_system.run_if(ui_enabled)).chain(),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct PerfOverlay {
+    visible: bool,
+    frame_started: Option<Instant>,
+}
+
+/// Milliseconds since `start`, for [`Diagnostics::add_measurement`].
+pub fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+fn start_frame(mut overlay: ResMut<PerfOverlay>) {
+    overlay.frame_started = Some(Instant::now());
+}
+
+fn finish_frame(overlay: Res<PerfOverlay>, mut diagnostics: Diagnostics) {
+    if let Some(started) = overlay.frame_started {
+        diagnostics.add_measurement(&MAIN_WORLD, || elapsed_ms(started));
+    }
+}
+
+fn toggle_overlay(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    mut overlay: ResMut<PerfOverlay>,
+) {
+    if keyboard.just_pressed(settings.keys.toggle_perf_overlay) {
+        overlay.visible = !overlay.visible;
+    }
+}
+
+fn smoothed(diagnostics: &DiagnosticsStore, path: &DiagnosticPath) -> Option<f64> {
+    diagnostics.get(path)?.smoothed()
+}
+
+/// Where each frame goes. Rendering runs in its own world, so its share is
+/// estimated as the frame time not spent in the main world schedules.
+fn perf_overlay_system(
+    mut contexts: EguiContexts,
+    overlay: Res<PerfOverlay>,
+    diagnostics: Res<DiagnosticsStore>,
+) {
+    if !overlay.visible {
+        return;
+    }
+    let Some(frame) = smoothed(&diagnostics, &FrameTimeDiagnosticsPlugin::FRAME_TIME) else {
+        return;
+    };
+    let spatial = smoothed(&diagnostics, &SPATIAL_BUILD).unwrap_or(0.0);
+    let simulation = smoothed(&diagnostics, &SIMULATION).unwrap_or(0.0);
+    let main_world = smoothed(&diagnostics, &MAIN_WORLD).unwrap_or(0.0);
+    let rows = [
+        (
+            "Spatial grid",
+            spatial,
+            egui::Color32::from_rgb(86, 180, 233),
+        ),
+        (
+            "Simulation",
+            simulation,
+            egui::Color32::from_rgb(230, 159, 0),
+        ),
+        (
+            "Other systems",
+            (main_world - spatial - simulation).max(0.0),
+            egui::Color32::from_rgb(0, 158, 115),
+        ),
+        (
+            "Rendering & wait",
+            (frame - main_world).max(0.0),
+            egui::Color32::from_rgb(204, 121, 167),
+        ),
+    ];
+
+    egui::Area::new(egui::Id::new("perf_overlay"))
+        .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+        .show(contexts.ctx_mut(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label(format!("Frame {:.2} ms ({:.0} FPS)", frame, 1000.0 / frame));
+                egui::Grid::new("perf_overlay_grid").show(ui, |ui| {
+                    for (name, ms, color) in rows {
+                        ui.label(name);
+                        let (rect, _) = ui
+                            .allocate_exact_size(egui::vec2(BAR_WIDTH, 10.0), egui::Sense::hover());
+                        let fraction = (ms / frame.max(f64::EPSILON)).clamp(0.0, 1.0) as f32;
+                        let mut bar = rect;
+                        bar.set_width(rect.width() * fraction);
+                        ui.painter()
+                            .rect_filled(rect, 0.0, egui::Color32::from_gray(40));
+                        ui.painter().rect_filled(bar, 0.0, color);
+                        ui.label(format!("{:.2} ms", ms));
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+}
@@ -0,0 +1,318 @@
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    buffers::ParticleBuffers, egui_color, replay, spawn, ui_enabled, update_particles,
+    ParticleSystem,
+};
+
+/// Between the background and the particles.
+const PHEROMONE_Z: f32 = -5.0;
+/// sRGB tint of the field at full concentration.
+const PHEROMONE_TINT: Vec3 = Vec3::new(0.3, 1.0, 0.5);
+/// Concentration drawn fully opaque.
+const DISPLAY_SATURATION: f32 = 20.0;
+
+pub struct PheromonePlugin;
+
+impl Plugin for PheromonePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PheromoneField>()
+            .add_systems(Startup, spawn_field_sprite)
+            .add_systems(
+                Update,
+                (
+                    update_field
+                        .after(update_particles)
+                        .run_if(not(replay::is_playing_back)),
+                    render_field,
+                    pheromone_ui_system.run_if(ui_enabled),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// How particles lay down and follow the chemical field. Part of the rules,
+/// so presets and saves carry it.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PheromoneRules {
+    pub enabled: bool,
+    /// Concentration each particle adds to its cell per second.
+    pub deposit: f32,
+    /// Rate at which cells even out with their neighbors, per second.
+    pub diffusion: f32,
+    /// Fraction of the concentration lost per second.
+    pub evaporation: f32,
+    /// Per species pull up the gradient, negative to flee it. Species past
+    /// the end follow it at strength 1.
+    pub sensitivity: Vec<f32>,
+}
+
+impl Default for PheromoneRules {
+    fn default() -> Self {
+        PheromoneRules {
+            enabled: false,
+            deposit: 5.0,
+            diffusion: 2.0,
+            evaporation: 0.3,
+            sensitivity: Vec::new(),
+        }
+    }
+}
+
+impl PheromoneRules {
+    fn sensitivity(&self, species: usize) -> f32 {
+        self.sensitivity.get(species).copied().unwrap_or(1.0)
+    }
+}
+
+/// Scalar concentration on a grid over the world bounds, stepped once per
+/// simulation tick.
+#[derive(Resource)]
+pub struct PheromoneField {
+    /// Cell edge in world units.
+    pub cell_size: f32,
+    pub visible: bool,
+    width: usize,
+    height: usize,
+    cells: Vec<f32>,
+    scratch: Vec<f32>,
+    image: Handle<Image>,
+}
+
+impl Default for PheromoneField {
+    fn default() -> Self {
+        PheromoneField {
+            cell_size: 10.0,
+            visible: true,
+            width: 0,
+            height: 0,
+            cells: Vec::new(),
+            scratch: Vec::new(),
+            image: Handle::default(),
+        }
+    }
+}
+
+impl PheromoneField {
+    fn cell(&self, pos: Vec2) -> Option<(usize, usize)> {
+        let bounds = spawn::world_bounds();
+        let cell = ((pos - bounds.min) / self.cell_size).floor();
+        (cell.x >= 0.0
+            && cell.y >= 0.0
+            && (cell.x as usize) < self.width
+            && (cell.y as usize) < self.height)
+            .then_some((cell.x as usize, cell.y as usize))
+    }
+
+    fn value(&self, x: usize, y: usize) -> f32 {
+        self.cells[y * self.width + x]
+    }
+
+    /// Force on a particle of `species` at `pos` along the local gradient,
+    /// saturating at the species' sensitivity.
+    pub fn gradient_force(&self, pos: Vec2, rules: &PheromoneRules, species: usize) -> Vec2 {
+        if !rules.enabled {
+            return Vec2::ZERO;
+        }
+        let Some((x, y)) = self.cell(pos) else {
+            return Vec2::ZERO;
+        };
+        let (left, right) = (x.saturating_sub(1), (x + 1).min(self.width - 1));
+        let (down, up) = (y.saturating_sub(1), (y + 1).min(self.height - 1));
+        let gradient = Vec2::new(
+            self.value(right, y) - self.value(left, y),
+            self.value(x, up) - self.value(x, down),
+        ) / 2.0;
+        gradient / (1.0 + gradient.length()) * rules.sensitivity(species)
+    }
+
+    /// Resizes the grid to cover the world, clearing it if the size changed.
+    fn fit(&mut self) {
+        let size = spawn::world_bounds().size() / self.cell_size.max(1.0);
+        let (width, height) = (size.x.ceil() as usize, size.y.ceil() as usize);
+        if (width, height) != (self.width, self.height) {
+            self.width = width;
+            self.height = height;
+            self.cells = vec![0.0; width * height];
+        }
+    }
+}
+
+fn spawn_field_sprite(
+    mut commands: Commands,
+    mut field: ResMut<PheromoneField>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    field.image = images.add(Image::new_fill(
+        Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ));
+    commands.spawn((
+        Sprite {
+            image: field.image.clone(),
+            custom_size: Some(spawn::world_bounds().size()),
+            ..Default::default()
+        },
+        Transform::from_xyz(0.0, 0.0, PHEROMONE_Z),
+        Visibility::Hidden,
+        PheromoneSprite,
+    ));
+}
+
+#[derive(Component)]
+struct PheromoneSprite;
+
+/// Deposits at every particle, then diffuses and evaporates the field.
+fn update_field(
+    time: Res<Time>,
+    particle_system: Res<ParticleSystem>,
+    buffers: Res<ParticleBuffers>,
+    mut field: ResMut<PheromoneField>,
+) {
+    let rules = &particle_system.pheromones;
+    if !rules.enabled {
+        return;
+    }
+    let step = time.delta_secs();
+    field.fit();
+    let field = &mut *field;
+    let (w, h) = (field.width, field.height);
+    let deposit = rules.deposit * step;
+    for &pos in &buffers.front {
+        if let Some((x, y)) = field.cell(pos) {
+            field.cells[y * w + x] += deposit;
+        }
+    }
+
+    // Relax each cell toward the mean of its 3x3 neighborhood
+    let blend = (rules.diffusion * step).clamp(0.0, 1.0);
+    let keep = (-rules.evaporation * step).exp();
+    field.scratch.resize(w * h, 0.0);
+    for y in 0..h {
+        for x in 0..w {
+            let mut sum = 0.0;
+            let mut count = 0.0;
+            for ny in y.saturating_sub(1)..(y + 2).min(h) {
+                for nx in x.saturating_sub(1)..(x + 2).min(w) {
+                    sum += field.cells[ny * w + nx];
+                    count += 1.0;
+                }
+            }
+            let value = field.cells[y * w + x];
+            field.scratch[y * w + x] = (value + (sum / count - value) * blend) * keep;
+        }
+    }
+    std::mem::swap(&mut field.cells, &mut field.scratch);
+}
+
+fn render_field(
+    particle_system: Res<ParticleSystem>,
+    field: Res<PheromoneField>,
+    mut images: ResMut<Assets<Image>>,
+    mut sprite: Query<&mut Visibility, With<PheromoneSprite>>,
+) {
+    let Ok(mut visibility) = sprite.get_single_mut() else {
+        return;
+    };
+    if !particle_system.pheromones.enabled || !field.visible || field.cells.is_empty() {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+    visibility.set_if_neq(Visibility::Visible);
+
+    let Some(image) = images.get_mut(&field.image) else {
+        return;
+    };
+    let size = Extent3d {
+        width: field.width as u32,
+        height: field.height as u32,
+        depth_or_array_layers: 1,
+    };
+    if image.texture_descriptor.size != size {
+        image.resize(size);
+    }
+    // Image rows run top to bottom, grid rows bottom to top
+    let rows = image.data.chunks_exact_mut(field.width * 4);
+    for (row, y) in rows.zip((0..field.height).rev()) {
+        for (pixel, x) in row.chunks_exact_mut(4).zip(0..field.width) {
+            let level = (field.value(x, y) / DISPLAY_SATURATION).clamp(0.0, 1.0);
+            let [r, g, b] = PHEROMONE_TINT
+                .to_array()
+                .map(|channel| (channel * 255.0) as u8);
+            pixel.copy_from_slice(&[r, g, b, (level * 255.0) as u8]);
+        }
+    }
+}
+
+fn pheromone_ui_system(
+    mut contexts: EguiContexts,
+    mut particle_system: ResMut<ParticleSystem>,
+    mut field: ResMut<PheromoneField>,
+) {
+    egui::Window::new("Pheromones")
+        .default_pos([400.0, 640.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Particles lay a chemical trail and climb or flee its gradient.");
+            ui.add_space(5.0);
+
+            let particle_system = &mut *particle_system;
+            let rules = &mut particle_system.pheromones;
+            ui.checkbox(&mut rules.enabled, "Pheromone field");
+            ui.checkbox(&mut field.visible, "Show field");
+            ui.add(egui::Slider::new(&mut field.cell_size, 4.0..=40.0).text("cell size"));
+            ui.add(egui::Slider::new(&mut rules.deposit, 0.0..=50.0).text("deposit/s"));
+            ui.add(egui::Slider::new(&mut rules.diffusion, 0.0..=20.0).text("diffusion/s"));
+            ui.add(
+                egui::Slider::new(&mut rules.evaporation, 0.0..=5.0)
+                    .logarithmic(true)
+                    .text("evaporation/s"),
+            );
+            if ui.button("Clear Field").clicked() {
+                field.cells.fill(0.0);
+            }
+
+            ui.add_space(5.0);
+            ui.label("Sensitivity per species");
+            let n = particle_system.colors.len();
+            rules.sensitivity.resize(n, 1.0);
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("pheromone_sensitivity_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (color, sensitivity) in particle_system
+                            .colors
+                            .iter()
+                            .zip(rules.sensitivity.iter_mut())
+                        {
+                            let (rect, _) = ui
+                                .allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                            ui.painter().rect_filled(rect, 2.0, egui_color(*color));
+                            ui.add(
+                                egui::DragValue::new(sensitivity)
+                                    .range(-2.0..=2.0)
+                                    .speed(0.01),
+                            );
+                            ui.end_row();
+                        }
+                    });
+            });
+        });
+}
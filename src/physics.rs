@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+
+/// Present while particles are rigid bodies: `update_particles` then leaves
+/// positions to the physics engine and only computes target velocities.
+#[derive(Resource)]
+pub struct RigidBodies;
+
+/// Whether `--physics` was passed. Without the `avian` feature the flag only
+/// produces a warning.
+pub fn requested() -> bool {
+    let requested = std::env::args().any(|arg| arg == "--physics");
+    if requested && !cfg!(feature = "avian") {
+        eprintln!("--physics needs a build with the `avian` feature; using the default backend");
+        return false;
+    }
+    requested
+}
+
+#[cfg(feature = "avian")]
+pub use rigid::PhysicsPlugin;
+
+#[cfg(feature = "avian")]
+mod rigid {
+    use avian2d::prelude::*;
+    use bevy::prelude::*;
+
+    use super::RigidBodies;
+    use crate::{Particle, Velocity, PARTICLE_SIZE};
+
+    /// How quickly the external force closes the gap between a body's
+    /// velocity and the velocity particle-life forces ask for, per second.
+    const RESPONSE: f32 = 20.0;
+
+    /// Turns particles into dynamic rigid bodies with circle colliders, so
+    /// they collide instead of overlapping. Particle-life forces reach them
+    /// as external forces.
+    pub struct PhysicsPlugin;
+
+    impl Plugin for PhysicsPlugin {
+        fn build(&self, app: &mut App) {
+            app.add_plugins(PhysicsPlugins::default())
+                .insert_resource(Gravity(Vec2::ZERO))
+                .insert_resource(RigidBodies)
+                .add_systems(
+                    Update,
+                    (attach_bodies, detach_bodies, apply_particle_forces)
+                        .chain()
+                        .after(crate::update_particles),
+                );
+        }
+    }
+
+    fn attach_bodies(mut commands: Commands, added: Query<Entity, Added<Particle>>) {
+        for entity in &added {
+            commands.entity(entity).insert((
+                RigidBody::Dynamic,
+                Collider::circle(PARTICLE_SIZE / 2.0),
+                Mass(1.0),
+                Restitution::new(0.2),
+                ExternalForce::default(),
+                LinearVelocity::default(),
+            ));
+        }
+    }
+
+    /// Pooled particles lose their body so parked entities do not collide.
+    fn detach_bodies(
+        mut commands: Commands,
+        mut removed: RemovedComponents<Particle>,
+        parked: Query<(), (With<RigidBody>, Without<Particle>)>,
+    ) {
+        for entity in removed.read() {
+            if parked.contains(entity) {
+                commands
+                    .entity(entity)
+                    .remove::<(RigidBody, Collider, Mass, ExternalForce, LinearVelocity)>();
+            }
+        }
+    }
+
+    /// Steers each body toward the velocity computed by `update_particles`.
+    fn apply_particle_forces(
+        mut bodies: Query<(&Velocity, &LinearVelocity, &mut ExternalForce), With<Particle>>,
+    ) {
+        for (target, velocity, mut force) in &mut bodies {
+            force.set_force((target.0 - velocity.0) * RESPONSE);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+        view::NoFrustumCulling,
+    },
+};
+
+use crate::{
+    heatmap::Heatmap,
+    interpolation,
+    particle_assets::{ParticleAssets, ParticleMaterial},
+    settings::Settings,
+    spawn,
+    species::SpeciesToggles,
+    MainCamera, Particle, PARTICLE_SIZE,
+};
+
+/// Fraction of the threshold the count has to drop below before particles
+/// go back to their own meshes, so a count hovering at the threshold does
+/// not flip between the two every frame.
+const HYSTERESIS: f32 = 0.9;
+
+/// Cheap render path for very large counts. Above
+/// [`Settings::point_sprite_threshold`] particles the per-particle meshes are
+/// hidden and every particle in view is written as a flat colored quad into
+/// one mesh, drawn in a single call from a single vertex buffer. Small counts
+/// keep the shaded meshes.
+pub struct PointSpritePlugin;
+
+impl Plugin for PointSpritePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PointSprites>()
+            .add_systems(Startup, spawn_sprite_batch)
+            .add_systems(Update, select_render_path)
+            .add_systems(
+                PostUpdate,
+                build_sprite_batch.after(interpolation::interpolate_transforms),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct PointSprites {
+    /// Particles are drawn as sprites and their meshes hidden.
+    pub active: bool,
+}
+
+/// The entity drawing every sprite.
+#[derive(Component)]
+struct SpriteBatch;
+
+fn spawn_sprite_batch(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    );
+    commands.spawn((
+        Mesh2d(meshes.add(mesh)),
+        // White, so the vertex colors show as they are
+        MeshMaterial2d(materials.add(ColorMaterial::default())),
+        Transform::default(),
+        Visibility::Hidden,
+        // The bounds change every frame
+        NoFrustumCulling,
+        SpriteBatch,
+    ));
+}
+
+fn select_render_path(
+    settings: Res<Settings>,
+    mut sprites: ResMut<PointSprites>,
+    particles: Query<(), With<Particle>>,
+) {
+    let threshold = settings.point_sprite_threshold;
+    let count = particles.iter().count();
+    let active = if threshold == 0 {
+        false
+    } else if sprites.active {
+        count as f32 > threshold as f32 * HYSTERESIS
+    } else {
+        count > threshold
+    };
+    if sprites.active != active {
+        info!(
+            "Drawing {} particles as {}",
+            count,
+            if active { "point sprites" } else { "meshes" }
+        );
+        sprites.active = active;
+    }
+}
+
+/// Rewrites the batch mesh from the particles in view.
+fn build_sprite_batch(
+    sprites: Res<PointSprites>,
+    (heatmap, toggles): (Res<Heatmap>, Res<SpeciesToggles>),
+    (assets, materials): (Res<ParticleAssets>, Res<Assets<ParticleMaterial>>),
+    mut meshes: ResMut<Assets<Mesh>>,
+    camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    particles: Query<(&Transform, &Particle)>,
+    mut batch: Query<(&Mesh2d, &mut Visibility), With<SpriteBatch>>,
+) {
+    let Ok((mesh, mut visibility)) = batch.get_single_mut() else {
+        return;
+    };
+    if !sprites.active || heatmap.enabled {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+
+    let camera = camera.get_single().ok();
+    let view = spawn::view_bounds(camera).inflate(PARTICLE_SIZE);
+    // Sprites stay at least a pixel wide however far out the camera zooms
+    let min_size = camera.map_or(0.0, |(transform, projection)| {
+        projection.scale * transform.scale.x
+    });
+    let colors = assets.colors(&materials);
+    let mut positions = Vec::new();
+    let mut vertex_colors = Vec::new();
+    for (transform, particle) in &particles {
+        let center = transform.translation.truncate();
+        if !view.contains(center) || toggles.is_hidden(particle.color_id) {
+            continue;
+        }
+        let half = (PARTICLE_SIZE * transform.scale.x).max(min_size) / 2.0;
+        for corner in [
+            Vec2::new(-half, -half),
+            Vec2::new(half, -half),
+            Vec2::new(half, half),
+            Vec2::new(-half, half),
+        ] {
+            positions.push((center + corner).extend(transform.translation.z).to_array());
+        }
+        let color = colors
+            .get(particle.color_id)
+            .copied()
+            .unwrap_or(LinearRgba::WHITE)
+            .to_f32_array();
+        vertex_colors.extend([color; 4]);
+    }
+    if positions.is_empty() {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+    visibility.set_if_neq(Visibility::Inherited);
+
+    let quads = positions.len() as u32 / 4;
+    let indices = (0..quads)
+        .flat_map(|quad| [0, 1, 2, 0, 2, 3].map(|corner| quad * 4 + corner))
+        .collect();
+    let Some(mesh) = meshes.get_mut(&mesh.0) else {
+        return;
+    };
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, vertex_colors);
+    mesh.insert_indices(Indices::U32(indices));
+}
@@ -0,0 +1,98 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    freeze, interpolation, life, lod,
+    particle_assets::{ParticleAssets, ParticleMaterial},
+    Particle, ParticleSystem, Velocity,
+};
+
+/// Parked entities kept beyond this are despawned for real, so shrinking the
+/// particle count does not hold on to memory forever.
+const MAX_POOLED: usize = 20_000;
+
+/// Entities of removed particles. They lose the [`Particle`] component and are
+/// hidden, so no system sees them until [`ParticleSpawner::spawn`] hands them
+/// out again.
+#[derive(Resource, Default)]
+pub struct ParticlePool {
+    free: Vec<Entity>,
+}
+
+/// Spawns and removes particles through the pool. Regenerating thousands of
+/// particles then moves existing entities instead of spawning new ones.
+#[derive(SystemParam)]
+pub struct ParticleSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    pool: ResMut<'w, ParticlePool>,
+    assets: ResMut<'w, ParticleAssets>,
+    materials: ResMut<'w, Assets<ParticleMaterial>>,
+}
+
+impl ParticleSpawner<'_, '_> {
+    /// Places a particle of `color_id` at `position`, at rest and newly born.
+    /// The returned commands can add to it, e.g. a starting [`Velocity`].
+    pub fn spawn(&mut self, position: Vec2, color_id: usize, color: Color) -> EntityCommands<'_> {
+        let material = self.assets.material(color_id, color, &mut self.materials);
+        let state = (
+            Transform::from_translation(position.extend(0.0)),
+            interpolation::TickPositions::at(position),
+            MeshMaterial2d(material),
+            Particle { color_id },
+            Velocity::default(),
+            life::Age::default(),
+            lod::LodClock::default(),
+            Visibility::Inherited,
+        );
+        match self.pool.free.pop() {
+            Some(entity) => {
+                let mut entity = self.commands.entity(entity);
+                entity.insert(state);
+                entity
+            }
+            None => self
+                .commands
+                .spawn((Mesh2d(self.assets.quad.clone()), state)),
+        }
+    }
+
+    /// Removes a particle, parking its entity for reuse. Each entity must be
+    /// passed at most once per frame.
+    pub fn despawn(&mut self, entity: Entity) {
+        if self.pool.free.len() >= MAX_POOLED {
+            self.commands.entity(entity).despawn();
+            return;
+        }
+        self.commands
+            .entity(entity)
+            .remove::<(Particle, freeze::Frozen)>()
+            .insert(Visibility::Hidden);
+        self.pool.free.push(entity);
+    }
+}
+
+/// Moves, respecies, removes and adds particles until they match `shown`, a
+/// frame received from a server or read from a recording. Species beyond the
+/// palette are dropped.
+pub fn show_particles(
+    spawner: &mut ParticleSpawner,
+    particle_system: &ParticleSystem,
+    particles: &mut Query<(Entity, &mut Transform, &mut Particle)>,
+    shown: impl IntoIterator<Item = (Vec2, usize)>,
+) {
+    let n = particle_system.colors.len();
+    let mut shown = shown.into_iter().filter(|&(_, color_id)| color_id < n);
+    for (entity, mut transform, mut particle) in particles {
+        let Some((pos, color_id)) = shown.next() else {
+            spawner.despawn(entity);
+            continue;
+        };
+        transform.translation = pos.extend(0.0);
+        // Recoloring is left to the contrast pass, which picks up the change
+        if particle.color_id != color_id {
+            particle.color_id = color_id;
+        }
+    }
+    for (pos, color_id) in shown {
+        spawner.spawn(pos, color_id, particle_system.colors[color_id]);
+    }
+}
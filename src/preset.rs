@@ -0,0 +1,366 @@
+use bevy::{prelude::*, window::FileDragAndDrop};
+use bevy_egui::egui;
+
+use crate::{flow, settings::Settings, Particle, ParticleSystem};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub const PRESETS_DIR: &str = "presets";
+
+pub struct PresetPlugin;
+
+impl Plugin for PresetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LoadPreset>()
+            .add_event::<PresetSaved>()
+            .init_resource::<PresetStatus>()
+            .add_systems(Update, (read_dropped_files, apply_presets).chain());
+        #[cfg(not(target_arch = "wasm32"))]
+        hot_reload::build(app);
+        #[cfg(target_arch = "wasm32")]
+        {
+            let dropped = browser_drop::DroppedPresets::default();
+            browser_drop::listen(dropped.clone());
+            app.insert_resource(dropped)
+                .add_systems(Update, browser_drop::forward.before(apply_presets));
+        }
+    }
+}
+
+/// Sent when the current rules were saved or copied as a preset.
+#[derive(Event)]
+pub struct PresetSaved;
+
+/// Replace the current rules with a preset file's contents. `name` is the
+/// file name and decides the format: `.json` is JSON, anything else RON.
+#[derive(Event)]
+pub struct LoadPreset {
+    pub name: String,
+    pub text: String,
+}
+
+#[derive(Resource, Default)]
+pub struct PresetStatus {
+    message: Option<String>,
+}
+
+/// Parses preset text. Missing fields keep their defaults and the matrix is
+/// resized to the palette, so hand-written presets only need what they change.
+pub fn parse_preset(name: &str, text: &str) -> Result<ParticleSystem, String> {
+    let mut rules: ParticleSystem = if name.to_lowercase().ends_with(".json") {
+        serde_json::from_str(text).map_err(|error| error.to_string())?
+    } else {
+        ron::from_str(text).map_err(|error| error.to_string())?
+    };
+    if rules.colors.is_empty() {
+        return Err("preset has no colors".to_string());
+    }
+    rules.fit_to_palette();
+    Ok(rules)
+}
+
+pub fn preset_to_ron(rules: &ParticleSystem) -> Result<String, String> {
+    ron::ser::to_string_pretty(rules, ron::ser::PrettyConfig::default())
+        .map_err(|error| error.to_string())
+}
+
+fn read_dropped_files(
+    mut drops: EventReader<FileDragAndDrop>,
+    mut presets: EventWriter<LoadPreset>,
+    mut status: ResMut<PresetStatus>,
+) {
+    for drop in drops.read() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = drop else {
+            continue;
+        };
+        let name = path_buf
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        // Images become the flow field instead
+        if flow::is_image(&name) {
+            continue;
+        }
+        match std::fs::read_to_string(path_buf) {
+            Ok(text) => {
+                presets.send(LoadPreset { name, text });
+            }
+            Err(error) => status.message = Some(format!("Could not read {}: {}", name, error)),
+        }
+    }
+}
+
+/// Swaps in the new rules while keeping the particles where they are.
+/// Species beyond the new palette wrap around.
+pub fn apply_presets(
+    mut presets: EventReader<LoadPreset>,
+    mut particle_system: ResMut<ParticleSystem>,
+    mut settings: ResMut<Settings>,
+    mut status: ResMut<PresetStatus>,
+    mut particles: Query<&mut Particle>,
+) {
+    for preset in presets.read() {
+        let rules = match parse_preset(&preset.name, &preset.text) {
+            Ok(rules) => rules,
+            Err(error) => {
+                status.message = Some(format!("{} is not a valid preset: {}", preset.name, error));
+                continue;
+            }
+        };
+        *particle_system = rules;
+
+        let n = particle_system.colors.len();
+        for mut particle in &mut particles {
+            if particle.color_id >= n {
+                particle.color_id %= n;
+            }
+        }
+
+        info!("Loaded preset {}", preset.name);
+        status.message = Some(format!("Loaded {}", preset.name));
+        settings.last_preset = Some(preset.name.clone());
+    }
+}
+
+/// The "Presets" dock panel.
+pub fn presets_panel(
+    InMut(ui): InMut<egui::Ui>,
+    particle_system: Res<ParticleSystem>,
+    settings: Res<Settings>,
+    mut status: ResMut<PresetStatus>,
+    mut saved: EventWriter<PresetSaved>,
+) {
+    ui.label("Drop a preset .ron or .json file onto the window to load it.");
+    if let Some(last) = &settings.last_preset {
+        ui.label(format!("Last preset: {}", last));
+    }
+    ui.horizontal(|ui| {
+        if ui.button("Copy Current Rules").clicked() {
+            match preset_to_ron(&particle_system) {
+                Ok(text) => {
+                    ui.ctx().copy_text(text);
+                    status.message = Some("Copied rules as RON".to_string());
+                    saved.send(PresetSaved);
+                }
+                Err(error) => status.message = Some(format!("Copy failed: {}", error)),
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if ui.button("Save Current Rules").clicked() {
+            status.message = Some(match save_preset(&particle_system) {
+                Ok(path) => {
+                    saved.send(PresetSaved);
+                    format!("Saved {}", path.display())
+                }
+                Err(error) => format!("Save failed: {}", error),
+            });
+        }
+    });
+    if let Some(message) = &status.message {
+        ui.label(message);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_preset(rules: &ParticleSystem) -> Result<std::path::PathBuf, String> {
+    std::fs::create_dir_all(PRESETS_DIR).map_err(|error| error.to_string())?;
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let path = std::path::Path::new(PRESETS_DIR).join(format!("preset_{}.ron", seconds));
+    std::fs::write(&path, preset_to_ron(rules)?).map_err(|error| error.to_string())?;
+    Ok(path)
+}
+
+/// winit does not report file drops in the browser, so listen for the DOM
+/// `drop` event on the page and read the file through the Blob API.
+#[cfg(target_arch = "wasm32")]
+mod browser_drop {
+    use std::sync::{Arc, Mutex};
+
+    use bevy::prelude::*;
+    use wasm_bindgen::{closure::Closure, JsCast};
+    use wasm_bindgen_futures::{spawn_local, JsFuture};
+
+    use super::LoadPreset;
+
+    /// Files read by the browser, waiting to be turned into [`LoadPreset`] events.
+    #[derive(Resource, Clone, Default)]
+    pub struct DroppedPresets(Arc<Mutex<Vec<LoadPreset>>>);
+
+    pub fn listen(dropped: DroppedPresets) {
+        let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+            return;
+        };
+
+        // Without cancelling dragover the browser opens the file instead
+        let dragover =
+            Closure::<dyn FnMut(web_sys::DragEvent)>::new(|event: web_sys::DragEvent| {
+                event.prevent_default();
+            });
+        let drop =
+            Closure::<dyn FnMut(web_sys::DragEvent)>::new(move |event: web_sys::DragEvent| {
+                event.prevent_default();
+                let Some(files) = event.data_transfer().and_then(|transfer| transfer.files())
+                else {
+                    return;
+                };
+                for index in 0..files.length() {
+                    let Some(file) = files.get(index) else {
+                        continue;
+                    };
+                    let dropped = dropped.clone();
+                    spawn_local(async move {
+                        let Ok(text) = JsFuture::from(file.text()).await else {
+                            warn!("Could not read dropped file {}", file.name());
+                            return;
+                        };
+                        let Some(text) = text.as_string() else {
+                            return;
+                        };
+                        if let Ok(mut queue) = dropped.0.lock() {
+                            queue.push(LoadPreset {
+                                name: file.name(),
+                                text,
+                            });
+                        }
+                    });
+                }
+            });
+
+        let _ = document
+            .add_event_listener_with_callback("dragover", dragover.as_ref().unchecked_ref());
+        let _ = document.add_event_listener_with_callback("drop", drop.as_ref().unchecked_ref());
+        // The listeners live for the whole page
+        dragover.forget();
+        drop.forget();
+    }
+
+    pub fn forward(dropped: Res<DroppedPresets>, mut presets: EventWriter<LoadPreset>) {
+        if let Ok(mut queue) = dropped.0.lock() {
+            for preset in queue.drain(..) {
+                presets.send(preset);
+            }
+        }
+    }
+}
+
+/// Watches `presets/` through Bevy's asset server, so saving a preset file in
+/// an editor applies it to the running simulation at once.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod hot_reload {
+    use bevy::{
+        asset::{
+            io::{AssetSource, Reader},
+            AssetLoader, LoadContext, LoadedFolder,
+        },
+        prelude::*,
+    };
+    use std::{io, time::Duration};
+
+    use super::{LoadPreset, PRESETS_DIR};
+
+    /// Asset source name of the presets directory.
+    const SOURCE: &str = "presets";
+    /// Editors often write a file in several steps; wait for them to finish.
+    const DEBOUNCE: Duration = Duration::from_millis(300);
+
+    /// Adds the presets directory as a watched asset source. Sources have to
+    /// exist before `AssetPlugin` is built, so `main` calls this before adding
+    /// `DefaultPlugins`; without it presets are not reloaded.
+    pub fn register_source(app: &mut App) {
+        // The watcher is only created for a directory that exists
+        if let Err(error) = std::fs::create_dir_all(PRESETS_DIR) {
+            warn!("Could not create {}: {}", PRESETS_DIR, error);
+        }
+        app.register_asset_source(
+            SOURCE,
+            AssetSource::build()
+                .with_reader(AssetSource::get_default_reader(PRESETS_DIR.to_string()))
+                .with_watcher(AssetSource::get_default_watcher(
+                    PRESETS_DIR.to_string(),
+                    DEBOUNCE,
+                )),
+        );
+    }
+
+    /// Text of a preset file; parsed when applied, like dropped files.
+    #[derive(Asset, TypePath)]
+    pub struct PresetFile {
+        text: String,
+    }
+
+    #[derive(Default)]
+    struct PresetFileLoader;
+
+    impl AssetLoader for PresetFileLoader {
+        type Asset = PresetFile;
+        type Settings = ();
+        type Error = io::Error;
+
+        async fn load(
+            &self,
+            reader: &mut dyn Reader,
+            _settings: &(),
+            _load_context: &mut LoadContext<'_>,
+        ) -> Result<PresetFile, io::Error> {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let text = String::from_utf8(bytes)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            Ok(PresetFile { text })
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["ron", "json"]
+        }
+    }
+
+    /// Keeps every file in the presets directory loaded, so the asset server
+    /// notices when one changes.
+    #[derive(Resource)]
+    struct WatchedPresets {
+        _folder: Handle<LoadedFolder>,
+    }
+
+    pub fn build(app: &mut App) {
+        app.init_asset::<PresetFile>()
+            .init_asset_loader::<PresetFileLoader>()
+            .add_systems(Startup, watch_presets)
+            .add_systems(Update, reload_presets.before(super::apply_presets));
+    }
+
+    fn watch_presets(mut commands: Commands, asset_server: Res<AssetServer>) {
+        if asset_server.get_source(SOURCE).is_err() {
+            return;
+        }
+        let folder = asset_server.load_folder(format!("{}://", SOURCE));
+        commands.insert_resource(WatchedPresets { _folder: folder });
+    }
+
+    /// Applies a preset again whenever its file is modified on disk.
+    fn reload_presets(
+        mut events: EventReader<AssetEvent<PresetFile>>,
+        asset_server: Res<AssetServer>,
+        files: Res<Assets<PresetFile>>,
+        mut presets: EventWriter<LoadPreset>,
+    ) {
+        for event in events.read() {
+            let AssetEvent::Modified { id } = event else {
+                continue;
+            };
+            let (Some(path), Some(file)) = (asset_server.get_path(*id), files.get(*id)) else {
+                continue;
+            };
+            let name = path
+                .path()
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            info!("Preset {} changed on disk", name);
+            presets.send(LoadPreset {
+                name,
+                text: file.text.clone(),
+            });
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+
+const LEAF_CAPACITY: usize = 8;
+const MAX_DEPTH: u32 = 16;
+
+/// Particles of one species aggregated inside a node.
+#[derive(Clone, Copy)]
+struct SpeciesGroup {
+    species: usize,
+    count: u32,
+    position_sum: Vec2,
+}
+
+struct Node {
+    center: Vec2,
+    half_size: f32,
+    depth: u32,
+    /// Index of the first of four consecutive children, if subdivided.
+    children: Option<usize>,
+    particles: Vec<u32>,
+    groups: Vec<SpeciesGroup>,
+}
+
+impl Node {
+    fn new(center: Vec2, half_size: f32, depth: u32) -> Self {
+        Node {
+            center,
+            half_size,
+            depth,
+            children: None,
+            particles: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    fn quadrant(&self, pos: Vec2) -> usize {
+        (pos.x >= self.center.x) as usize | (((pos.y >= self.center.y) as usize) << 1)
+    }
+
+    fn add(&mut self, pos: Vec2, species: usize) {
+        match self
+            .groups
+            .iter_mut()
+            .find(|group| group.species == species)
+        {
+            Some(group) => {
+                group.count += 1;
+                group.position_sum += pos;
+            }
+            None => self.groups.push(SpeciesGroup {
+                species,
+                count: 1,
+                position_sum: pos,
+            }),
+        }
+    }
+
+    /// Squared distance from `pos` to the node's bounding square.
+    fn distance_squared(&self, pos: Vec2) -> f32 {
+        let d = ((pos - self.center).abs() - Vec2::splat(self.half_size)).max(Vec2::ZERO);
+        d.length_squared()
+    }
+}
+
+/// Barnes–Hut quadtree over particle positions. Distant nodes whose size to
+/// distance ratio is below `theta` act as one point per species at that
+/// species' center of mass, so large attraction radii cost roughly
+/// O(n log n) instead of O(n²).
+#[derive(Default)]
+pub struct QuadTree {
+    nodes: Vec<Node>,
+    stack: Vec<usize>,
+}
+
+impl QuadTree {
+    pub fn build(&mut self, positions: &[Vec2], species: &[usize]) {
+        self.nodes.clear();
+        if positions.is_empty() {
+            return;
+        }
+        let (min, max) = positions
+            .iter()
+            .fold((Vec2::MAX, Vec2::MIN), |(min, max), &pos| {
+                (min.min(pos), max.max(pos))
+            });
+        let half_size = (max - min).max_element() * 0.5 + 1.0;
+        self.nodes.push(Node::new((min + max) * 0.5, half_size, 0));
+
+        for index in 0..positions.len() {
+            self.insert(index as u32, positions, species);
+        }
+    }
+
+    fn insert(&mut self, index: u32, positions: &[Vec2], species: &[usize]) {
+        let pos = positions[index as usize];
+        let mut node = 0;
+        loop {
+            self.nodes[node].add(pos, species[index as usize]);
+            match self.nodes[node].children {
+                Some(first) => node = first + self.nodes[node].quadrant(pos),
+                None => {
+                    self.nodes[node].particles.push(index);
+                    if self.nodes[node].particles.len() > LEAF_CAPACITY
+                        && self.nodes[node].depth < MAX_DEPTH
+                    {
+                        self.subdivide(node, positions, species);
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    fn subdivide(&mut self, node: usize, positions: &[Vec2], species: &[usize]) {
+        let first = self.nodes.len();
+        let center = self.nodes[node].center;
+        let quarter = self.nodes[node].half_size * 0.5;
+        let depth = self.nodes[node].depth + 1;
+        for quadrant in 0..4 {
+            let offset = Vec2::new(
+                if quadrant & 1 == 1 { quarter } else { -quarter },
+                if quadrant & 2 == 2 { quarter } else { -quarter },
+            );
+            self.nodes.push(Node::new(center + offset, quarter, depth));
+        }
+        self.nodes[node].children = Some(first);
+
+        let particles = std::mem::take(&mut self.nodes[node].particles);
+        for index in particles {
+            let pos = positions[index as usize];
+            let child = first + self.nodes[node].quadrant(pos);
+            self.nodes[child].add(pos, species[index as usize]);
+            self.nodes[child].particles.push(index);
+        }
+        for child in first..first + 4 {
+            if self.nodes[child].particles.len() > LEAF_CAPACITY && depth < MAX_DEPTH {
+                self.subdivide(child, positions, species);
+            }
+        }
+    }
+
+    /// Sums `force(distance / radius, other_species) * direction` over every
+    /// particle (or approximated group) within `radius` of `pos`, returning the
+    /// total force and the number of particles it represents.
+    pub fn accumulate(
+        &mut self,
+        pos: Vec2,
+        radius: f32,
+        theta: f32,
+        positions: &[Vec2],
+        species: &[usize],
+        force: &dyn Fn(f32, usize) -> f32,
+    ) -> (Vec2, f32) {
+        let mut total = Vec2::ZERO;
+        let mut count = 0.0;
+        if self.nodes.is_empty() {
+            return (total, count);
+        }
+
+        let mut interact = |other_pos: Vec2, other_species: usize, weight: f32| {
+            let to_other = other_pos - pos;
+            let distance = to_other.length() / radius;
+            if distance < 1.0 && to_other != Vec2::ZERO {
+                total += to_other.normalize() * force(distance, other_species) * weight;
+                count += weight;
+            }
+        };
+
+        let radius_squared = radius * radius;
+        let QuadTree { nodes, stack } = self;
+        stack.clear();
+        stack.push(0);
+        while let Some(index) = stack.pop() {
+            let node = &nodes[index];
+            if node.distance_squared(pos) >= radius_squared {
+                continue;
+            }
+            let Some(first) = node.children else {
+                for &other in &node.particles {
+                    interact(positions[other as usize], species[other as usize], 1.0);
+                }
+                continue;
+            };
+
+            let outside = node.distance_squared(pos) > 0.0;
+            let distance = (node.center - pos).length();
+            if outside && node.half_size * 2.0 < theta * distance {
+                for group in &node.groups {
+                    let center_of_mass = group.position_sum / group.count as f32;
+                    interact(center_of_mass, group.species, group.count as f32);
+                }
+            } else {
+                stack.extend(first..first + 4);
+            }
+        }
+        (total, count)
+    }
+}
@@ -0,0 +1,157 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+use bevy_egui::{egui, EguiContexts};
+use rand::seq::IteratorRandom;
+
+use crate::{pool::ParticleSpawner, spawn::SpawnRequest, ui_enabled, Particle};
+
+const EVALUATION_INTERVAL: f32 = 1.0;
+const SETTLE_TIME: f32 = 3.0;
+const DEGRADE_BELOW: f32 = 0.9;
+const RESTORE_ABOVE: f32 = 1.2;
+const RADIUS_STEP: f32 = 0.85;
+const MIN_RADIUS_SCALE: f32 = 0.5;
+const MAX_UPDATE_STRIDE: u32 = 4;
+const PARTICLE_STEP: f32 = 0.1;
+const MIN_PARTICLES: usize = 500;
+
+pub struct QualityPlugin;
+
+impl Plugin for QualityPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(QualityGovernor::default()).add_systems(
+            Update,
+            (govern_quality, quality_ui_system.run_if(ui_enabled)),
+        );
+    }
+}
+
+/// Trades simulation fidelity for frame rate. Degrades by shrinking the
+/// interaction radius, then updating forces less often, then removing
+/// particles, and restores in the reverse order when there is headroom.
+#[derive(Resource)]
+pub struct QualityGovernor {
+    pub enabled: bool,
+    pub target_fps: f32,
+    /// Multiplier applied to the attraction radius by the simulation.
+    pub radius_scale: f32,
+    /// Forces are integrated every `update_stride` frames.
+    pub update_stride: u32,
+    /// Particles removed by the governor that will be restored later.
+    pub removed_particles: usize,
+    timer: Timer,
+    settle: f32,
+}
+
+impl Default for QualityGovernor {
+    fn default() -> Self {
+        QualityGovernor {
+            enabled: false,
+            target_fps: 60.0,
+            radius_scale: 1.0,
+            update_stride: 1,
+            removed_particles: 0,
+            timer: Timer::from_seconds(EVALUATION_INTERVAL, TimerMode::Repeating),
+            settle: 0.0,
+        }
+    }
+}
+
+impl QualityGovernor {
+    pub fn is_degraded(&self) -> bool {
+        self.radius_scale < 1.0 || self.update_stride > 1 || self.removed_particles > 0
+    }
+}
+
+fn govern_quality(
+    mut spawner: ParticleSpawner,
+    time: Res<Time>,
+    diagnostics: Res<DiagnosticsStore>,
+    mut governor: ResMut<QualityGovernor>,
+    mut spawn_requests: EventWriter<SpawnRequest>,
+    particles: Query<Entity, With<Particle>>,
+) {
+    governor.settle -= time.delta_secs();
+    if !governor.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    if !governor.enabled {
+        governor.radius_scale = 1.0;
+        governor.update_stride = 1;
+        // Give back the particles it took
+        if governor.removed_particles > 0 {
+            spawn_requests.send(SpawnRequest::Scatter {
+                count: governor.removed_particles,
+            });
+            governor.removed_particles = 0;
+        }
+        return;
+    }
+    if governor.settle > 0.0 {
+        return;
+    }
+    let Some(fps) = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+    else {
+        return;
+    };
+    let fps = fps as f32;
+
+    if fps < governor.target_fps * DEGRADE_BELOW {
+        if governor.radius_scale > MIN_RADIUS_SCALE {
+            governor.radius_scale = (governor.radius_scale * RADIUS_STEP).max(MIN_RADIUS_SCALE);
+        } else if governor.update_stride < MAX_UPDATE_STRIDE {
+            governor.update_stride *= 2;
+        } else {
+            let count = particles.iter().count();
+            let remove =
+                ((count as f32 * PARTICLE_STEP) as usize).min(count.saturating_sub(MIN_PARTICLES));
+            if remove == 0 {
+                return;
+            }
+            let mut rng = rand::rng();
+            for entity in particles.iter().choose_multiple(&mut rng, remove) {
+                spawner.despawn(entity);
+            }
+            governor.removed_particles += remove;
+        }
+        info!(
+            "Quality reduced at {:.0} FPS: radius x{:.2}, stride {}, {} particles removed",
+            fps, governor.radius_scale, governor.update_stride, governor.removed_particles
+        );
+    } else if fps > governor.target_fps * RESTORE_ABOVE && governor.is_degraded() {
+        if governor.removed_particles > 0 {
+            let count = (governor.removed_particles as f32 * 0.5).ceil() as usize;
+            spawn_requests.send(SpawnRequest::Scatter { count });
+            governor.removed_particles -= count;
+        } else if governor.update_stride > 1 {
+            governor.update_stride /= 2;
+        } else {
+            governor.radius_scale = (governor.radius_scale / RADIUS_STEP).min(1.0);
+        }
+        info!(
+            "Quality restored at {:.0} FPS: radius x{:.2}, stride {}, {} particles removed",
+            fps, governor.radius_scale, governor.update_stride, governor.removed_particles
+        );
+    } else {
+        return;
+    }
+    governor.settle = SETTLE_TIME;
+}
+
+fn quality_ui_system(mut contexts: EguiContexts, mut governor: ResMut<QualityGovernor>) {
+    egui::Window::new("Adaptive Quality")
+        .default_pos([400.0, 450.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut governor.enabled, "Hold target FPS");
+            ui.add(egui::Slider::new(&mut governor.target_fps, 15.0..=144.0).text("target FPS"));
+            ui.label(format!("Radius scale: {:.2}", governor.radius_scale));
+            ui.label(format!("Update every {} frame(s)", governor.update_stride));
+            ui.label(format!("Particles removed: {}", governor.removed_particles));
+        });
+}
@@ -0,0 +1,61 @@
+use bevy::render::{renderer::RenderAdapterInfo, settings::WgpuSettings};
+
+#[cfg(target_arch = "wasm32")]
+use bevy::render::settings::{Backends, WgpuLimits};
+
+/// Renderer settings for the windowed app. Bevy can only target one of
+/// WebGPU and WebGL2 per build, so web builds come in two flavors: the
+/// default WebGPU build and a `webgl2` feature build, with the page loading
+/// whichever the browser supports (see the README). The simulation itself
+/// always runs on the CPU, so only rendering changes.
+pub fn wgpu_settings() -> WgpuSettings {
+    #[cfg(all(target_arch = "wasm32", feature = "webgl2"))]
+    {
+        bevy::log::info!("WebGL2 build");
+        WgpuSettings {
+            backends: Some(Backends::GL),
+            limits: WgpuLimits::downlevel_webgl2_defaults(),
+            ..Default::default()
+        }
+    }
+    #[cfg(all(target_arch = "wasm32", not(feature = "webgl2")))]
+    {
+        if !browser_supports_webgpu() {
+            bevy::log::error!("This browser has no WebGPU; load the webgl2 build instead");
+        }
+        WgpuSettings {
+            backends: Some(Backends::BROWSER_WEBGPU),
+            limits: WgpuLimits::default(),
+            ..Default::default()
+        }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    WgpuSettings::default()
+}
+
+/// Whether the browser exposes `navigator.gpu`. Browsers that expose it but
+/// cannot provide an adapter still fail later; this only catches the common
+/// case of WebGPU not being shipped at all.
+#[cfg(all(target_arch = "wasm32", not(feature = "webgl2")))]
+fn browser_supports_webgpu() -> bool {
+    let Some(window) = web_sys::window() else {
+        return false;
+    };
+    js_sys::Reflect::get(&window.navigator(), &"gpu".into())
+        .map(|gpu| !gpu.is_undefined() && !gpu.is_null())
+        .unwrap_or(false)
+}
+
+/// Human readable name of the active graphics path, e.g. "WebGL2 (ANGLE ...)".
+pub fn describe(info: &RenderAdapterInfo) -> String {
+    let path = match info.backend.to_str() {
+        "webgpu" => "WebGPU",
+        "gl" if cfg!(target_arch = "wasm32") => "WebGL2",
+        "gl" => "OpenGL",
+        "vulkan" => "Vulkan",
+        "metal" => "Metal",
+        "dx12" => "DirectX 12",
+        other => other,
+    };
+    format!("{} ({})", path, info.name)
+}
@@ -0,0 +1,889 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    pool::{self, ParticleSpawner},
+    settings::Settings,
+    snapshot::ParticleSnapshot,
+    ui_enabled, Particle, ParticleSystem,
+};
+
+const REPLAY_PATH: &str = "replays/replay.plr";
+const REPLAY_MAGIC: &[u8; 4] = b"PLRP";
+const REPLAY_VERSION: u32 = 1;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Replay::default())
+            .add_observer(note_removed_particle)
+            .add_systems(
+                Update,
+                (
+                    handle_replay_keys,
+                    record_frame,
+                    record_events,
+                    play_frame,
+                    resimulate,
+                    replay_ui_system.run_if(ui_enabled),
+                )
+                    .chain()
+                    .after(crate::spawn::handle_spawn_requests)
+                    .before(crate::update_particles),
+            );
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct ReplayRules {
+    pub colors: Vec<[f32; 4]>,
+    pub behavior_matrix: Vec<Vec<f32>>,
+    pub speed: f32,
+    pub beta: f32,
+    pub gamma: f32,
+    pub attraction_radius: f32,
+}
+
+impl ReplayRules {
+    fn capture(particle_system: &ParticleSystem) -> Self {
+        ReplayRules {
+            colors: particle_system
+                .colors
+                .iter()
+                .map(|color| color.to_srgba().to_f32_array())
+                .collect(),
+            behavior_matrix: particle_system.behavior_matrix.clone(),
+            speed: particle_system.force_strength,
+            beta: particle_system.beta,
+            gamma: particle_system.gamma,
+            attraction_radius: particle_system.attraction_radius,
+        }
+    }
+
+    fn apply(&self, particle_system: &mut ParticleSystem) {
+        particle_system.colors = self
+            .colors
+            .iter()
+            .map(|&rgba| Color::from(Srgba::from_f32_array(rgba)))
+            .collect();
+        particle_system.behavior_matrix = self.behavior_matrix.clone();
+        particle_system.force_strength = self.speed;
+        particle_system.beta = self.beta;
+        particle_system.gamma = self.gamma;
+        particle_system.attraction_radius = self.attraction_radius;
+        particle_system.fit_to_palette();
+    }
+}
+
+/// A change made while recording, applied at the same tick when
+/// re-simulating.
+#[derive(Clone)]
+pub enum ReplayEvent {
+    /// A parameter set by its scripting name, e.g. `speed`.
+    Parameter { name: String, value: f32 },
+    /// One behavior matrix entry edited.
+    Behavior { from: usize, to: usize, value: f32 },
+    /// New colors or species count, with the whole matrix.
+    Rules(ReplayRules),
+    /// Particles added, by position and species.
+    Spawn(Vec<(Vec2, u16)>),
+    /// Particles removed, e.g. by the brush or the life rules, by their last
+    /// position and species.
+    Despawn(Vec<(Vec2, u16)>),
+    /// Every particle after all of them were replaced, by a reset or a
+    /// clear.
+    Respawn(Vec<(Vec2, u16)>),
+}
+
+/// Events with the tick they took effect on.
+type ReplayEvents = Vec<(u64, ReplayEvent)>;
+
+pub struct ReplayFrame {
+    pub tick: u64,
+    /// Only stored when the rules changed since the previous frame.
+    pub rules: Option<ReplayRules>,
+    pub particles: Vec<(Vec2, u16)>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ReplayMode {
+    Idle,
+    Recording,
+    Playback,
+    /// Running the simulation again from the first frame, applying the
+    /// recorded events at their ticks.
+    Resimulating,
+}
+
+#[derive(Resource)]
+pub struct Replay {
+    pub mode: ReplayMode,
+    pub frames: Vec<ReplayFrame>,
+    /// Changes made while recording, by the first tick they affected.
+    pub events: ReplayEvents,
+    /// Record a frame every `interval` ticks.
+    pub interval: u32,
+    pub cursor: usize,
+    pub playing: bool,
+    tick: u64,
+    last_rules: Option<ReplayRules>,
+    /// Rules and parameters as of the last recorded tick, to diff against.
+    recorded: Option<(ReplayRules, [(&'static str, f32); 6])>,
+    /// Index of the next event to apply when re-simulating.
+    next_event: usize,
+    /// Particles removed since the last recorded tick.
+    removed: Vec<(Vec2, u16)>,
+    shown_frame: Option<usize>,
+    message: Option<String>,
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Replay {
+            mode: ReplayMode::Idle,
+            frames: Vec::new(),
+            events: Vec::new(),
+            interval: 10,
+            cursor: 0,
+            playing: false,
+            tick: 0,
+            last_rules: None,
+            recorded: None,
+            next_event: 0,
+            removed: Vec::new(),
+            shown_frame: None,
+            message: None,
+        }
+    }
+}
+
+impl Replay {
+    fn start_recording(&mut self) {
+        self.mode = ReplayMode::Recording;
+        self.frames.clear();
+        self.events.clear();
+        self.tick = 0;
+        self.last_rules = None;
+        self.recorded = None;
+        self.removed.clear();
+    }
+
+    fn start_playback(&mut self) {
+        if self.frames.is_empty() {
+            self.message = Some("Nothing recorded yet".to_string());
+            return;
+        }
+        self.mode = ReplayMode::Playback;
+        self.cursor = 0;
+        self.playing = true;
+        self.shown_frame = None;
+    }
+
+    fn start_resimulating(&mut self) {
+        if self.frames.is_empty() {
+            self.message = Some("Nothing recorded yet".to_string());
+            return;
+        }
+        self.mode = ReplayMode::Resimulating;
+        self.tick = self.frames[0].tick;
+        self.next_event = 0;
+        self.shown_frame = None;
+    }
+
+    /// Rules in effect at `frame`, i.e. the most recent rules stored at or before it.
+    fn rules_at(&self, frame: usize) -> Option<&ReplayRules> {
+        self.frames[..=frame]
+            .iter()
+            .rev()
+            .find_map(|frame| frame.rules.as_ref())
+    }
+}
+
+pub fn is_playing_back(replay: Res<Replay>) -> bool {
+    replay.mode == ReplayMode::Playback
+}
+
+fn handle_replay_keys(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    mut replay: ResMut<Replay>,
+) {
+    if keyboard.just_pressed(settings.keys.record_replay) {
+        if replay.mode == ReplayMode::Recording {
+            replay.mode = ReplayMode::Idle;
+        } else {
+            replay.start_recording();
+        }
+    }
+    if keyboard.just_pressed(settings.keys.play_replay) {
+        if replay.mode == ReplayMode::Playback {
+            replay.mode = ReplayMode::Idle;
+        } else {
+            replay.start_playback();
+        }
+    }
+}
+
+fn record_frame(
+    particle_system: Res<ParticleSystem>,
+    mut replay: ResMut<Replay>,
+    particles: Query<(&Transform, &Particle)>,
+) {
+    if replay.mode != ReplayMode::Recording {
+        return;
+    }
+    replay.tick += 1;
+    if !(replay.tick - 1).is_multiple_of(replay.interval.max(1) as u64) {
+        return;
+    }
+
+    let rules = ReplayRules::capture(&particle_system);
+    let changed = replay.last_rules.as_ref() != Some(&rules);
+    let frame = ReplayFrame {
+        tick: replay.tick,
+        rules: changed.then(|| rules.clone()),
+        particles: particles
+            .iter()
+            .map(|(transform, particle)| {
+                (transform.translation.truncate(), particle.color_id as u16)
+            })
+            .collect(),
+    };
+    if changed {
+        replay.last_rules = Some(rules);
+    }
+    replay.frames.push(frame);
+}
+
+/// Notes where a particle was when it was removed, while its components are
+/// still there, so the removal can be recorded.
+fn note_removed_particle(
+    trigger: Trigger<OnRemove, Particle>,
+    particles: Query<(&Transform, &Particle)>,
+    mut replay: ResMut<Replay>,
+) {
+    if replay.mode != ReplayMode::Recording {
+        return;
+    }
+    if let Ok((transform, particle)) = particles.get(trigger.entity()) {
+        let listed = (transform.translation.truncate(), particle.color_id as u16);
+        replay.removed.push(listed);
+    }
+}
+
+/// Logs parameter changes, matrix edits and added or removed particles as
+/// events. Runs before the simulation, so each event is stamped with the
+/// first tick it affected.
+fn record_events(
+    particle_system: Res<ParticleSystem>,
+    mut replay: ResMut<Replay>,
+    added: Query<(&Transform, &Particle), Added<Particle>>,
+    particles: Query<(&Transform, &Particle)>,
+) {
+    let removed = std::mem::take(&mut replay.removed);
+    if replay.mode != ReplayMode::Recording {
+        return;
+    }
+    let replay = &mut *replay;
+    let tick = replay.tick;
+    let rules = ReplayRules::capture(&particle_system);
+    let parameters = particle_system.parameters();
+    let Some((last_rules, last_parameters)) = replay.recorded.replace((rules.clone(), parameters))
+    else {
+        // The first frame holds the starting state
+        return;
+    };
+
+    let events = &mut replay.events;
+    if rules.colors != last_rules.colors
+        || rules.behavior_matrix.len() != last_rules.behavior_matrix.len()
+    {
+        events.push((tick, ReplayEvent::Rules(rules)));
+    } else {
+        for (from, (row, last_row)) in rules
+            .behavior_matrix
+            .iter()
+            .zip(&last_rules.behavior_matrix)
+            .enumerate()
+        {
+            for (to, (&value, &last)) in row.iter().zip(last_row).enumerate() {
+                if value != last {
+                    events.push((tick, ReplayEvent::Behavior { from, to, value }));
+                }
+            }
+        }
+    }
+    for ((name, value), (_, last)) in parameters.into_iter().zip(last_parameters) {
+        if value != last {
+            let name = name.to_string();
+            events.push((tick, ReplayEvent::Parameter { name, value }));
+        }
+    }
+
+    let listed = |(transform, particle): (&Transform, &Particle)| {
+        (transform.translation.truncate(), particle.color_id as u16)
+    };
+    // Only a reset or a clear leaves none of the previous particles
+    let replaced = !removed.is_empty() && particles.iter().len() == added.iter().count();
+    if replaced {
+        events.push((
+            tick,
+            ReplayEvent::Respawn(particles.iter().map(listed).collect()),
+        ));
+        return;
+    }
+    if !removed.is_empty() {
+        events.push((tick, ReplayEvent::Despawn(removed)));
+    }
+    if !added.is_empty() {
+        events.push((tick, ReplayEvent::Spawn(added.iter().map(listed).collect())));
+    }
+}
+
+/// Shows the recorded particles, see [`pool::show_particles`].
+fn show_recorded(
+    recorded: &[(Vec2, u16)],
+    spawner: &mut ParticleSpawner,
+    particle_system: &ParticleSystem,
+    particles: &mut Query<(Entity, &mut Transform, &mut Particle)>,
+) {
+    let recorded = recorded
+        .iter()
+        .map(|&(pos, color_id)| (pos, color_id as usize));
+    pool::show_particles(spawner, particle_system, particles, recorded);
+}
+
+fn spawn_particles(recorded: &[(Vec2, u16)], spawner: &mut ParticleSpawner, colors: &[Color]) {
+    for &(pos, color_id) in recorded {
+        let color_id = color_id as usize;
+        // Species beyond a palette shrunk since are dropped
+        if let Some(&color) = colors.get(color_id) {
+            spawner.spawn(pos, color_id, color);
+        }
+    }
+}
+
+/// Removes, for each of `recorded`, the closest remaining particle of the
+/// same species, which is the removed one itself when the run has not
+/// drifted from the recording.
+fn despawn_particles(
+    recorded: &[(Vec2, u16)],
+    spawner: &mut ParticleSpawner,
+    particles: &Query<(Entity, &mut Transform, &mut Particle)>,
+) {
+    let mut despawned = Vec::with_capacity(recorded.len());
+    for &(pos, color_id) in recorded {
+        let closest = particles
+            .iter()
+            .filter(|(entity, _, particle)| {
+                particle.color_id == color_id as usize && !despawned.contains(entity)
+            })
+            .min_by(|(_, a, _), (_, b, _)| {
+                let a = a.translation.truncate().distance_squared(pos);
+                let b = b.translation.truncate().distance_squared(pos);
+                a.total_cmp(&b)
+            });
+        if let Some((entity, _, _)) = closest {
+            spawner.despawn(entity);
+            despawned.push(entity);
+        }
+    }
+}
+
+fn play_frame(
+    mut spawner: ParticleSpawner,
+    mut particle_system: ResMut<ParticleSystem>,
+    mut replay: ResMut<Replay>,
+    mut particles: Query<(Entity, &mut Transform, &mut Particle)>,
+) {
+    if replay.mode != ReplayMode::Playback || replay.frames.is_empty() {
+        return;
+    }
+    replay.cursor = replay.cursor.min(replay.frames.len() - 1);
+    if replay.shown_frame == Some(replay.cursor) {
+        if replay.playing && replay.cursor + 1 < replay.frames.len() {
+            replay.cursor += 1;
+        } else {
+            return;
+        }
+    }
+
+    let cursor = replay.cursor;
+    if let Some(rules) = replay.rules_at(cursor) {
+        rules.apply(&mut particle_system);
+    }
+
+    show_recorded(
+        &replay.frames[cursor].particles,
+        &mut spawner,
+        &particle_system,
+        &mut particles,
+    );
+    replay.shown_frame = Some(cursor);
+}
+
+/// Restores the first frame, then each tick applies that tick's events and
+/// leaves the rest to the simulation. Without deterministic mode the run
+/// drifts from the recording, but every change still lands on its tick.
+fn resimulate(
+    mut spawner: ParticleSpawner,
+    mut particle_system: ResMut<ParticleSystem>,
+    mut replay: ResMut<Replay>,
+    mut particles: Query<(Entity, &mut Transform, &mut Particle)>,
+) {
+    if replay.mode != ReplayMode::Resimulating || replay.frames.is_empty() {
+        return;
+    }
+    let replay = &mut *replay;
+    if replay.shown_frame.is_none() {
+        if let Some(rules) = replay.rules_at(0) {
+            rules.apply(&mut particle_system);
+        }
+        show_recorded(
+            &replay.frames[0].particles,
+            &mut spawner,
+            &particle_system,
+            &mut particles,
+        );
+        replay.shown_frame = Some(0);
+        return;
+    }
+
+    replay.tick += 1;
+    let tick = replay.tick;
+    while let Some((_, event)) = replay
+        .events
+        .get(replay.next_event)
+        .filter(|(event_tick, _)| *event_tick <= tick)
+    {
+        replay.next_event += 1;
+        match event {
+            ReplayEvent::Parameter { name, value } => {
+                if let Some(parameter) = particle_system.parameter_mut(name) {
+                    *parameter = *value;
+                }
+            }
+            ReplayEvent::Behavior { from, to, value } => {
+                if let Some(entry) = particle_system
+                    .behavior_matrix
+                    .get_mut(*from)
+                    .and_then(|row| row.get_mut(*to))
+                {
+                    *entry = *value;
+                }
+            }
+            ReplayEvent::Rules(rules) => rules.apply(&mut particle_system),
+            ReplayEvent::Spawn(added) => {
+                spawn_particles(added, &mut spawner, &particle_system.colors)
+            }
+            ReplayEvent::Despawn(removed) => despawn_particles(removed, &mut spawner, &particles),
+            ReplayEvent::Respawn(all) => {
+                show_recorded(all, &mut spawner, &particle_system, &mut particles)
+            }
+        }
+    }
+
+    let last_tick = replay.frames.last().map_or(0, |frame| frame.tick);
+    if replay.next_event == replay.events.len() && tick >= last_tick {
+        replay.mode = ReplayMode::Idle;
+        replay.message = Some(format!("Re-simulated up to tick {}", tick));
+    }
+}
+
+fn replay_ui_system(
+    mut contexts: EguiContexts,
+    mut replay: ResMut<Replay>,
+    settings: Res<Settings>,
+) {
+    let (record_key, play_key) = (settings.keys.record_replay, settings.keys.play_replay);
+    egui::Window::new("Replay")
+        .default_pos([400.0, 10.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                let recording = replay.mode == ReplayMode::Recording;
+                let action = if recording {
+                    "Stop Recording"
+                } else {
+                    "Record"
+                };
+                if ui
+                    .button(format!("{} ({:?})", action, record_key))
+                    .clicked()
+                {
+                    if recording {
+                        replay.mode = ReplayMode::Idle;
+                    } else {
+                        replay.start_recording();
+                    }
+                }
+                ui.label("every");
+                ui.add(egui::DragValue::new(&mut replay.interval).range(1..=600));
+                ui.label("ticks");
+            });
+            ui.label(format!(
+                "Frames: {}, events: {}",
+                replay.frames.len(),
+                replay.events.len()
+            ));
+
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    let result =
+                        save_replay(Path::new(REPLAY_PATH), &replay.frames, &replay.events);
+                    replay.message = Some(match result {
+                        Ok(()) => format!("Saved to {}", REPLAY_PATH),
+                        Err(err) => format!("Save failed: {}", err),
+                    });
+                }
+                if ui.button("Load").clicked() {
+                    match load_replay(Path::new(REPLAY_PATH)) {
+                        Ok((frames, events)) => {
+                            replay.mode = ReplayMode::Idle;
+                            replay.message = Some(format!(
+                                "Loaded {} frames and {} events",
+                                frames.len(),
+                                events.len()
+                            ));
+                            replay.frames = frames;
+                            replay.events = events;
+                        }
+                        Err(err) => replay.message = Some(format!("Load failed: {}", err)),
+                    }
+                }
+            });
+
+            ui.separator();
+            if replay.mode == ReplayMode::Playback {
+                let last = replay.frames.len().saturating_sub(1);
+                ui.horizontal(|ui| {
+                    let label = if replay.playing { "Pause" } else { "Play" };
+                    if ui.button(label).clicked() {
+                        replay.playing = !replay.playing;
+                    }
+                    if ui.button(format!("Stop ({:?})", play_key)).clicked() {
+                        replay.mode = ReplayMode::Idle;
+                    }
+                });
+                ui.add(egui::Slider::new(&mut replay.cursor, 0..=last).text("frame"));
+                if let Some(frame) = replay.frames.get(replay.cursor) {
+                    ui.label(format!("Tick {}", frame.tick));
+                }
+                if ui.button("Resume Simulation From Here").clicked() {
+                    let keep = replay.cursor + 1;
+                    replay.frames.truncate(keep);
+                    // Changes after this frame never happened in the resumed run
+                    let tick = replay.frames[replay.cursor].tick;
+                    let kept_events = replay
+                        .events
+                        .partition_point(|(event_tick, _)| *event_tick <= tick);
+                    replay.events.truncate(kept_events);
+                    replay.mode = ReplayMode::Idle;
+                }
+            } else if replay.mode == ReplayMode::Resimulating {
+                let last_tick = replay.frames.last().map_or(0, |frame| frame.tick);
+                ui.label(format!(
+                    "Re-simulating tick {} of {}",
+                    replay.tick, last_tick
+                ));
+                if ui.button("Stop").clicked() {
+                    replay.mode = ReplayMode::Idle;
+                }
+            } else {
+                ui.horizontal(|ui| {
+                    if ui.button(format!("Play Back ({:?})", play_key)).clicked() {
+                        replay.start_playback();
+                    }
+                    if ui
+                        .button("Re-simulate")
+                        .on_hover_text(
+                            "Runs the simulation again from the first frame with the recorded \
+                             changes; exact in deterministic mode",
+                        )
+                        .clicked()
+                    {
+                        replay.start_resimulating();
+                    }
+                });
+            }
+
+            if let Some(message) = &replay.message {
+                ui.label(message);
+            }
+        });
+}
+
+fn save_replay(
+    path: &Path,
+    frames: &[ReplayFrame],
+    events: &[(u64, ReplayEvent)],
+) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(REPLAY_MAGIC)?;
+    write_u32(&mut writer, REPLAY_VERSION)?;
+    write_u32(&mut writer, frames.len() as u32)?;
+    for frame in frames {
+        writer.write_all(&frame.tick.to_le_bytes())?;
+        match &frame.rules {
+            Some(rules) => {
+                writer.write_all(&[1])?;
+                write_rules(&mut writer, rules)?;
+            }
+            None => writer.write_all(&[0])?,
+        }
+        write_particles(&mut writer, &frame.particles)?;
+    }
+
+    write_u32(&mut writer, events.len() as u32)?;
+    for (tick, event) in events {
+        writer.write_all(&tick.to_le_bytes())?;
+        match event {
+            ReplayEvent::Parameter { name, value } => {
+                writer.write_all(&[0])?;
+                write_u32(&mut writer, name.len() as u32)?;
+                writer.write_all(name.as_bytes())?;
+                write_f32(&mut writer, *value)?;
+            }
+            ReplayEvent::Behavior { from, to, value } => {
+                writer.write_all(&[1])?;
+                write_u32(&mut writer, *from as u32)?;
+                write_u32(&mut writer, *to as u32)?;
+                write_f32(&mut writer, *value)?;
+            }
+            ReplayEvent::Rules(rules) => {
+                writer.write_all(&[2])?;
+                write_rules(&mut writer, rules)?;
+            }
+            ReplayEvent::Spawn(particles) => {
+                writer.write_all(&[3])?;
+                write_particles(&mut writer, particles)?;
+            }
+            ReplayEvent::Respawn(particles) => {
+                writer.write_all(&[4])?;
+                write_particles(&mut writer, particles)?;
+            }
+            ReplayEvent::Despawn(particles) => {
+                writer.write_all(&[5])?;
+                write_particles(&mut writer, particles)?;
+            }
+        }
+    }
+    writer.flush()
+}
+
+fn write_rules(writer: &mut impl Write, rules: &ReplayRules) -> io::Result<()> {
+    write_u32(writer, rules.colors.len() as u32)?;
+    for color in &rules.colors {
+        for channel in color {
+            write_f32(writer, *channel)?;
+        }
+    }
+    for row in &rules.behavior_matrix {
+        for value in row {
+            write_f32(writer, *value)?;
+        }
+    }
+    for value in [
+        rules.speed,
+        rules.beta,
+        rules.gamma,
+        rules.attraction_radius,
+    ] {
+        write_f32(writer, value)?;
+    }
+    Ok(())
+}
+
+fn write_particles(writer: &mut impl Write, particles: &[(Vec2, u16)]) -> io::Result<()> {
+    let mut snapshot = ParticleSnapshot::default();
+    for &(pos, color_id) in particles {
+        snapshot.push(pos, color_id as usize, None);
+    }
+    let bytes = snapshot.encode();
+    write_u32(writer, bytes.len() as u32)?;
+    writer.write_all(&bytes)
+}
+
+/// Frames and events of a replay file.
+fn load_replay(path: &Path) -> io::Result<(Vec<ReplayFrame>, ReplayEvents)> {
+    let file = File::open(path)?;
+    // No count can ask for more items than the file has bytes for
+    let limit = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != REPLAY_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a replay file",
+        ));
+    }
+    let version = read_u32(&mut reader)?;
+    if version != REPLAY_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported replay version {}", version),
+        ));
+    }
+
+    // A frame is at least its tick and rules flag
+    let frame_count = read_count(&mut reader, limit, 9)?;
+    let mut frames = Vec::with_capacity(frame_count);
+    for _ in 0..frame_count {
+        let mut tick = [0; 8];
+        reader.read_exact(&mut tick)?;
+        let mut has_rules = [0; 1];
+        reader.read_exact(&mut has_rules)?;
+        let rules = if has_rules[0] == 1 {
+            Some(read_rules(&mut reader, limit)?)
+        } else {
+            None
+        };
+
+        let particles = read_particles(&mut reader, limit)?;
+        frames.push(ReplayFrame {
+            tick: u64::from_le_bytes(tick),
+            rules,
+            particles,
+        });
+    }
+
+    if frames.first().is_some_and(|frame| frame.rules.is_none()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "first replay frame has no rules",
+        ));
+    }
+
+    // An event is at least its tick and kind
+    let event_count = read_count(&mut reader, limit, 9)?;
+    let mut events = Vec::with_capacity(event_count);
+    for _ in 0..event_count {
+        let mut tick = [0; 8];
+        reader.read_exact(&mut tick)?;
+        let mut kind = [0; 1];
+        reader.read_exact(&mut kind)?;
+        let event = match kind[0] {
+            0 => {
+                let length = read_count(&mut reader, limit, 1)?;
+                let mut name = String::new();
+                (&mut reader)
+                    .take(length as u64)
+                    .read_to_string(&mut name)?;
+                ReplayEvent::Parameter {
+                    name,
+                    value: read_f32(&mut reader)?,
+                }
+            }
+            1 => ReplayEvent::Behavior {
+                from: read_u32(&mut reader)? as usize,
+                to: read_u32(&mut reader)? as usize,
+                value: read_f32(&mut reader)?,
+            },
+            2 => ReplayEvent::Rules(read_rules(&mut reader, limit)?),
+            3 => ReplayEvent::Spawn(read_particles(&mut reader, limit)?),
+            4 => ReplayEvent::Respawn(read_particles(&mut reader, limit)?),
+            5 => ReplayEvent::Despawn(read_particles(&mut reader, limit)?),
+            kind => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown replay event {}", kind),
+                ))
+            }
+        };
+        events.push((u64::from_le_bytes(tick), event));
+    }
+    Ok((frames, events))
+}
+
+/// Particles stored as a [`ParticleSnapshot`] in a file of `limit` bytes.
+fn read_particles(reader: &mut impl Read, limit: u64) -> io::Result<Vec<(Vec2, u16)>> {
+    let length = read_count(reader, limit, 1)?;
+    let mut bytes = Vec::with_capacity(length);
+    reader.take(length as u64).read_to_end(&mut bytes)?;
+    let snapshot = ParticleSnapshot::decode(&bytes)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    Ok(snapshot
+        .positions
+        .into_iter()
+        .zip(snapshot.species)
+        .collect())
+}
+
+/// Rules from a file of `limit` bytes.
+fn read_rules(reader: &mut impl Read, limit: u64) -> io::Result<ReplayRules> {
+    // A color and a matrix row per species, and the row grows with the count
+    let n = read_count(reader, limit, 16)?;
+    if (n as u64).saturating_mul(n as u64).saturating_mul(4) > limit {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} species do not fit in the file", n),
+        ));
+    }
+    let mut colors = Vec::with_capacity(n);
+    for _ in 0..n {
+        colors.push([
+            read_f32(reader)?,
+            read_f32(reader)?,
+            read_f32(reader)?,
+            read_f32(reader)?,
+        ]);
+    }
+    let mut behavior_matrix = vec![vec![0.0; n]; n];
+    for row in &mut behavior_matrix {
+        for value in row {
+            *value = read_f32(reader)?;
+        }
+    }
+    Ok(ReplayRules {
+        colors,
+        behavior_matrix,
+        speed: read_f32(reader)?,
+        beta: read_f32(reader)?,
+        gamma: read_f32(reader)?,
+        attraction_radius: read_f32(reader)?,
+    })
+}
+
+fn write_u32(writer: &mut impl Write, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_f32(writer: &mut impl Write, value: f32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+/// A count of items at least `item_size` bytes each, rejected when they could
+/// not fit in a file of `limit` bytes, so a corrupt count fails the load
+/// instead of allocating without bound.
+fn read_count(reader: &mut impl Read, limit: u64, item_size: u64) -> io::Result<usize> {
+    let count = read_u32(reader)?;
+    if u64::from(count).saturating_mul(item_size) > limit {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("count {} does not fit in the file", count),
+        ));
+    }
+    Ok(count as usize)
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(f32::from_le_bytes(bytes))
+}
@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use rand::Rng;
+
+use crate::{settings::Settings, ui_enabled, MainCamera, ParticleSystem};
+
+/// Angular speeds of the camera's Lissajous drift, in radians per second.
+/// Incommensurate so the path does not visibly repeat.
+const DRIFT_FREQUENCIES: Vec2 = Vec2::new(0.05, 0.037);
+
+pub struct ScreensaverPlugin;
+
+impl Plugin for ScreensaverPlugin {
+    fn build(&self, app: &mut App) {
+        let screensaver = Screensaver {
+            enabled: std::env::args().any(|arg| arg == "--screensaver"),
+            ..Default::default()
+        };
+        app.insert_resource(screensaver).add_systems(
+            Update,
+            (
+                change_rules,
+                drift_camera,
+                screensaver_ui_system.run_if(ui_enabled),
+            ),
+        );
+    }
+}
+
+/// Unattended display mode: new rules every few seconds, optionally morphed
+/// in gradually, with new colors and a slowly wandering camera.
+#[derive(Resource)]
+pub struct Screensaver {
+    pub enabled: bool,
+    /// Seconds between rule changes.
+    pub interval: f32,
+    /// Seconds spent blending into new rules; 0 switches at once.
+    pub morph_time: f32,
+    pub cycle_palette: bool,
+    /// How far the camera wanders from where it started, in world units.
+    pub camera_drift: f32,
+    elapsed: f32,
+    morph: Option<Morph>,
+    drift_time: f32,
+}
+
+impl Default for Screensaver {
+    fn default() -> Self {
+        Screensaver {
+            enabled: false,
+            interval: 30.0,
+            morph_time: 5.0,
+            cycle_palette: true,
+            camera_drift: 300.0,
+            elapsed: 0.0,
+            morph: None,
+            drift_time: 0.0,
+        }
+    }
+}
+
+/// Blend from the previous rules toward the next ones.
+struct Morph {
+    matrix: (Vec<Vec<f32>>, Vec<Vec<f32>>),
+    colors: (Vec<Color>, Vec<Color>),
+    progress: f32,
+}
+
+fn change_rules(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut screensaver: ResMut<Screensaver>,
+    mut particle_system: ResMut<ParticleSystem>,
+) {
+    if !screensaver.enabled {
+        screensaver.morph = None;
+        return;
+    }
+    let step = time.delta_secs();
+    let screensaver = &mut *screensaver;
+    screensaver.elapsed += step;
+    if screensaver.elapsed >= screensaver.interval {
+        screensaver.elapsed = 0.0;
+        let n = particle_system.colors.len();
+        let mut rng = rand::rng();
+        let matrix: Vec<Vec<f32>> = (0..n)
+            .map(|_| (0..n).map(|_| rng.random_range(-1.0..=1.0)).collect())
+            .collect();
+        let colors = if screensaver.cycle_palette {
+            settings.palette.generate(n)
+        } else {
+            particle_system.colors.clone()
+        };
+        screensaver.morph = Some(Morph {
+            matrix: (particle_system.behavior_matrix.clone(), matrix),
+            colors: (particle_system.colors.clone(), colors),
+            progress: 0.0,
+        });
+    }
+
+    let Some(morph) = &mut screensaver.morph else {
+        return;
+    };
+    // Give up if the species count changed underneath
+    if morph.matrix.0.len() != particle_system.colors.len() {
+        screensaver.morph = None;
+        return;
+    }
+    morph.progress = if screensaver.morph_time > 0.0 {
+        (morph.progress + step / screensaver.morph_time).min(1.0)
+    } else {
+        1.0
+    };
+    let t = morph.progress;
+    let (from, to) = &morph.matrix;
+    for ((row, from_row), to_row) in particle_system.behavior_matrix.iter_mut().zip(from).zip(to) {
+        for ((value, &start), &end) in row.iter_mut().zip(from_row).zip(to_row) {
+            *value = start + (end - start) * t;
+        }
+    }
+    let (from, to) = &morph.colors;
+    for ((color, start), end) in particle_system.colors.iter_mut().zip(from).zip(to) {
+        *color = start.mix(end, t);
+    }
+    if t >= 1.0 {
+        screensaver.morph = None;
+    }
+}
+
+/// Moves the camera along a slow Lissajous curve. Only the change since the
+/// last frame is applied, so manual camera moves still work.
+fn drift_camera(
+    time: Res<Time>,
+    mut screensaver: ResMut<Screensaver>,
+    mut camera: Query<&mut Transform, With<MainCamera>>,
+) {
+    if !screensaver.enabled || screensaver.camera_drift <= 0.0 {
+        return;
+    }
+    let Ok(mut transform) = camera.get_single_mut() else {
+        return;
+    };
+    let offset = |t: f32| {
+        let phase = DRIFT_FREQUENCIES * t;
+        Vec2::new(phase.x.sin(), phase.y.sin())
+    };
+    let before = offset(screensaver.drift_time);
+    screensaver.drift_time += time.delta_secs();
+    let after = offset(screensaver.drift_time);
+    transform.translation += ((after - before) * screensaver.camera_drift).extend(0.0);
+}
+
+fn screensaver_ui_system(mut contexts: EguiContexts, mut screensaver: ResMut<Screensaver>) {
+    egui::Window::new("Screensaver")
+        .default_pos([400.0, 880.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Unattended mode: new rules every interval. Start with --screensaver.");
+            ui.add_space(5.0);
+            ui.checkbox(&mut screensaver.enabled, "Screensaver");
+            ui.add(
+                egui::Slider::new(&mut screensaver.interval, 5.0..=600.0)
+                    .logarithmic(true)
+                    .text("interval (s)"),
+            );
+            ui.add(egui::Slider::new(&mut screensaver.morph_time, 0.0..=60.0).text("morph (s)"));
+            ui.checkbox(&mut screensaver.cycle_palette, "Cycle palettes");
+            ui.add(
+                egui::Slider::new(&mut screensaver.camera_drift, 0.0..=1000.0).text("camera drift"),
+            );
+        });
+}
@@ -0,0 +1,365 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Scope, AST, FLOAT, INT};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::{pool::ParticleSpawner, replay, ui_enabled, Particle, ParticleSystem};
+
+const DEFAULT_SCRIPT_PATH: &str = "script.rhai";
+const TICK_FUNCTION: &str = "tick";
+/// Bounds each script call, so a runaway loop reports an error instead of
+/// freezing the app.
+const MAX_OPERATIONS: u64 = 5_000_000;
+#[cfg(not(target_arch = "wasm32"))]
+const RELOAD_INTERVAL: f32 = 1.0;
+
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        let mut host = ScriptHost::default();
+        // `--script <path>` runs a script from launch and reloads it on change
+        let args: Vec<String> = std::env::args().collect();
+        if let Some(index) = args.iter().position(|arg| arg == "--script") {
+            if let Some(path) = args.get(index + 1) {
+                host.path = path.clone();
+            }
+            host.enabled = true;
+        }
+
+        app.insert_resource(host).add_systems(
+            Update,
+            (
+                run_script.run_if(not(replay::is_playing_back)),
+                script_ui_system.run_if(ui_enabled),
+            )
+                .chain(),
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Update, reload_script.before(run_script));
+    }
+}
+
+/// What a script can see and change during one call. Filled from the ECS
+/// before the call and written back after it.
+#[derive(Default)]
+struct ScriptWorld {
+    particle_system: ParticleSystem,
+    /// Set when the script changed the matrix or a parameter.
+    dirty: bool,
+    time: f32,
+    particles: Vec<(Entity, Vec2, usize)>,
+    spawns: Vec<(Vec2, usize)>,
+    despawns: Vec<Entity>,
+}
+
+type SharedWorld = Arc<Mutex<ScriptWorld>>;
+
+fn with_world<T>(world: &SharedWorld, f: impl FnOnce(&mut ScriptWorld) -> T) -> T {
+    f(&mut world.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+fn checked_index(value: INT, len: usize, what: &str) -> Result<usize, Box<EvalAltResult>> {
+    usize::try_from(value)
+        .ok()
+        .filter(|&index| index < len)
+        .ok_or_else(|| format!("{} {} out of range 0..{}", what, value, len).into())
+}
+
+fn parameter<'a>(
+    particle_system: &'a mut ParticleSystem,
+    name: &str,
+) -> Result<&'a mut f32, Box<EvalAltResult>> {
+    particle_system
+        .parameter_mut(name)
+        .ok_or_else(|| format!("unknown parameter '{}'", name).into())
+}
+
+/// The functions available to scripts; see the README for the list.
+fn build_engine(world: &SharedWorld) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.on_print(|text| info!("script: {}", text));
+
+    let w = world.clone();
+    engine.register_fn("species_count", move || {
+        with_world(&w, |world| world.particle_system.colors.len() as INT)
+    });
+    let w = world.clone();
+    engine.register_fn("get_behavior", move |from: INT, to: INT| {
+        with_world(&w, |world| {
+            let n = world.particle_system.colors.len();
+            let (from, to) = (
+                checked_index(from, n, "species")?,
+                checked_index(to, n, "species")?,
+            );
+            Ok::<_, Box<EvalAltResult>>(world.particle_system.get_behavior(from, to) as FLOAT)
+        })
+    });
+    let w = world.clone();
+    engine.register_fn("set_behavior", move |from: INT, to: INT, value: FLOAT| {
+        with_world(&w, |world| {
+            let n = world.particle_system.colors.len();
+            let (from, to) = (
+                checked_index(from, n, "species")?,
+                checked_index(to, n, "species")?,
+            );
+            world.particle_system.behavior_matrix[from][to] = (value as f32).clamp(-1.0, 1.0);
+            world.dirty = true;
+            Ok::<_, Box<EvalAltResult>>(())
+        })
+    });
+    let w = world.clone();
+    engine.register_fn("get_param", move |name: &str| {
+        with_world(&w, |world| {
+            parameter(&mut world.particle_system, name).map(|value| *value as FLOAT)
+        })
+    });
+    let w = world.clone();
+    engine.register_fn("set_param", move |name: &str, value: FLOAT| {
+        with_world(&w, |world| {
+            *parameter(&mut world.particle_system, name)? = value as f32;
+            world.dirty = true;
+            Ok::<_, Box<EvalAltResult>>(())
+        })
+    });
+    let w = world.clone();
+    engine.register_fn("time", move || with_world(&w, |world| world.time as FLOAT));
+    let w = world.clone();
+    engine.register_fn("particle_count", move || {
+        with_world(&w, |world| world.particles.len() as INT)
+    });
+    let w = world.clone();
+    engine.register_fn("particle_position", move |index: INT| {
+        with_world(&w, |world| {
+            let index = checked_index(index, world.particles.len(), "particle")?;
+            let (_, position, _) = world.particles[index];
+            Ok::<_, Box<EvalAltResult>>(vec![
+                Dynamic::from(position.x as FLOAT),
+                Dynamic::from(position.y as FLOAT),
+            ] as Array)
+        })
+    });
+    let w = world.clone();
+    engine.register_fn("particle_species", move |index: INT| {
+        with_world(&w, |world| {
+            let index = checked_index(index, world.particles.len(), "particle")?;
+            Ok::<_, Box<EvalAltResult>>(world.particles[index].2 as INT)
+        })
+    });
+    let w = world.clone();
+    engine.register_fn("spawn", move |x: FLOAT, y: FLOAT, species: INT| {
+        with_world(&w, |world| {
+            let species = checked_index(species, world.particle_system.colors.len(), "species")?;
+            world.spawns.push((Vec2::new(x as f32, y as f32), species));
+            Ok::<_, Box<EvalAltResult>>(())
+        })
+    });
+    let w = world.clone();
+    engine.register_fn("despawn", move |index: INT| {
+        with_world(&w, |world| {
+            let index = checked_index(index, world.particles.len(), "particle")?;
+            let entity = world.particles[index].0;
+            world.despawns.push(entity);
+            Ok::<_, Box<EvalAltResult>>(())
+        })
+    });
+    engine
+}
+
+/// A Rhai script that runs its top-level code once when loaded and then its
+/// `tick(dt)` function every frame.
+#[derive(Resource)]
+pub struct ScriptHost {
+    pub enabled: bool,
+    pub path: String,
+    source: String,
+    engine: Engine,
+    ast: Option<AST>,
+    scope: Scope<'static>,
+    world: SharedWorld,
+    has_tick: bool,
+    /// The top-level code of a newly loaded script still has to run.
+    initialize: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    modified: Option<std::time::SystemTime>,
+    #[cfg(not(target_arch = "wasm32"))]
+    reload_timer: Timer,
+    status: Option<String>,
+}
+
+impl Default for ScriptHost {
+    fn default() -> Self {
+        let world = SharedWorld::default();
+        ScriptHost {
+            enabled: false,
+            path: DEFAULT_SCRIPT_PATH.to_string(),
+            source: String::new(),
+            engine: build_engine(&world),
+            ast: None,
+            scope: Scope::new(),
+            world,
+            has_tick: false,
+            initialize: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            modified: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            reload_timer: Timer::from_seconds(RELOAD_INTERVAL, TimerMode::Repeating),
+            status: None,
+        }
+    }
+}
+
+impl ScriptHost {
+    fn compile(&mut self) {
+        match self.engine.compile(&self.source) {
+            Ok(ast) => {
+                self.has_tick = ast
+                    .iter_functions()
+                    .any(|function| function.name == TICK_FUNCTION);
+                self.ast = Some(ast);
+                self.initialize = true;
+                self.status = Some(if self.has_tick {
+                    "Script loaded".to_string()
+                } else {
+                    format!("Script loaded without a {}(dt) function", TICK_FUNCTION)
+                });
+            }
+            Err(error) => self.status = Some(format!("Compile error: {}", error)),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_file(&mut self) {
+        match std::fs::read_to_string(&self.path) {
+            Ok(text) => {
+                self.source = text;
+                self.compile();
+            }
+            Err(error) => self.status = Some(format!("Could not read {}: {}", self.path, error)),
+        }
+    }
+}
+
+/// Recompiles the script whenever its file's modification time changes.
+#[cfg(not(target_arch = "wasm32"))]
+fn reload_script(time: Res<Time>, mut host: ResMut<ScriptHost>) {
+    if !host.enabled || !host.reload_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let modified = std::fs::metadata(&host.path)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    if modified.is_some() && modified != host.modified {
+        host.modified = modified;
+        host.load_file();
+    }
+}
+
+fn run_script(
+    mut spawner: ParticleSpawner,
+    time: Res<Time>,
+    mut host: ResMut<ScriptHost>,
+    mut particle_system: ResMut<ParticleSystem>,
+    particles: Query<(Entity, &Transform, &Particle)>,
+) {
+    let ScriptHost {
+        enabled,
+        engine,
+        ast,
+        scope,
+        world,
+        has_tick,
+        initialize,
+        status,
+        ..
+    } = &mut *host;
+    let Some(ast) = ast else {
+        return;
+    };
+    if !*enabled || !(*initialize || *has_tick) {
+        return;
+    }
+
+    with_world(world, |world| {
+        world.particle_system = particle_system.clone();
+        world.dirty = false;
+        world.time = time.elapsed_secs();
+        world.particles.clear();
+        world
+            .particles
+            .extend(particles.iter().map(|(entity, transform, particle)| {
+                (entity, transform.translation.truncate(), particle.color_id)
+            }));
+        world.spawns.clear();
+        world.despawns.clear();
+    });
+
+    let result = if *initialize {
+        *initialize = false;
+        *scope = Scope::new();
+        engine.run_ast_with_scope(scope, ast)
+    } else {
+        let options = CallFnOptions::new().eval_ast(false);
+        let dt = time.delta_secs() as FLOAT;
+        engine
+            .call_fn_with_options::<Dynamic>(options, scope, ast, TICK_FUNCTION, (dt,))
+            .map(|_| ())
+    };
+    // Stop on the first error rather than logging it every frame
+    if let Err(error) = result {
+        *status = Some(format!("Script error: {}", error));
+        *enabled = false;
+        return;
+    }
+
+    with_world(world, |world| {
+        if world.dirty {
+            let scripted = &world.particle_system;
+            particle_system.behavior_matrix = scripted.behavior_matrix.clone();
+            particle_system.force_strength = scripted.force_strength;
+            particle_system.time_scale = scripted.time_scale;
+            particle_system.beta = scripted.beta;
+            particle_system.gamma = scripted.gamma;
+            particle_system.attraction_radius = scripted.attraction_radius;
+            particle_system.temperature = scripted.temperature;
+        }
+        world.despawns.sort_unstable();
+        world.despawns.dedup();
+        for entity in world.despawns.drain(..) {
+            spawner.despawn(entity);
+        }
+        for (position, color_id) in world.spawns.drain(..) {
+            spawner.spawn(position, color_id, particle_system.colors[color_id]);
+        }
+    });
+}
+
+fn script_ui_system(mut contexts: EguiContexts, mut host: ResMut<ScriptHost>) {
+    egui::Window::new("Script")
+        .default_pos([620.0, 570.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.checkbox(&mut host.enabled, "Run script");
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut host.path);
+                if ui.button("Reload File").clicked() {
+                    host.load_file();
+                }
+            });
+            ui.label("Rhai source:");
+            ui.add(
+                egui::TextEdit::multiline(&mut host.source)
+                    .code_editor()
+                    .desired_rows(8),
+            );
+            if ui.button("Run Text").clicked() {
+                host.compile();
+                host.enabled = true;
+            }
+            if let Some(status) = &host.status {
+                ui.label(status);
+            }
+        });
+}
@@ -0,0 +1,468 @@
+use bevy::{prelude::*, window::PresentMode};
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    background::BackgroundTheme,
+    hardware::HardwareProfile,
+    menu,
+    midi::MidiBinding,
+    palette::{self, PaletteSettings},
+    particle_assets::ParticleLook,
+    shapes,
+    stress::StressConfig,
+    ui_enabled, ParticleCount, ParticleSystem,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+const SETTINGS_PATH: &str = "settings.ron";
+#[cfg(target_arch = "wasm32")]
+const STORAGE_KEY: &str = "particle_life_settings";
+
+/// Persists [`Settings`] between sessions. The resource itself is inserted by
+/// `main` because the window needs it before plugins are built.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>()
+            .add_systems(
+                Update,
+                settings_ui_system.run_if(ui_enabled.or(in_state(menu::AppState::Menu))),
+            )
+            .add_systems(Last, save_settings);
+    }
+}
+
+/// User preferences restored at startup from `settings.ron` (native) or
+/// localStorage (wasm). Unknown or missing fields fall back to defaults, so
+/// files from older versions keep loading.
+#[derive(Resource, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub particle_count: usize,
+    pub vsync: bool,
+    /// Most frames per second to run at; 0 leaves the rate uncapped.
+    pub fps_cap: u32,
+    /// Start with a particle count picked for this machine while the saved
+    /// count is still the stock default.
+    pub hardware_defaults: bool,
+    /// Compute the forces on a separate thread (desktop only).
+    pub simulation_thread: bool,
+    /// Slow to a trickle while the window is unfocused or the tab is hidden.
+    pub background_throttle: bool,
+    /// Fixed steps, no timing-dependent shortcuts and seeded resets, so runs
+    /// repeat bit for bit.
+    pub deterministic: bool,
+    /// Also round positions to a fixed-point grid every tick.
+    pub fixed_point: bool,
+    /// Seed that deterministic resets lay out the world from.
+    pub seed: u64,
+    /// Most simulation ticks per second; 0 ticks every frame.
+    pub tick_rate: u32,
+    /// Draw particles between their last two ticks when ticks are less
+    /// frequent than frames.
+    pub interpolate: bool,
+    pub background: BackgroundTheme,
+    /// Shift species lightness away from the background's so all stay visible.
+    pub contrast_adjust: bool,
+    /// How colors are generated for new species.
+    pub palette: PaletteSettings,
+    /// Draw each species with its own marker shape instead of all circles.
+    pub species_shapes: bool,
+    /// Edge softness, glow and zoom behavior of the particle shader.
+    pub particle_look: ParticleLook,
+    /// Above this many particles they are drawn as flat point sprites from
+    /// one buffer; 0 always draws meshes.
+    pub point_sprite_threshold: usize,
+    pub keys: KeyBindings,
+    pub midi_bindings: Vec<MidiBinding>,
+    /// Name of the last preset that was loaded, if any.
+    pub last_preset: Option<String>,
+    /// Arrangement of the docked panels, as RON.
+    pub dock_layout: Option<String>,
+    /// The controls overview was dismissed, so it no longer opens at launch.
+    pub seen_help: bool,
+    /// Tutorial steps completed, so the tutorial resumes where it was left.
+    pub tutorial_progress: usize,
+    /// Time constant of the camera's pan and zoom easing in seconds; 0 moves
+    /// it instantly.
+    pub camera_smoothing: f32,
+    /// Ticks simulated before the first frame with particles is shown.
+    pub warmup_ticks: u32,
+    /// Minutes between autosaves of the whole world; 0 turns them off.
+    pub autosave_minutes: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            particle_count: crate::NUM_PARTICLES,
+            vsync: true,
+            fps_cap: 0,
+            background_throttle: true,
+            simulation_thread: false,
+            hardware_defaults: true,
+            deterministic: false,
+            fixed_point: false,
+            seed: 0,
+            tick_rate: 0,
+            interpolate: true,
+            background: BackgroundTheme::default(),
+            contrast_adjust: true,
+            palette: PaletteSettings::default(),
+            species_shapes: false,
+            particle_look: ParticleLook::default(),
+            point_sprite_threshold: 50_000,
+            keys: KeyBindings::default(),
+            midi_bindings: Vec::new(),
+            last_preset: None,
+            dock_layout: None,
+            seen_help: false,
+            tutorial_progress: 0,
+            camera_smoothing: 0.12,
+            warmup_ticks: 0,
+            autosave_minutes: 5.0,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub camera_up: KeyCode,
+    pub camera_down: KeyCode,
+    pub camera_left: KeyCode,
+    pub camera_right: KeyCode,
+    pub zoom_in: KeyCode,
+    pub zoom_out: KeyCode,
+    pub speed_up: KeyCode,
+    pub slow_down: KeyCode,
+    pub restart: KeyCode,
+    pub respawn: KeyCode,
+    pub regenerate_behaviors: KeyCode,
+    pub reroll_matrix: KeyCode,
+    pub perturb_matrix: KeyCode,
+    pub regenerate_constants: KeyCode,
+    pub export: KeyCode,
+    pub record_replay: KeyCode,
+    pub play_replay: KeyCode,
+    pub cycle_backend: KeyCode,
+    pub toggle_audio: KeyCode,
+    pub toggle_heatmap: KeyCode,
+    pub toggle_flow: KeyCode,
+    pub toggle_perf_overlay: KeyCode,
+    pub cycle_log_level: KeyCode,
+    pub toggle_help: KeyCode,
+    pub toggle_minimap: KeyCode,
+    pub toggle_legend: KeyCode,
+    pub pause: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            camera_up: KeyCode::KeyW,
+            camera_down: KeyCode::KeyS,
+            camera_left: KeyCode::KeyA,
+            camera_right: KeyCode::KeyD,
+            zoom_in: KeyCode::ArrowUp,
+            zoom_out: KeyCode::ArrowDown,
+            speed_up: KeyCode::ArrowRight,
+            slow_down: KeyCode::ArrowLeft,
+            restart: KeyCode::KeyR,
+            respawn: KeyCode::KeyN,
+            regenerate_behaviors: KeyCode::KeyQ,
+            reroll_matrix: KeyCode::KeyY,
+            perturb_matrix: KeyCode::KeyU,
+            regenerate_constants: KeyCode::KeyT,
+            export: KeyCode::KeyX,
+            record_replay: KeyCode::F5,
+            play_replay: KeyCode::F6,
+            cycle_backend: KeyCode::F2,
+            toggle_audio: KeyCode::KeyM,
+            toggle_heatmap: KeyCode::KeyH,
+            toggle_flow: KeyCode::KeyF,
+            toggle_perf_overlay: KeyCode::F3,
+            cycle_log_level: KeyCode::F4,
+            // H already toggles the heatmap
+            toggle_help: KeyCode::F1,
+            toggle_minimap: KeyCode::KeyO,
+            toggle_legend: KeyCode::KeyL,
+            pause: KeyCode::KeyP,
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Every binding with a display name, in the order shown in the UI.
+    pub fn entries_mut(&mut self) -> [(&'static str, &mut KeyCode); 27] {
+        [
+            ("Camera up", &mut self.camera_up),
+            ("Camera down", &mut self.camera_down),
+            ("Camera left", &mut self.camera_left),
+            ("Camera right", &mut self.camera_right),
+            ("Zoom in", &mut self.zoom_in),
+            ("Zoom out", &mut self.zoom_out),
+            ("Speed up time", &mut self.speed_up),
+            ("Slow down time", &mut self.slow_down),
+            ("Restart", &mut self.restart),
+            ("Respawn particles", &mut self.respawn),
+            ("New behaviors", &mut self.regenerate_behaviors),
+            ("Reroll matrix", &mut self.reroll_matrix),
+            ("Perturb matrix", &mut self.perturb_matrix),
+            ("New constants", &mut self.regenerate_constants),
+            ("Export CSV", &mut self.export),
+            ("Record replay", &mut self.record_replay),
+            ("Play replay", &mut self.play_replay),
+            ("Cycle backend", &mut self.cycle_backend),
+            ("Toggle audio", &mut self.toggle_audio),
+            ("Toggle heatmap", &mut self.toggle_heatmap),
+            ("Toggle flow field", &mut self.toggle_flow),
+            ("Performance overlay", &mut self.toggle_perf_overlay),
+            ("Cycle log level", &mut self.cycle_log_level),
+            ("Help", &mut self.toggle_help),
+            ("Toggle minimap", &mut self.toggle_minimap),
+            ("Toggle species legend", &mut self.toggle_legend),
+            ("Pause", &mut self.pause),
+        ]
+    }
+
+    /// Every binding with its display name, in the same order.
+    pub fn entries(&self) -> [(&'static str, KeyCode); 27] {
+        self.clone().entries_mut().map(|(name, key)| (name, *key))
+    }
+}
+
+impl Settings {
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+
+    /// Loads saved settings, falling back to defaults if there are none or
+    /// they cannot be parsed.
+    pub fn load() -> Self {
+        let Some(text) = read_stored() else {
+            return Settings::default();
+        };
+        match ron::from_str(&text) {
+            Ok(settings) => settings,
+            Err(error) => {
+                warn!("Ignoring unreadable settings: {}", error);
+                Settings::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(text) => write_stored(&text),
+            Err(error) => warn!("Failed to serialize settings: {}", error),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_stored() -> Option<String> {
+    std::fs::read_to_string(SETTINGS_PATH).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_stored(text: &str) {
+    if let Err(error) = std::fs::write(SETTINGS_PATH, text) {
+        warn!("Failed to write {}: {}", SETTINGS_PATH, error);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_stored() -> Option<String> {
+    local_storage()?.get_item(STORAGE_KEY).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_stored(text: &str) {
+    let stored = local_storage().map(|storage| storage.set_item(STORAGE_KEY, text));
+    if !matches!(stored, Some(Ok(()))) {
+        warn!("Failed to write settings to localStorage");
+    }
+}
+
+/// Saves on exit. Browsers never deliver `AppExit` when a tab is closed, so
+/// on wasm every change is written immediately instead.
+fn save_settings(
+    mut exit: EventReader<AppExit>,
+    particle_count: Res<ParticleCount>,
+    stress: Option<Res<StressConfig>>,
+    mut settings: ResMut<Settings>,
+) {
+    // A stress run's count is not the user's preference
+    if stress.is_none() && settings.particle_count != particle_count.count {
+        settings.particle_count = particle_count.count;
+    }
+    let exiting = exit.read().count() > 0;
+    let save_now = cfg!(target_arch = "wasm32") && settings.is_changed() && !settings.is_added();
+    if exiting || save_now {
+        settings.save();
+    }
+}
+
+/// Index into [`KeyBindings::entries_mut`] waiting for a new key.
+#[derive(Resource, Default)]
+struct Rebinding(Option<usize>);
+
+fn settings_ui_system(
+    mut contexts: EguiContexts,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut rebinding: ResMut<Rebinding>,
+    hardware: Res<HardwareProfile>,
+    mut windows: Query<&mut Window>,
+) {
+    // Edit a copy so the resource is only marked changed when something was edited
+    let mut edited = settings.clone();
+    if let Some(index) = rebinding.0 {
+        if let Some(&key) = keyboard.get_just_pressed().next() {
+            if key != KeyCode::Escape {
+                *edited.keys.entries_mut()[index].1 = key;
+            }
+            rebinding.0 = None;
+        }
+    }
+
+    egui::Window::new("Settings")
+        .default_pos([620.0, 10.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            if ui.checkbox(&mut edited.vsync, "VSync").changed() {
+                let present_mode = edited.present_mode();
+                for mut window in &mut windows {
+                    window.present_mode = present_mode;
+                }
+            }
+            ui.add(egui::Slider::new(&mut edited.fps_cap, 0..=240).text("FPS cap (0 = uncapped)"));
+            ui.horizontal(|ui| {
+                for cap in [30, 60] {
+                    if ui.button(format!("{} FPS", cap)).clicked() {
+                        edited.fps_cap = cap;
+                    }
+                }
+                if ui.button("Uncapped").clicked() {
+                    edited.fps_cap = 0;
+                }
+            });
+            ui.checkbox(
+                &mut edited.background_throttle,
+                "Throttle in the background",
+            );
+            ui.checkbox(
+                &mut edited.hardware_defaults,
+                "Pick the default particle count for this hardware",
+            );
+            ui.label(format!("Detected: {}", hardware.summary()));
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.checkbox(
+                &mut edited.simulation_thread,
+                "Simulate on a separate thread (core forces only)",
+            );
+            ui.add(
+                egui::Slider::new(&mut edited.camera_smoothing, 0.0..=1.0)
+                    .text("camera smoothing (s)"),
+            );
+            ui.add(
+                egui::Slider::new(&mut edited.warmup_ticks, 0..=5000)
+                    .text("warm-up ticks at launch"),
+            );
+            ui.add(
+                egui::Slider::new(&mut edited.autosave_minutes, 0.0..=60.0)
+                    .text("autosave interval (min, 0 = off)"),
+            );
+            ui.add(
+                egui::Slider::new(&mut edited.tick_rate, 0..=240)
+                    .text("simulation ticks per second (0 = every frame)"),
+            );
+            ui.checkbox(&mut edited.interpolate, "Smooth motion between ticks");
+
+            ui.separator();
+            ui.checkbox(&mut edited.deterministic, "Deterministic simulation")
+                .on_hover_text("Fixed 1/60 s steps and seeded resets, for replays and networking");
+            ui.add_enabled_ui(edited.deterministic, |ui| {
+                ui.checkbox(&mut edited.fixed_point, "Fixed-point positions");
+                ui.horizontal(|ui| {
+                    ui.label("Seed");
+                    ui.add(egui::DragValue::new(&mut edited.seed));
+                });
+            });
+
+            ui.separator();
+            edited.background.settings_ui(ui);
+            ui.checkbox(
+                &mut edited.contrast_adjust,
+                "Adjust species colors for contrast",
+            );
+
+            ui.separator();
+            edited.particle_look.settings_ui(ui);
+            ui.horizontal(|ui| {
+                ui.label("Point sprites above");
+                ui.add(
+                    egui::DragValue::new(&mut edited.point_sprite_threshold)
+                        .speed(100)
+                        .range(0..=1_000_000),
+                );
+                ui.label("particles (0 = never)");
+            });
+
+            ui.separator();
+            ui.label("Key bindings (click, then press a key; Esc cancels)");
+            egui::Grid::new("key_bindings").show(ui, |ui| {
+                for (index, (name, key)) in edited.keys.entries_mut().into_iter().enumerate() {
+                    ui.label(name);
+                    let text = if rebinding.0 == Some(index) {
+                        "press a key…".to_string()
+                    } else {
+                        format!("{:?}", key)
+                    };
+                    if ui.button(text).clicked() {
+                        rebinding.0 = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+            if ui.button("Reset to defaults").clicked() {
+                edited.keys = KeyBindings::default();
+            }
+        });
+    settings.set_if_neq(edited);
+}
+
+/// The "Palette" dock panel: species colors and shapes.
+pub fn palette_panel(
+    InMut(ui): InMut<egui::Ui>,
+    mut settings: ResMut<Settings>,
+    mut particle_system: ResMut<ParticleSystem>,
+) {
+    let mut edited = settings.clone();
+    edited.palette.settings_ui(ui);
+    ui.checkbox(&mut edited.species_shapes, "Species shapes");
+    ui.horizontal(|ui| {
+        if ui.button("Reshape species").clicked() {
+            particle_system.shapes = shapes::generate(particle_system.colors.len());
+        }
+        if ui.button("Recolor species").clicked() {
+            particle_system.colors = edited.palette.generate(particle_system.colors.len());
+        }
+        if let Some(distance) = palette::closest_pair(&particle_system.colors) {
+            ui.label(format!("closest pair ΔE {:.3}", distance));
+        }
+    });
+    settings.set_if_neq(edited);
+}
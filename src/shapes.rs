@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    particle_assets::{ParticleAssets, ParticleMaterial},
+    settings::Settings,
+    ParticleSystem,
+};
+
+pub struct ShapesPlugin;
+
+impl Plugin for ShapesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, apply_shapes);
+    }
+}
+
+/// Marker shape drawn for a species, so species stay apart even when their
+/// colors are hard to tell apart.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum SpeciesShape {
+    #[default]
+    Circle,
+    Square,
+    Triangle,
+}
+
+impl SpeciesShape {
+    const ALL: [SpeciesShape; 3] = [
+        SpeciesShape::Circle,
+        SpeciesShape::Square,
+        SpeciesShape::Triangle,
+    ];
+
+    /// Shape number `particle.wgsl` draws. Each shape covers about the same
+    /// area as the circle.
+    pub fn shader_index(self) -> f32 {
+        match self {
+            SpeciesShape::Circle => 0.0,
+            SpeciesShape::Square => 1.0,
+            SpeciesShape::Triangle => 2.0,
+        }
+    }
+
+    /// Character for the shape in the UI.
+    pub fn glyph(self) -> &'static str {
+        match self {
+            SpeciesShape::Circle => "●",
+            SpeciesShape::Square => "■",
+            SpeciesShape::Triangle => "▲",
+        }
+    }
+}
+
+/// Shapes for `count` species, each shape used equally often in random order.
+pub fn generate(count: usize) -> Vec<SpeciesShape> {
+    let mut shapes: Vec<SpeciesShape> = (0..count)
+        .map(|i| SpeciesShape::ALL[i % SpeciesShape::ALL.len()])
+        .collect();
+    shapes.shuffle(&mut rand::rng());
+    shapes
+}
+
+/// Sets each species material's shape, or circles when shapes are off.
+fn apply_shapes(
+    settings: Res<Settings>,
+    particle_system: Res<ParticleSystem>,
+    mut assets: ResMut<ParticleAssets>,
+    mut materials: ResMut<Assets<ParticleMaterial>>,
+) {
+    let shapes = if settings.species_shapes {
+        particle_system.shapes.clone()
+    } else {
+        Vec::new()
+    };
+    if assets.shapes() != shapes.as_slice() {
+        let look = settings.particle_look;
+        assets.set_style(look, shapes, &mut materials);
+    }
+}
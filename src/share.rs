@@ -0,0 +1,165 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    preset::{parse_preset, LoadPreset},
+    settings::Settings,
+    ui_enabled, ParticleSystem,
+};
+
+const FRAGMENT_KEY: &str = "rules=";
+const SEED_KEY: &str = "&seed=";
+const SHARE_NAME: &str = "share link";
+
+pub struct SharePlugin;
+
+impl Plugin for SharePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShareState>()
+            .add_systems(Update, share_ui_system.run_if(ui_enabled));
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(Startup, load_rules_from_url.before(crate::setup));
+    }
+}
+
+#[derive(Resource, Default)]
+struct ShareState {
+    link: String,
+    pasted: String,
+    message: Option<String>,
+}
+
+/// The rules as a compact preset: RON, deflated and base64url encoded so the
+/// result is safe in a URL fragment.
+pub fn encode_rules(particle_system: &ParticleSystem) -> Result<String, String> {
+    let text = ron::to_string(particle_system).map_err(|error| error.to_string())?;
+    let compressed = miniz_oxide::deflate::compress_to_vec(text.as_bytes(), 9);
+    Ok(URL_SAFE_NO_PAD.encode(compressed))
+}
+
+/// Accepts a full link, a `#rules=...` fragment, or just the encoded data,
+/// and returns the preset text inside.
+pub fn decode_rules(link: &str) -> Result<String, String> {
+    let data = link
+        .rsplit_once(FRAGMENT_KEY)
+        .map_or(link, |(_, data)| data)
+        .split('&')
+        .next()
+        .unwrap_or_default()
+        .trim();
+    let compressed = URL_SAFE_NO_PAD
+        .decode(data)
+        .map_err(|error| format!("not valid base64: {}", error))?;
+    let bytes = miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, 1 << 24)
+        .map_err(|error| format!("could not decompress: {:?}", error))?;
+    String::from_utf8(bytes).map_err(|error| error.to_string())
+}
+
+/// The seed a link was shared with, present when deterministic mode was on.
+pub fn link_seed(link: &str) -> Result<Option<u64>, String> {
+    link.rsplit_once(SEED_KEY)
+        .map(|(_, seed)| {
+            let seed = seed.trim();
+            seed.parse().map_err(|_| format!("invalid seed {}", seed))
+        })
+        .transpose()
+}
+
+/// Link to the current page with the rules in its fragment. Outside the
+/// browser there is no page, so only the fragment is produced.
+fn share_link(encoded: &str, seed: Option<u64>) -> String {
+    let mut fragment = format!("#{}{}", FRAGMENT_KEY, encoded);
+    if let Some(seed) = seed {
+        fragment.push_str(&format!("{}{}", SEED_KEY, seed));
+    }
+    #[cfg(target_arch = "wasm32")]
+    if let Some(location) = web_sys::window().map(|window| window.location()) {
+        if let (Ok(origin), Ok(path)) = (location.origin(), location.pathname()) {
+            return format!("{}{}{}", origin, path, fragment);
+        }
+    }
+    fragment
+}
+
+/// Deterministic mode with the link's seed, so resets lay out the same world
+/// as for whoever shared it.
+fn apply_seed(settings: &mut Settings, seed: Option<u64>) {
+    if let Some(seed) = seed {
+        settings.deterministic = true;
+        settings.seed = seed;
+    }
+}
+
+/// Applies rules from the page URL before the first particles are spawned.
+#[cfg(target_arch = "wasm32")]
+fn load_rules_from_url(
+    mut particle_system: ResMut<ParticleSystem>,
+    mut settings: ResMut<Settings>,
+) {
+    let Some(hash) = web_sys::window().and_then(|window| window.location().hash().ok()) else {
+        return;
+    };
+    if !hash.contains(FRAGMENT_KEY) {
+        return;
+    }
+    let rules = decode_rules(&hash).and_then(|text| parse_preset(SHARE_NAME, &text));
+    match rules.and_then(|rules| Ok((rules, link_seed(&hash)?))) {
+        Ok((rules, seed)) => {
+            *particle_system = rules;
+            apply_seed(&mut settings, seed);
+            info!("Loaded rules from share link");
+        }
+        Err(error) => warn!("Ignoring share link: {}", error),
+    }
+}
+
+fn share_ui_system(
+    mut contexts: EguiContexts,
+    mut state: ResMut<ShareState>,
+    particle_system: Res<ParticleSystem>,
+    mut settings: ResMut<Settings>,
+    mut presets: EventWriter<LoadPreset>,
+) {
+    egui::Window::new("Share")
+        .default_pos([620.0, 220.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            if ui.button("Copy Share Link").clicked() {
+                match encode_rules(&particle_system) {
+                    Ok(encoded) => {
+                        let seed = settings.deterministic.then_some(settings.seed);
+                        state.link = share_link(&encoded, seed);
+                        ui.ctx().copy_text(state.link.clone());
+                        state.message =
+                            Some(format!("Copied a {} character link", state.link.len()));
+                    }
+                    Err(error) => state.message = Some(format!("Copy failed: {}", error)),
+                }
+            }
+            ui.separator();
+            ui.label("Paste a share link:");
+            ui.text_edit_singleline(&mut state.pasted);
+            if ui.button("Open Link").clicked() {
+                // Loading goes through the preset path, which keeps the
+                // particles and wraps species that no longer exist
+                let opened = decode_rules(&state.pasted)
+                    .and_then(|text| parse_preset(SHARE_NAME, &text).map(|_| text))
+                    .and_then(|text| Ok((text, link_seed(&state.pasted)?)));
+                match opened {
+                    Ok((text, seed)) => {
+                        presets.send(LoadPreset {
+                            name: SHARE_NAME.to_string(),
+                            text,
+                        });
+                        apply_seed(&mut settings, seed);
+                        state.message = Some("Loaded rules from link".to_string());
+                    }
+                    Err(error) => state.message = Some(format!("Invalid link: {}", error)),
+                }
+            }
+            if let Some(message) = &state.message {
+                ui.label(message);
+            }
+        });
+}
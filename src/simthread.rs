@@ -0,0 +1,295 @@
+use bevy::{
+    ecs::{component::Tick as ChangeTick, system::SystemChangeTick},
+    prelude::*,
+};
+use std::{
+    sync::{mpsc, Mutex, PoisonError},
+    thread,
+};
+
+use crate::{
+    backend::{GridBackend, SimulationBackend},
+    freeze::Frozen,
+    menu::AppState,
+    network, physics, replay,
+    settings::Settings,
+    species::SpeciesToggles,
+    PairForces, Particle, ParticleSystem, Velocity,
+};
+
+/// Longest simulated step one job may take. A worker that falls behind slows
+/// the simulation down instead of taking steps too large to be stable.
+const MAX_STEP: f32 = 1.0 / 20.0;
+
+/// Runs the particle forces on a dedicated thread on desktop, so a heavy
+/// tick never blocks rendering or input. Each frame the latest finished tick
+/// is applied and, once the worker is free, the current state is handed to
+/// it. Positions then trail the rules by a tick. Enabled in the Settings
+/// window; the main-thread extras (level of detail, overlays, pheromones,
+/// sub-steps and rigid bodies) only run without it.
+pub struct SimThreadPlugin;
+
+impl Plugin for SimThreadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SimThread>().add_systems(
+            Update,
+            (
+                stop_worker,
+                (apply_finished_tick, submit_tick)
+                    .chain()
+                    .run_if(is_active)
+                    .run_if(in_state(AppState::Running))
+                    .run_if(not(replay::is_playing_back))
+                    .run_if(not(network::is_remote)),
+            )
+                .chain()
+                .before(crate::update_particles),
+        );
+    }
+}
+
+/// Whether the simulation thread replaces `update_particles`. Browsers have
+/// no threads to spare, rigid bodies need the main-thread path, and
+/// deterministic runs cannot have ticks land whenever the worker finishes.
+pub fn is_active(settings: Res<Settings>, rigid_bodies: Option<Res<physics::RigidBodies>>) -> bool {
+    cfg!(not(target_arch = "wasm32"))
+        && settings.simulation_thread
+        && !settings.deterministic
+        && rigid_bodies.is_none()
+}
+
+/// A copy of the world handed to the worker.
+struct Job {
+    entities: Vec<Entity>,
+    positions: Vec<Vec2>,
+    velocities: Vec<Vec2>,
+    species: Vec<usize>,
+    /// Frozen particles still act on the others but do not move.
+    pinned: Vec<bool>,
+    rules: ParticleSystem,
+    toggles: SpeciesToggles,
+    step: f32,
+}
+
+/// The worker's result for a [`Job`], in the same order.
+struct Tick {
+    entities: Vec<Entity>,
+    positions: Vec<Vec2>,
+    velocities: Vec<Vec2>,
+}
+
+struct Worker {
+    jobs: mpsc::Sender<Job>,
+    ticks: Mutex<mpsc::Receiver<Tick>>,
+    /// A job was sent and its tick not yet applied.
+    busy: bool,
+    /// When the job was taken, to tell which particles were moved by other
+    /// systems since.
+    submitted: ChangeTick,
+}
+
+#[derive(Resource, Default)]
+struct SimThread {
+    worker: Option<Worker>,
+    /// Simulated time owed since the last job was sent.
+    pending: f32,
+}
+
+fn spawn_worker() -> Option<Worker> {
+    let (jobs, queued) = mpsc::channel::<Job>();
+    let (finished, ticks) = mpsc::channel();
+    let spawned = thread::Builder::new()
+        .name("simulation".to_string())
+        .spawn(move || {
+            let mut backend = GridBackend::default();
+            for job in queued {
+                if finished.send(simulate(job, &mut backend)).is_err() {
+                    break;
+                }
+            }
+        });
+    match spawned {
+        Ok(_) => {
+            info!("Simulating on a separate thread");
+            Some(Worker {
+                jobs,
+                ticks: Mutex::new(ticks),
+                busy: false,
+                submitted: ChangeTick::default(),
+            })
+        }
+        Err(error) => {
+            error!("Could not start the simulation thread: {}", error);
+            None
+        }
+    }
+}
+
+/// One tick of the core forces, as `update_particles` computes them.
+fn simulate(job: Job, backend: &mut GridBackend) -> Tick {
+    let Job {
+        entities,
+        mut positions,
+        mut velocities,
+        species,
+        pinned,
+        rules,
+        toggles,
+        step,
+    } = job;
+    let profile = rules.force_profile.profile(rules.beta, rules.gamma);
+    let attraction_radius = rules.max_interaction_radius();
+    let pair_forces = PairForces {
+        rules: &rules,
+        profile: &*profile,
+        attraction_radius,
+        radius_scale: 1.0,
+    };
+    let front = positions.clone();
+    // Hidden species have no effect on the others, so leave them out of the
+    // neighbor search
+    let (neighbors, neighbor_species): (Vec<Vec2>, Vec<usize>) = front
+        .iter()
+        .zip(&species)
+        .filter(|(_, &other_species)| !toggles.is_hidden(other_species))
+        .unzip();
+    backend.prepare(&neighbors, &neighbor_species, attraction_radius);
+    for index in 0..front.len() {
+        let color_id = species[index];
+        if pinned[index] || !toggles.moves(color_id) {
+            continue;
+        }
+        let pos = front[index];
+        let zone = rules.zone_at(pos);
+        let (mut force, count) = backend.accumulate(
+            pos,
+            attraction_radius,
+            &neighbors,
+            &neighbor_species,
+            &|distance, other_color_id| pair_forces.force(zone, color_id, other_color_id, distance),
+        );
+        if count > 0.0 {
+            force /= count;
+        }
+        let force = rules.force_limit.total(force);
+        let motion = rules
+            .species_motion
+            .get(color_id)
+            .copied()
+            .unwrap_or_default();
+        let speed = zone
+            .and_then(|zone| zone.speed)
+            .unwrap_or(rules.force_strength);
+        velocities[index] = motion.integrate(velocities[index], force * speed, step);
+        positions[index] = pos + velocities[index] * step;
+    }
+    Tick {
+        entities,
+        positions,
+        velocities,
+    }
+}
+
+fn stop_worker(
+    settings: Res<Settings>,
+    rigid_bodies: Option<Res<physics::RigidBodies>>,
+    mut sim_thread: ResMut<SimThread>,
+) {
+    let active = is_active(settings, rigid_bodies);
+    if !active && sim_thread.worker.is_some() {
+        // Dropping the sender ends the worker's loop
+        sim_thread.worker = None;
+        sim_thread.pending = 0.0;
+        info!("Simulating on the main thread");
+    }
+}
+
+/// Writes the latest finished tick back. Particles that were removed, moved
+/// or respawned meanwhile keep their new state; ones added meanwhile join
+/// the next job.
+fn apply_finished_tick(
+    system_ticks: SystemChangeTick,
+    mut sim_thread: ResMut<SimThread>,
+    mut particles: Query<(&mut Transform, &mut Velocity), With<Particle>>,
+) {
+    let Some(worker) = sim_thread.worker.as_mut().filter(|worker| worker.busy) else {
+        return;
+    };
+    let received = worker
+        .ticks
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .try_recv();
+    let submitted = worker.submitted;
+    let tick = match received {
+        Ok(tick) => {
+            worker.busy = false;
+            tick
+        }
+        Err(mpsc::TryRecvError::Empty) => return,
+        Err(mpsc::TryRecvError::Disconnected) => {
+            warn!("The simulation thread stopped");
+            sim_thread.worker = None;
+            return;
+        }
+    };
+    for ((entity, position), velocity) in tick
+        .entities
+        .iter()
+        .zip(tick.positions)
+        .zip(tick.velocities)
+    {
+        let Ok((mut transform, mut current)) = particles.get_mut(*entity) else {
+            continue;
+        };
+        if !transform
+            .last_changed()
+            .is_newer_than(submitted, system_ticks.this_run())
+        {
+            transform.translation = position.extend(transform.translation.z);
+            current.0 = velocity;
+        }
+    }
+}
+
+fn submit_tick(
+    system_ticks: SystemChangeTick,
+    time: Res<Time>,
+    particle_system: Res<ParticleSystem>,
+    toggles: Res<SpeciesToggles>,
+    mut sim_thread: ResMut<SimThread>,
+    particles: Query<(Entity, &Transform, &Particle, &Velocity, Has<Frozen>)>,
+) {
+    sim_thread.pending += time.delta_secs() * particle_system.time_scale;
+    if sim_thread.worker.is_none() {
+        sim_thread.worker = spawn_worker();
+    }
+    let step = sim_thread.pending.min(MAX_STEP);
+    let Some(worker) = sim_thread.worker.as_mut().filter(|worker| !worker.busy) else {
+        return;
+    };
+    let mut job = Job {
+        entities: Vec::with_capacity(particles.iter().len()),
+        positions: Vec::new(),
+        velocities: Vec::new(),
+        species: Vec::new(),
+        pinned: Vec::new(),
+        rules: particle_system.clone(),
+        toggles: toggles.clone(),
+        step,
+    };
+    for (entity, transform, particle, velocity, frozen) in &particles {
+        job.entities.push(entity);
+        job.positions.push(transform.translation.truncate());
+        job.velocities.push(velocity.0);
+        job.species.push(particle.color_id);
+        job.pinned.push(frozen);
+    }
+    if worker.jobs.send(job).is_err() {
+        sim_thread.worker = None;
+        return;
+    }
+    worker.busy = true;
+    worker.submitted = system_ticks.this_run();
+    sim_thread.pending = 0.0;
+}
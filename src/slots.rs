@@ -0,0 +1,309 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+
+use crate::{pool::ParticleSpawner, ui_enabled, Particle, ParticleCount, ParticleSystem, Velocity};
+
+pub const SLOT_COUNT: usize = 9;
+const THUMBNAIL_WIDTH: usize = 160;
+const THUMBNAIL_HEIGHT: usize = 90;
+const THUMBNAIL_BACKGROUND: [u8; 4] = [43, 44, 47, 255];
+#[cfg(not(target_arch = "wasm32"))]
+const SAVES_DIR: &str = "saves";
+
+pub struct SlotsPlugin;
+
+impl Plugin for SlotsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SaveSlots::default()).add_systems(
+            Update,
+            (slots_ui_system.run_if(ui_enabled), apply_slot_action).chain(),
+        );
+        // In the browser slots only live for the session, as full snapshots
+        // quickly exceed localStorage quotas
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Startup, load_slots_from_disk);
+    }
+}
+
+/// A full copy of the simulation: rules, every particle, and a preview.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SaveSlot {
+    pub name: String,
+    pub particle_system: ParticleSystem,
+    /// Position, species and velocity of every particle.
+    pub particles: Vec<(Vec2, usize, Vec2)>,
+    pub thumbnail: Thumbnail,
+    #[serde(skip)]
+    texture: Option<egui::TextureHandle>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SaveSlot {
+    /// Checks that the matrix, species ids and thumbnail fit the palette and
+    /// each other, so a corrupt or hand-edited file is skipped instead of
+    /// panicking when loaded or drawn.
+    fn check(&self) -> Result<(), String> {
+        let n = self.particle_system.colors.len();
+        let matrix = &self.particle_system.behavior_matrix;
+        if matrix.len() != n || matrix.iter().any(|row| row.len() != n) {
+            return Err(format!("behavior matrix is not {} by {}", n, n));
+        }
+        if let Some(&(_, color_id, _)) = self
+            .particles
+            .iter()
+            .find(|(_, color_id, _)| *color_id >= n)
+        {
+            return Err(format!(
+                "particle of species {} but only {} species",
+                color_id, n
+            ));
+        }
+        let thumbnail = &self.thumbnail;
+        if thumbnail.rgba.len() != thumbnail.width * thumbnail.height * 4 {
+            return Err("thumbnail size does not match its pixels".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Small RGBA preview rasterized from particle positions on the CPU, so it
+/// works the same headless, on native and in the browser.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Thumbnail {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+}
+
+impl Thumbnail {
+    /// Draws every particle as a pixel, framing their bounding box.
+    pub fn render(particles: impl Iterator<Item = (Vec2, Color)> + Clone) -> Self {
+        let (width, height) = (THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT);
+        let mut rgba = THUMBNAIL_BACKGROUND.repeat(width * height);
+
+        let (min, max) = particles
+            .clone()
+            .fold((Vec2::MAX, Vec2::MIN), |(min, max), (pos, _)| {
+                (min.min(pos), max.max(pos))
+            });
+        if min.x <= max.x {
+            // Keep the aspect ratio by fitting the larger side
+            let center = (min + max) * 0.5;
+            let scale = ((max - min) / Vec2::new(width as f32, height as f32))
+                .max_element()
+                .max(f32::EPSILON)
+                * 1.05;
+            for (pos, color) in particles {
+                let pixel = (pos - center) / scale;
+                let x = (pixel.x + width as f32 * 0.5) as usize;
+                // Flip y: world space points up, image rows go down
+                let y = (height as f32 * 0.5 - pixel.y) as usize;
+                if x < width && y < height {
+                    let offset = (y * width + x) * 4;
+                    rgba[offset..offset + 4].copy_from_slice(&color.to_srgba().to_u8_array());
+                }
+            }
+        }
+        Thumbnail {
+            width,
+            height,
+            rgba,
+        }
+    }
+}
+
+enum SlotAction {
+    Save(usize),
+    Load(usize),
+    Rename(usize),
+    Delete(usize),
+}
+
+#[derive(Resource)]
+pub struct SaveSlots {
+    pub slots: Vec<Option<SaveSlot>>,
+    pending: Option<SlotAction>,
+    message: Option<String>,
+}
+
+impl Default for SaveSlots {
+    fn default() -> Self {
+        SaveSlots {
+            slots: vec![None; SLOT_COUNT],
+            pending: None,
+            message: None,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn slot_path(index: usize) -> std::path::PathBuf {
+    std::path::Path::new(SAVES_DIR).join(format!("slot_{}.ron", index + 1))
+}
+
+/// Restores slots saved by earlier sessions.
+#[cfg(not(target_arch = "wasm32"))]
+fn load_slots_from_disk(mut slots: ResMut<SaveSlots>) {
+    for index in 0..SLOT_COUNT {
+        let Ok(text) = std::fs::read_to_string(slot_path(index)) else {
+            continue;
+        };
+        let slot = ron::from_str::<SaveSlot>(&text)
+            .map_err(|error| error.to_string())
+            .and_then(|slot| slot.check().map(|()| slot));
+        match slot {
+            Ok(slot) => slots.slots[index] = Some(slot),
+            Err(error) => warn!("Ignoring unreadable save slot {}: {}", index + 1, error),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_slot(index: usize, slot: &SaveSlot) -> Result<(), String> {
+    std::fs::create_dir_all(SAVES_DIR).map_err(|error| error.to_string())?;
+    let text = ron::to_string(slot).map_err(|error| error.to_string())?;
+    std::fs::write(slot_path(index), text).map_err(|error| error.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_slot(_index: usize, _slot: &SaveSlot) -> Result<(), String> {
+    Ok(())
+}
+
+fn apply_slot_action(
+    mut spawner: ParticleSpawner,
+    mut slots: ResMut<SaveSlots>,
+    mut particle_system: ResMut<ParticleSystem>,
+    mut particle_count: ResMut<ParticleCount>,
+    particles: Query<(Entity, &Transform, &Particle, &Velocity)>,
+) {
+    let Some(action) = slots.pending.take() else {
+        return;
+    };
+
+    match action {
+        SlotAction::Save(index) => {
+            let saved: Vec<(Vec2, usize, Vec2)> = particles
+                .iter()
+                .map(|(_, transform, particle, velocity)| {
+                    (
+                        transform.translation.truncate(),
+                        particle.color_id,
+                        velocity.0,
+                    )
+                })
+                .collect();
+            let thumbnail = Thumbnail::render(
+                saved
+                    .iter()
+                    .map(|&(pos, color_id, _)| (pos, particle_system.colors[color_id])),
+            );
+            let name = slots.slots[index]
+                .as_ref()
+                .map_or_else(|| format!("Slot {}", index + 1), |slot| slot.name.clone());
+            let slot = SaveSlot {
+                name,
+                particle_system: particle_system.clone(),
+                particles: saved,
+                thumbnail,
+                texture: None,
+            };
+            slots.message = Some(match write_slot(index, &slot) {
+                Ok(()) => format!("Saved slot {}", index + 1),
+                Err(error) => format!("Saved slot {} for this session only: {}", index + 1, error),
+            });
+            slots.slots[index] = Some(slot);
+        }
+        SlotAction::Load(index) => {
+            let Some(slot) = &slots.slots[index] else {
+                return;
+            };
+            for (entity, ..) in &particles {
+                spawner.despawn(entity);
+            }
+            *particle_system = slot.particle_system.clone();
+            for &(pos, color_id, velocity) in &slot.particles {
+                spawner
+                    .spawn(pos, color_id, particle_system.colors[color_id])
+                    .insert(Velocity(velocity));
+            }
+            particle_count.count = slot.particles.len();
+            slots.message = Some(format!("Loaded {}", slot.name));
+        }
+        SlotAction::Rename(index) => {
+            if let Some(slot) = &slots.slots[index] {
+                if let Err(error) = write_slot(index, slot) {
+                    slots.message = Some(format!("Failed to rename slot {}: {}", index + 1, error));
+                }
+            }
+        }
+        SlotAction::Delete(index) => {
+            slots.slots[index] = None;
+            #[cfg(not(target_arch = "wasm32"))]
+            let _ = std::fs::remove_file(slot_path(index));
+            slots.message = Some(format!("Cleared slot {}", index + 1));
+        }
+    }
+}
+
+fn slots_ui_system(mut contexts: EguiContexts, mut slots: ResMut<SaveSlots>) {
+    let ctx = contexts.ctx_mut();
+    egui::Window::new("Save Slots")
+        .default_pos([620.0, 80.0])
+        .default_open(false)
+        .show(ctx, |ui| {
+            let mut action = None;
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (index, slot) in slots.slots.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}", index + 1));
+                        match slot {
+                            Some(slot) => {
+                                let texture = slot.texture.get_or_insert_with(|| {
+                                    ui.ctx().load_texture(
+                                        format!("save_slot_{}", index),
+                                        egui::ColorImage::from_rgba_unmultiplied(
+                                            [slot.thumbnail.width, slot.thumbnail.height],
+                                            &slot.thumbnail.rgba,
+                                        ),
+                                        egui::TextureOptions::LINEAR,
+                                    )
+                                });
+                                ui.image((texture.id(), texture.size_vec2()));
+                                ui.vertical(|ui| {
+                                    if ui.text_edit_singleline(&mut slot.name).lost_focus() {
+                                        action = Some(SlotAction::Rename(index));
+                                    }
+                                    ui.label(format!("{} particles", slot.particles.len()));
+                                    ui.horizontal(|ui| {
+                                        if ui.button("Load").clicked() {
+                                            action = Some(SlotAction::Load(index));
+                                        }
+                                        if ui.button("Overwrite").clicked() {
+                                            action = Some(SlotAction::Save(index));
+                                        }
+                                        if ui.button("Clear").clicked() {
+                                            action = Some(SlotAction::Delete(index));
+                                        }
+                                    });
+                                });
+                            }
+                            None => {
+                                if ui.button("Save here").clicked() {
+                                    action = Some(SlotAction::Save(index));
+                                }
+                            }
+                        }
+                    });
+                    ui.separator();
+                }
+            });
+            if let Some(message) = &slots.message {
+                ui.label(message);
+            }
+            if action.is_some() {
+                slots.pending = action;
+            }
+        });
+}
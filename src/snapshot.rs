@@ -0,0 +1,215 @@
+use bevy::prelude::*;
+use std::io::Read;
+
+const MAGIC: &[u8; 4] = b"PLSN";
+const VERSION: u16 = 1;
+/// Flag bit set when velocities follow the species.
+const HAS_VELOCITIES: u16 = 1;
+/// Magic, version, flags and particle count.
+const HEADER_BYTES: usize = 12;
+/// Largest particle count accepted when decoding, so a corrupt header cannot
+/// make us allocate without bound.
+const MAX_PARTICLES: usize = 1 << 24;
+
+/// Every particle's state in the compact binary form shared by autosaves,
+/// replays and network frames: a versioned header, then positions as `f32`
+/// pairs, species as `u16` and optionally velocities, all little endian and
+/// zstd-compressed.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct ParticleSnapshot {
+    pub positions: Vec<Vec2>,
+    pub species: Vec<u16>,
+    /// One per particle when present.
+    pub velocities: Option<Vec<Vec2>>,
+}
+
+impl ParticleSnapshot {
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Adds a particle. Once any particle has a velocity, those pushed
+    /// without one, before or after, are stored at rest.
+    pub fn push(&mut self, pos: Vec2, species: usize, velocity: Option<Vec2>) {
+        let index = self.positions.len();
+        self.positions.push(pos);
+        self.species.push(species as u16);
+        match (&mut self.velocities, velocity) {
+            (Some(velocities), velocity) => velocities.push(velocity.unwrap_or_default()),
+            (None, Some(velocity)) => {
+                let mut velocities = vec![Vec2::ZERO; index];
+                velocities.push(velocity);
+                self.velocities = Some(velocities);
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// Position, species and velocity (zero when not stored) of each particle.
+    pub fn iter(&self) -> impl Iterator<Item = (Vec2, usize, Vec2)> + '_ {
+        self.positions
+            .iter()
+            .zip(&self.species)
+            .enumerate()
+            .map(|(index, (&pos, &species))| {
+                let velocity = self
+                    .velocities
+                    .as_ref()
+                    .and_then(|velocities| velocities.get(index))
+                    .copied()
+                    .unwrap_or_default();
+                (pos, species as usize, velocity)
+            })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let count = self.len();
+        let velocities = self
+            .velocities
+            .as_ref()
+            .filter(|velocities| velocities.len() == count);
+        let flags = if velocities.is_some() {
+            HAS_VELOCITIES
+        } else {
+            0
+        };
+
+        let mut payload = Vec::with_capacity(count * if velocities.is_some() { 18 } else { 10 });
+        for pos in &self.positions {
+            payload.extend_from_slice(&pos.x.to_le_bytes());
+            payload.extend_from_slice(&pos.y.to_le_bytes());
+        }
+        for species in &self.species {
+            payload.extend_from_slice(&species.to_le_bytes());
+        }
+        for velocity in velocities.into_iter().flatten() {
+            payload.extend_from_slice(&velocity.x.to_le_bytes());
+            payload.extend_from_slice(&velocity.y.to_le_bytes());
+        }
+
+        let mut bytes = Vec::with_capacity(HEADER_BYTES + payload.len() / 2);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&flags.to_le_bytes());
+        bytes.extend_from_slice(&(count as u32).to_le_bytes());
+        // Fastest level: network frames are encoded many times a second
+        bytes.extend(ruzstd::encoding::compress_to_vec(
+            payload.as_slice(),
+            ruzstd::encoding::CompressionLevel::Fastest,
+        ));
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let (header, compressed) = bytes
+            .split_at_checked(HEADER_BYTES)
+            .ok_or("snapshot is truncated")?;
+        if &header[0..4] != MAGIC {
+            return Err("not a particle snapshot".to_string());
+        }
+        let field = |at: usize| u16::from_le_bytes([header[at], header[at + 1]]);
+        let version = field(4);
+        if version != VERSION {
+            return Err(format!("unsupported snapshot version {}", version));
+        }
+        let flags = field(6);
+        let count = u32::from_le_bytes(header[8..12].try_into().unwrap()) as usize;
+        if count > MAX_PARTICLES {
+            return Err(format!("snapshot claims {} particles", count));
+        }
+
+        let has_velocities = flags & HAS_VELOCITIES != 0;
+        let expected = count * if has_velocities { 18 } else { 10 };
+        let mut payload = Vec::with_capacity(expected);
+        let decoder = ruzstd::decoding::StreamingDecoder::new(compressed)
+            .map_err(|error| format!("could not decompress snapshot: {}", error))?;
+        // One byte more than expected reveals trailing garbage
+        decoder
+            .take(expected as u64 + 1)
+            .read_to_end(&mut payload)
+            .map_err(|error| format!("could not decompress snapshot: {}", error))?;
+        if payload.len() != expected {
+            return Err("snapshot payload does not match its header".to_string());
+        }
+
+        let vec2s = |bytes: &[u8]| -> Vec<Vec2> {
+            bytes
+                .chunks_exact(8)
+                .map(|chunk| {
+                    Vec2::new(
+                        f32::from_le_bytes(chunk[0..4].try_into().unwrap()),
+                        f32::from_le_bytes(chunk[4..8].try_into().unwrap()),
+                    )
+                })
+                .collect()
+        };
+        let (positions, rest) = payload.split_at(count * 8);
+        let (species, velocities) = rest.split_at(count * 2);
+        Ok(ParticleSnapshot {
+            positions: vec2s(positions),
+            species: species
+                .chunks_exact(2)
+                .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+                .collect(),
+            velocities: has_velocities.then(|| vec2s(velocities)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(velocities: bool) -> ParticleSnapshot {
+        let mut snapshot = ParticleSnapshot::default();
+        for index in 0..100 {
+            let pos = Vec2::new(index as f32 * 1.5 - 40.0, -(index as f32) / 3.0);
+            let velocity = velocities.then(|| Vec2::new(index as f32, 0.25));
+            snapshot.push(pos, index % 7, velocity);
+        }
+        snapshot
+    }
+
+    #[test]
+    fn round_trips() {
+        for velocities in [false, true] {
+            let snapshot = sample(velocities);
+            assert_eq!(ParticleSnapshot::decode(&snapshot.encode()), Ok(snapshot));
+        }
+        let empty = ParticleSnapshot::default();
+        assert_eq!(ParticleSnapshot::decode(&empty.encode()), Ok(empty));
+    }
+
+    #[test]
+    fn mixed_velocities_stay_aligned() {
+        let mut snapshot = ParticleSnapshot::default();
+        snapshot.push(Vec2::ZERO, 0, None);
+        snapshot.push(Vec2::ONE, 1, Some(Vec2::X));
+        snapshot.push(Vec2::NEG_ONE, 2, None);
+        let velocities: Vec<Vec2> = snapshot.iter().map(|(_, _, velocity)| velocity).collect();
+        assert_eq!(velocities, [Vec2::ZERO, Vec2::X, Vec2::ZERO]);
+
+        let decoded = ParticleSnapshot::decode(&snapshot.encode()).unwrap();
+        assert_eq!(decoded, snapshot);
+    }
+
+    #[test]
+    fn rejects_damaged_input() {
+        let bytes = sample(true).encode();
+        assert!(ParticleSnapshot::decode(&bytes[..bytes.len() / 2]).is_err());
+        assert!(ParticleSnapshot::decode(&bytes[..HEADER_BYTES - 1]).is_err());
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert!(ParticleSnapshot::decode(&wrong_magic).is_err());
+
+        // A header claiming more particles than the payload holds
+        let mut wrong_count = bytes;
+        wrong_count[8] = wrong_count[8].wrapping_add(1);
+        assert!(ParticleSnapshot::decode(&wrong_count).is_err());
+    }
+}
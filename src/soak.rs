@@ -0,0 +1,226 @@
+use bevy::{input::InputSystem, prelude::*};
+use std::{fmt::Write as _, fs, time::Duration};
+
+use crate::{particle_assets::ParticleMaterial, settings::Settings, Headless, Particle};
+
+const CHECK_INTERVAL: f32 = 60.0;
+const REGENERATE_INTERVAL: f32 = 300.0;
+const GROWTH_LIMIT: f32 = 1.5;
+const GROWTH_SLACK: usize = 64;
+const REPORT_PATH: &str = "soak_report.txt";
+
+/// Headless long-run configuration, enabled with `--soak <hours>`.
+#[derive(Resource, Clone)]
+pub struct SoakConfig {
+    pub duration: Duration,
+}
+
+impl SoakConfig {
+    pub fn from_args() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        let index = args.iter().position(|arg| arg == "--soak")?;
+        let hours = args
+            .get(index + 1)
+            .and_then(|value| value.parse::<f64>().ok())
+            .unwrap_or(1.0);
+        Some(SoakConfig {
+            duration: Duration::from_secs_f64(hours * 3600.0),
+        })
+    }
+}
+
+pub struct SoakPlugin {
+    pub config: SoakConfig,
+}
+
+impl Plugin for SoakPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.config.clone())
+            .insert_resource(Headless)
+            .insert_resource(SoakState::default())
+            .add_systems(PreUpdate, exercise_spawn_paths.after(InputSystem))
+            .add_systems(Last, check_soak);
+    }
+}
+
+#[derive(Clone, Copy)]
+struct SoakSample {
+    elapsed: f32,
+    entities: usize,
+    particles: usize,
+    meshes: usize,
+    materials: usize,
+    memory_kb: Option<usize>,
+}
+
+#[derive(Resource)]
+struct SoakState {
+    check_timer: Timer,
+    regenerate_timer: Timer,
+    ticks: u64,
+    baseline: Option<SoakSample>,
+    samples: Vec<SoakSample>,
+    failures: Vec<String>,
+}
+
+impl Default for SoakState {
+    fn default() -> Self {
+        SoakState {
+            check_timer: Timer::from_seconds(CHECK_INTERVAL, TimerMode::Repeating),
+            regenerate_timer: Timer::from_seconds(REGENERATE_INTERVAL, TimerMode::Repeating),
+            ticks: 0,
+            baseline: None,
+            samples: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+}
+
+/// Periodically presses the restart key so the regeneration spawn path is
+/// exercised too.
+fn exercise_spawn_paths(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut state: ResMut<SoakState>,
+    mut keyboard: ResMut<ButtonInput<KeyCode>>,
+) {
+    keyboard.release(settings.keys.restart);
+    if state.regenerate_timer.tick(time.delta()).just_finished() {
+        keyboard.press(settings.keys.restart);
+    }
+}
+
+fn check_soak(
+    time: Res<Time>,
+    config: Res<SoakConfig>,
+    mut state: ResMut<SoakState>,
+    (meshes, materials): (Res<Assets<Mesh>>, Res<Assets<ParticleMaterial>>),
+    entities: Query<Entity>,
+    particles: Query<(), With<Particle>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    state.ticks += 1;
+    let finished = time.elapsed() >= config.duration;
+    if !state.check_timer.tick(time.delta()).just_finished() && !finished {
+        return;
+    }
+
+    let sample = SoakSample {
+        elapsed: time.elapsed_secs(),
+        entities: entities.iter().count(),
+        particles: particles.iter().count(),
+        meshes: meshes.len(),
+        materials: materials.len(),
+        memory_kb: resident_memory_kb(),
+    };
+    info!(
+        "soak: {:.0}s entities={} particles={} meshes={} materials={} memory={:?}kB",
+        sample.elapsed,
+        sample.entities,
+        sample.particles,
+        sample.meshes,
+        sample.materials,
+        sample.memory_kb
+    );
+
+    if let Some(baseline) = state.baseline {
+        let mut failures = Vec::new();
+        check_bounded(
+            &mut failures,
+            "entities",
+            baseline.entities,
+            sample.entities,
+        );
+        check_bounded(&mut failures, "meshes", baseline.meshes, sample.meshes);
+        check_bounded(
+            &mut failures,
+            "materials",
+            baseline.materials,
+            sample.materials,
+        );
+        if let (Some(base), Some(current)) = (baseline.memory_kb, sample.memory_kb) {
+            check_bounded(&mut failures, "memory (kB)", base, current);
+        }
+        for failure in failures {
+            error!("soak: {}", failure);
+            state
+                .failures
+                .push(format!("{:.0}s: {}", sample.elapsed, failure));
+        }
+    } else {
+        state.baseline = Some(sample);
+    }
+    state.samples.push(sample);
+
+    if finished {
+        let report = soak_report(&state);
+        if let Err(err) = fs::write(REPORT_PATH, &report) {
+            error!("soak: failed to write {}: {}", REPORT_PATH, err);
+        }
+        info!("soak: finished, report written to {}", REPORT_PATH);
+        exit.send(if state.failures.is_empty() {
+            AppExit::Success
+        } else {
+            AppExit::error()
+        });
+    }
+}
+
+fn check_bounded(failures: &mut Vec<String>, name: &str, baseline: usize, current: usize) {
+    let limit = (baseline as f32 * GROWTH_LIMIT) as usize + GROWTH_SLACK;
+    if current > limit {
+        failures.push(format!(
+            "{} grew from {} to {} (limit {})",
+            name, baseline, current, limit
+        ));
+    }
+}
+
+fn soak_report(state: &SoakState) -> String {
+    let mut report = String::new();
+    let _ = writeln!(report, "Particle Life soak report");
+    let _ = writeln!(report, "ticks: {}", state.ticks);
+    let _ = writeln!(
+        report,
+        "result: {}",
+        if state.failures.is_empty() {
+            "PASS"
+        } else {
+            "FAIL"
+        }
+    );
+    let _ = writeln!(report);
+    let _ = writeln!(
+        report,
+        "elapsed_s\tentities\tparticles\tmeshes\tmaterials\tmemory_kb"
+    );
+    for sample in &state.samples {
+        let _ = writeln!(
+            report,
+            "{:.0}\t{}\t{}\t{}\t{}\t{}",
+            sample.elapsed,
+            sample.entities,
+            sample.particles,
+            sample.meshes,
+            sample.materials,
+            sample
+                .memory_kb
+                .map_or_else(|| "n/a".to_string(), |kb| kb.to_string())
+        );
+    }
+    if !state.failures.is_empty() {
+        let _ = writeln!(report);
+        let _ = writeln!(report, "failures:");
+        for failure in &state.failures {
+            let _ = writeln!(report, "  {}", failure);
+        }
+    }
+    report
+}
+
+/// Resident set size from `/proc/self/statm`, only available on Linux.
+fn resident_memory_kb() -> Option<usize> {
+    let statm = fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: usize = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * 4)
+}
@@ -0,0 +1,617 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_egui::{egui, EguiContexts};
+use rand::{
+    distr::{weighted::WeightedIndex, Distribution},
+    Rng,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    determinism, egui_color, loading, lod, pool::ParticleSpawner, ui_enabled, MainCamera, Particle,
+    ParticleSystem, WINDOW_HEIGHT, WINDOW_WIDTH,
+};
+
+/// Brush radius range, in world units.
+const BRUSH_RADII: std::ops::RangeInclusive<f32> = 5.0..=400.0;
+/// Spray rate range, in particles per second.
+const BRUSH_RATES: std::ops::RangeInclusive<f32> = 10.0..=5000.0;
+
+pub struct SpawnPlugin;
+
+impl Plugin for SpawnPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnRequest>()
+            .init_resource::<SpawnPattern>()
+            .init_resource::<SprayBrush>()
+            .add_systems(
+                Update,
+                (
+                    (
+                        spawn_at_cursor,
+                        paint_species,
+                        draw_brush_outline,
+                        brush_ui_system,
+                    )
+                        .run_if(ui_enabled),
+                    handle_spawn_requests,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Area new particles are placed in, centered on the origin.
+pub fn world_bounds() -> Rect {
+    Rect::from_center_size(Vec2::ZERO, Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT))
+}
+
+/// Area additions are scattered over: what the camera currently shows, so
+/// particles appear where the user is looking even after panning far from
+/// the origin. Falls back to [`world_bounds`] without a camera.
+pub fn view_bounds(camera: Option<(&Transform, &OrthographicProjection)>) -> Rect {
+    camera
+        .map(|(transform, projection)| lod::camera_view(transform, projection))
+        .filter(|view| !view.is_empty())
+        .unwrap_or_else(world_bounds)
+}
+
+/// Where a reset places particles. Different initial conditions can lead the
+/// same rules to very different transients.
+#[derive(Resource, Clone, Copy, PartialEq, Default)]
+pub enum SpawnPattern {
+    /// Evenly spaced lattice over the world.
+    #[default]
+    Grid,
+    Uniform,
+    /// Uniformly filled disk around the origin; `radius` is a fraction of the
+    /// world's half height.
+    Disk {
+        radius: f32,
+    },
+    /// Annulus around the origin with a Gaussian `width`, both fractions of
+    /// the world's half height.
+    Ring {
+        radius: f32,
+        width: f32,
+    },
+    /// `count` Gaussian blobs at random centers with standard deviation
+    /// `spread` in world units.
+    Clusters {
+        count: usize,
+        spread: f32,
+    },
+}
+
+impl SpawnPattern {
+    fn label(&self) -> &'static str {
+        match self {
+            SpawnPattern::Grid => "Grid",
+            SpawnPattern::Uniform => "Uniform random",
+            SpawnPattern::Disk { .. } => "Disk",
+            SpawnPattern::Ring { .. } => "Ring",
+            SpawnPattern::Clusters { .. } => "Clusters",
+        }
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        let mut selected = self.label();
+        egui::ComboBox::from_label("Spawn Pattern")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for label in ["Grid", "Uniform random", "Disk", "Ring", "Clusters"] {
+                    ui.selectable_value(&mut selected, label, label);
+                }
+            });
+        if selected != self.label() {
+            *self = match selected {
+                "Uniform random" => SpawnPattern::Uniform,
+                "Disk" => SpawnPattern::Disk { radius: 0.5 },
+                "Ring" => SpawnPattern::Ring {
+                    radius: 0.7,
+                    width: 0.05,
+                },
+                "Clusters" => SpawnPattern::Clusters {
+                    count: 6,
+                    spread: 40.0,
+                },
+                _ => SpawnPattern::Grid,
+            };
+        }
+        match self {
+            SpawnPattern::Grid | SpawnPattern::Uniform => {}
+            SpawnPattern::Disk { radius } => {
+                ui.add(egui::Slider::new(radius, 0.05..=1.0).text("radius"));
+            }
+            SpawnPattern::Ring { radius, width } => {
+                ui.add(egui::Slider::new(radius, 0.05..=1.0).text("radius"));
+                ui.add(egui::Slider::new(width, 0.0..=0.3).text("width"));
+            }
+            SpawnPattern::Clusters { count, spread } => {
+                ui.add(egui::Slider::new(count, 1..=50).text("clusters"));
+                ui.add(egui::Slider::new(spread, 5.0..=300.0).text("spread"));
+            }
+        }
+        ui.label("Takes effect on the next restart.");
+    }
+
+    /// Positions for `count` particles.
+    fn positions(&self, count: usize, bounds: Rect, rng: &mut impl Rng) -> Vec<Vec2> {
+        let scale = bounds.half_size().y;
+        match *self {
+            SpawnPattern::Grid => {
+                let grid_size = (count as f32).sqrt().ceil().max(1.0) as usize;
+                let spacing = bounds.size() / grid_size as f32;
+                (0..count)
+                    .map(|index| {
+                        let cell =
+                            Vec2::new((index / grid_size) as f32, (index % grid_size) as f32);
+                        bounds.min + (cell + 0.5) * spacing
+                    })
+                    .collect()
+            }
+            SpawnPattern::Uniform => (0..count).map(|_| uniform_in(bounds, rng)).collect(),
+            SpawnPattern::Disk { radius } => (0..count)
+                .map(|_| {
+                    bounds.center()
+                        + random_direction(rng) * radius * scale * rng.random::<f32>().sqrt()
+                })
+                .collect(),
+            SpawnPattern::Ring { radius, width } => (0..count)
+                .map(|_| {
+                    let distance = (radius + width * gaussian(rng).x) * scale;
+                    bounds.center() + random_direction(rng) * distance
+                })
+                .collect(),
+            SpawnPattern::Clusters {
+                count: clusters,
+                spread,
+            } => {
+                let centers: Vec<Vec2> = (0..clusters.max(1))
+                    .map(|_| uniform_in(bounds, rng))
+                    .collect();
+                (0..count)
+                    .map(|index| centers[index % centers.len()] + gaussian(rng) * spread)
+                    .collect()
+            }
+        }
+    }
+}
+
+pub fn uniform_in(bounds: Rect, rng: &mut impl Rng) -> Vec2 {
+    Vec2::new(
+        rng.random_range(bounds.min.x..bounds.max.x),
+        rng.random_range(bounds.min.y..bounds.max.y),
+    )
+}
+
+/// Layout math goes through libm so seeded layouts match on every platform.
+fn random_direction(rng: &mut impl Rng) -> Vec2 {
+    let (sin, cos) = libm::sincosf(rng.random_range(0.0..std::f32::consts::TAU));
+    Vec2::new(cos, sin)
+}
+
+/// Two independent standard normal samples (Box–Muller).
+fn gaussian(rng: &mut impl Rng) -> Vec2 {
+    let radius = (-2.0 * libm::logf(1.0 - rng.random::<f32>())).sqrt();
+    random_direction(rng) * radius
+}
+
+/// Relative share of each species among newly spawned particles.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Default)]
+pub enum SpawnWeights {
+    #[default]
+    Uniform,
+    /// Species `i` gets weight `1 / (i + 1)^exponent`, so a few species
+    /// dominate and the rest are rare.
+    Zipf { exponent: f32 },
+    /// One weight per species; missing entries count as 1.
+    Custom(Vec<f32>),
+}
+
+impl SpawnWeights {
+    fn label(&self) -> &'static str {
+        match self {
+            SpawnWeights::Uniform => "Uniform",
+            SpawnWeights::Zipf { .. } => "Zipf",
+            SpawnWeights::Custom(_) => "Custom",
+        }
+    }
+
+    pub fn weights(&self, species: usize) -> Vec<f32> {
+        match self {
+            SpawnWeights::Uniform => vec![1.0; species],
+            SpawnWeights::Zipf { exponent } => (0..species)
+                .map(|i| libm::powf(i as f32 + 1.0, -exponent))
+                .collect(),
+            SpawnWeights::Custom(weights) => (0..species)
+                .map(|i| weights.get(i).copied().unwrap_or(1.0).max(0.0))
+                .collect(),
+        }
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut egui::Ui, colors: &[Color]) {
+        let mut selected = self.label();
+        egui::ComboBox::from_label("Spawn Weights")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for label in ["Uniform", "Zipf", "Custom"] {
+                    ui.selectable_value(&mut selected, label, label);
+                }
+            });
+        if selected != self.label() {
+            let weights = match selected {
+                "Zipf" => SpawnWeights::Zipf { exponent: 1.0 },
+                // Start from the current distribution
+                "Custom" => SpawnWeights::Custom(self.weights(colors.len())),
+                _ => SpawnWeights::Uniform,
+            };
+            *self = weights;
+        }
+
+        match self {
+            SpawnWeights::Uniform => {}
+            SpawnWeights::Zipf { exponent } => {
+                ui.add(egui::Slider::new(exponent, 0.0..=3.0).text("exponent"));
+            }
+            SpawnWeights::Custom(weights) => {
+                weights.resize(colors.len(), 1.0);
+                ui.collapsing("Weights", |ui| {
+                    for (weight, &color) in weights.iter_mut().zip(colors) {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(egui_color(color), "⏺");
+                            ui.add(egui::Slider::new(weight, 0.0..=10.0));
+                        });
+                    }
+                });
+            }
+        }
+        ui.label("Applies to particles spawned from now on.");
+    }
+
+    /// Picks species by weight, falling back to uniform when every weight is zero.
+    fn sampler(&self, species: usize) -> Option<WeightedIndex<f32>> {
+        WeightedIndex::new(self.weights(species)).ok()
+    }
+}
+
+fn pick_species(sampler: &Option<WeightedIndex<f32>>, species: usize, rng: &mut impl Rng) -> usize {
+    match sampler {
+        Some(sampler) => sampler.sample(rng),
+        None => rng.random_range(0..species),
+    }
+}
+
+/// Ways of creating particles, all handled by one system so every caller
+/// gets the same layout, assets and bounds. Species come from the current
+/// [`ParticleSystem`] when the request is handled.
+#[derive(Event, Clone, Copy)]
+pub enum SpawnRequest {
+    /// Replace every particle with `count` new ones laid out by the current
+    /// [`SpawnPattern`].
+    Reset { count: usize },
+    /// Like `Reset`, but always at uniformly random positions, to check
+    /// whether a structure forms again from a different start.
+    Reshuffle { count: usize },
+    /// Add `count` particles at uniformly random positions in the current
+    /// view.
+    Scatter { count: usize },
+    /// Add `count` particles within `radius` of `position`, all of `species`
+    /// when given and following the spawn weights otherwise.
+    Burst {
+        position: Vec2,
+        radius: f32,
+        count: usize,
+        species: Option<usize>,
+    },
+}
+
+/// The windows and main camera, for tools that act where the cursor points.
+#[derive(SystemParam)]
+pub struct WorldCursor<'w, 's> {
+    windows: Query<'w, 's, &'static Window>,
+    camera: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<MainCamera>>,
+}
+
+impl WorldCursor<'_, '_> {
+    /// World position under the cursor, if it is over the window.
+    pub fn position(&self) -> Option<Vec2> {
+        let cursor = self.windows.iter().find_map(Window::cursor_position)?;
+        let (camera, camera_transform) = self.camera.get_single().ok()?;
+        camera.viewport_to_world_2d(camera_transform, cursor).ok()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum BrushMode {
+    #[default]
+    Spray,
+    /// Reassigns the species of existing particles instead of adding new
+    /// ones, keeping the population size.
+    Paint,
+}
+
+/// Right-drag brush: while the button is held, particles appear uniformly
+/// within `radius` of the cursor at `rate` per second, or in paint mode the
+/// particles under it take on the brush species.
+#[derive(Resource)]
+pub struct SprayBrush {
+    pub mode: BrushMode,
+    pub radius: f32,
+    pub rate: f32,
+    /// Species sprayed or painted by the brush and added by left clicks;
+    /// `None` follows the spawn weights and paints nothing.
+    pub species: Option<usize>,
+    pub outline: bool,
+    /// Fraction of a particle owed from earlier frames.
+    carry: f32,
+}
+
+impl Default for SprayBrush {
+    fn default() -> Self {
+        SprayBrush {
+            mode: BrushMode::Spray,
+            radius: 40.0,
+            rate: 600.0,
+            species: None,
+            outline: true,
+            carry: 0.0,
+        }
+    }
+}
+
+/// Left click adds a particle at the cursor; holding the right button sprays
+/// with the [`SprayBrush`].
+pub fn spawn_at_cursor(
+    mut contexts: EguiContexts,
+    time: Res<Time>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut brush: ResMut<SprayBrush>,
+    cursor: WorldCursor,
+    mut requests: EventWriter<SpawnRequest>,
+) {
+    let (radius, count) = if buttons.just_pressed(MouseButton::Left) {
+        (0.0, 1)
+    } else if buttons.pressed(MouseButton::Right) && brush.mode == BrushMode::Spray {
+        // The first frame of a press always paints something
+        let owed = brush.carry + brush.rate * time.delta_secs();
+        let count = if buttons.just_pressed(MouseButton::Right) {
+            owed.max(1.0) as usize
+        } else {
+            owed as usize
+        };
+        brush.carry = (owed - count as f32).max(0.0);
+        (brush.radius, count)
+    } else {
+        brush.carry = 0.0;
+        return;
+    };
+    if count == 0 || contexts.ctx_mut().wants_pointer_input() {
+        return;
+    }
+    if let Some(position) = cursor.position() {
+        requests.send(SpawnRequest::Burst {
+            position,
+            radius,
+            count,
+            species: brush.species,
+        });
+    }
+}
+
+/// Paint mode: every particle under the brush switches to the brush species.
+/// The material follows through `particle_assets`.
+fn paint_species(
+    mut contexts: EguiContexts,
+    buttons: Res<ButtonInput<MouseButton>>,
+    brush: Res<SprayBrush>,
+    particle_system: Res<ParticleSystem>,
+    cursor: WorldCursor,
+    mut particles: Query<(&Transform, &mut Particle)>,
+) {
+    if brush.mode != BrushMode::Paint || !buttons.pressed(MouseButton::Right) {
+        return;
+    }
+    let Some(species) = brush
+        .species
+        .filter(|&species| species < particle_system.colors.len())
+    else {
+        return;
+    };
+    if contexts.ctx_mut().wants_pointer_input() {
+        return;
+    }
+    let Some(position) = cursor.position() else {
+        return;
+    };
+    let radius_squared = brush.radius * brush.radius;
+    for (transform, mut particle) in &mut particles {
+        if particle.color_id != species
+            && transform.translation.truncate().distance_squared(position) <= radius_squared
+        {
+            particle.color_id = species;
+        }
+    }
+}
+
+fn draw_brush_outline(
+    mut contexts: EguiContexts,
+    buttons: Res<ButtonInput<MouseButton>>,
+    brush: Res<SprayBrush>,
+    particle_system: Res<ParticleSystem>,
+    cursor: WorldCursor,
+    mut gizmos: Gizmos,
+) {
+    if !brush.outline || contexts.ctx_mut().is_pointer_over_area() {
+        return;
+    }
+    let Some(position) = cursor.position() else {
+        return;
+    };
+    let color = brush
+        .species
+        .and_then(|species| particle_system.colors.get(species).copied())
+        .unwrap_or(Color::WHITE);
+    let alpha = if buttons.pressed(MouseButton::Right) {
+        0.8
+    } else {
+        0.3
+    };
+    gizmos.circle_2d(
+        Isometry2d::from_translation(position),
+        brush.radius,
+        color.with_alpha(alpha),
+    );
+}
+
+fn brush_ui_system(
+    mut contexts: EguiContexts,
+    particle_system: Res<ParticleSystem>,
+    mut brush: ResMut<SprayBrush>,
+) {
+    egui::Window::new("Brush")
+        .default_pos([620.0, 850.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut brush.mode, BrushMode::Spray, "Spray");
+                ui.selectable_value(&mut brush.mode, BrushMode::Paint, "Paint Species");
+            });
+            ui.label(match brush.mode {
+                BrushMode::Spray => "Hold the right mouse button to spray particles.",
+                BrushMode::Paint => {
+                    "Hold the right mouse button to recolor the particles under the brush."
+                }
+            });
+            ui.add(
+                egui::Slider::new(&mut brush.radius, BRUSH_RADII)
+                    .logarithmic(true)
+                    .text("radius"),
+            );
+            if brush.mode == BrushMode::Spray {
+                ui.add(
+                    egui::Slider::new(&mut brush.rate, BRUSH_RATES)
+                        .logarithmic(true)
+                        .text("particles / s"),
+                );
+            }
+            let colors = &particle_system.colors;
+            if brush.species.is_some_and(|species| species >= colors.len()) {
+                brush.species = None;
+            }
+            let label = |species: Option<usize>| match species {
+                Some(species) => egui::RichText::new(format!("Species {}", species + 1))
+                    .color(egui_color(colors[species])),
+                None => egui::RichText::new("Spawn weights"),
+            };
+            egui::ComboBox::from_label("Species")
+                .selected_text(label(brush.species))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut brush.species, None, label(None));
+                    for species in 0..colors.len() {
+                        ui.selectable_value(
+                            &mut brush.species,
+                            Some(species),
+                            label(Some(species)),
+                        );
+                    }
+                });
+            if brush.mode == BrushMode::Paint && brush.species.is_none() {
+                ui.label("Pick a species to paint with.");
+            }
+            ui.checkbox(&mut brush.outline, "Show outline");
+        });
+}
+
+pub fn handle_spawn_requests(
+    mut requests: EventReader<SpawnRequest>,
+    mut spawner: ParticleSpawner,
+    mut pending: ResMut<loading::PendingSpawns>,
+    particle_system: Res<ParticleSystem>,
+    (pattern, mut spawn_rng): (Res<SpawnPattern>, ResMut<determinism::SpawnRng>),
+    camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    particles: Query<Entity, With<Particle>>,
+) {
+    let n = particle_system.colors.len();
+    if n == 0 {
+        requests.clear();
+        return;
+    }
+    // A reset replaces everything requested before it in the same frame
+    let requests: Vec<SpawnRequest> = requests.read().copied().collect();
+    let start = requests
+        .iter()
+        .rposition(|request| {
+            matches!(
+                request,
+                SpawnRequest::Reset { .. } | SpawnRequest::Reshuffle { .. }
+            )
+        })
+        .unwrap_or(0);
+    // Resets lay out the whole world around the origin, which the other
+    // world-sized layers assume; additions go where the camera is
+    let bounds = world_bounds();
+    let view = view_bounds(camera.get_single().ok());
+    let sampler = particle_system.spawn_weights.sampler(n);
+    for &request in &requests[start..] {
+        match request {
+            SpawnRequest::Reset { count } | SpawnRequest::Reshuffle { count } => {
+                spawn_rng.restart();
+                let rng = spawn_rng.rng();
+                for entity in &particles {
+                    spawner.despawn(entity);
+                }
+                let pattern = match request {
+                    SpawnRequest::Reshuffle { .. } => SpawnPattern::Uniform,
+                    _ => *pattern,
+                };
+                let positions = pattern.positions(count, bounds, rng);
+                let layout: Vec<(Vec2, usize)> = positions
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, position)| {
+                        // Uniform weights keep species evenly interleaved
+                        let color_id = if particle_system.spawn_weights == SpawnWeights::Uniform {
+                            index % n
+                        } else {
+                            pick_species(&sampler, n, rng)
+                        };
+                        (position, color_id)
+                    })
+                    .collect();
+                if count > loading::LOADING_THRESHOLD {
+                    pending.start(layout);
+                    continue;
+                }
+                pending.cancel();
+                for (position, color_id) in layout {
+                    spawner.spawn(position, color_id, particle_system.colors[color_id]);
+                }
+                debug!(particles = count, species = n, "Respawned all particles");
+            }
+            SpawnRequest::Scatter { count } => {
+                let rng = spawn_rng.rng();
+                for _ in 0..count {
+                    let position = uniform_in(view, rng);
+                    let color_id = pick_species(&sampler, n, rng);
+                    spawner.spawn(position, color_id, particle_system.colors[color_id]);
+                }
+            }
+            SpawnRequest::Burst {
+                position,
+                radius,
+                count,
+                species,
+            } => {
+                let rng = spawn_rng.rng();
+                for _ in 0..count {
+                    let offset = random_direction(rng) * radius * rng.random::<f32>().sqrt();
+                    let position = position + offset;
+                    let color_id = species
+                        .filter(|&species| species < n)
+                        .unwrap_or_else(|| pick_species(&sampler, n, rng));
+                    spawner.spawn(position, color_id, particle_system.colors[color_id]);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{egui_color, ui_enabled, ParticleSystem};
+
+/// Number keys that toggle the first species; Shift freezes instead of hides.
+const SPECIES_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+pub struct SpeciesPlugin;
+
+impl Plugin for SpeciesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpeciesToggles>().add_systems(
+            Update,
+            (toggle_species_keys, species_ui_system.run_if(ui_enabled)).chain(),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpeciesState {
+    #[default]
+    Active,
+    /// Stays in place but still pushes and pulls the others.
+    Frozen,
+    /// Invisible, in place and without effect on the others.
+    Hidden,
+}
+
+/// Species switched off at runtime. Not part of the rules, so presets and
+/// shares do not carry it.
+#[derive(Resource, Clone, Default)]
+pub struct SpeciesToggles {
+    states: Vec<SpeciesState>,
+}
+
+impl SpeciesToggles {
+    pub fn state(&self, species: usize) -> SpeciesState {
+        self.states.get(species).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, species: usize, state: SpeciesState) {
+        if self.states.len() <= species {
+            self.states.resize(species + 1, SpeciesState::Active);
+        }
+        self.states[species] = state;
+    }
+
+    /// Switches between `state` and active.
+    fn toggle(&mut self, species: usize, state: SpeciesState) {
+        let next = if self.state(species) == state {
+            SpeciesState::Active
+        } else {
+            state
+        };
+        self.set(species, next);
+    }
+
+    pub fn moves(&self, species: usize) -> bool {
+        self.state(species) == SpeciesState::Active
+    }
+
+    pub fn is_hidden(&self, species: usize) -> bool {
+        self.state(species) == SpeciesState::Hidden
+    }
+
+    pub fn any_hidden(&self) -> bool {
+        self.states.contains(&SpeciesState::Hidden)
+    }
+
+    pub fn any_disabled(&self) -> bool {
+        self.states
+            .iter()
+            .any(|&state| state != SpeciesState::Active)
+    }
+}
+
+fn toggle_species_keys(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut contexts: EguiContexts,
+    particle_system: Res<ParticleSystem>,
+    mut toggles: ResMut<SpeciesToggles>,
+) {
+    // Digits typed into text fields are not meant for us
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let state = if shift {
+        SpeciesState::Frozen
+    } else {
+        SpeciesState::Hidden
+    };
+    for (species, key) in SPECIES_KEYS
+        .into_iter()
+        .enumerate()
+        .take(particle_system.colors.len())
+    {
+        if keyboard.just_pressed(key) {
+            toggles.toggle(species, state);
+        }
+    }
+}
+
+/// "Frozen" and "Hidden" checkboxes for a species in `state`, returning the
+/// state they leave it in. Hidden implies frozen.
+pub fn state_checkboxes(ui: &mut egui::Ui, mut state: SpeciesState) -> SpeciesState {
+    let mut frozen = state != SpeciesState::Active;
+    let mut hidden = state == SpeciesState::Hidden;
+    if ui.checkbox(&mut frozen, "Frozen").changed() {
+        state = if frozen {
+            SpeciesState::Frozen
+        } else {
+            SpeciesState::Active
+        };
+    }
+    if ui.checkbox(&mut hidden, "Hidden").changed() {
+        state = if hidden {
+            SpeciesState::Hidden
+        } else {
+            SpeciesState::Frozen
+        };
+    }
+    state
+}
+
+fn species_ui_system(
+    mut contexts: EguiContexts,
+    particle_system: Res<ParticleSystem>,
+    mut toggles: ResMut<SpeciesToggles>,
+) {
+    egui::Window::new("Species")
+        .default_pos([620.0, 710.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Frozen species stay in place; hidden ones also stop acting on the others.");
+            ui.label("Keys 1-9 hide a species, Shift+1-9 freezes it.");
+            egui::Grid::new("species_toggles").show(ui, |ui| {
+                for (species, color) in particle_system.colors.iter().enumerate() {
+                    let (rect, _) =
+                        ui.allocate_exact_size(egui::vec2(14.0, 14.0), egui::Sense::hover());
+                    ui.painter().rect_filled(rect, 2.0, egui_color(*color));
+                    ui.label(format!("Species {}", species + 1));
+                    let state = state_checkboxes(ui, toggles.state(species));
+                    if state != toggles.state(species) {
+                        toggles.set(species, state);
+                    }
+                    ui.end_row();
+                }
+            });
+            if toggles.any_disabled() && ui.button("Enable All").clicked() {
+                toggles.states.clear();
+            }
+        });
+}
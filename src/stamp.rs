@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    interactions,
+    pool::ParticleSpawner,
+    spawn::{self, WorldCursor},
+    ui_enabled, ParticleSystem,
+};
+
+const PREVIEW_COLOR: Color = Color::srgba(1.0, 0.85, 0.3, 0.8);
+/// Particles one stamp adds at most, so a huge filled rectangle cannot stall
+/// the frame.
+const MAX_STAMP: usize = 20000;
+
+pub struct StampPlugin;
+
+impl Plugin for StampPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StampTool>().add_systems(
+            Update,
+            (
+                stamp_with_mouse
+                    .before(spawn::spawn_at_cursor)
+                    .run_if(ui_enabled),
+                stamp_ui_system.run_if(ui_enabled),
+            ),
+        );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum StampKind {
+    Line,
+    Circle,
+    FilledRect,
+}
+
+/// State of the stamping tool, which places evenly spaced particles along a
+/// dragged line or circle outline, or over a dragged rectangle.
+#[derive(Resource)]
+struct StampTool {
+    /// Shape placed by the next left drag, which then adds no single particle.
+    drawing: Option<StampKind>,
+    start: Option<Vec2>,
+    /// Distance between neighboring particles in world units.
+    spacing: f32,
+    species: usize,
+    /// Second species placed on every other particle.
+    alternate: Option<usize>,
+}
+
+impl Default for StampTool {
+    fn default() -> Self {
+        StampTool {
+            drawing: None,
+            start: None,
+            spacing: 8.0,
+            species: 0,
+            alternate: None,
+        }
+    }
+}
+
+/// Points `spacing` apart from `start` to `end`, both included.
+fn line_points(start: Vec2, end: Vec2, spacing: f32) -> Vec<Vec2> {
+    let steps = (start.distance(end) / spacing).floor() as usize;
+    (0..=steps.min(MAX_STAMP - 1))
+        .map(|step| start.lerp(end, step as f32 / steps.max(1) as f32))
+        .collect()
+}
+
+fn stamp_points(kind: StampKind, start: Vec2, end: Vec2, spacing: f32) -> Vec<Vec2> {
+    match kind {
+        StampKind::Line => line_points(start, end, spacing),
+        StampKind::Circle => {
+            let radius = start.distance(end);
+            let count =
+                ((std::f32::consts::TAU * radius / spacing).floor() as usize).clamp(1, MAX_STAMP);
+            (0..count)
+                .map(|index| {
+                    let angle = std::f32::consts::TAU * index as f32 / count as f32;
+                    start + Vec2::from_angle(angle) * radius
+                })
+                .collect()
+        }
+        StampKind::FilledRect => {
+            let (min, max) = (start.min(end), start.max(end));
+            let cells = ((max - min) / spacing).floor().as_uvec2() + 1;
+            (0..cells.y)
+                .flat_map(|row| (0..cells.x).map(move |column| UVec2::new(column, row)))
+                .take(MAX_STAMP)
+                .map(|cell| min + cell.as_vec2() * spacing)
+                .collect()
+        }
+    }
+}
+
+fn draw_preview(gizmos: &mut Gizmos, kind: StampKind, start: Vec2, end: Vec2) {
+    match kind {
+        StampKind::Line => gizmos.line_2d(start, end, PREVIEW_COLOR),
+        StampKind::Circle => {
+            gizmos.circle_2d(
+                Isometry2d::from_translation(start),
+                start.distance(end),
+                PREVIEW_COLOR,
+            );
+        }
+        StampKind::FilledRect => {
+            gizmos.rect_2d(
+                Isometry2d::from_translation((start + end) / 2.0),
+                (end - start).abs(),
+                PREVIEW_COLOR,
+            );
+        }
+    }
+}
+
+fn stamp_with_mouse(
+    mut contexts: EguiContexts,
+    (mut buttons, keyboard): (ResMut<ButtonInput<MouseButton>>, Res<ButtonInput<KeyCode>>),
+    cursor: WorldCursor,
+    mut tool: ResMut<StampTool>,
+    particle_system: Res<ParticleSystem>,
+    mut spawner: ParticleSpawner,
+    mut gizmos: Gizmos,
+) {
+    let Some(kind) = tool.drawing else {
+        return;
+    };
+    if keyboard.just_pressed(KeyCode::Escape) {
+        tool.drawing = None;
+        tool.start = None;
+        return;
+    }
+    let cursor = cursor.position();
+    if buttons.just_pressed(MouseButton::Left) && !contexts.ctx_mut().wants_pointer_input() {
+        tool.start = cursor;
+        // The click starts the stamp instead of adding a particle
+        buttons.clear_just_pressed(MouseButton::Left);
+    }
+    let (Some(start), Some(end)) = (tool.start, cursor) else {
+        return;
+    };
+    if !buttons.just_released(MouseButton::Left) {
+        draw_preview(&mut gizmos, kind, start, end);
+        return;
+    }
+    // The tool stays armed, so several shapes can be stamped in a row
+    tool.start = None;
+    let n = particle_system.colors.len();
+    if tool.species >= n {
+        return;
+    }
+    let alternate = tool.alternate.filter(|&species| species < n);
+    let points = stamp_points(kind, start, end, tool.spacing.max(1.0));
+    for (index, position) in points.iter().enumerate() {
+        let color_id = match alternate {
+            Some(other) if index % 2 == 1 => other,
+            _ => tool.species,
+        };
+        spawner.spawn(*position, color_id, particle_system.colors[color_id]);
+    }
+    debug!(particles = points.len(), "Stamped particles");
+}
+
+fn stamp_ui_system(
+    mut contexts: EguiContexts,
+    particle_system: Res<ParticleSystem>,
+    mut tool: ResMut<StampTool>,
+) {
+    egui::Window::new("Stamp")
+        .default_pos([620.0, 920.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Places evenly spaced particles, e.g. two opposing walls of species.");
+            ui.horizontal(|ui| {
+                for (kind, label) in [
+                    (StampKind::Line, "Line"),
+                    (StampKind::Circle, "Circle"),
+                    (StampKind::FilledRect, "Filled Rectangle"),
+                ] {
+                    if ui
+                        .selectable_label(tool.drawing == Some(kind), label)
+                        .clicked()
+                    {
+                        tool.drawing = (tool.drawing != Some(kind)).then_some(kind);
+                        tool.start = None;
+                    }
+                }
+            });
+            if tool.drawing.is_some() {
+                ui.label("Drag in the world to stamp, Escape to stop.");
+            }
+            ui.add(egui::Slider::new(&mut tool.spacing, 2.0..=50.0).text("spacing"));
+
+            let colors = &particle_system.colors;
+            if colors.is_empty() {
+                return;
+            }
+            let tool = &mut *tool;
+            tool.species = tool.species.min(colors.len() - 1);
+            interactions::species_combo(ui, "Species", &mut tool.species, colors);
+            let mut alternating = tool.alternate.is_some();
+            if ui
+                .checkbox(&mut alternating, "Alternate with a second species")
+                .changed()
+            {
+                tool.alternate = alternating.then_some((tool.species + 1) % colors.len());
+            }
+            if let Some(other) = &mut tool.alternate {
+                *other = (*other).min(colors.len() - 1);
+                interactions::species_combo(ui, "Second species", other, colors);
+            }
+        });
+}
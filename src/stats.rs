@@ -0,0 +1,196 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{egui_color, Particle, ParticleSystem};
+
+const SAMPLE_INTERVAL: f32 = 1.0;
+const HISTORY_LENGTH: usize = 300;
+const CLUSTER_CELL_SIZE: f32 = 20.0;
+const CLUSTER_MIN_DENSITY: usize = 4;
+const CLUSTER_MIN_CELLS: usize = 3;
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SimulationStats::default())
+            .add_systems(Update, sample_stats);
+    }
+}
+
+#[derive(Clone)]
+pub struct StatsSample {
+    pub time: f32,
+    pub species_counts: Vec<usize>,
+    pub average_speed: f32,
+    pub cluster_count: usize,
+}
+
+#[derive(Resource)]
+pub struct SimulationStats {
+    pub samples: VecDeque<StatsSample>,
+    timer: Timer,
+    last_positions: HashMap<Entity, Vec3>,
+}
+
+impl Default for SimulationStats {
+    fn default() -> Self {
+        SimulationStats {
+            samples: VecDeque::with_capacity(HISTORY_LENGTH),
+            timer: Timer::from_seconds(SAMPLE_INTERVAL, TimerMode::Repeating),
+            last_positions: HashMap::new(),
+        }
+    }
+}
+
+impl SimulationStats {
+    pub fn latest(&self) -> Option<&StatsSample> {
+        self.samples.back()
+    }
+
+    fn push(&mut self, sample: StatsSample) {
+        if self.samples.len() == HISTORY_LENGTH {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+}
+
+fn sample_stats(
+    time: Res<Time>,
+    particle_system: Res<ParticleSystem>,
+    mut stats: ResMut<SimulationStats>,
+    particles: Query<(Entity, &Transform, &Particle)>,
+) {
+    if !stats.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let mut species_counts = vec![0; particle_system.colors.len()];
+    let mut positions = HashMap::with_capacity(stats.last_positions.len());
+    let mut total_distance = 0.0;
+    let mut moved = 0;
+
+    for (entity, transform, particle) in &particles {
+        if let Some(count) = species_counts.get_mut(particle.color_id) {
+            *count += 1;
+        }
+        let pos = transform.translation;
+        if let Some(last) = stats.last_positions.get(&entity) {
+            total_distance += pos.distance(*last);
+            moved += 1;
+        }
+        positions.insert(entity, pos);
+    }
+
+    let average_speed = if moved > 0 {
+        total_distance / moved as f32 / SAMPLE_INTERVAL
+    } else {
+        0.0
+    };
+    let cluster_count = count_clusters(positions.values().copied());
+
+    stats.last_positions = positions;
+    stats.push(StatsSample {
+        time: time.elapsed_secs(),
+        species_counts,
+        average_speed,
+        cluster_count,
+    });
+}
+
+/// Counts clusters as 8-connected groups of dense cells on a coarse grid.
+pub fn count_clusters(positions: impl Iterator<Item = Vec3>) -> usize {
+    let mut cells: HashMap<(i32, i32), usize> = HashMap::new();
+    for pos in positions {
+        let cell = (
+            (pos.x / CLUSTER_CELL_SIZE).floor() as i32,
+            (pos.y / CLUSTER_CELL_SIZE).floor() as i32,
+        );
+        *cells.entry(cell).or_default() += 1;
+    }
+
+    let dense: HashSet<(i32, i32)> = cells
+        .into_iter()
+        .filter(|&(_, count)| count >= CLUSTER_MIN_DENSITY)
+        .map(|(cell, _)| cell)
+        .collect();
+
+    let mut visited = HashSet::new();
+    let mut clusters = 0;
+    for &start in &dense {
+        if !visited.insert(start) {
+            continue;
+        }
+        let mut stack = vec![start];
+        let mut size = 0;
+        while let Some((x, y)) = stack.pop() {
+            size += 1;
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    let neighbor = (x + dx, y + dy);
+                    if dense.contains(&neighbor) && visited.insert(neighbor) {
+                        stack.push(neighbor);
+                    }
+                }
+            }
+        }
+        if size >= CLUSTER_MIN_CELLS {
+            clusters += 1;
+        }
+    }
+    clusters
+}
+
+/// The "Statistics" dock panel.
+pub fn stats_panel(
+    InMut(ui): InMut<egui::Ui>,
+    particle_system: Res<ParticleSystem>,
+    stats: Res<SimulationStats>,
+) {
+    if let Some(latest) = stats.latest() {
+        ui.label(format!("Average speed: {:.1}", latest.average_speed));
+        ui.label(format!("Clusters: {}", latest.cluster_count));
+    }
+
+    ui.add_space(10.0);
+    ui.label("Population");
+    Plot::new("population_plot")
+        .height(160.0)
+        .allow_scroll(false)
+        .show(ui, |plot_ui| {
+            for (species, color) in particle_system.colors.iter().enumerate() {
+                let points: PlotPoints = stats
+                    .samples
+                    .iter()
+                    .filter_map(|sample| {
+                        let count = *sample.species_counts.get(species)?;
+                        Some([sample.time as f64, count as f64])
+                    })
+                    .collect();
+                plot_ui.line(Line::new(points).color(egui_color(*color)));
+            }
+        });
+
+    ui.label("Speed and clusters");
+    Plot::new("energy_plot")
+        .height(120.0)
+        .allow_scroll(false)
+        .legend(Legend::default())
+        .show(ui, |plot_ui| {
+            let speed: PlotPoints = stats
+                .samples
+                .iter()
+                .map(|sample| [sample.time as f64, sample.average_speed as f64])
+                .collect();
+            let clusters: PlotPoints = stats
+                .samples
+                .iter()
+                .map(|sample| [sample.time as f64, sample.cluster_count as f64])
+                .collect();
+            plot_ui.line(Line::new(speed).name("Average speed"));
+            plot_ui.line(Line::new(clusters).name("Clusters"));
+        });
+}
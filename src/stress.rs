@@ -0,0 +1,147 @@
+use bevy::{
+    diagnostic::{DiagnosticPath, DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    backend::{self, SimulationBackends},
+    lod::LodSettings,
+    perf,
+    quality::QualityGovernor,
+    ui_enabled, Particle,
+};
+
+const DEFAULT_PARTICLES: usize = 100_000;
+const LOG_INTERVAL: f32 = 5.0;
+
+/// Particle count of a stress run, enabled with `--stress <count>`.
+#[derive(Resource, Clone)]
+pub struct StressConfig {
+    pub particles: usize,
+}
+
+impl StressConfig {
+    pub fn from_args() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        let index = args.iter().position(|arg| arg == "--stress")?;
+        let particles = args
+            .get(index + 1)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_PARTICLES);
+        Some(StressConfig { particles })
+    }
+}
+
+/// Runs the full particle count on the grid backend with nothing that would
+/// lighten the load (quality governor and level of detail off), and reports
+/// the achieved rates in a HUD and the log.
+pub struct StressPlugin {
+    pub config: StressConfig,
+}
+
+impl Plugin for StressPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.config.clone())
+            .add_systems(Startup, configure_stress)
+            .add_systems(
+                Update,
+                (log_stress_report, stress_hud_system.run_if(ui_enabled)),
+            );
+    }
+}
+
+fn configure_stress(
+    mut backends: ResMut<SimulationBackends>,
+    mut quality: ResMut<QualityGovernor>,
+    mut lod: ResMut<LodSettings>,
+) {
+    backends.select(backend::GRID);
+    quality.enabled = false;
+    lod.enabled = false;
+}
+
+/// Rates achieved by the current stress run.
+struct StressReport {
+    particles: usize,
+    backend: &'static str,
+    /// Spatial build plus force integration, per tick.
+    tick_ms: Option<f64>,
+    fps: Option<f64>,
+}
+
+impl StressReport {
+    fn new(
+        diagnostics: &DiagnosticsStore,
+        backends: &SimulationBackends,
+        particles: usize,
+    ) -> Self {
+        let smoothed = |path: &DiagnosticPath| {
+            diagnostics
+                .get(path)
+                .and_then(|diagnostic| diagnostic.smoothed())
+        };
+        let tick_ms = smoothed(&perf::SPATIAL_BUILD)
+            .zip(smoothed(&perf::SIMULATION))
+            .map(|(spatial, simulation)| spatial + simulation);
+        StressReport {
+            particles,
+            backend: backends.active_name(),
+            tick_ms,
+            fps: smoothed(&FrameTimeDiagnosticsPlugin::FPS),
+        }
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "{} particles, {} backend",
+            self.particles, self.backend
+        )];
+        if let Some(tick_ms) = self.tick_ms {
+            lines.push(format!(
+                "Simulation tick {:.2} ms, at most {:.0} ticks/s",
+                tick_ms,
+                1000.0 / tick_ms.max(f64::EPSILON)
+            ));
+        }
+        if let Some(fps) = self.fps {
+            lines.push(format!("{:.1} frames/s achieved", fps));
+        }
+        lines
+    }
+}
+
+fn log_stress_report(
+    time: Res<Time>,
+    mut timer: Local<Option<Timer>>,
+    diagnostics: Res<DiagnosticsStore>,
+    backends: Res<SimulationBackends>,
+    particles: Query<(), With<Particle>>,
+) {
+    let timer =
+        timer.get_or_insert_with(|| Timer::from_seconds(LOG_INTERVAL, TimerMode::Repeating));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let report = StressReport::new(&diagnostics, &backends, particles.iter().len());
+    info!("Stress: {}", report.lines().join("; "));
+}
+
+fn stress_hud_system(
+    mut contexts: EguiContexts,
+    diagnostics: Res<DiagnosticsStore>,
+    backends: Res<SimulationBackends>,
+    particles: Query<(), With<Particle>>,
+) {
+    let report = StressReport::new(&diagnostics, &backends, particles.iter().len());
+    egui::Area::new(egui::Id::new("stress_hud"))
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 30.0])
+        .show(contexts.ctx_mut(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.strong("Stress test");
+                for line in report.lines() {
+                    ui.label(line);
+                }
+            });
+        });
+}
@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{quality::QualityGovernor, ui_enabled, ParticleSystem};
+
+pub struct SubstepPlugin;
+
+impl Plugin for SubstepPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Substepping>()
+            .add_systems(Update, substep_ui_system.run_if(ui_enabled));
+    }
+}
+
+/// Splits a tick into shorter sub-steps when particles could otherwise move
+/// far relative to the interaction radius in one step, which is what makes
+/// forces blow up at high speed multipliers.
+#[derive(Resource)]
+pub struct Substepping {
+    pub enabled: bool,
+    /// Largest distance a particle at full force may move in one sub-step, as
+    /// a fraction of the interaction radius.
+    pub max_travel: f32,
+    pub max_substeps: u32,
+}
+
+impl Default for Substepping {
+    fn default() -> Self {
+        Substepping {
+            enabled: true,
+            max_travel: 0.5,
+            max_substeps: 8,
+        }
+    }
+}
+
+impl Substepping {
+    /// Sub-steps for a tick of `step` seconds. Averaged neighbor forces are at
+    /// most about one, so a particle moves up to `speed * step` per tick.
+    pub fn count(&self, step: f32, speed: f32, radius: f32) -> u32 {
+        if !self.enabled || radius <= 0.0 || self.max_travel <= 0.0 {
+            return 1;
+        }
+        let travel = speed * step / radius;
+        ((travel / self.max_travel).ceil() as u32).clamp(1, self.max_substeps.max(1))
+    }
+}
+
+fn substep_ui_system(
+    mut contexts: EguiContexts,
+    time: Res<Time>,
+    quality: Res<QualityGovernor>,
+    particle_system: Res<ParticleSystem>,
+    mut substepping: ResMut<Substepping>,
+) {
+    egui::Window::new("Sub-stepping")
+        .default_pos([620.0, 640.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Splits fast ticks so high speeds stay stable.");
+            ui.checkbox(&mut substepping.enabled, "Sub-stepping");
+            ui.add(
+                egui::Slider::new(&mut substepping.max_travel, 0.05..=1.0)
+                    .text("max travel per step (× radius)"),
+            );
+            ui.add(egui::Slider::new(&mut substepping.max_substeps, 1..=32).text("max sub-steps"));
+            let count = substepping.count(
+                time.delta_secs() * quality.update_stride as f32 * particle_system.time_scale,
+                particle_system.fastest_speed(),
+                particle_system.max_interaction_radius() * quality.radius_scale,
+            );
+            ui.label(format!("Current: {} sub-step(s) per tick", count));
+        });
+}
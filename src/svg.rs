@@ -0,0 +1,286 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    particle_assets::ParticleMaterial, settings::Settings, shapes::SpeciesShape, spawn, ui_enabled,
+    MainCamera, Particle, ParticleSystem, PARTICLE_SIZE,
+};
+
+const EXPORT_DIR: &str = "exports";
+const TRAIL_LENGTHS: std::ops::RangeInclusive<usize> = 2..=240;
+
+/// Writes the particles in view, with their sizes, colors and shapes, as an
+/// SVG file that stays sharp at any print size. Optionally each particle
+/// trails a line through its recent positions.
+pub struct SvgPlugin;
+
+impl Plugin for SvgPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SvgExport>().add_systems(
+            Update,
+            (record_trails, export_svg, svg_ui_system.run_if(ui_enabled)).chain(),
+        );
+    }
+}
+
+#[derive(Resource)]
+struct SvgExport {
+    trails: bool,
+    /// Frames of history a trail covers.
+    trail_length: usize,
+    history: HashMap<Entity, VecDeque<Vec2>>,
+    requested: bool,
+    message: Option<String>,
+}
+
+impl Default for SvgExport {
+    fn default() -> Self {
+        SvgExport {
+            trails: false,
+            trail_length: 30,
+            history: HashMap::new(),
+            requested: false,
+            message: None,
+        }
+    }
+}
+
+fn record_trails(
+    mut export: ResMut<SvgExport>,
+    particles: Query<(Entity, &Transform, Ref<Particle>)>,
+) {
+    if !export.trails {
+        if !export.history.is_empty() {
+            export.history.clear();
+        }
+        return;
+    }
+    let export = &mut *export;
+    export
+        .history
+        .retain(|&entity, _| particles.contains(entity));
+    for (entity, transform, particle) in &particles {
+        let trail = export.history.entry(entity).or_default();
+        // A reused entity starts a new particle somewhere else
+        if particle.is_added() {
+            trail.clear();
+        }
+        trail.push_back(transform.translation.truncate());
+        while trail.len() > export.trail_length {
+            trail.pop_front();
+        }
+    }
+}
+
+/// One particle as drawn.
+struct Mark<'a> {
+    pos: Vec2,
+    color: Color,
+    shape: SpeciesShape,
+    scale: f32,
+    trail: Option<&'a VecDeque<Vec2>>,
+}
+
+/// `#rrggbb` plus the opacity, for SVG paint attributes.
+fn paint(color: Color) -> (String, f32) {
+    let [r, g, b, a] = color.to_srgba().to_u8_array();
+    (format!("#{:02x}{:02x}{:02x}", r, g, b), a as f32 / 255.0)
+}
+
+/// The SVG document showing `view`. SVG's y axis points down, so y is
+/// flipped.
+fn svg_document<'a>(
+    view: Rect,
+    background: Color,
+    marks: impl Iterator<Item = Mark<'a>>,
+) -> String {
+    let size = view.size();
+    let to_svg = |pos: Vec2| Vec2::new(pos.x - view.min.x, view.max.y - pos.y);
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.0}" height="{h:.0}" viewBox="0 0 {w:.2} {h:.2}">"#,
+        w = size.x,
+        h = size.y
+    );
+    let (fill, opacity) = paint(background);
+    let _ = writeln!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="{}" fill-opacity="{:.3}"/>"#,
+        fill, opacity
+    );
+
+    // Particles are drawn over every trail
+    let mut shapes = String::new();
+    for Mark {
+        pos,
+        color,
+        shape,
+        scale,
+        trail,
+    } in marks
+    {
+        let (fill, opacity) = paint(color);
+        if let Some(trail) = trail.filter(|trail| trail.len() > 1) {
+            let points: Vec<String> = trail
+                .iter()
+                .map(|&point| {
+                    let point = to_svg(point);
+                    format!("{:.2},{:.2}", point.x, point.y)
+                })
+                .collect();
+            let _ = writeln!(
+                svg,
+                r#"<polyline points="{}" fill="none" stroke="{}" stroke-opacity="{:.3}" stroke-width="{:.2}"/>"#,
+                points.join(" "),
+                fill,
+                opacity * 0.5,
+                PARTICLE_SIZE * 0.4 * scale
+            );
+        }
+        let center = to_svg(pos);
+        let fill = format!(r#"fill="{}" fill-opacity="{:.3}""#, fill, opacity);
+        let _ = match shape {
+            SpeciesShape::Circle => writeln!(
+                shapes,
+                r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" {}/>"#,
+                center.x,
+                center.y,
+                PARTICLE_SIZE / 2.0 * scale,
+                fill
+            ),
+            SpeciesShape::Square => {
+                let side = PARTICLE_SIZE * 0.9 * scale;
+                writeln!(
+                    shapes,
+                    r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" {}/>"#,
+                    center.x - side / 2.0,
+                    center.y - side / 2.0,
+                    side,
+                    side,
+                    fill
+                )
+            }
+            SpeciesShape::Triangle => {
+                // Matches the shader, a regular triangle pointing up
+                let radius = PARTICLE_SIZE * 0.67 * scale;
+                let points: Vec<String> = [90.0f32, 210.0, 330.0]
+                    .iter()
+                    .map(|degrees| {
+                        let corner = center + Vec2::from_angle(-degrees.to_radians()) * radius;
+                        format!("{:.2},{:.2}", corner.x, corner.y)
+                    })
+                    .collect();
+                writeln!(
+                    shapes,
+                    r#"<polygon points="{}" {}/>"#,
+                    points.join(" "),
+                    fill
+                )
+            }
+        };
+    }
+    svg.push_str(&shapes);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn write_svg(path: &Path, svg: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, svg)
+}
+
+fn export_svg(
+    mut export: ResMut<SvgExport>,
+    settings: Res<Settings>,
+    particle_system: Res<ParticleSystem>,
+    clear_color: Res<ClearColor>,
+    materials: Res<Assets<ParticleMaterial>>,
+    camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    particles: Query<(
+        Entity,
+        &Transform,
+        &Particle,
+        &MeshMaterial2d<ParticleMaterial>,
+    )>,
+) {
+    if !std::mem::take(&mut export.requested) {
+        return;
+    }
+    let view = spawn::view_bounds(camera.get_single().ok());
+    // Keep particles overlapping the edge
+    let margin = view.inflate(PARTICLE_SIZE);
+    let in_view = particles
+        .iter()
+        .filter(|(_, transform, ..)| margin.contains(transform.translation.truncate()))
+        .map(|(entity, transform, particle, material)| {
+            let color = materials
+                .get(&material.0)
+                .map(|material| Color::from(material.color))
+                .or_else(|| particle_system.colors.get(particle.color_id).copied())
+                .unwrap_or(Color::WHITE);
+            let shape = if settings.species_shapes {
+                particle_system
+                    .shapes
+                    .get(particle.color_id)
+                    .copied()
+                    .unwrap_or_default()
+            } else {
+                SpeciesShape::Circle
+            };
+            Mark {
+                pos: transform.translation.truncate(),
+                color,
+                shape,
+                scale: transform.scale.x,
+                trail: export.history.get(&entity),
+            }
+        });
+    let svg = svg_document(view, clear_color.0, in_view);
+
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let path = PathBuf::from(EXPORT_DIR).join(format!("frame_{}.svg", stamp));
+    export.message = Some(match write_svg(&path, &svg) {
+        Ok(()) => {
+            info!("Exported SVG to {}", path.display());
+            format!("Exported to {}", path.display())
+        }
+        Err(error) => {
+            error!("Failed to export SVG to {}: {}", path.display(), error);
+            format!("Export failed: {}", error)
+        }
+    });
+}
+
+fn svg_ui_system(mut contexts: EguiContexts, mut export: ResMut<SvgExport>) {
+    egui::Window::new("SVG Export")
+        .default_pos([10.0, 1020.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Particles in view as vector shapes, for posters and papers.");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut export.trails, "Trails over");
+                ui.add_enabled(
+                    export.trails,
+                    egui::DragValue::new(&mut export.trail_length).range(TRAIL_LENGTHS),
+                );
+                ui.label("frames");
+            });
+            if ui.button("Export SVG").clicked() {
+                export.requested = true;
+            }
+            if let Some(message) = &export.message {
+                ui.label(message);
+            }
+        });
+}
@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    determinism::SpawnRng, freeze, replay, species::SpeciesToggles, update_particles, Particle,
+    ParticleSystem,
+};
+
+/// Temperature below which annealing snaps to zero.
+const ANNEALING_FLOOR: f32 = 0.01;
+
+pub struct TemperaturePlugin;
+
+impl Plugin for TemperaturePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (anneal_temperature, apply_brownian_noise)
+                .chain()
+                .after(update_particles)
+                .after(crate::spawn::handle_spawn_requests)
+                .run_if(not(replay::is_playing_back)),
+        );
+    }
+}
+
+/// Jitters every particle by a Gaussian displacement with standard deviation
+/// `temperature * sqrt(dt)`, i.e. Brownian motion independent of frame rate.
+/// Rule zones can override the temperature locally. Frozen particles and
+/// frozen or hidden species stay put.
+fn apply_brownian_noise(
+    time: Res<Time>,
+    particle_system: Res<ParticleSystem>,
+    toggles: Res<SpeciesToggles>,
+    mut spawn_rng: ResMut<SpawnRng>,
+    mut particles: Query<(&mut Transform, &Particle), Without<freeze::Frozen>>,
+) {
+    let heated_zone = particle_system.zones.iter().any(|zone| {
+        zone.temperature
+            .is_some_and(|temperature| temperature > 0.0)
+    });
+    if particle_system.temperature <= 0.0 && !heated_zone {
+        return;
+    }
+    let scale = time.delta_secs().sqrt();
+    let rng = spawn_rng.noise();
+    for (mut transform, particle) in &mut particles {
+        if !toggles.moves(particle.color_id) {
+            continue;
+        }
+        let temperature = particle_system
+            .zone_at(transform.translation.truncate())
+            .and_then(|zone| zone.temperature)
+            .unwrap_or(particle_system.temperature);
+        if temperature <= 0.0 {
+            continue;
+        }
+        let jitter = gaussian_pair(rng) * temperature * scale;
+        transform.translation += jitter.extend(0.0);
+    }
+}
+
+fn anneal_temperature(time: Res<Time>, mut particle_system: ResMut<ParticleSystem>) {
+    if !particle_system.annealing || particle_system.temperature <= 0.0 {
+        return;
+    }
+    let decay = (-particle_system.annealing_rate * time.delta_secs()).exp();
+    particle_system.temperature *= decay;
+    if particle_system.temperature < ANNEALING_FLOOR {
+        particle_system.temperature = 0.0;
+        particle_system.annealing = false;
+    }
+}
+
+/// Two independent standard normal samples via the Box–Muller transform.
+pub fn gaussian_pair(rng: &mut impl Rng) -> Vec2 {
+    let u1: f32 = rng.random_range(f32::EPSILON..1.0);
+    let u2: f32 = rng.random();
+    let radius = (-2.0 * u1.ln()).sqrt();
+    Vec2::from_angle(std::f32::consts::TAU * u2) * radius
+}
@@ -0,0 +1,293 @@
+use bevy::{color::Mix, prelude::*};
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+
+use crate::{replay, ui_enabled, ParticleSystem};
+
+const DEFAULT_TIMELINE_PATH: &str = "timeline.ron";
+const SECONDS_PER_DAY: f32 = 86_400.0;
+
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        let mut player = TimelinePlayer::default();
+        // `--timeline <path>` starts playing immediately, for unattended installations
+        let args: Vec<String> = std::env::args().collect();
+        if let Some(index) = args.iter().position(|arg| arg == "--timeline") {
+            if let Some(path) = args.get(index + 1) {
+                player.path = path.clone();
+            }
+            player.load_file();
+            player.playing = player.timeline.is_some();
+        }
+
+        app.insert_resource(player).add_systems(
+            Update,
+            (
+                play_timeline.run_if(not(replay::is_playing_back)),
+                timeline_ui_system.run_if(ui_enabled),
+            ),
+        );
+    }
+}
+
+/// Keyframed parameter automation, written by hand as RON. Every field of a
+/// keyframe is optional; each parameter is interpolated between the nearest
+/// keyframes that set it and held after the last one.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Timeline {
+    #[serde(default)]
+    pub looping: bool,
+    /// Keyframe times are seconds since local midnight instead of since start.
+    #[serde(default)]
+    pub time_of_day: bool,
+    pub keyframes: Vec<Keyframe>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keyframe {
+    pub time: f32,
+    pub speed: Option<f32>,
+    pub beta: Option<f32>,
+    pub gamma: Option<f32>,
+    pub attraction_radius: Option<f32>,
+    pub temperature: Option<f32>,
+    /// Full behavior matrix; blended element-wise between keyframes.
+    pub matrix: Option<Vec<Vec<f32>>>,
+    /// sRGB colors, one per species; blended in Oklab between keyframes.
+    pub palette: Option<Vec<[f32; 3]>>,
+}
+
+impl Timeline {
+    fn duration(&self) -> f32 {
+        self.keyframes
+            .iter()
+            .map(|keyframe| keyframe.time)
+            .fold(0.0, f32::max)
+    }
+
+    /// The keyframes around `time` that set a field, and how far between them
+    /// `time` is. Before the first or after the last, that keyframe is held.
+    fn span<'a, T>(
+        &'a self,
+        time: f32,
+        field: impl Fn(&'a Keyframe) -> Option<&'a T>,
+    ) -> Option<(&'a T, &'a T, f32)> {
+        let mut before: Option<(f32, &T)> = None;
+        let mut after: Option<(f32, &T)> = None;
+        for keyframe in &self.keyframes {
+            let Some(value) = field(keyframe) else {
+                continue;
+            };
+            if keyframe.time <= time {
+                if before.is_none_or(|(t, _)| keyframe.time >= t) {
+                    before = Some((keyframe.time, value));
+                }
+            } else if after.is_none_or(|(t, _)| keyframe.time < t) {
+                after = Some((keyframe.time, value));
+            }
+        }
+        match (before, after) {
+            (Some((t0, a)), Some((t1, b))) => Some((a, b, (time - t0) / (t1 - t0))),
+            (Some((_, a)), None) => Some((a, a, 0.0)),
+            (None, Some((_, b))) => Some((b, b, 0.0)),
+            (None, None) => None,
+        }
+    }
+
+    fn scalar(&self, time: f32, field: impl Fn(&Keyframe) -> Option<&f32>) -> Option<f32> {
+        self.span(time, field).map(|(a, b, t)| a + (b - a) * t)
+    }
+}
+
+#[derive(Resource)]
+pub struct TimelinePlayer {
+    pub timeline: Option<Timeline>,
+    pub path: String,
+    pub playing: bool,
+    pub time: f32,
+    source: String,
+    message: Option<String>,
+}
+
+impl Default for TimelinePlayer {
+    fn default() -> Self {
+        TimelinePlayer {
+            timeline: None,
+            path: DEFAULT_TIMELINE_PATH.to_string(),
+            playing: false,
+            time: 0.0,
+            source: String::new(),
+            message: None,
+        }
+    }
+}
+
+impl TimelinePlayer {
+    fn load_str(&mut self, text: &str) {
+        match ron::from_str::<Timeline>(text) {
+            Ok(timeline) => {
+                self.message = Some(format!(
+                    "Loaded {} keyframes over {:.0}s",
+                    timeline.keyframes.len(),
+                    timeline.duration()
+                ));
+                self.timeline = Some(timeline);
+                self.time = 0.0;
+            }
+            Err(error) => self.message = Some(format!("Invalid timeline: {}", error)),
+        }
+    }
+
+    fn load_file(&mut self) {
+        match std::fs::read_to_string(&self.path) {
+            Ok(text) => {
+                self.load_str(&text);
+                self.source = text;
+            }
+            Err(error) => self.message = Some(format!("Could not read {}: {}", self.path, error)),
+        }
+    }
+}
+
+/// Seconds since local midnight.
+fn time_of_day() -> f32 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let now = js_sys::Date::new_0();
+        now.get_hours() as f32 * 3600.0 + now.get_minutes() as f32 * 60.0 + now.get_seconds() as f32
+    }
+    // Without a timezone database native builds use UTC
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        (seconds % SECONDS_PER_DAY as u64) as f32
+    }
+}
+
+fn play_timeline(
+    time: Res<Time>,
+    mut player: ResMut<TimelinePlayer>,
+    mut particle_system: ResMut<ParticleSystem>,
+) {
+    if !player.playing {
+        return;
+    }
+    let Some(timeline) = player.timeline.clone() else {
+        return;
+    };
+
+    let now = if timeline.time_of_day {
+        time_of_day()
+    } else {
+        let mut now = player.time + time.delta_secs();
+        let duration = timeline.duration();
+        if timeline.looping && duration > 0.0 {
+            now %= duration;
+        }
+        now
+    };
+    player.time = now;
+
+    if let Some(speed) = timeline.scalar(now, |k| k.speed.as_ref()) {
+        particle_system.force_strength = speed;
+    }
+    if let Some(beta) = timeline.scalar(now, |k| k.beta.as_ref()) {
+        particle_system.beta = beta;
+    }
+    if let Some(gamma) = timeline.scalar(now, |k| k.gamma.as_ref()) {
+        particle_system.gamma = gamma;
+    }
+    if let Some(radius) = timeline.scalar(now, |k| k.attraction_radius.as_ref()) {
+        particle_system.attraction_radius = radius;
+    }
+    if let Some(temperature) = timeline.scalar(now, |k| k.temperature.as_ref()) {
+        particle_system.temperature = temperature;
+    }
+
+    // Matrices and palettes only apply when they match the current species count
+    let n = particle_system.colors.len();
+    if let Some((a, b, t)) = timeline.span(now, |k| k.matrix.as_ref()) {
+        if a.len() == n && b.len() == n {
+            for (row, (row_a, row_b)) in particle_system
+                .behavior_matrix
+                .iter_mut()
+                .zip(a.iter().zip(b))
+            {
+                for (value, (x, y)) in row.iter_mut().zip(row_a.iter().zip(row_b)) {
+                    *value = x + (y - x) * t;
+                }
+            }
+        }
+    }
+    if let Some((a, b, t)) = timeline.span(now, |k| k.palette.as_ref()) {
+        if a.len() == n && b.len() == n {
+            let palette: Vec<Color> = a
+                .iter()
+                .zip(b)
+                .map(|(&[r0, g0, b0], &[r1, g1, b1])| {
+                    let from = Oklaba::from(Srgba::rgb(r0, g0, b0));
+                    let to = Oklaba::from(Srgba::rgb(r1, g1, b1));
+                    Color::from(from.mix(&to, t))
+                })
+                .collect();
+            if palette != particle_system.colors {
+                particle_system.colors = palette;
+            }
+        }
+    }
+}
+
+fn timeline_ui_system(mut contexts: EguiContexts, mut player: ResMut<TimelinePlayer>) {
+    egui::Window::new("Timeline")
+        .default_pos([620.0, 500.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut player.path);
+                if ui.button("Load File").clicked() {
+                    player.load_file();
+                }
+            });
+            ui.label("Timeline RON:");
+            ui.add(
+                egui::TextEdit::multiline(&mut player.source)
+                    .code_editor()
+                    .desired_rows(6),
+            );
+            if ui.button("Load Text").clicked() {
+                let source = player.source.clone();
+                player.load_str(&source);
+            }
+
+            if let Some(timeline) = &player.timeline {
+                let duration = timeline.duration();
+                let time_of_day = timeline.time_of_day;
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let label = if player.playing { "Pause" } else { "Play" };
+                    if ui.button(label).clicked() {
+                        player.playing = !player.playing;
+                    }
+                    if ui.button("Rewind").clicked() {
+                        player.time = 0.0;
+                    }
+                });
+                if time_of_day {
+                    ui.label(format!("Time of day: {:.0}s", player.time));
+                } else {
+                    ui.add(
+                        egui::Slider::new(&mut player.time, 0.0..=duration.max(0.001)).text("s"),
+                    );
+                }
+            }
+            if let Some(message) = &player.message {
+                ui.label(message);
+            }
+        });
+}
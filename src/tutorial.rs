@@ -0,0 +1,210 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    dock::Panel, help::key_name, preset::PresetSaved, settings::Settings, spawn::SpawnRequest,
+    ui_enabled, ControlledCamera, ParticleSystem,
+};
+
+/// Camera travel in world units that completes the pan step.
+const PAN_DISTANCE: f32 = 50.0;
+/// Relative change of the camera scale that completes the zoom step.
+const ZOOM_CHANGE: f32 = 0.05;
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tutorial>().add_systems(
+            Update,
+            (
+                advance_tutorial.after(crate::move_camera),
+                tutorial_ui_system.run_if(ui_enabled),
+            )
+                .chain(),
+        );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TutorialStep {
+    Pan,
+    Zoom,
+    Spawn,
+    EditMatrix,
+    SavePreset,
+}
+
+const STEPS: [TutorialStep; 5] = [
+    TutorialStep::Pan,
+    TutorialStep::Zoom,
+    TutorialStep::Spawn,
+    TutorialStep::EditMatrix,
+    TutorialStep::SavePreset,
+];
+
+impl TutorialStep {
+    fn title(self) -> &'static str {
+        match self {
+            TutorialStep::Pan => "Move around",
+            TutorialStep::Zoom => "Zoom",
+            TutorialStep::Spawn => "Add particles",
+            TutorialStep::EditMatrix => "Change a rule",
+            TutorialStep::SavePreset => "Keep your rules",
+        }
+    }
+
+    fn instructions(self, settings: &Settings) -> String {
+        let keys = &settings.keys;
+        match self {
+            TutorialStep::Pan => format!(
+                "Move the camera with {}, {}, {} and {}.",
+                key_name(keys.camera_up),
+                key_name(keys.camera_left),
+                key_name(keys.camera_down),
+                key_name(keys.camera_right)
+            ),
+            TutorialStep::Zoom => format!(
+                "Zoom in with {} and out with {}.",
+                key_name(keys.zoom_in),
+                key_name(keys.zoom_out)
+            ),
+            TutorialStep::Spawn => {
+                "Left click in the world to add a particle, or hold the right button to spray them."
+                    .to_string()
+            }
+            TutorialStep::EditMatrix => "Drag any slider in the highlighted Matrix Editor. \
+                 Row i, column j is how strongly species i is drawn to species j; \
+                 negative values repel."
+                .to_string(),
+            TutorialStep::SavePreset => "In the highlighted Presets panel, save the current \
+                 rules to a file or copy them to the clipboard."
+                .to_string(),
+        }
+    }
+
+    /// Dock panel the step is done in.
+    fn panel(self) -> Option<Panel> {
+        match self {
+            TutorialStep::EditMatrix => Some(Panel::Matrix),
+            TutorialStep::SavePreset => Some(Panel::Presets),
+            _ => None,
+        }
+    }
+}
+
+/// Guided walk through the basic interactions. Each step completes when the
+/// user has done it, and progress is kept in [`Settings::tutorial_progress`].
+#[derive(Resource, Default)]
+pub struct Tutorial {
+    /// Index into [`STEPS`]; equal to its length once finished.
+    step: Option<usize>,
+    /// Camera and matrix when the current step began, to notice changes.
+    baseline: Option<(Transform, Vec<Vec<f32>>)>,
+}
+
+impl Tutorial {
+    /// Resumes after the completed steps, or starts over once all are done.
+    pub fn start(&mut self, progress: usize) {
+        self.step = Some(if progress < STEPS.len() { progress } else { 0 });
+        self.baseline = None;
+    }
+
+    fn current(&self) -> Option<TutorialStep> {
+        STEPS.get(self.step?).copied()
+    }
+
+    /// Panel the current step wants the user to look at.
+    pub fn highlighted(&self) -> Option<Panel> {
+        self.current()?.panel()
+    }
+}
+
+fn advance_tutorial(
+    mut tutorial: ResMut<Tutorial>,
+    mut settings: ResMut<Settings>,
+    camera: Query<&Transform, With<ControlledCamera>>,
+    particle_system: Res<ParticleSystem>,
+    mut spawn_requests: EventReader<SpawnRequest>,
+    mut saved: EventReader<PresetSaved>,
+) {
+    // Drain both every frame so events from before a step do not count
+    let spawned = spawn_requests
+        .read()
+        .any(|request| matches!(request, SpawnRequest::Burst { .. }));
+    let saved = saved.read().count() > 0;
+    let (Some(index), Some(step)) = (tutorial.step, tutorial.current()) else {
+        return;
+    };
+    let Ok(transform) = camera.get_single() else {
+        return;
+    };
+    let Some((start, matrix)) = &tutorial.baseline else {
+        tutorial.baseline = Some((*transform, particle_system.behavior_matrix.clone()));
+        return;
+    };
+
+    let done = match step {
+        TutorialStep::Pan => {
+            start
+                .translation
+                .truncate()
+                .distance(transform.translation.truncate())
+                > PAN_DISTANCE
+        }
+        TutorialStep::Zoom => (transform.scale.x / start.scale.x - 1.0).abs() > ZOOM_CHANGE,
+        TutorialStep::Spawn => spawned,
+        TutorialStep::EditMatrix => particle_system.behavior_matrix != *matrix,
+        TutorialStep::SavePreset => saved,
+    };
+    if done {
+        tutorial.step = Some(index + 1);
+        tutorial.baseline = None;
+        settings.tutorial_progress = settings.tutorial_progress.max(index + 1);
+    }
+}
+
+fn tutorial_ui_system(
+    mut contexts: EguiContexts,
+    settings: Res<Settings>,
+    mut tutorial: ResMut<Tutorial>,
+) {
+    let Some(index) = tutorial.step else {
+        return;
+    };
+    egui::Window::new("Tutorial")
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -20.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.add(
+                egui::ProgressBar::new(index as f32 / STEPS.len() as f32).text(format!(
+                    "{} of {} steps",
+                    index,
+                    STEPS.len()
+                )),
+            );
+            match STEPS.get(index) {
+                Some(step) => {
+                    ui.heading(step.title());
+                    ui.label(step.instructions(&settings));
+                    ui.horizontal(|ui| {
+                        if ui.button("Skip Step").clicked() {
+                            tutorial.step = Some(index + 1);
+                            tutorial.baseline = None;
+                        }
+                        if ui.button("Exit Tutorial").clicked() {
+                            tutorial.step = None;
+                        }
+                    });
+                }
+                None => {
+                    ui.heading("All done");
+                    ui.label("The other panels and windows hold many more rules to explore.");
+                    if ui.button("Close").clicked() {
+                        tutorial.step = None;
+                    }
+                }
+            }
+        });
+}
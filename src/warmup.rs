@@ -0,0 +1,77 @@
+use bevy::{prelude::*, utils::Instant};
+use std::time::Duration;
+
+use crate::{
+    loading::PendingSpawns, network::RemoteWorld, settings::Settings, update_particles, Particle,
+};
+
+/// Simulated time per warm-up tick, one frame at 60 FPS.
+const WARMUP_STEP: Duration = Duration::from_nanos(16_666_667);
+
+/// Simulates a number of ticks as fast as possible before the first frame
+/// with particles is drawn, so screenshots and recordings start from
+/// developed structures. Set in the Settings window, or with
+/// `--warmup <ticks>`. Only the particle forces run during warm-up.
+pub struct WarmupPlugin;
+
+impl Plugin for WarmupPlugin {
+    fn build(&self, app: &mut App) {
+        let ticks =
+            ticks_from_args().unwrap_or_else(|| app.world().resource::<Settings>().warmup_ticks);
+        if ticks == 0 {
+            return;
+        }
+        app.insert_resource(Warmup { ticks }).add_systems(
+            PostUpdate,
+            warm_up
+                .before(TransformSystem::TransformPropagate)
+                .run_if(resource_exists::<Warmup>),
+        );
+    }
+}
+
+fn ticks_from_args() -> Option<u32> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|arg| arg == "--warmup")?;
+    args.get(index + 1)?.parse().ok()
+}
+
+/// Warm-up still to run once the first particles exist.
+#[derive(Resource)]
+struct Warmup {
+    ticks: u32,
+}
+
+fn warm_up(world: &mut World) {
+    // A shared world client shows the server's simulation instead
+    if world.contains_resource::<RemoteWorld>() {
+        world.remove_resource::<Warmup>();
+        return;
+    }
+    let mut particles = world.query_filtered::<(), With<Particle>>();
+    if particles.iter(world).next().is_none() || world.resource::<PendingSpawns>().is_loading() {
+        return;
+    }
+    let Some(Warmup { ticks }) = world.remove_resource::<Warmup>() else {
+        return;
+    };
+
+    // Each tick sees a fixed step however long the frame took
+    let time = *world.resource::<Time>();
+    let started = Instant::now();
+    for _ in 0..ticks {
+        let mut step = time;
+        step.advance_by(WARMUP_STEP);
+        world.insert_resource(step);
+        if let Err(error) = world.run_system_cached(update_particles) {
+            warn!("Warm-up stopped: {}", error);
+            break;
+        }
+    }
+    world.insert_resource(time);
+    info!(
+        "Warmed up {} ticks in {:.1} s",
+        ticks,
+        started.elapsed().as_secs_f32()
+    );
+}
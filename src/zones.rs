@@ -0,0 +1,305 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    spawn::{self, WorldCursor},
+    ui_enabled, ParticleSystem,
+};
+
+const ZONE_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.35);
+const SELECTED_COLOR: Color = Color::srgba(1.0, 0.85, 0.3, 0.8);
+/// Drags shorter than this in world units do not create a zone.
+const MIN_ZONE_SIZE: f32 = 5.0;
+
+pub struct ZonesPlugin;
+
+impl Plugin for ZonesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ZoneTool>().add_systems(
+            Update,
+            (
+                draw_zone_with_mouse
+                    .before(spawn::spawn_at_cursor)
+                    .run_if(ui_enabled),
+                draw_zones,
+                zones_ui_system.run_if(ui_enabled),
+            ),
+        );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ZoneShape {
+    Rect { min: Vec2, max: Vec2 },
+    Circle { center: Vec2, radius: f32 },
+}
+
+impl ZoneShape {
+    fn from_drag(kind: ZoneKind, start: Vec2, end: Vec2) -> Self {
+        match kind {
+            ZoneKind::Rect => ZoneShape::Rect {
+                min: start.min(end),
+                max: start.max(end),
+            },
+            ZoneKind::Circle => ZoneShape::Circle {
+                center: start,
+                radius: start.distance(end),
+            },
+        }
+    }
+
+    fn contains(&self, pos: Vec2) -> bool {
+        match *self {
+            ZoneShape::Rect { min, max } => pos.cmpge(min).all() && pos.cmple(max).all(),
+            ZoneShape::Circle { center, radius } => pos.distance_squared(center) <= radius * radius,
+        }
+    }
+
+    fn size(&self) -> f32 {
+        match *self {
+            ZoneShape::Rect { min, max } => (max - min).min_element(),
+            ZoneShape::Circle { radius, .. } => radius,
+        }
+    }
+
+    fn draw(&self, gizmos: &mut Gizmos, color: Color) {
+        match *self {
+            ZoneShape::Rect { min, max } => {
+                gizmos.rect_2d(
+                    Isometry2d::from_translation((min + max) / 2.0),
+                    max - min,
+                    color,
+                );
+            }
+            ZoneShape::Circle { center, radius } => {
+                gizmos.circle_2d(Isometry2d::from_translation(center), radius, color);
+            }
+        }
+    }
+}
+
+/// A region whose particles follow its own rules instead of the globals.
+/// Where zones overlap the first one in the list wins.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleZone {
+    pub shape: ZoneShape,
+    pub speed: Option<f32>,
+    pub temperature: Option<f32>,
+    /// Multiplies every matrix entry inside the zone, including its own
+    /// matrix.
+    pub matrix_scale: f32,
+    /// Replaces the global matrix inside the zone; pairs outside it fall back
+    /// to the global matrix.
+    pub matrix: Option<Vec<Vec<f32>>>,
+}
+
+impl Default for RuleZone {
+    fn default() -> Self {
+        RuleZone {
+            shape: ZoneShape::Rect {
+                min: Vec2::ZERO,
+                max: Vec2::ZERO,
+            },
+            speed: None,
+            temperature: None,
+            matrix_scale: 1.0,
+            matrix: None,
+        }
+    }
+}
+
+impl RuleZone {
+    pub fn contains(&self, pos: Vec2) -> bool {
+        self.shape.contains(pos)
+    }
+
+    /// Behavior of `from` toward `to` inside the zone, given the global value.
+    pub fn behavior(&self, global: f32, from: usize, to: usize) -> f32 {
+        let behavior = self
+            .matrix
+            .as_ref()
+            .and_then(|matrix| matrix.get(from)?.get(to).copied())
+            .unwrap_or(global);
+        behavior * self.matrix_scale
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ZoneKind {
+    Rect,
+    Circle,
+}
+
+/// State of the zone drawing tool.
+#[derive(Resource, Default)]
+struct ZoneTool {
+    /// Shape drawn by the next left drag, which then adds no particle.
+    drawing: Option<ZoneKind>,
+    start: Option<Vec2>,
+    selected: Option<usize>,
+}
+
+fn draw_zone_with_mouse(
+    mut contexts: EguiContexts,
+    mut buttons: ResMut<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    cursor: WorldCursor,
+    mut tool: ResMut<ZoneTool>,
+    mut particle_system: ResMut<ParticleSystem>,
+    mut gizmos: Gizmos,
+) {
+    let Some(kind) = tool.drawing else {
+        return;
+    };
+    if keyboard.just_pressed(KeyCode::Escape) {
+        *tool = ZoneTool::default();
+        return;
+    }
+    let cursor = cursor.position();
+    if buttons.just_pressed(MouseButton::Left) && !contexts.ctx_mut().wants_pointer_input() {
+        tool.start = cursor;
+        // The click draws the zone instead of adding a particle
+        buttons.clear_just_pressed(MouseButton::Left);
+    }
+    let (Some(start), Some(end)) = (tool.start, cursor) else {
+        return;
+    };
+    let shape = ZoneShape::from_drag(kind, start, end);
+    if buttons.just_released(MouseButton::Left) {
+        if shape.size() >= MIN_ZONE_SIZE {
+            particle_system.zones.push(RuleZone {
+                shape,
+                ..Default::default()
+            });
+            tool.selected = Some(particle_system.zones.len() - 1);
+        }
+        tool.drawing = None;
+        tool.start = None;
+    } else {
+        shape.draw(&mut gizmos, SELECTED_COLOR);
+    }
+}
+
+fn draw_zones(particle_system: Res<ParticleSystem>, tool: Res<ZoneTool>, mut gizmos: Gizmos) {
+    for (index, zone) in particle_system.zones.iter().enumerate() {
+        let color = if tool.selected == Some(index) {
+            SELECTED_COLOR
+        } else {
+            ZONE_COLOR
+        };
+        zone.shape.draw(&mut gizmos, color);
+    }
+}
+
+/// Checkbox that switches an override on, seeded with the global value, plus
+/// a slider for it.
+fn override_ui(
+    ui: &mut egui::Ui,
+    label: &str,
+    value: &mut Option<f32>,
+    global: f32,
+    range: std::ops::RangeInclusive<f32>,
+) {
+    ui.horizontal(|ui| {
+        let mut enabled = value.is_some();
+        if ui.checkbox(&mut enabled, label).changed() {
+            *value = enabled.then_some(global);
+        }
+        if let Some(value) = value {
+            ui.add(egui::Slider::new(value, range));
+        }
+    });
+}
+
+fn zones_ui_system(
+    mut contexts: EguiContexts,
+    mut particle_system: ResMut<ParticleSystem>,
+    mut tool: ResMut<ZoneTool>,
+) {
+    egui::Window::new("Rule Zones")
+        .default_pos([400.0, 820.0])
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label("Regions where speed, temperature or the matrix differ from the globals.");
+            ui.horizontal(|ui| {
+                for (kind, label) in [
+                    (ZoneKind::Rect, "Draw Rectangle"),
+                    (ZoneKind::Circle, "Draw Circle"),
+                ] {
+                    if ui
+                        .selectable_label(tool.drawing == Some(kind), label)
+                        .clicked()
+                    {
+                        tool.drawing = (tool.drawing != Some(kind)).then_some(kind);
+                        tool.start = None;
+                    }
+                }
+            });
+            if tool.drawing.is_some() {
+                ui.label("Drag in the world to draw the zone, Escape to cancel.");
+            }
+            ui.add_space(5.0);
+
+            let particle_system = &mut *particle_system;
+            let n = particle_system.colors.len();
+            let mut removed = None;
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (index, zone) in particle_system.zones.iter_mut().enumerate() {
+                    let header = egui::CollapsingHeader::new(format!("Zone {}", index + 1))
+                        .id_salt(("rule_zone", index))
+                        .show(ui, |ui| {
+                            override_ui(
+                                ui,
+                                "Force strength",
+                                &mut zone.speed,
+                                particle_system.force_strength,
+                                0.0..=3200.0,
+                            );
+                            override_ui(
+                                ui,
+                                "Temperature",
+                                &mut zone.temperature,
+                                particle_system.temperature,
+                                0.0..=50.0,
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut zone.matrix_scale, -2.0..=2.0)
+                                    .text("matrix scale"),
+                            );
+                            ui.horizontal(|ui| {
+                                let mut own = zone.matrix.is_some();
+                                if ui.checkbox(&mut own, "Own matrix").changed() {
+                                    zone.matrix =
+                                        own.then(|| particle_system.behavior_matrix.clone());
+                                }
+                                if let Some(matrix) = &mut zone.matrix {
+                                    if ui.button("Reroll").clicked() {
+                                        let mut rng = rand::rng();
+                                        *matrix = (0..n)
+                                            .map(|_| {
+                                                (0..n)
+                                                    .map(|_| rng.random_range(-1.0..=1.0))
+                                                    .collect()
+                                            })
+                                            .collect();
+                                    }
+                                }
+                            });
+                            if ui.button("Delete Zone").clicked() {
+                                removed = Some(index);
+                            }
+                        });
+                    if header.header_response.clicked() {
+                        tool.selected = Some(index);
+                    }
+                }
+            });
+            if let Some(index) = removed {
+                particle_system.zones.remove(index);
+                tool.selected = None;
+            }
+        });
+}
@@ -0,0 +1,128 @@
+// Golden-run regression tests: the simulation runs headless from a fixed
+// seed for a fixed number of ticks, and a checksum of the final particle
+// positions is compared against the value stored in `tests/golden`. Set
+// `BLESS_GOLDEN=1` to rewrite the stored values after an intended change to
+// the physics; without it a missing file is a failure.
+
+use bevy::math::Vec2;
+use particle_life_rust::headless::HeadlessSimulation;
+use std::{fs, path::PathBuf};
+
+const SEED: u64 = 0x5EED;
+const SPECIES: usize = 6;
+const PARTICLES: usize = 600;
+const SPAWN_EXTENT: f32 = 400.0;
+const TICKS: usize = 120;
+/// Positions are rounded to this before hashing, so the checksum does not
+/// depend on digits far below anything visible.
+const QUANTUM: f32 = 0.01;
+/// Ticks the backends are compared over. They sum forces in different
+/// orders, so rounding differences grow chaotically over longer runs.
+const AGREEMENT_TICKS: usize = 10;
+/// How far particles may end up, on average, from where the first backend
+/// put them. An average rather than a maximum, since Barnes–Hut approximates
+/// distant clusters and so moves a few particles noticeably differently.
+const AGREEMENT_TOLERANCE: f32 = 0.01;
+
+fn simulate(backend: usize, ticks: usize) -> Vec<Vec2> {
+    let mut simulation = HeadlessSimulation::new(SEED, SPECIES, PARTICLES, SPAWN_EXTENT, backend);
+    for _ in 0..ticks {
+        simulation.step();
+    }
+    simulation.positions()
+}
+
+/// FNV-1a over the quantized positions, in spawn order.
+fn position_checksum(positions: &[Vec2]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for pos in positions {
+        for value in [pos.x, pos.y] {
+            let quantized = (value / QUANTUM).round() as i64;
+            for byte in quantized.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+    }
+    hash
+}
+
+fn run(backend: usize) -> u64 {
+    position_checksum(&simulate(backend, TICKS))
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.txt", name))
+}
+
+/// Compares `checksum` with the stored value, writing it instead when
+/// blessing.
+fn check_golden(name: &str, checksum: u64) {
+    let path = golden_path(name);
+    let actual = format!("{:016x}", checksum);
+    if std::env::var_os("BLESS_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, format!("{}\n", actual)).unwrap();
+        return;
+    }
+    let stored = fs::read_to_string(&path).unwrap_or_else(|error| {
+        panic!(
+            "no golden run for {} at {} ({}); record it with BLESS_GOLDEN=1",
+            name,
+            path.display(),
+            error
+        )
+    });
+    assert_eq!(
+        stored.trim(),
+        actual,
+        "{} drifted from its golden run; if the change is intended, rerun with BLESS_GOLDEN=1",
+        name
+    );
+}
+
+#[test]
+fn same_seed_gives_the_same_run() {
+    assert_eq!(run(0), run(0));
+}
+
+#[test]
+fn grid_backend_matches_golden_run() {
+    check_golden("grid", run(0));
+}
+
+#[test]
+fn naive_backend_matches_golden_run() {
+    check_golden("naive", run(1));
+}
+
+#[test]
+fn quadtree_backend_matches_golden_run() {
+    check_golden("quadtree", run(2));
+}
+
+#[test]
+fn backends_agree() {
+    let names = HeadlessSimulation::backends();
+    let reference = simulate(0, AGREEMENT_TICKS);
+    for (backend, name) in names.iter().enumerate().skip(1) {
+        let positions = simulate(backend, AGREEMENT_TICKS);
+        let drift = reference
+            .iter()
+            .zip(&positions)
+            .map(|(a, b)| a.distance(*b))
+            .sum::<f32>()
+            / reference.len() as f32;
+        assert!(
+            drift <= AGREEMENT_TOLERANCE,
+            "{} drifted {} on average from {} after {} ticks",
+            name,
+            drift,
+            names[0],
+            AGREEMENT_TICKS
+        );
+    }
+}